@@ -0,0 +1,157 @@
+use image::{DynamicImage, GenericImageView, Rgba, RgbaImage};
+use log::debug;
+use rayon::prelude::*;
+
+use crate::error::AppError;
+use crate::graphics::generate_blurred_background;
+use crate::processor::traits::BackgroundKind;
+use crate::processor::white::utils::create_expanded_canvas;
+
+// =========================================================
+// 🟢 补边到目标宽高比 (1:1 / 4:5 / 9:16 等)
+// =========================================================
+// 社交平台会把 3:2 的成品裁掉一截，这里在绘制完成后把画布对称补到目标比例：
+// 白底系列按背景色延伸；透明系列从成品边缘的模糊背景出发，越往外越过渡到按同样参数从原图生成的新背景
+// (两张模糊背景的取景不同，直接拼接会在接缝处留下一条明显的线)。
+// 只补不裁：比目标更宽的图补上下，更窄的补左右。
+
+// 目标比例的范围 (1:4 ~ 4:1)，更极端的比例会把成品补成细长条，补边面积成倍增长
+const MAX_RATIO: u32 = 4;
+// 补边后的像素上限 (2.5 亿，RGBA 约 1 GB)，超出时报错而不是去分配画布
+const MAX_PADDED_PIXELS: u64 = 250_000_000;
+
+/// 检查目标比例：两边都不能为 0，长短边之比不超过 4:1
+pub fn validate_ratio(ratio: (u32, u32)) -> Result<(), AppError> {
+    let (rw, rh) = ratio;
+    let (long, short) = (rw.max(rh) as u64, rw.min(rh) as u64);
+    if short == 0 || long > short * MAX_RATIO as u64 {
+        return Err(AppError::System(format!(
+            "目标宽高比 {}:{} 超出范围 (1:{} ~ {}:1)", rw, rh, MAX_RATIO, MAX_RATIO
+        )));
+    }
+    Ok(())
+}
+
+/// 补边后的画布尺寸 (严格等于目标比例)；已经是目标比例时返回 None
+/// 比例超出范围或补边后像素数超过上限时返回错误
+pub fn padded_size(w: u32, h: u32, ratio: (u32, u32)) -> Result<Option<(u32, u32)>, AppError> {
+    validate_ratio(ratio)?;
+    let (rw, rh) = ratio;
+    // 先约分，[1080, 1350] 和 [4, 5] 结果一样
+    let g = gcd(rw, rh);
+    let (rw, rh) = (rw as u64 / g as u64, rh as u64 / g as u64);
+
+    // 取能同时容纳宽和高的最小倍数，两边都只增不减
+    let k = (w as u64).div_ceil(rw).max((h as u64).div_ceil(rh));
+    let (new_w, new_h) = (k * rw, k * rh);
+    if new_w * new_h > MAX_PADDED_PIXELS {
+        return Err(AppError::System(format!(
+            "补边到 {}:{} 后为 {}x{}，超过 {} 像素上限", ratio.0, ratio.1, new_w, new_h, MAX_PADDED_PIXELS
+        )));
+    }
+    // 上限远小于 u32::MAX²，两边都能放进 u32
+    let (new_w, new_h) = (new_w as u32, new_h as u32);
+    Ok(((new_w, new_h) != (w, h)).then_some((new_w, new_h)))
+}
+
+fn gcd(a: u32, b: u32) -> u32 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+/// 把成品补到目标比例，不需要补边时返回 None
+/// `source` 是原图 (模糊背景从它生成，与处理器绘制时的取材一致)
+pub fn pad_to_aspect(
+    img: &DynamicImage,
+    source: &DynamicImage,
+    ratio: (u32, u32),
+    background: BackgroundKind,
+) -> Result<Option<DynamicImage>, AppError> {
+    let (w, h) = img.dimensions();
+    let Some((new_w, new_h)) = padded_size(w, h, ratio)? else {
+        return Ok(None);
+    };
+    // 多出来的奇数像素放在下边 / 右边
+    let (left, top) = ((new_w - w) / 2, (new_h - h) / 2);
+    let (right, bottom) = (new_w - w - left, new_h - h - top);
+    debug!("  -> [Aspect] {}x{} -> {}x{} ({}:{})", w, h, new_w, new_h, ratio.0, ratio.1);
+
+    let padded = match background {
+        BackgroundKind::Solid(color) => create_expanded_canvas(img, top, bottom, left, right, color)?,
+        BackgroundKind::Blurred { blur_radius, tone } => {
+            let mut canvas = generate_blurred_background(source, new_w, new_h, blur_radius, tone).to_rgba8();
+            extend_edges(&mut canvas, &img.to_rgba8(), (left, top, right, bottom));
+            canvas
+        },
+    };
+    Ok(Some(DynamicImage::ImageRgba8(padded)))
+}
+
+/// 把成品贴到背景中央，补边区域从成品最外一圈像素平滑过渡 (smoothstep) 到背景
+/// 接缝处与成品边缘完全一致，外侧才逐渐变成新背景
+fn extend_edges(canvas: &mut RgbaImage, fg: &RgbaImage, (left, top, right, bottom): (u32, u32, u32, u32)) {
+    let (fg_w, fg_h) = fg.dimensions();
+    let canvas_w = canvas.width() as usize;
+
+    // 超出成品的距离占该侧补边宽度的比例 (0 代表在成品内)
+    let outside = |pos: u32, start: u32, len: u32, before: u32, after: u32| -> f32 {
+        if pos < start {
+            (start - pos) as f32 / before as f32
+        } else if pos >= start + len {
+            (pos - (start + len - 1)) as f32 / after as f32
+        } else {
+            0.0
+        }
+    };
+
+    canvas.par_chunks_mut(canvas_w * 4).enumerate().for_each(|(y, row)| {
+        let y = y as u32;
+        let fy = y.saturating_sub(top).min(fg_h - 1);
+        let ty = outside(y, top, fg_h, top, bottom);
+        for x in 0..canvas_w as u32 {
+            let fx = x.saturating_sub(left).min(fg_w - 1);
+            let t = ty.max(outside(x, left, fg_w, left, right));
+            let Rgba(src) = *fg.get_pixel(fx, fy);
+            let offset = (x * 4) as usize;
+            let dst = &mut row[offset..offset + 4];
+            if t <= 0.0 {
+                dst.copy_from_slice(&src);
+                continue;
+            }
+            let w = t * t * (3.0 - 2.0 * t);
+            for (d, s) in dst.iter_mut().zip(src) {
+                *d = (s as f32 * (1.0 - w) + *d as f32 * w).round() as u8;
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pads_to_exact_ratio() {
+        assert_eq!(padded_size(1500, 1000, (1, 1)).unwrap(), Some((1500, 1500)));
+        assert_eq!(padded_size(1500, 1000, (1080, 1350)).unwrap(), Some((1500, 1875)));
+        assert_eq!(padded_size(1000, 1000, (1, 1)).unwrap(), None);
+    }
+
+    #[test]
+    fn ratio_must_stay_within_4_to_1() {
+        assert!(padded_size(1500, 1000, (4, 1)).is_ok());
+        assert!(padded_size(1500, 1000, (1, 4)).is_ok());
+        for ratio in [(0, 1), (1, 0), (5, 1), (1, 5), (1, 4_000_000)] {
+            assert!(padded_size(1500, 1000, ratio).is_err(), "{:?}", ratio);
+            assert!(validate_ratio(ratio).is_err(), "{:?}", ratio);
+        }
+    }
+
+    #[test]
+    fn refuses_huge_canvas_instead_of_allocating() {
+        // 60MP 横图补到 1:4 会变成 9504x38016 (3.6 亿像素)
+        let err = padded_size(9504, 6336, (1, 4)).unwrap_err();
+        assert_eq!(err.code(), "SYSTEM_ERROR");
+        // 4:5 只多一点，正常通过
+        assert_eq!(padded_size(9504, 6336, (4, 5)).unwrap(), Some((9504, 11880)));
+    }
+}
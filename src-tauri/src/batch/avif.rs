@@ -0,0 +1,62 @@
+use image::DynamicImage;
+#[cfg(feature = "avif")]
+use image::codecs::avif::AvifEncoder;
+#[cfg(feature = "avif")]
+use image::ImageEncoder;
+#[cfg(feature = "avif")]
+use log::{debug, error};
+
+use crate::error::AppError;
+
+// =========================================================
+// AVIF 编码
+// =========================================================
+// 走 image 自带的 AVIF 编码器 (ravif / rav1e，纯 Rust)，由 avif feature 控制。
+// rav1e 编码很吃 CPU，速度设得太低整批会慢得难以接受，默认 6。
+
+/// 当前构建是否能输出 AVIF
+pub fn supported() -> bool {
+    cfg!(feature = "avif")
+}
+
+/// 编码为 AVIF (输入应为 8 位 RGB/RGBA，保留 Alpha)
+#[cfg(feature = "avif")]
+pub fn encode(img: &DynamicImage, quality: u8, speed: u8) -> Result<Vec<u8>, AppError> {
+    // 编码器内部取值范围 1-10，超出时夹紧
+    let speed = speed.clamp(1, 10);
+    debug!("  -> AVIF 编码 (quality={}, speed={})", quality, speed);
+
+    let mut encoded = Vec::new();
+    // 图片之间已经按批次并发，编码器自身不再开线程，避免和 rayon 池抢核
+    let encoder = AvifEncoder::new_with_speed_quality(&mut encoded, speed, quality)
+        .with_num_threads(Some(1));
+    encoder.write_image(img.as_bytes(), img.width(), img.height(), img.color().into())
+        .map_err(|e| {
+            error!("❌ [Save] AVIF 编码失败: {}", e);
+            AppError::Image(e)
+        })?;
+    Ok(encoded)
+}
+
+#[cfg(not(feature = "avif"))]
+pub fn encode(_img: &DynamicImage, _quality: u8, _speed: u8) -> Result<Vec<u8>, AppError> {
+    Err(AppError::System("当前版本未启用 AVIF 编码".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // avif 特性要真的决定编码器是否存在 (image 的默认特性会无条件带上 avif)
+    #[test]
+    fn encoder_follows_feature() {
+        let img = DynamicImage::ImageRgb8(image::RgbImage::from_pixel(16, 16, image::Rgb([200, 100, 50])));
+        let result = encode(&img, 80, 10);
+        assert_eq!(supported(), result.is_ok());
+        if let Ok(bytes) = result {
+            // ISOBMFF：第 4-8 字节是 "ftyp"
+            assert_eq!(bytes.get(4..8), Some(&b"ftyp"[..]));
+        }
+        assert_eq!(image::ImageFormat::Avif.writing_enabled(), supported());
+    }
+}
@@ -0,0 +1,107 @@
+use log::debug;
+
+// =========================================================
+// 内存感知的并发控制
+// =========================================================
+// par_iter 默认每个 CPU 核心同时处理一张图；高像素原图解码 + 合成画布的峰值内存
+// 可能是几百 MB，2000 张 36MP 的批次在 16GB 机器上会直接把系统拖进交换区。
+
+// 每个像素的峰值内存估算 (字节)：解码 RGB8 + RGBA8 源图副本 + 加边框后的画布 + 编码缓冲，留足余量
+const BYTES_PER_PIXEL_ESTIMATE: u64 = 16;
+// 读不到尺寸时 (RAW、损坏文件) 按 36MP 估算
+const FALLBACK_PIXELS: u64 = 36_000_000;
+// 只抽样前几个文件读头部，避免大批次启动时遍历全部文件
+const SAMPLE_FILES: usize = 8;
+// 内存预算只用一半，剩下的留给系统、WebView 和 rayon 以外的分配
+const MEMORY_BUDGET_DIVISOR: u64 = 2;
+
+/// 物理内存总量 (字节)，查询失败返回 None
+#[cfg(unix)]
+pub fn total_memory() -> Option<u64> {
+    // SAFETY: sysconf 只读取系统参数
+    let (pages, page_size) = unsafe { (libc::sysconf(libc::_SC_PHYS_PAGES), libc::sysconf(libc::_SC_PAGESIZE)) };
+    if pages <= 0 || page_size <= 0 {
+        return None;
+    }
+    Some(pages as u64 * page_size as u64)
+}
+
+#[cfg(windows)]
+pub fn total_memory() -> Option<u64> {
+    use windows_sys::Win32::System::SystemInformation::{GlobalMemoryStatusEx, MEMORYSTATUSEX};
+
+    // dwLength 必须先填好结构体大小，其余字段由系统写入
+    // SAFETY: MEMORYSTATUSEX 是纯数据结构，全零是合法值；指针在调用期间有效
+    let mut status: MEMORYSTATUSEX = unsafe { std::mem::zeroed() };
+    status.dwLength = std::mem::size_of::<MEMORYSTATUSEX>() as u32;
+    if unsafe { GlobalMemoryStatusEx(&mut status) } == 0 || status.ullTotalPhys == 0 {
+        return None;
+    }
+    Some(status.ullTotalPhys)
+}
+
+// 其他平台不查询，只按 CPU 核心数限制
+#[cfg(not(any(unix, windows)))]
+pub fn total_memory() -> Option<u64> {
+    None
+}
+
+/// 估算单张图片处理时的峰值内存：抽样文件中最大的那张
+pub fn estimate_per_image_bytes(file_paths: &[String]) -> u64 {
+    let max_pixels = file_paths
+        .iter()
+        .take(SAMPLE_FILES)
+        .map(|p| match image::image_dimensions(p) {
+            Ok((w, h)) => w as u64 * h as u64,
+            Err(_) => FALLBACK_PIXELS,
+        })
+        .max()
+        .unwrap_or(FALLBACK_PIXELS);
+    max_pixels * BYTES_PER_PIXEL_ESTIMATE
+}
+
+/// 决定本批次的并发数
+/// 用户指定 max_concurrent 时直接使用 (至少 1)；
+/// 否则取 min(CPU 核心数, 内存预算 / 单张估算)
+pub fn choose_concurrency(requested: Option<usize>, file_paths: &[String]) -> usize {
+    if let Some(n) = requested {
+        return n.max(1);
+    }
+
+    let cpus = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(4);
+    let Some(total) = total_memory() else {
+        return cpus;
+    };
+
+    let per_image = estimate_per_image_bytes(file_paths);
+    let by_memory = (total / MEMORY_BUDGET_DIVISOR / per_image.max(1)).max(1) as usize;
+    debug!(
+        "🧮 [Concurrency] CPU={}, 内存={} MB, 单张估算={} MB, 内存允许={}",
+        cpus, total / 1_048_576, per_image / 1_048_576, by_memory
+    );
+    cpus.min(by_memory)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(any(unix, windows))]
+    #[test]
+    fn total_memory_is_known_on_desktop_platforms() {
+        let total = total_memory().expect("unix / Windows 都应能查询物理内存");
+        assert!(total >= 256 * 1_048_576, "物理内存 {} 字节不合理", total);
+    }
+
+    #[test]
+    fn requested_concurrency_wins() {
+        assert_eq!(choose_concurrency(Some(3), &[]), 3);
+        assert_eq!(choose_concurrency(Some(0), &[]), 1);
+    }
+
+    #[test]
+    fn unreadable_files_use_fallback_estimate() {
+        let paths = vec!["/nonexistent/a.jpg".to_string()];
+        assert_eq!(estimate_per_image_bytes(&paths), FALLBACK_PIXELS * BYTES_PER_PIXEL_ESTIMATE);
+    }
+}
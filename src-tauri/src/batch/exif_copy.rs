@@ -0,0 +1,97 @@
+use std::fs::File;
+use std::io::BufReader;
+
+use log::debug;
+
+// =========================================================
+// 原图 EXIF 透传 (JPG)
+// =========================================================
+// image crate 的编码器不会写 EXIF，这里在编码完成后把原图的 TIFF 块
+// 作为 APP1 段插入到 JPG 数据流中。
+
+const APP1_EXIF_HEADER: &[u8] = b"Exif\0\0";
+// APP1 段长度字段为 u16，包含长度字段自身的 2 字节
+const MAX_APP1_PAYLOAD: usize = u16::MAX as usize - 2;
+const TAG_ORIENTATION: u16 = 0x0112;
+
+/// 读取原图的原始 EXIF (TIFF) 数据，并把方向改写为 1
+/// 管线已经通过 load_image_auto_rotate 摆正了像素，保留原方向会导致二次旋转
+pub fn read_exif_for_copy(path: &str) -> Option<Vec<u8>> {
+    let file = File::open(path).ok()?;
+    let mut bufreader = BufReader::new(file);
+    let exif = exif::Reader::new().read_from_container(&mut bufreader).ok()?;
+
+    let mut tiff = exif.buf().to_vec();
+    if !reset_orientation(&mut tiff) {
+        debug!("  -> EXIF 中未找到方向标签: {}", path);
+    }
+    Some(tiff)
+}
+
+/// 将 TIFF 数据作为 APP1 段插入 JPG (位于 SOI / JFIF APP0 之后)
+/// 数据过大或 JPG 结构异常时返回 Err，调用方保留原始编码结果
+pub fn inject_exif_into_jpeg(jpeg: &[u8], tiff: &[u8]) -> Result<Vec<u8>, String> {
+    if jpeg.len() < 4 || jpeg[0] != 0xFF || jpeg[1] != 0xD8 {
+        return Err("不是有效的 JPG 数据".to_string());
+    }
+    let payload_len = APP1_EXIF_HEADER.len() + tiff.len();
+    if payload_len > MAX_APP1_PAYLOAD {
+        return Err(format!("EXIF 数据过大 ({} 字节)，超出 APP1 段上限", tiff.len()));
+    }
+
+    // 跳过编码器写入的 JFIF APP0，EXIF 紧随其后
+    let mut insert_at = 2;
+    if jpeg[2] == 0xFF && jpeg[3] == 0xE0 && jpeg.len() >= 6 {
+        let app0_len = u16::from_be_bytes([jpeg[4], jpeg[5]]) as usize;
+        insert_at = (4 + app0_len).min(jpeg.len());
+    }
+
+    let mut out = Vec::with_capacity(jpeg.len() + payload_len + 4);
+    out.extend_from_slice(&jpeg[..insert_at]);
+    out.extend_from_slice(&[0xFF, 0xE1]);
+    out.extend_from_slice(&((payload_len + 2) as u16).to_be_bytes());
+    out.extend_from_slice(APP1_EXIF_HEADER);
+    out.extend_from_slice(tiff);
+    out.extend_from_slice(&jpeg[insert_at..]);
+    Ok(out)
+}
+
+/// 就地把 IFD0 中的 Orientation 改写为 1，返回是否找到该标签
+fn reset_orientation(tiff: &mut [u8]) -> bool {
+    if tiff.len() < 8 {
+        return false;
+    }
+    let big_endian = match &tiff[0..2] {
+        b"MM" => true,
+        b"II" => false,
+        _ => return false,
+    };
+    let read_u16 = |b: &[u8], at: usize| -> u16 {
+        let pair = [b[at], b[at + 1]];
+        if big_endian { u16::from_be_bytes(pair) } else { u16::from_le_bytes(pair) }
+    };
+    let read_u32 = |b: &[u8], at: usize| -> u32 {
+        let quad = [b[at], b[at + 1], b[at + 2], b[at + 3]];
+        if big_endian { u32::from_be_bytes(quad) } else { u32::from_le_bytes(quad) }
+    };
+
+    let ifd0 = read_u32(tiff, 4) as usize;
+    if ifd0 + 2 > tiff.len() {
+        return false;
+    }
+    let count = read_u16(tiff, ifd0) as usize;
+
+    for i in 0..count {
+        let entry = ifd0 + 2 + i * 12;
+        if entry + 12 > tiff.len() {
+            break;
+        }
+        // Orientation 规范类型为 SHORT (3)，count 为 1，值直接存放在 entry 内
+        if read_u16(tiff, entry) == TAG_ORIENTATION && read_u16(tiff, entry + 2) == 3 {
+            let one = if big_endian { 1u16.to_be_bytes() } else { 1u16.to_le_bytes() };
+            tiff[entry + 8..entry + 10].copy_from_slice(&one);
+            return true;
+        }
+    }
+    false
+}
@@ -0,0 +1,229 @@
+use std::sync::{Condvar, Mutex};
+
+// =========================================================
+// 文件句柄压力控制
+// =========================================================
+// 大批量处理时，rayon 的每个任务都可能同时持有 EXIF 读取、解码、输出写入的句柄。
+// 读取类句柄都在各自函数内打开并立即释放；输出写入通过 IoSlots 限制并发数量。
+
+// Windows 没有 rlimit，Win32 句柄上限很高，这里按保守的经验值估算
+#[cfg(windows)]
+const WINDOWS_ASSUMED_LIMIT: u64 = 8192;
+
+// 低于该值时提示用户 (macOS 默认 256，部分 Linux CI 为 1024)
+const LOW_LIMIT_THRESHOLD: u64 = 1024;
+
+/// 当前进程可打开的文件数上限 (软限制)，查询失败返回 None
+#[cfg(unix)]
+pub fn open_file_limit() -> Option<u64> {
+    let mut rl = libc::rlimit { rlim_cur: 0, rlim_max: 0 };
+    // SAFETY: getrlimit 只写入我们传入的结构体
+    let ret = unsafe { libc::getrlimit(libc::RLIMIT_NOFILE, &mut rl) };
+    if ret != 0 {
+        return None;
+    }
+    // rlim_t 在 Linux / macOS 上就是 u64；无限制时为 RLIM_INFINITY (u64::MAX)
+    Some(rl.rlim_cur)
+}
+
+#[cfg(windows)]
+pub fn open_file_limit() -> Option<u64> {
+    Some(WINDOWS_ASSUMED_LIMIT)
+}
+
+#[cfg(not(any(unix, windows)))]
+pub fn open_file_limit() -> Option<u64> {
+    None
+}
+
+/// 根据句柄上限决定输出写入的并发数：留出大量余量给读取、日志和 WebView
+pub fn output_slot_count(limit: Option<u64>) -> usize {
+    match limit {
+        Some(l) => ((l / 16) as usize).clamp(2, 32),
+        None => 8,
+    }
+}
+
+/// 句柄上限偏低时返回提示文本 (用于批次总结)
+pub fn fd_pressure_warning(limit: Option<u64>, total_files: usize) -> Option<String> {
+    let limit = limit?;
+    if limit >= LOW_LIMIT_THRESHOLD || total_files < 100 {
+        return None;
+    }
+    Some(format!(
+        "系统文件句柄上限较低 ({})，大批量处理可能出现 IO 错误，建议调高 ulimit -n",
+        limit
+    ))
+}
+
+/// 简单的计数信号量 (输出文件写入槽位)
+pub struct IoSlots {
+    available: Mutex<usize>,
+    cv: Condvar,
+}
+
+impl IoSlots {
+    pub fn new(count: usize) -> Self {
+        Self {
+            available: Mutex::new(count.max(1)),
+            cv: Condvar::new(),
+        }
+    }
+
+    /// 阻塞直到拿到一个槽位，guard 释放时自动归还
+    pub fn acquire(&self) -> IoSlotGuard<'_> {
+        let mut n = self.available.lock().unwrap();
+        while *n == 0 {
+            n = self.cv.wait(n).unwrap();
+        }
+        *n -= 1;
+        IoSlotGuard { slots: self }
+    }
+}
+
+pub struct IoSlotGuard<'a> {
+    slots: &'a IoSlots,
+}
+
+impl Drop for IoSlotGuard<'_> {
+    fn drop(&mut self) {
+        *self.slots.available.lock().unwrap() += 1;
+        self.slots.cv.notify_one();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn slot_count_scales_with_limit() {
+        assert_eq!(output_slot_count(None), 8);
+        assert_eq!(output_slot_count(Some(0)), 2);
+        assert_eq!(output_slot_count(Some(256)), 16);
+        // RLIM_INFINITY 等超大值按上限 32
+        assert_eq!(output_slot_count(Some(u64::MAX)), 32);
+    }
+
+    #[test]
+    fn pressure_warning_only_for_low_limit_and_large_batch() {
+        assert!(fd_pressure_warning(Some(256), 500).is_some());
+        assert!(fd_pressure_warning(Some(256), 50).is_none());
+        assert!(fd_pressure_warning(Some(LOW_LIMIT_THRESHOLD), 500).is_none());
+        assert!(fd_pressure_warning(None, 500).is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn open_file_limit_is_queried() {
+        assert!(open_file_limit().is_some_and(|l| l > 0));
+    }
+
+    // 同时持有的槽位数不超过 count
+    #[test]
+    fn io_slots_bound_concurrency() {
+        let slots = Arc::new(IoSlots::new(2));
+        let (active, peak) = (Arc::new(AtomicUsize::new(0)), Arc::new(AtomicUsize::new(0)));
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let (slots, active, peak) = (slots.clone(), active.clone(), peak.clone());
+                std::thread::spawn(move || {
+                    let _guard = slots.acquire();
+                    let now = active.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, Ordering::SeqCst);
+                    std::thread::sleep(std::time::Duration::from_millis(5));
+                    active.fetch_sub(1, Ordering::SeqCst);
+                })
+            })
+            .collect();
+        for h in handles {
+            h.join().unwrap();
+        }
+        assert!(peak.load(Ordering::SeqCst) <= 2);
+        assert_eq!(*slots.available.lock().unwrap(), 2);
+    }
+
+    // 压力测试：把 fd 软上限压到 64，经完整流水线 (build_pipeline + run_guarded) 并行处理 500 个文件，
+    // 读 EXIF → 解码 → 绘制 → 编码 → 写出，任何一步泄漏或长时间持有句柄都会在这里以 EMFILE 失败。
+    // rlimit 是整个进程共享的，所以测试重新执行自身的测试二进制，只在子进程里压低上限。
+    #[cfg(unix)]
+    const STRESS_CHILD_ENV: &str = "FRAMER_FD_STRESS_CHILD";
+
+    #[cfg(unix)]
+    #[test]
+    fn stress_500_files_under_low_fd_limit() {
+        if std::env::var_os(STRESS_CHILD_ENV).is_some() {
+            return stress_500_files_in_child();
+        }
+        // module_path! 带 crate 名前缀，测试过滤用的是去掉它之后的路径
+        let name = concat!(module_path!(), "::stress_500_files_under_low_fd_limit");
+        let name = name.split_once("::").map_or(name, |(_, rest)| rest);
+        let output = std::process::Command::new(std::env::current_exe().unwrap())
+            .args([name, "--exact", "--test-threads=1"])
+            .env(STRESS_CHILD_ENV, "1")
+            .output()
+            .unwrap();
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(
+            output.status.success(),
+            "子进程失败:\n{}\n{}",
+            stdout,
+            String::from_utf8_lossy(&output.stderr)
+        );
+        // 确认子进程确实跑到了这个测试，而不是被过滤成 0 个
+        assert!(stdout.contains("1 passed"), "{}", stdout);
+    }
+
+    #[cfg(unix)]
+    fn stress_500_files_in_child() {
+        use rayon::prelude::*;
+        use serde_json::json;
+        use std::time::Duration;
+        use crate::batch::pipeline::{build_pipeline, build_pool, GlobalContext};
+
+        const FILES: usize = 500;
+        const LOW_LIMIT: u64 = 64;
+
+        let dir = crate::test_support::temp_dir("fd_stress");
+        std::fs::create_dir_all(dir.join("out")).unwrap();
+        let src = crate::test_support::fixture("ev_spot.jpg");
+        let inputs: Vec<String> = (0..FILES)
+            .map(|i| {
+                let p = dir.join(format!("DSC_{:04}.jpg", i));
+                std::fs::copy(&src, &p).unwrap();
+                p.to_string_lossy().into_owned()
+            })
+            .collect();
+
+        let mut rl = libc::rlimit { rlim_cur: 0, rlim_max: 0 };
+        // SAFETY: 只读写我们传入的结构体；子进程结束时上限随之失效，不需要恢复
+        unsafe {
+            assert_eq!(libc::getrlimit(libc::RLIMIT_NOFILE, &mut rl), 0);
+            let low = libc::rlimit { rlim_cur: LOW_LIMIT.min(rl.rlim_cur), rlim_max: rl.rlim_max };
+            assert_eq!(libc::setrlimit(libc::RLIMIT_NOFILE, &low), 0);
+        }
+
+        let context: crate::models::BatchContext = serde_json::from_value(json!({
+            "style": "TransparentClassic",
+            "exportSettings": { "targetDir": dir.join("out"), "format": "jpg", "quality": 90 },
+        })).unwrap();
+        // 在压低上限之后创建，输出槽位按 64 计算
+        let global = GlobalContext::for_test(&context, FILES);
+        let processor = crate::processor::create_context_processor(&context).unwrap();
+        let pipeline = build_pipeline(Arc::new(processor));
+        build_pool(8).unwrap().install(|| {
+            inputs.par_iter().enumerate().for_each(|(index, path)| {
+                pipeline.run_guarded(&global, None, path.clone(), index);
+            });
+        });
+
+        let summary = global.tally.lock().unwrap().summary("finished", FILES, Duration::ZERO);
+        let written = std::fs::read_dir(dir.join("out")).unwrap().count();
+        let _ = std::fs::remove_dir_all(&dir);
+
+        assert_eq!((summary.succeeded, summary.failed, summary.skipped), (FILES, 0, 0), "{:?}", summary);
+        assert_eq!(written, FILES);
+    }
+}
@@ -0,0 +1,158 @@
+use image::codecs::jpeg::JpegEncoder;
+use image::{DynamicImage, ImageEncoder};
+use log::{debug, error};
+
+use crate::error::AppError;
+use crate::models::{ChromaSubsampling, ExportConfig};
+
+// =========================================================
+// JPG 编码
+// =========================================================
+// image 自带的编码器固定 4:4:4 基线 (默认霍夫曼表)，不支持其他色度抽样、渐进式和霍夫曼表优化；
+// 需要这些选项时走 mozjpeg (可选 feature)，默认设置仍使用自带编码器，输出与旧版本一致。
+
+/// JPG 编码参数 (从导出配置取)
+#[derive(Debug, Clone, Copy)]
+pub struct JpegOptions {
+    pub quality: u8,
+    pub subsampling: ChromaSubsampling,
+    pub progressive: bool,
+    pub optimize_coding: bool,
+}
+
+impl JpegOptions {
+    pub fn from_export(export: &ExportConfig) -> Self {
+        Self {
+            quality: export.quality,
+            subsampling: export.subsampling,
+            progressive: export.progressive,
+            optimize_coding: export.optimize_coding,
+        }
+    }
+
+    /// 自带编码器做不到、需要 mozjpeg 的选项
+    fn needs_mozjpeg(&self) -> bool {
+        self.subsampling != ChromaSubsampling::Yuv444 || self.progressive || self.optimize_coding
+    }
+}
+
+/// 当前构建不支持的选项 (会回退为 4:4:4 基线)，全部支持时返回 None；用于批次总结里提示一次
+pub fn fallback_note(opts: &JpegOptions) -> Option<String> {
+    if cfg!(feature = "mozjpeg") || !opts.needs_mozjpeg() {
+        return None;
+    }
+    let mut unsupported = Vec::new();
+    if opts.subsampling != ChromaSubsampling::Yuv444 {
+        unsupported.push(format!("色度抽样 {:?}", opts.subsampling));
+    }
+    if opts.progressive {
+        unsupported.push("渐进式".to_string());
+    }
+    if opts.optimize_coding {
+        unsupported.push("霍夫曼表优化".to_string());
+    }
+    Some(format!("当前版本未启用 mozjpeg，{} 已回退为 4:4:4 基线编码", unsupported.join("、")))
+}
+
+/// 编码为 JPG (输入应为 8 位 RGB 或灰度，Alpha 已由调用方去掉)
+pub fn encode(img: &DynamicImage, opts: &JpegOptions) -> Result<Vec<u8>, AppError> {
+    #[cfg(feature = "mozjpeg")]
+    if opts.needs_mozjpeg() {
+        if let DynamicImage::ImageRgb8(rgb) = img {
+            return encode_mozjpeg(rgb, opts).map_err(|e| {
+                error!("❌ [Save] mozjpeg 编码失败: {}", e);
+                AppError::System(format!("JPG 编码失败: {}", e))
+            });
+        }
+    }
+
+    // 灰度图没有色度分量，抽样设置无意义；渐进式 / 霍夫曼表优化同样只有 mozjpeg 路径支持
+    if opts.needs_mozjpeg() {
+        debug!("  -> 当前构建不支持 {:?}，使用 4:4:4 基线编码", opts);
+    }
+
+    let mut encoded = Vec::new();
+    let encoder = JpegEncoder::new_with_quality(&mut encoded, opts.quality);
+    encoder.write_image(img.as_bytes(), img.width(), img.height(), img.color().into())
+        .map_err(|e| {
+            error!("❌ [Save] JPG 编码失败: {}", e);
+            AppError::Image(e)
+        })?;
+    Ok(encoded)
+}
+
+#[cfg(feature = "mozjpeg")]
+fn encode_mozjpeg(rgb: &image::RgbImage, opts: &JpegOptions) -> Result<Vec<u8>, String> {
+    // libjpeg 的错误通过 panic 展开抛出，必须 catch_unwind，否则会带崩整个 rayon 任务
+    std::panic::catch_unwind(|| -> std::io::Result<Vec<u8>> {
+        let mut comp = mozjpeg::Compress::new(mozjpeg::ColorSpace::JCS_RGB);
+        // mozjpeg 的默认配置自带渐进式 + 霍夫曼表优化；先退回 libjpeg 的基线配置，再按选项逐项开启
+        comp.set_fastest_defaults();
+        comp.set_size(rgb.width() as usize, rgb.height() as usize);
+        comp.set_quality(opts.quality as f32);
+        // 每个色度像素覆盖的亮度像素 (水平, 垂直)
+        let px = match opts.subsampling {
+            ChromaSubsampling::Yuv444 => (1, 1),
+            ChromaSubsampling::Yuv422 => (2, 1),
+            ChromaSubsampling::Yuv420 => (2, 2),
+        };
+        comp.set_chroma_sampling_pixel_sizes(px, px);
+        if opts.progressive {
+            comp.set_progressive_mode();
+        }
+        comp.set_optimize_coding(opts.optimize_coding);
+
+        let mut started = comp.start_compress(Vec::new())?;
+        started.write_scanlines(rgb.as_raw())?;
+        started.finish()
+    })
+    .map_err(|_| "libjpeg 内部错误".to_string())?
+    .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 渐变 + 细节纹理，接近真实照片的熵
+    fn photo() -> DynamicImage {
+        DynamicImage::ImageRgb8(image::RgbImage::from_fn(320, 240, |x, y| {
+            image::Rgb([(x * 255 / 319) as u8, (y * 255 / 239) as u8, ((x * 7 + y * 13) % 64 + 96) as u8])
+        }))
+    }
+
+    fn opts(progressive: bool, optimize_coding: bool) -> JpegOptions {
+        JpegOptions { quality: 90, subsampling: ChromaSubsampling::Yuv444, progressive, optimize_coding }
+    }
+
+    #[test]
+    fn every_option_combination_decodes() {
+        for (progressive, optimize) in [(false, false), (true, false), (false, true), (true, true)] {
+            let bytes = encode(&photo(), &opts(progressive, optimize)).unwrap();
+            let decoded = image::load_from_memory(&bytes).unwrap();
+            assert_eq!((decoded.width(), decoded.height()), (320, 240));
+        }
+    }
+
+    #[test]
+    fn default_options_need_no_fallback_note() {
+        assert_eq!(fallback_note(&opts(false, false)), None);
+        let note = fallback_note(&opts(true, true));
+        if cfg!(feature = "mozjpeg") {
+            assert_eq!(note, None);
+        } else {
+            let note = note.unwrap();
+            assert!(note.contains("渐进式") && note.contains("霍夫曼表优化"), "{}", note);
+        }
+    }
+
+    #[cfg(feature = "mozjpeg")]
+    #[test]
+    fn progressive_optimized_output_is_smaller() {
+        let baseline = encode(&photo(), &opts(false, false)).unwrap();
+        let tuned = encode(&photo(), &opts(true, true)).unwrap();
+        assert!(tuned.len() < baseline.len(), "{} >= {}", tuned.len(), baseline.len());
+        // SOF2 (渐进式帧头) 存在
+        assert!(tuned.windows(2).any(|w| w == [0xFF, 0xC2]));
+    }
+}
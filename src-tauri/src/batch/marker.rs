@@ -0,0 +1,219 @@
+// src-tauri/src/batch/marker.rs
+
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use once_cell::sync::Lazy;
+
+use crate::models::StyleOptions;
+
+// =========================================================
+// 🟢 成品标记：防止把自己的输出再加一次框
+// =========================================================
+// 输出目录选成原图目录再重跑时，上次的成品 (还带着透传的 EXIF) 会被当成原图再加一圈边框。
+// 写盘时在 JPG 里插一个 COM 段、PNG 里插一个 tEXt 块作为标记，读取时只看文件头，不解码像素。
+// WebP / TIFF / AVIF 不写标记，改按默认命名识别：文件名以 _{样式后缀} 结尾 (尺寸版本再接 _{版本名})。
+
+/// 标记内容 (JPG 的 COM 段原文；PNG 里是 tEXt 的值，关键字为 Software)
+const MARKER: &[u8] = b"Framed by nikon-framer";
+const PNG_TEXT_KEYWORD: &[u8] = b"Software";
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+// 不写标记、按文件名识别的格式
+const UNMARKED_EXTENSIONS: [&str; 4] = ["webp", "tif", "tiff", "avif"];
+// 所有样式的文件名后缀 (带前导下划线)
+static OUTPUT_SUFFIXES: Lazy<Vec<String>> = Lazy::new(|| {
+    StyleOptions::catalog().iter().map(|s| format!("_{}", s.filename_suffix())).collect()
+});
+
+// 标记总是写在文件头部 (JPG 的 APP 段附近、PNG 的 IHDR 之后)，读这么多足够
+const HEADER_READ_LIMIT: u64 = 256 * 1024;
+
+/// 在 JPG 的 SOI / JFIF APP0 之后插入 COM 标记段；结构异常时原样返回
+pub fn mark_jpeg(jpeg: &[u8]) -> Vec<u8> {
+    if jpeg.len() < 4 || jpeg[0] != 0xFF || jpeg[1] != 0xD8 {
+        return jpeg.to_vec();
+    }
+    let mut insert_at = 2;
+    if jpeg[2] == 0xFF && jpeg[3] == 0xE0 && jpeg.len() >= 6 {
+        let app0_len = u16::from_be_bytes([jpeg[4], jpeg[5]]) as usize;
+        insert_at = (4 + app0_len).min(jpeg.len());
+    }
+
+    let mut out = Vec::with_capacity(jpeg.len() + MARKER.len() + 4);
+    out.extend_from_slice(&jpeg[..insert_at]);
+    out.extend_from_slice(&[0xFF, 0xFE]);
+    out.extend_from_slice(&((MARKER.len() + 2) as u16).to_be_bytes());
+    out.extend_from_slice(MARKER);
+    out.extend_from_slice(&jpeg[insert_at..]);
+    out
+}
+
+/// 在 PNG 的 IHDR 之后插入 tEXt 标记块；结构异常时原样返回
+pub fn mark_png(png: &[u8]) -> Vec<u8> {
+    // 签名 8 字节 + IHDR (长度 4 + 类型 4 + 数据 13 + CRC 4)
+    let insert_at = PNG_SIGNATURE.len() + 25;
+    if png.len() < insert_at || !png.starts_with(PNG_SIGNATURE) || &png[12..16] != b"IHDR" {
+        return png.to_vec();
+    }
+
+    let mut body = Vec::with_capacity(4 + PNG_TEXT_KEYWORD.len() + 1 + MARKER.len());
+    body.extend_from_slice(b"tEXt");
+    body.extend_from_slice(PNG_TEXT_KEYWORD);
+    body.push(0);
+    body.extend_from_slice(MARKER);
+
+    let mut out = Vec::with_capacity(png.len() + body.len() + 8);
+    out.extend_from_slice(&png[..insert_at]);
+    out.extend_from_slice(&((body.len() - 4) as u32).to_be_bytes());
+    out.extend_from_slice(&body);
+    out.extend_from_slice(&crc32(&body).to_be_bytes());
+    out.extend_from_slice(&png[insert_at..]);
+    out
+}
+
+/// 文件是否是本程序的成品：JPG / PNG 看标记 (只读文件头)，其余格式看默认命名；读取失败按未标记处理
+pub fn is_framed_output(path: &str) -> bool {
+    let Ok(file) = File::open(path) else { return false };
+    let mut head = Vec::new();
+    if file.take(HEADER_READ_LIMIT).read_to_end(&mut head).is_err() {
+        return false;
+    }
+    if head.starts_with(&[0xFF, 0xD8]) {
+        jpeg_has_marker(&head)
+    } else if head.starts_with(PNG_SIGNATURE) {
+        png_has_marker(&head)
+    } else {
+        has_output_name(Path::new(path))
+    }
+}
+
+// WebP / TIFF / AVIF：默认命名的成品 ({stem}_{样式} 或 {stem}_{样式}_{版本名})
+// 自定义文件名模板写出的这几种格式识别不了，需要用户自己避开输出目录
+fn has_output_name(path: &Path) -> bool {
+    let ext = path.extension().and_then(|e| e.to_str()).map(|e| e.to_ascii_lowercase());
+    if !ext.is_some_and(|e| UNMARKED_EXTENSIONS.contains(&e.as_str())) {
+        return false;
+    }
+    let Some(stem) = path.file_stem().and_then(|s| s.to_str()) else { return false };
+    OUTPUT_SUFFIXES.iter().any(|suffix| {
+        stem.ends_with(suffix.as_str()) || stem.contains(&format!("{}_", suffix))
+    })
+}
+
+// 逐段遍历到 SOS (图像数据) 为止，找内容一致的 COM 段
+fn jpeg_has_marker(data: &[u8]) -> bool {
+    let mut pos = 2;
+    while pos + 4 <= data.len() {
+        if data[pos] != 0xFF {
+            return false;
+        }
+        let kind = data[pos + 1];
+        // SOS 之后是熵编码数据，标记不会在那里
+        if kind == 0xDA {
+            return false;
+        }
+        let len = u16::from_be_bytes([data[pos + 2], data[pos + 3]]) as usize;
+        if len < 2 {
+            return false;
+        }
+        if kind == 0xFE && data.get(pos + 4..pos + 2 + len) == Some(MARKER) {
+            return true;
+        }
+        pos += 2 + len;
+    }
+    false
+}
+
+// 逐块遍历到 IDAT 为止，找关键字和内容一致的 tEXt 块
+fn png_has_marker(data: &[u8]) -> bool {
+    let mut pos = PNG_SIGNATURE.len();
+    while pos + 8 <= data.len() {
+        let len = u32::from_be_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]]) as usize;
+        let kind = &data[pos + 4..pos + 8];
+        if kind == b"IDAT" {
+            return false;
+        }
+        if kind == b"tEXt" {
+            if let Some(text) = data.get(pos + 8..pos + 8 + len) {
+                if let Some(nul) = text.iter().position(|&b| b == 0) {
+                    if &text[..nul] == PNG_TEXT_KEYWORD && &text[nul + 1..] == MARKER {
+                        return true;
+                    }
+                }
+            }
+        }
+        pos += 12 + len;
+    }
+    false
+}
+
+// PNG 块校验 (CRC-32/ISO-HDLC)；只给几十字节的标记块用，逐位计算即可
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &b in bytes {
+        crc ^= b as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn temp_file(name: &str, bytes: &[u8]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("framer_marker_{}_{}", std::process::id(), name));
+        std::fs::write(&path, bytes).unwrap();
+        path
+    }
+
+    #[test]
+    fn marked_jpeg_and_png_are_detected() {
+        let jpeg = [0xFF, 0xD8, 0xFF, 0xDA, 0x00, 0x02, 0xFF, 0xD9];
+        let mut png = PNG_SIGNATURE.to_vec();
+        png.extend_from_slice(&13u32.to_be_bytes());
+        png.extend_from_slice(b"IHDR");
+        png.extend_from_slice(&[0; 17]);
+        png.extend_from_slice(&[0, 0, 0, 0]);
+        png.extend_from_slice(b"IDAT");
+
+        assert!(!jpeg_has_marker(&jpeg));
+        assert!(jpeg_has_marker(&mark_jpeg(&jpeg)));
+        assert!(!png_has_marker(&png));
+        assert!(png_has_marker(&mark_png(&png)));
+    }
+
+    #[test]
+    fn unmarked_formats_fall_back_to_output_name() {
+        // 内容不是 JPG / PNG，只能靠文件名判断
+        let riff = b"RIFF\0\0\0\0WEBPVP8 ";
+        let cases = [
+            ("DSC_0001_WhiteClassic.webp", true),
+            ("DSC_0001_WhiteClassic_web.webp", true),
+            ("DSC_0001_TransparentMaster.AVIF", true),
+            ("DSC_0001_WhiteModern.tif", true),
+            ("DSC_0001.webp", false),
+            ("DSC_0001_WhiteClassicX.webp", false),
+            // JPG / PNG 以外的扩展名才走命名规则
+            ("DSC_0001_WhiteClassic.heic", false),
+        ];
+        for (name, expected) in cases {
+            let path = temp_file(name, riff);
+            assert_eq!(is_framed_output(path.to_str().unwrap()), expected, "{}", name);
+            let _ = std::fs::remove_file(path);
+        }
+    }
+
+    #[test]
+    fn jpeg_named_like_output_still_needs_marker() {
+        // JPG / PNG 有标记可查，不按文件名误判 (用户自己的图可能恰好同名)
+        let path = temp_file("DSC_0002_WhiteClassic.jpg", &[0xFF, 0xD8, 0xFF, 0xD9]);
+        assert!(!is_framed_output(path.to_str().unwrap()));
+        let _ = std::fs::remove_file(path);
+    }
+}
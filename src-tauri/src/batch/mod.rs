@@ -1,3 +1,20 @@
+pub mod aspect;
+pub mod avif;
+pub mod concurrency;
+pub mod exif_copy;
+pub mod io_limits;
+pub mod jpeg;
+pub mod marker;
+pub mod ordered;
 pub mod pipeline;
+pub mod report;
+pub mod resume;
+pub mod sidecar;
+pub mod storage;
+pub mod summary;
+pub mod timings;
+pub mod watch;
+pub mod watchdog;
+pub mod webp;
 
-pub use pipeline::*;
\ No newline at end of file
+pub use pipeline::*;
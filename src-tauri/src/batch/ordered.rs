@@ -0,0 +1,80 @@
+// src/batch/ordered.rs
+// 🟢 有序进度模式：文件仍然并行处理，只是进度事件按提交顺序发出 (前端列表从上到下依次完成)
+// 工作线程把完成事件投进有界 channel，由单独的发射线程按原始序号重排后再 emit
+
+use std::collections::BTreeMap;
+use std::sync::mpsc::{self, SyncSender};
+use std::sync::Mutex;
+use std::thread::JoinHandle;
+
+use log::warn;
+use serde_json::Value;
+use tauri::{Emitter, Window};
+
+// channel 容量：发射线程只做重排和 emit，很少积压；满了工作线程短暂阻塞即可
+const CHANNEL_BOUND: usize = 64;
+
+pub struct OrderedEmitter {
+    tx: Mutex<Option<SyncSender<(usize, Value)>>>,
+    handle: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl OrderedEmitter {
+    /// indices：本批次要处理的原始序号 (升序)，发射线程按这个顺序放行
+    pub fn spawn(window: Window, indices: Vec<usize>) -> Self {
+        let (tx, rx) = mpsc::sync_channel::<(usize, Value)>(CHANNEL_BOUND);
+        let spawned = std::thread::Builder::new()
+            .name("framer-progress".to_string())
+            .spawn(move || {
+                let mut expected = indices.into_iter().peekable();
+                let mut pending = BTreeMap::new();
+                let mut emitted = 0usize;
+                let mut emit = |mut payload: Value| {
+                    // current 按发出顺序重新计数，否则进度条会跳着走
+                    emitted += 1;
+                    payload["current"] = emitted.into();
+                    let _ = window.emit("process-progress", payload);
+                };
+
+                for (index, payload) in rx {
+                    pending.insert(index, payload);
+                    while let Some(next) = expected.peek() {
+                        let Some(payload) = pending.remove(next) else { break };
+                        emit(payload);
+                        expected.next();
+                    }
+                }
+
+                // channel 关闭 (批次结束)：用户停止 / 超时放弃的文件不会上报，排在它们后面的事件按序号补发
+                for payload in pending.into_values() {
+                    emit(payload);
+                }
+            });
+
+        match spawned {
+            Ok(handle) => Self { tx: Mutex::new(Some(tx)), handle: Mutex::new(Some(handle)) },
+            Err(e) => {
+                // 线程创建失败：退回直接发送 (send 发现没有 channel 时返回 payload 由调用方 emit)
+                warn!("⚠️ [Progress] 无法创建有序进度线程，改为按完成顺序发送: {}", e);
+                Self { tx: Mutex::new(None), handle: Mutex::new(None) }
+            },
+        }
+    }
+
+    /// 投递一条完成事件；发射线程不可用或已结束时原样返回，由调用方直接 emit
+    pub fn send(&self, index: usize, payload: Value) -> Option<Value> {
+        let tx = self.tx.lock().unwrap().clone();
+        match tx {
+            Some(tx) => tx.send((index, payload)).err().map(|e| e.0 .1),
+            None => Some(payload),
+        }
+    }
+
+    /// 批次结束：关闭 channel 并等待剩余事件发完 (必须在批次总结之前调用)
+    pub fn finish(&self) {
+        self.tx.lock().unwrap().take();
+        if let Some(handle) = self.handle.lock().unwrap().take() {
+            let _ = handle.join();
+        }
+    }
+}
@@ -1,72 +1,232 @@
 use std::borrow::Cow;
-use std::fs::File;
-use std::io::BufWriter;
-use std::path::PathBuf;
-use std::sync::{Arc, atomic::{AtomicUsize, Ordering}};
-use std::time::Instant;
+use std::io::Cursor;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Mutex, atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering}};
+use std::time::{Duration, Instant};
 
 use image::codecs::jpeg::JpegEncoder;
-use image::codecs::png::PngEncoder;
-use image::{ImageEncoder, DynamicImage};
-use log::{info, error, debug}; // 🟢 引入标准日志宏
-use tauri::{Window, State, Emitter};
+use image::codecs::png::{CompressionType, FilterType as PngFilter, PngEncoder};
+use image::codecs::tiff::TiffEncoder;
+use image::imageops::FilterType;
+use image::{ColorType, ImageEncoder, DynamicImage, GenericImageView};
+use log::{info, error, debug, warn}; // 🟢 引入标准日志宏
+use tauri::{Window, State, Emitter, Manager};
 use rayon::prelude::*;
 use serde_json::json;
+use base64::{Engine as _, engine::general_purpose};
 
 // 🟢 引入错误定义
 use crate::error::AppError; 
 
 use crate::commands::{get_exif_data, has_exif};
-use crate::models::{ExportConfig, ExportImageFormat, StyleOptions};
-use crate::utils::calculate_target_path_core;
+use crate::models::{ExportConfig, ExportImageFormat, FrameLabels, PngCompression, SignatureConfig, StyleOptions, TextOverrides};
+use crate::utils::{calculate_original_copy_path, calculate_target_path_core, calculate_variant_path, variant_label, NamingContext, OutputNames, PlannedOutput};
 use crate::AppState;
+use crate::cancel::{self, CancelToken};
 use crate::parser::{models::ParsedImageContext};
-use crate::processor::traits::FrameProcessor;
-use crate::graphics::load_image_auto_rotate; 
+use crate::processor::traits::{BackgroundKind, FrameProcessor};
+use crate::graphics::decode_cache::{self, load_image_cached};
+use crate::graphics::color::{self, SourceProfile};
+use crate::graphics::integrity;
+use super::aspect;
+use super::avif;
+use super::concurrency;
+use super::exif_copy;
+use super::io_limits::{self, IoSlots};
+use super::jpeg;
+use super::marker;
+use super::ordered::OrderedEmitter;
+use super::report::{self, FileReport};
+use super::resume::{self, ResumeJournal};
+use super::sidecar::{self, SidecarRecord};
+use super::storage::{self, StorageBreaker};
+use super::summary::BatchTally;
+use super::timings::{StepTimings, TimingPhase, TimingStats};
+use super::watchdog::Watchdog;
+use super::webp;
 
 // =========================================================
 // 1. 上下文定义 (Context)
 // =========================================================
 
 pub struct GlobalContext {
-    pub window: Window,
+    // 进度事件发往的窗口 (测试里没有窗口，为 None 时不发事件)
+    pub window: Option<Window>,
     pub app_state: Arc<AppState>,
     pub options: StyleOptions,
+    // 叠加签名 (只用于写 sidecar，绘制已经组合进处理器)
+    pub signature: Option<SignatureConfig>,
     pub total_files: usize,
     pub completed_count: Arc<AtomicUsize>,
     pub export: ExportConfig,
+    pub deterministic: bool,
+    // 输出文件写入槽位 (限制同时打开的输出句柄数量)
+    pub output_slots: IoSlots,
+    // 存储熔断器 (磁盘满 / 只读时中止批次)
+    pub storage: StorageBreaker,
+    // 本批次已分配的输出路径 (文件名模板可能让不同原图算出同一个名字)
+    pub claimed_outputs: Mutex<OutputNames>,
+    // 正式批次在并行阶段之前按提交顺序分配好的输出路径 (原始序号 -> 路径)
+    // 预检和监听模式为空，处理到该文件时再分配
+    pub planned_outputs: HashMap<usize, PlannedOutput>,
+    // 断点续传清单 (app 数据目录不可用时为 None，不影响处理)
+    pub resume: Option<ResumeJournal>,
+    // 每个文件的处理结果 (write_report 开启时收集，批次结束写入报告)
+    pub reports: Mutex<Vec<FileReport>>,
+    // 单文件超时 (None 代表不限制，直接在调度线程上处理)
+    pub file_timeout: Option<Duration>,
+    // 源文件大小上限 (字节)
+    pub max_file_size: Option<u64>,
+    // 进度事件里附带成品缩略图 (前端胶片条)
+    pub emit_thumbnails: bool,
+    // 手动填写的边框文字 (解析 EXIF 后、绘制前覆盖)
+    pub overrides: Option<TextOverrides>,
+    // 边框固定文字 (只用于写 sidecar，绘制已经组合进处理器)
+    pub labels: Option<FrameLabels>,
+    // 胶片扫描模式：无 EXIF 不跳过
+    pub allow_missing_exif: bool,
+    // 带成品标记的文件也照常处理
+    pub reframe_outputs: bool,
+    // 成功文件的分步耗时汇总 (批次总结里输出最小 / 平均 / 最大值)
+    pub timing_stats: Mutex<TimingStats>,
+    // 🟢 成功 / 跳过 (按原因) / 失败计数，批次结束时随 process-summary 事件发出
+    pub tally: Mutex<BatchTally>,
+    // 🟢 有序进度模式：完成事件按提交顺序发出 (None 代表按完成顺序直接发送)
+    pub ordered_progress: Option<OrderedEmitter>,
 }
 
 impl GlobalContext {
     // 🔴 变更：返回值从 Result<PathBuf, String> 改为 Result<PathBuf, AppError>
-    pub fn calculate_target_path(&self, original_file_path: &str) -> Result<PathBuf, AppError> {
+    pub fn calculate_target_path(&self, original_file_path: &str, naming: NamingContext) -> Result<PathBuf, AppError> {
         // 调用 core 逻辑，并将返回的 String 错误包装进 AppError::PathCalculation
         calculate_target_path_core(
             original_file_path, 
             &self.export, 
-            &self.options
+            &self.options,
+            naming,
         ).map_err(|e| AppError::PathCalculation(e))
     }
+
+    /// 文件的输出路径 (主输出 + 尺寸版本，批内重名已追加 _1、_2 …)
+    /// 正式批次直接取预先分配的结果；预检和监听模式第一次用到时登记，结果存进 task，同一文件不会登记两次
+    fn output_plan(&self, task: &mut TaskContext) -> Result<PlannedOutput, AppError> {
+        if let Some(plan) = &task.plan {
+            return Ok(plan.clone());
+        }
+        let plan = match self.planned_outputs.get(&task.index) {
+            Some(plan) => plan.clone(),
+            None => {
+                let naming = NamingContext { exif: task.parsed_ctx.as_ref(), counter: Some(task.index + 1) };
+                let target = self.calculate_target_path(&task.file_path, naming)?;
+                self.claimed_outputs.lock().unwrap().plan(target, &self.export.variants)
+            },
+        };
+        if plan.main != plan.target {
+            debug!("  -> 输出重名，改为: {:?}", plan.main);
+        }
+        task.plan = Some(plan.clone());
+        Ok(plan)
+    }
+
+    /// 向前端发送事件 (没有窗口时忽略，发送失败也不影响处理)
+    fn emit<S: serde::Serialize + Clone>(&self, event: &str, payload: S) {
+        if let Some(window) = &self.window {
+            let _ = window.emit(event, payload);
+        }
+    }
+}
+
+#[cfg(test)]
+impl GlobalContext {
+    /// 测试用：不挂窗口 (不发事件)、不做断点续传，其余参数与正式批次一致
+    pub(crate) fn for_test(context: &crate::models::BatchContext, total_files: usize) -> Self {
+        GlobalContext {
+            window: None,
+            app_state: Arc::new(AppState::new()),
+            options: context.options.clone(),
+            signature: context.signature.clone(),
+            total_files,
+            completed_count: Arc::new(AtomicUsize::new(0)),
+            export: context.export.clone(),
+            deterministic: context.deterministic,
+            output_slots: IoSlots::new(io_limits::output_slot_count(io_limits::open_file_limit())),
+            storage: StorageBreaker::default(),
+            claimed_outputs: Mutex::new(OutputNames::default()),
+            planned_outputs: HashMap::new(),
+            resume: None,
+            reports: Mutex::new(Vec::new()),
+            file_timeout: (context.file_timeout_secs > 0).then(|| Duration::from_secs(context.file_timeout_secs)),
+            max_file_size: context.max_file_size_mb.map(|mb| mb.saturating_mul(1_048_576)),
+            emit_thumbnails: false,
+            overrides: context.overrides.clone(),
+            labels: context.labels.clone(),
+            allow_missing_exif: context.allow_missing_exif,
+            reframe_outputs: context.reframe_outputs,
+            timing_stats: Mutex::new(TimingStats::default()),
+            tally: Mutex::new(BatchTally::default()),
+            ordered_progress: None,
+        }
+    }
 }
 
 pub struct TaskContext {
     pub file_path: String,
+    pub index: usize, // 在批次中的序号 (从 0 开始)
     pub image: Option<DynamicImage>,
     pub parsed_ctx: Option<ParsedImageContext>,
     pub final_image: Option<DynamicImage>,
     pub output_path: Option<PathBuf>,
+    // 分配到的输出路径 (output_plan 第一次调用时填入)
+    pub plan: Option<PlannedOutput>,
+    // 尺寸版本的输出 (与 export.variants 顺序一致)，随进度事件发给前端
+    pub variant_outputs: Vec<VariantOutput>,
+    pub original_copy_path: Option<PathBuf>,
+    pub started_at: Instant,
+    // 成功但需要提醒用户的情况 (如 16 位降为 8 位)，随进度事件发给前端
+    pub warnings: Vec<String>,
+    // 原图内嵌的 ICC 配置文件 (没有时按 sRGB 处理)，名称随进度事件发给前端
+    pub source_profile: Option<SourceProfile>,
+    // 成品缩略图 (data URL)，只在 emit_thumbnails 开启且成功时生成
+    pub thumbnail: Option<String>,
+    // 看门狗放弃了这个任务 (超时或用户停止)：后续步骤不再执行，结果也不再写盘
+    pub abandoned: Arc<AtomicBool>,
+    // 🟢 分步耗时 (加载 / 解析 / 绘制 / 编码)，成功时随进度事件发给前端
+    pub timings: StepTimings,
+}
+
+/// 一个尺寸版本的输出 (name 为写进文件名的版本名)
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct VariantOutput {
+    pub name: String,
+    pub path: PathBuf,
 }
 
 impl TaskContext {
-    pub fn new(file_path: String) -> Self {
+    pub fn new(file_path: String, index: usize) -> Self {
         Self {
             file_path,
+            index,
             image: None,
             parsed_ctx: None,
             final_image: None,
             output_path: None,
+            plan: None,
+            variant_outputs: Vec::new(),
+            original_copy_path: None,
+            started_at: Instant::now(),
+            warnings: Vec::new(),
+            source_profile: None,
+            thumbnail: None,
+            abandoned: Arc::new(AtomicBool::new(false)),
+            timings: StepTimings::default(),
         }
     }
+
+    pub fn is_abandoned(&self) -> bool {
+        self.abandoned.load(Ordering::Relaxed)
+    }
 }
 
 // =========================================================
@@ -75,13 +235,72 @@ impl TaskContext {
 
 pub enum StepResult {
     Continue,
-    Skip(String),
+    Skip(SkipReason),
     Stop,
 }
 
+/// 跳过原因：code 是稳定的机器标识 (前端按它区分)，message 是展示用的固定文字
+/// (批次总结按文字归类，文件大小这类因文件而异的细节只写进日志)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SkipReason {
+    FileTooLarge { size_mb: u64 },
+    AlreadyFramed,
+    NoExif,
+    OutputExists,
+    Stopped,
+}
+
+impl SkipReason {
+    pub fn code(&self) -> &'static str {
+        match self {
+            SkipReason::FileTooLarge { .. } => "file_too_large",
+            SkipReason::AlreadyFramed => "already_framed",
+            SkipReason::NoExif => "no_exif",
+            SkipReason::OutputExists => "output_exists",
+            SkipReason::Stopped => "stopped",
+        }
+    }
+
+    pub fn message(&self) -> &'static str {
+        match self {
+            SkipReason::FileTooLarge { .. } => "文件过大",
+            SkipReason::AlreadyFramed => "已是加框成品",
+            SkipReason::NoExif => "无 EXIF 数据",
+            SkipReason::OutputExists => "输出已存在",
+            SkipReason::Stopped => "已停止",
+        }
+    }
+}
+
+// 和 AppError 一样序列化成 { code, message }，前端用 message.code 判断
+impl serde::Serialize for SkipReason {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeStruct;
+        let mut state = serializer.serialize_struct("SkipReason", 2)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", self.message())?;
+        state.end()
+    }
+}
+
+/// 单个文件跑完所有步骤后的结果 (用于统一上报)
+enum TaskOutcome {
+    Done,
+    Skipped(SkipReason),
+    Failed(AppError),
+}
+
 pub trait PipelineStep: Send + Sync {
     // 🔴 变更：错误类型改为 AppError
     fn execute(&self, global: &GlobalContext, task: &mut TaskContext) -> Result<StepResult, AppError>;
+
+    /// 🟢 耗时计入哪一段 (None 代表不计，如各种检查)
+    fn timing_phase(&self) -> Option<TimingPhase> {
+        None
+    }
 }
 
 
@@ -89,6 +308,16 @@ pub trait PipelineStep: Send + Sync {
 // 3. 具体步骤实现
 // =========================================================
 
+/// 读取并解析 EXIF，再套上手动填写的文字覆盖 (在处理器之前，所有样式一致)
+/// 命名 (CheckOverwriteStep / SaveImageStep) 和绘制用的是同一份结果
+fn parse_exif(global: &GlobalContext, file_path: &str) -> Result<ParsedImageContext, AppError> {
+    let mut ctx = crate::parser::parse(get_exif_data(file_path)?);
+    if let Some(overrides) = &global.overrides {
+        crate::parser::apply_overrides(&mut ctx, overrides);
+    }
+    Ok(ctx)
+}
+
 /// 步骤 1: 检查是否收到停止信号
 struct CheckStopStep;
 impl PipelineStep for CheckStopStep {
@@ -98,18 +327,96 @@ impl PipelineStep for CheckStopStep {
             info!("🛑 [Pipeline] 用户停止处理");
             return Ok(StepResult::Stop);
         }
+        // 存储熔断后不再调度新文件
+        if global.storage.is_aborted() {
+            global.storage.record_not_attempted();
+            return Ok(StepResult::Stop);
+        }
         Ok(StepResult::Continue)
     }
 }
 
+/// 步骤 1.5: 源文件大小检查 (空文件 / max_file_size_mb)
+/// 解码超大文件可能耗时几分钟且无法中断，在读取任何内容之前就跳过
+/// 🟢 空文件 (读卡中断) 记为不完整，不能被后面的 EXIF 检查当成 "无 EXIF" 跳过
+struct CheckFileSizeStep;
+impl PipelineStep for CheckFileSizeStep {
+    fn execute(&self, global: &GlobalContext, task: &mut TaskContext) -> Result<StepResult, AppError> {
+        match file_size_skip_reason(&task.file_path, global.max_file_size)? {
+            Some(reason) => {
+                if let SkipReason::FileTooLarge { size_mb } = reason {
+                    debug!("⚠️ [Check] 文件过大 ({} MB)，跳过: {}", size_mb, task.file_path);
+                }
+                Ok(StepResult::Skip(reason))
+            },
+            None => Ok(StepResult::Continue),
+        }
+    }
+}
+
+/// 空文件报不完整 (错误)，超过上限返回跳过原因 (limit 为字节数，None 代表不限制)
+fn file_size_skip_reason(path: &str, limit: Option<u64>) -> Result<Option<SkipReason>, AppError> {
+    integrity::check_not_empty(path)?;
+    let Some(limit) = limit else {
+        return Ok(None);
+    };
+    let size = std::fs::metadata(path)?.len();
+    Ok((size > limit).then_some(SkipReason::FileTooLarge { size_mb: size / 1_048_576 }))
+}
+
+/// 步骤 1.8: 成品标记检查 (reframe_outputs 关闭时)
+/// 只读文件头，在 EXIF 检查之前跳过上次输出的成品 (它们带着透传的 EXIF，EXIF 检查拦不住)
+struct CheckAlreadyFramedStep;
+impl PipelineStep for CheckAlreadyFramedStep {
+    fn execute(&self, global: &GlobalContext, task: &mut TaskContext) -> Result<StepResult, AppError> {
+        if global.reframe_outputs || !marker::is_framed_output(&task.file_path) {
+            return Ok(StepResult::Continue);
+        }
+        debug!("⏭️ [Check] 已是加框成品，跳过: {}", task.file_path);
+        Ok(StepResult::Skip(SkipReason::AlreadyFramed))
+    }
+}
+
 /// 步骤 2: 检查 EXIF 是否存在
 struct CheckExifStep;
 impl PipelineStep for CheckExifStep {
-    fn execute(&self, _global: &GlobalContext, task: &mut TaskContext) -> Result<StepResult, AppError> {
-        if !has_exif(&task.file_path) {
+    fn execute(&self, global: &GlobalContext, task: &mut TaskContext) -> Result<StepResult, AppError> {
+        // 胶片扫描模式：get_exif_data 会返回空数据，解析结果为品牌 Other、参数全空，由处理器按空值排版
+        if global.allow_missing_exif {
+            return Ok(StepResult::Continue);
+        }
+        // 扫描文件夹时已经检查过的直接用扫描结果
+        let present = global.app_state.known_exif_presence(&task.file_path)
+            .unwrap_or_else(|| has_exif(&task.file_path));
+        if !present {
             // 跳过不是错误，不需要 error!，warn 或 debug 即可
             debug!("⚠️ [Check] 无 EXIF 跳过: {}", task.file_path);
-            return Ok(StepResult::Skip("无 EXIF 数据".to_string()));
+            return Ok(StepResult::Skip(SkipReason::NoExif));
+        }
+        Ok(StepResult::Continue)
+    }
+}
+
+/// 步骤 2.5: 成品已存在则跳过 (skip_existing)
+/// 放在加载图片之前，避免为已处理过的文件做任何解码
+struct CheckOverwriteStep;
+impl PipelineStep for CheckOverwriteStep {
+    fn execute(&self, global: &GlobalContext, task: &mut TaskContext) -> Result<StepResult, AppError> {
+        // 可编辑模式 (签名) 参数可能变了，同名也要重新生成
+        if !global.export.skip_existing || global.options.is_editable() {
+            return Ok(StepResult::Continue);
+        }
+
+        // 文件名模板引用了 EXIF 时需要先解析，结果留给后续步骤复用
+        if global.export.template_needs_exif() && task.parsed_ctx.is_none() {
+            task.parsed_ctx = Some(parse_exif(global, &task.file_path)?);
+        }
+
+        // 按分配到的路径检查 (含批内重名的 _1、_2)；有尺寸版本时要全部都在才算处理过
+        let plan = global.output_plan(task)?;
+        if plan.all_exist() {
+            debug!("⏭️ [Check] 成品已存在，跳过: {:?}", plan.main);
+            return Ok(StepResult::Skip(SkipReason::OutputExists));
         }
         Ok(StepResult::Continue)
     }
@@ -118,13 +425,36 @@ impl PipelineStep for CheckExifStep {
 /// 步骤 3: 加载图片
 struct LoadImageStep;
 impl PipelineStep for LoadImageStep {
-    fn execute(&self, _global: &GlobalContext, task: &mut TaskContext) -> Result<StepResult, AppError> {
-        // 🟢 load_image_auto_rotate 现在返回 AppError，直接 ? 传播
+    fn execute(&self, global: &GlobalContext, task: &mut TaskContext) -> Result<StepResult, AppError> {
+        // 🟢 load_image_with_icc 现在返回 AppError，直接 ? 传播
         // 如果出错，AppError 会携带 context 信息
-        let img = load_image_auto_rotate(&task.file_path)?;
+        // 预览过的照片直接复用解码结果；批处理不写缓存 (每张只处理一次)
+        let source = load_image_cached(&global.app_state.decoded_images, &task.file_path, false)?;
+        // 未命中时没有其他引用，直接取出；命中时缓存还要留着，复制一份 (比重新解码快得多)
+        let mut img = Arc::try_unwrap(source.image).unwrap_or_else(|shared| (*shared).clone());
+        let icc = source.icc;
+        let profile = icc.and_then(SourceProfile::parse);
+
+        // 色彩管理：非 sRGB 原图先转换到 sRGB，边框和文字都按 sRGB 绘制
+        // 转换失败不影响出图，只是颜色和以前一样偏灰
+        if let Some(profile) = profile.as_ref().filter(|p| global.export.color_management && p.needs_conversion()) {
+            match profile.to_srgb(&img) {
+                Ok(converted) => img = converted,
+                Err(e) => {
+                    warn!("⚠️ [Load] {} [{}]", e, task.file_path);
+                    task.warnings.push(format!("{}，已按原始像素处理", e));
+                },
+            }
+        }
+        task.source_profile = profile;
+        task.timings.set_megapixels(img.width(), img.height());
         task.image = Some(img);
         Ok(StepResult::Continue)
     }
+
+    fn timing_phase(&self) -> Option<TimingPhase> {
+        Some(TimingPhase::Load)
+    }
 }
 
 /// 步骤 4: 核心处理
@@ -132,107 +462,527 @@ struct ProcessFrameStep {
     processor: Arc<Box<dyn FrameProcessor + Send + Sync>>,
 }
 impl PipelineStep for ProcessFrameStep {
-    fn execute(&self, _global: &GlobalContext, task: &mut TaskContext) -> Result<StepResult, AppError> {
+    fn execute(&self, global: &GlobalContext, task: &mut TaskContext) -> Result<StepResult, AppError> {
         let img = task.image.as_ref().ok_or_else(|| {
              AppError::System("逻辑错误: 步骤4执行时图片未加载".to_string())
         })?;
         
         // A. 解析数据 (get_exif_data 现在返回 Result<RawExifData, AppError>)
         // 如果这里出错（比如 IO 错误），直接传播中断
-        let raw_exif = get_exif_data(&task.file_path)?;
-        let parsed_ctx = crate::parser::parse(raw_exif);
+        let parsed_ctx = match task.parsed_ctx.take() {
+            Some(ctx) => ctx, // ParseExifStep / CheckOverwriteStep 已经解析过
+            None => parse_exif(global, &task.file_path)?,
+        };
         
         // B. 绘制合成
-        // processor.process 目前可能还返回 String 错误，我们需要包装一下
+        // 处理器错误原样传播 (字体缺失是 FONT_ERROR，取消由执行器静默结束)
         let final_img = self.processor.process(img, &parsed_ctx)
-            .map_err(|e| {
+            .inspect_err(|e| if !matches!(e, AppError::Cancelled) {
                 error!("❌ [Process] 绘图算法失败 [{}]: {}", task.file_path, e);
-                AppError::Image(image::ImageError::IoError(std::io::Error::new(std::io::ErrorKind::Other, e)))
-                // 或者用 AppError::System(format!("绘图失败: {}", e))
             })?;
-            
+
         task.parsed_ctx = Some(parsed_ctx);
         task.final_image = Some(final_img);
         Ok(StepResult::Continue)
     }
+
+    fn timing_phase(&self) -> Option<TimingPhase> {
+        Some(TimingPhase::Render)
+    }
+}
+
+/// 步骤 4.2: 补边到目标宽高比 (target_aspect)
+/// 在缩放之前执行，长边上限作用于补边后的画布
+struct PadToAspectStep {
+    background: BackgroundKind,
+}
+impl PipelineStep for PadToAspectStep {
+    fn execute(&self, global: &GlobalContext, task: &mut TaskContext) -> Result<StepResult, AppError> {
+        let Some(ratio) = global.export.target_aspect else {
+            return Ok(StepResult::Continue);
+        };
+        let final_img = task.final_image.as_ref().ok_or_else(|| {
+            AppError::System("逻辑错误: 补边时最终图未生成".to_string())
+        })?;
+        // 模糊背景从原图生成 (与处理器一致)；原图已释放时退回用成品
+        let source = task.image.as_ref().unwrap_or(final_img);
+
+        let t_pad = Instant::now();
+        if let Some(padded) = aspect::pad_to_aspect(final_img, source, ratio, self.background)? {
+            debug!("  -> [Aspect] 补边完成 ({:.2?})", t_pad.elapsed());
+            task.final_image = Some(padded);
+        }
+        Ok(StepResult::Continue)
+    }
+
+    fn timing_phase(&self) -> Option<TimingPhase> {
+        Some(TimingPhase::Render)
+    }
+}
+
+/// 步骤 4.5: 限制输出尺寸 (max_long_edge)
+/// 在合成之后缩放，边框和文字与照片一起等比缩小，版式比例不变
+struct ResizeOutputStep;
+impl PipelineStep for ResizeOutputStep {
+    fn execute(&self, global: &GlobalContext, task: &mut TaskContext) -> Result<StepResult, AppError> {
+        let Some(max_edge) = global.export.max_long_edge.filter(|&e| e > 0) else {
+            return Ok(StepResult::Continue);
+        };
+        let final_img = task.final_image.as_ref().ok_or_else(|| {
+            AppError::System("逻辑错误: 缩放时最终图未生成".to_string())
+        })?;
+
+        let (w, h) = final_img.dimensions();
+        if w.max(h) <= max_edge {
+            return Ok(StepResult::Continue);
+        }
+
+        // resize 会在 max_edge x max_edge 内保持宽高比
+        let t_resize = Instant::now();
+        let resized = final_img.resize(max_edge, max_edge, FilterType::Lanczos3);
+        debug!("  -> [Resize] {}x{} -> {}x{} ({:.2?})", w, h, resized.width(), resized.height(), t_resize.elapsed());
+
+        task.final_image = Some(resized);
+        Ok(StepResult::Continue)
+    }
+
+    fn timing_phase(&self) -> Option<TimingPhase> {
+        Some(TimingPhase::Render)
+    }
 }
 
 /// 步骤 5: 保存文件 (Pro版 & OCP & Structured Error)
 struct SaveImageStep;
 impl PipelineStep for SaveImageStep {
     fn execute(&self, global: &GlobalContext, task: &mut TaskContext) -> Result<StepResult, AppError> {
+        // 存储熔断后，已在计算中的文件也不再写盘
+        if global.storage.is_aborted() {
+            global.storage.record_not_attempted();
+            return Ok(StepResult::Stop);
+        }
+
+        // 1. 输出路径 (正式批次在并行阶段之前已经分配好)
+        let plan = global.output_plan(task)?;
+        let output_path = plan.main;
         let final_img = task.final_image.as_ref()
             .ok_or_else(|| AppError::System("逻辑错误: 最终图未生成".to_string()))?;
 
-        // 1. 路径计算 (已封装在 GlobalContext，返回 AppError)
-        let output_path = global.calculate_target_path(&task.file_path)?;
-
         debug!("💾 [Save] 准备写入: {:?}", output_path);
 
-        // 2. 自动创建父目录
-        if let Some(parent) = output_path.parent() {
-            if !parent.exists() {
-                std::fs::create_dir_all(parent).map_err(|e| {
-                    error!("❌ [Save] 创建目录失败 {:?}: {}", parent, e);
-                    AppError::Io(e) // 🟢 保持原始 IO 错误类型
-                })?;
+        // 2. 位深检查
+        // 大部分处理器在 8 位 RGBA 画布上合成 (没有 16 位合成路径)，16 位原图在这一步之前就已经降为 8 位；
+        // 只有直接在原图上绘制的样式 (TransparentMaster / Signature) 保留原位深。
+        // 输出格式能存 16 位 (PNG/TIFF) 时用户多半期望保留高位深，不能静默降级
+        let src_bits = task.image.as_ref().map_or(8, |img| bits_per_channel(img.color()));
+        if src_bits > 8 && bits_per_channel(final_img.color()) <= 8 && global.export.format.supports_16bit() {
+            let msg = format!("原图为 {} 位，边框合成仅支持 8 位，输出已降为 8 位", src_bits);
+            warn!("⚠️ [Save] {} [{}]", msg, task.file_path);
+            task.warnings.push(msg);
+        }
+
+        // 3. 编码 + 写盘
+        let encoded = encode_output(global, task, final_img, &global.export.format, global.export.quality)?;
+        // 编码可能很慢，期间看门狗已经放弃了这个任务 (超时已报错)，不能再写出成品
+        if task.is_abandoned() {
+            return Ok(StepResult::Stop);
+        }
+        write_output(global, &output_path, &encoded)?;
+
+        // 4. 尺寸版本：从已绘制好的成品逐个缩小，不再重新解码和绘制
+        let mut variant_outputs = Vec::with_capacity(global.export.variants.len());
+        for ((i, variant), path) in global.export.variants.iter().enumerate().zip(plan.variants) {
+            let scaled: Cow<DynamicImage> = match variant.max_long_edge.filter(|&e| e > 0) {
+                Some(edge) if final_img.width().max(final_img.height()) > edge => {
+                    cancel::check()?;
+                    Cow::Owned(final_img.resize(edge, edge, FilterType::Lanczos3))
+                },
+                _ => Cow::Borrowed(final_img),
+            };
+            debug!("💾 [Save] 尺寸版本 {}x{}: {:?}", scaled.width(), scaled.height(), path);
+
+            let encoded = encode_output(global, task, &scaled, &variant.format, variant.quality)?;
+            if task.is_abandoned() {
+                return Ok(StepResult::Stop);
             }
+            write_output(global, &path, &encoded)?;
+            variant_outputs.push(VariantOutput { name: variant_label(variant, i), path });
         }
 
-        // 3. 智能图像转换 (处理 JPG 不支持 Alpha 的问题)
-        let img_to_save: Cow<DynamicImage> = if !global.export.format.supports_alpha() && final_img.color().has_alpha() {
-            debug!("  -> 格式不支持透明度，正在转换为 RGB8..."); 
-            Cow::Owned(DynamicImage::ImageRgb8(final_img.to_rgb8()))
+        // 5. 可选：写入 sidecar (失败只警告，图片本身已经保存成功)
+        if global.export.write_sidecar {
+            let record = SidecarRecord {
+                app_version: env!("CARGO_PKG_VERSION").to_string(),
+                style: global.options.clone(),
+                export: global.export.clone(),
+                source_path: task.file_path.clone(),
+                source_hash: sidecar::hash_file(&task.file_path).unwrap_or_default(),
+                exif: task.parsed_ctx.as_ref().map(Into::into),
+                processing_ms: task.started_at.elapsed().as_millis(),
+                deterministic: global.deterministic,
+                signature: global.signature.clone(),
+                overrides: global.overrides.clone(),
+                labels: global.labels.clone(),
+            };
+            if let Err(e) = sidecar::write_sidecar(&output_path, &record) {
+                warn!("⚠️ [Save] Sidecar 写入失败 [{}]: {}", task.file_path, e);
+            }
+        }
+
+        task.output_path = Some(output_path);
+        task.variant_outputs = variant_outputs;
+        
+        // 成功日志 (info 级别，证明这张图搞定了)
+        info!("✅ [Save] 已保存: {:?}", task.file_path);
+        Ok(StepResult::Continue)
+    }
+
+    fn timing_phase(&self) -> Option<TimingPhase> {
+        Some(TimingPhase::Encode)
+    }
+}
+
+/// 按指定格式和质量编码到内存 (主输出和各尺寸版本共用)
+/// 输出文件只在真正写盘时才打开，避免编码期间 (可能很慢) 长时间占用句柄
+fn encode_output(
+    global: &GlobalContext,
+    task: &TaskContext,
+    final_img: &DynamicImage,
+    format: &ExportImageFormat,
+    quality: u8,
+) -> Result<Vec<u8>, AppError> {
+    // 1. 智能图像转换 (处理 JPG 不支持 Alpha 的问题)
+    let img_to_save: Cow<DynamicImage> = if !format.supports_alpha() && final_img.color().has_alpha() {
+        debug!("  -> 格式不支持透明度，正在转换为 RGB8..."); 
+        Cow::Owned(DynamicImage::ImageRgb8(final_img.to_rgb8()))
+    } else if !format.supports_16bit()
+        && !matches!(final_img, DynamicImage::ImageRgb8(_) | DynamicImage::ImageRgba8(_))
+    {
+        // JPG/WebP/AVIF 编码器只接受 8 位 RGB/RGBA，其他像素格式统一转换 (保留 Alpha)
+        debug!("  -> 输出格式仅支持 8 位，正在转换像素格式...");
+        if final_img.color().has_alpha() {
+            Cow::Owned(DynamicImage::ImageRgba8(final_img.to_rgba8()))
         } else {
-            Cow::Borrowed(final_img)
+            Cow::Owned(DynamicImage::ImageRgb8(final_img.to_rgb8()))
+        }
+    } else if matches!(format, ExportImageFormat::Tiff)
+        && matches!(final_img, DynamicImage::ImageLumaA8(_) | DynamicImage::ImageLumaA16(_))
+    {
+        // TIFF 编码器不支持灰度 + Alpha，按原位深转为 RGBA
+        debug!("  -> TIFF 不支持 LumaA，正在转换为 RGBA...");
+        match final_img {
+            DynamicImage::ImageLumaA16(_) => Cow::Owned(DynamicImage::ImageRgba16(final_img.to_rgba16())),
+            _ => Cow::Owned(DynamicImage::ImageRgba8(final_img.to_rgba8())),
+        }
+    } else {
+        Cow::Borrowed(final_img)
+    };
+
+    // 2. 编码到内存
+    // 编码很慢 (AVIF 尤甚)，开始前再确认一次没有被取消
+    cancel::check()?;
+    let t_encode = Instant::now();
+    // 🟢 map_err 模式：先记录日志，再抛出 AppError
+    let mut encoded = Vec::new();
+    let width = img_to_save.width();
+    let height = img_to_save.height();
+    let color_type = img_to_save.color().into();
+    // 关闭色彩管理时像素仍是原色彩空间，把原配置文件带上 (目前仅 JPG / PNG)
+    let passthrough_icc = task.source_profile.as_ref()
+        .filter(|_| !global.export.color_management)
+        .map(SourceProfile::icc);
+
+    match format {
+        ExportImageFormat::Png => {
+            let (compression, filter) = png_settings(global.export.png_compression);
+            let mut encoder = PngEncoder::new_with_quality(&mut encoded, compression, filter);
+            if let Some(icc) = passthrough_icc {
+                // 编码器不支持时只是少了配置文件
+                let _ = encoder.set_icc_profile(icc.to_vec());
+            }
+            encoder.write_image(img_to_save.as_bytes(), width, height, color_type)
+                .map_err(|e| {
+                    error!("❌ [Save] PNG 编码失败: {}", e);
+                    AppError::Image(e) // 自动转换 ImageError
+                })?;
+            // 成品标记 (下次被当成原图时跳过)
+            encoded = marker::mark_png(&encoded);
+        },
+        ExportImageFormat::Jpg => {
+            let options = jpeg::JpegOptions { quality, ..jpeg::JpegOptions::from_export(&global.export) };
+            encoded = jpeg::encode(&img_to_save, &options)?;
+            // 成品标记 (下次被当成原图时跳过)
+            encoded = marker::mark_jpeg(&encoded);
+
+            // 先放 ICC，下面的 EXIF 插在 APP0 之后，最终顺序为 APP0 / APP1 / APP2
+            if let Some(icc) = passthrough_icc {
+                match color::inject_icc_into_jpeg(&encoded, icc) {
+                    Ok(with_icc) => encoded = with_icc,
+                    Err(e) => warn!("⚠️ [Save] ICC 嵌入跳过 [{}]: {}", task.file_path, e),
+                }
+            }
+
+            if global.export.preserve_exif {
+                if let Some(tiff) = exif_copy::read_exif_for_copy(&task.file_path) {
+                    match exif_copy::inject_exif_into_jpeg(&encoded, &tiff) {
+                        Ok(with_exif) => encoded = with_exif,
+                        // EXIF 透传失败不影响成品，只是少了元数据
+                        Err(e) => warn!("⚠️ [Save] EXIF 透传跳过 [{}]: {}", task.file_path, e),
+                    }
+                }
+            }
+        },
+        ExportImageFormat::Webp => {
+            // quality < 100 走 libwebp 有损编码，未启用 libwebp 时回退为无损 (批次总结里提示)
+            encoded = webp::encode(&img_to_save, quality)?;
+        },
+        ExportImageFormat::Avif => {
+            encoded = avif::encode(&img_to_save, quality, global.export.avif_speed)?;
+        },
+        ExportImageFormat::Tiff => {
+            // TIFF 编码器需要 Seek (回写 IFD 偏移)，用 Cursor 包一层
+            // 按传入的位深写出：只有直接在原图上绘制的样式 (TransparentMaster / Signature) 会给出 16 位成品，
+            // 其余样式在 8 位画布上合成 (16 位原图在 SaveImageStep 里提醒)
+            let encoder = TiffEncoder::new(Cursor::new(&mut encoded));
+            encoder.write_image(img_to_save.as_bytes(), width, height, color_type)
+                .map_err(|e| {
+                    error!("❌ [Save] TIFF 编码失败: {}", e);
+                    AppError::Image(e)
+                })?;
+        },
+    }
+    debug!("  -> [PERF] {} 编码 {}x{}: {:.2?} ({} KB)", format.extension(), width, height, t_encode.elapsed(), encoded.len() / 1024);
+    Ok(encoded)
+}
+
+// 🟢 PNG 压缩级别 -> (压缩, 滤波)
+// fast 用固定 Up 滤波：6000x4000 实拍样张 Fast+Adaptive 185ms / 22.4MB，Fast+Up 158ms / 27.3MB；
+// NoFilter 反而更慢 (275ms / 72MB，写出的数据量太大)。default / best 仍用自适应，换体积
+fn png_settings(level: PngCompression) -> (CompressionType, PngFilter) {
+    match level {
+        PngCompression::Fast => (CompressionType::Fast, PngFilter::Up),
+        PngCompression::Default => (CompressionType::Default, PngFilter::Adaptive),
+        PngCompression::Best => (CompressionType::Best, PngFilter::Adaptive),
+    }
+}
+
+/// 写盘：自动创建父目录，持有槽位期间才打开输出文件，离开作用域即关闭
+/// 经过存储熔断器：存储类错误计入熔断，触发时通知前端 (整个批次只发一次)
+fn write_output(global: &GlobalContext, output_path: &Path, encoded: &[u8]) -> Result<(), AppError> {
+    let _slot = global.output_slots.acquire();
+    global.storage
+        .write(output_path, encoded, || {
+            global.emit("process-status", "aborted_storage");
+        })
+        .map_err(AppError::Io) // 🟢 保持原始 IO 错误类型
+}
+
+// 每个通道的位数 (8 / 16 / 32)
+fn bits_per_channel(color: ColorType) -> u16 {
+    color.bits_per_pixel() / color.channel_count() as u16
+}
+
+/// 步骤 6: 复制原图到成品目录 (可选)
+/// 成品已经保存成功，这里的任何 IO 问题都只记录警告，不影响该文件的状态
+struct CopyOriginalStep;
+impl PipelineStep for CopyOriginalStep {
+    fn execute(&self, global: &GlobalContext, task: &mut TaskContext) -> Result<StepResult, AppError> {
+        let (Some(copy_cfg), Some(output_path)) = (&global.export.copy_original, &task.output_path) else {
+            return Ok(StepResult::Continue);
         };
 
-        // 4. 创建文件流
-        let file = File::create(&output_path).map_err(|e| {
-            error!("❌ [Save] 创建文件句柄失败 {:?}: {}", output_path, e);
-            AppError::Io(e)
-        })?;
-        let mut writer = BufWriter::new(file);
-
-        // 5. 编码保存
-        // 🟢 map_err 模式：先记录日志，再抛出 AppError
-        let width = img_to_save.width();
-        let height = img_to_save.height();
-        let color_type = img_to_save.color().into();
-
-        match global.export.format {
-            ExportImageFormat::Png => {
-                let encoder = PngEncoder::new(&mut writer);
-                encoder.write_image(img_to_save.as_bytes(), width, height, color_type)
-                    .map_err(|e| {
-                        error!("❌ [Save] PNG 编码失败: {}", e);
-                        AppError::Image(e) // 自动转换 ImageError
-                    })?;
+        let dest = match calculate_original_copy_path(&task.file_path, output_path, copy_cfg, &global.options) {
+            Ok(p) => p,
+            Err(e) => {
+                warn!("⚠️ [CopyOriginal] 路径计算失败 [{}]: {}", task.file_path, e);
+                return Ok(StepResult::Continue);
+            }
+        };
+
+        match copy_original_to(Path::new(&task.file_path), &dest) {
+            Ok(true) => {},
+            Ok(false) => {
+                debug!("⏭️ [CopyOriginal] 源与目标相同，跳过: {:?}", dest);
+                return Ok(StepResult::Continue);
             },
-            ExportImageFormat::Jpg => {
-                let encoder = JpegEncoder::new_with_quality(&mut writer, global.export.quality);
-                encoder.write_image(img_to_save.as_bytes(), width, height, color_type)
-                    .map_err(|e| {
-                        error!("❌ [Save] JPG 编码失败: {}", e);
-                        AppError::Image(e)
-                    })?;
+            Err(e) => {
+                warn!("⚠️ [CopyOriginal] 复制失败 [{}] -> {:?}: {}", task.file_path, dest, e);
+                return Ok(StepResult::Continue);
             },
         }
 
-        task.output_path = Some(output_path);
-        
-        // 成功日志 (info 级别，证明这张图搞定了)
-        info!("✅ [Save] 已保存: {:?}", task.file_path);
+        debug!("📎 [CopyOriginal] 已复制原图: {:?}", dest);
+        task.original_copy_path = Some(dest);
+        Ok(StepResult::Continue)
+    }
+}
+
+/// 复制原图到 dest (按需创建目录) 并同步修改时间；返回 false 代表源与目标是同一个文件，没有复制
+fn copy_original_to(src: &Path, dest: &Path) -> std::io::Result<bool> {
+    // 同一文件保护：源和目标指向同一个文件时 (输出到原图目录且未改名) 不能自己复制自己
+    if dest.exists() {
+        if let (Ok(a), Ok(b)) = (std::fs::canonicalize(src), std::fs::canonicalize(dest)) {
+            if a == b {
+                return Ok(false);
+            }
+        }
+    }
+
+    if let Some(parent) = dest.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::copy(src, dest)?;
+
+    // fs::copy 不保留修改时间，手动同步一下 (照片管理软件常按修改时间排序)
+    let modified = std::fs::metadata(src).and_then(|m| m.modified());
+    let synced = modified.and_then(|t| {
+        std::fs::File::options().write(true).open(dest)?.set_modified(t)
+    });
+    if let Err(e) = synced {
+        debug!("⚠️ [CopyOriginal] 时间戳同步失败 {:?}: {}", dest, e);
+    }
+    Ok(true)
+}
+
+/// 步骤 7: 记录到断点续传清单
+/// 放在最后，只有前面所有步骤都成功的文件才算完成
+struct RecordResumeStep;
+impl PipelineStep for RecordResumeStep {
+    fn execute(&self, global: &GlobalContext, task: &mut TaskContext) -> Result<StepResult, AppError> {
+        if let (Some(journal), Some(_)) = (&global.resume, &task.output_path) {
+            journal.record(&task.file_path);
+        }
+        Ok(StepResult::Continue)
+    }
+}
+
+/// 步骤 8: 生成成品缩略图，随进度事件发给前端
+/// 成品此时还在内存里，直接在处理线程上缩小编码，不占主线程；失败只是没有缩略图
+struct ThumbnailStep;
+impl PipelineStep for ThumbnailStep {
+    fn execute(&self, global: &GlobalContext, task: &mut TaskContext) -> Result<StepResult, AppError> {
+        if !global.emit_thumbnails {
+            return Ok(StepResult::Continue);
+        }
+        let Some(final_img) = task.final_image.as_ref() else {
+            return Ok(StepResult::Continue);
+        };
+
+        // 按宽度缩放，竖图高度最多放到两倍宽度
+        let thumb = final_img.thumbnail(THUMBNAIL_WIDTH, THUMBNAIL_WIDTH * 2).to_rgb8();
+        let mut buffer = Vec::new();
+        let encoded = JpegEncoder::new_with_quality(&mut buffer, THUMBNAIL_QUALITY)
+            .write_image(thumb.as_raw(), thumb.width(), thumb.height(), image::ExtendedColorType::Rgb8);
+        match encoded {
+            Ok(()) => task.thumbnail = Some(format!("data:image/jpeg;base64,{}", general_purpose::STANDARD.encode(&buffer))),
+            Err(e) => debug!("⚠️ [Thumbnail] 缩略图编码失败 [{}]: {}", task.file_path, e),
+        }
+        Ok(StepResult::Continue)
+    }
+}
+
+// 缩略图宽度与质量：够胶片条显示即可，质量压低让事件负载保持在十几 KB
+const THUMBNAIL_WIDTH: u32 = 300;
+const THUMBNAIL_QUALITY: u8 = 60;
+
+
+/// 标准流水线 (批处理和监听模式共用同一套步骤)
+pub(super) fn build_pipeline(processor: Arc<Box<dyn FrameProcessor + Send + Sync>>) -> Pipeline {
+    let background = processor.background_kind();
+    Pipeline::new()
+        .add_step(CheckStopStep)
+        .add_step(CheckFileSizeStep)
+        .add_step(CheckAlreadyFramedStep)
+        .add_step(CheckExifStep)
+        .add_step(CheckOverwriteStep)
+        .add_step(LoadImageStep)
+        .add_step(ParseExifStep)
+        .add_step(ProcessFrameStep { processor })
+        .add_step(PadToAspectStep { background })
+        .add_step(ResizeOutputStep)
+        .add_step(SaveImageStep)
+        .add_step(CopyOriginalStep)
+        .add_step(RecordResumeStep)
+        .add_step(ThumbnailStep)
+}
+
+// =========================================================
+// 🟢 预检 (Dry Run)：只跑检查、解析和路径计算，不解码、不写盘
+// =========================================================
+
+/// 解析 EXIF (CheckOverwriteStep 已解析过则跳过)
+/// 正式处理时单独成步，解析耗时不混进绘制；预检时结果用于摘要和命名
+struct ParseExifStep;
+impl PipelineStep for ParseExifStep {
+    fn execute(&self, global: &GlobalContext, task: &mut TaskContext) -> Result<StepResult, AppError> {
+        if task.parsed_ctx.is_none() {
+            task.parsed_ctx = Some(parse_exif(global, &task.file_path)?);
+        }
         Ok(StepResult::Continue)
     }
+
+    fn timing_phase(&self) -> Option<TimingPhase> {
+        Some(TimingPhase::Parse)
+    }
 }
 
+/// 预检步骤: 计算输出路径并登记 (与 SaveImageStep 相同的命名和批内重名处理)，把冲突记为提醒
+struct PlanOutputStep;
+impl PipelineStep for PlanOutputStep {
+    fn execute(&self, global: &GlobalContext, task: &mut TaskContext) -> Result<StepResult, AppError> {
+        let plan = global.output_plan(task)?;
+        if plan.main != plan.target {
+            task.warnings.push(format!("与本批次其他文件重名，将改为 {}", plan.main.display()));
+        } else if plan.target.exists() {
+            task.warnings.push("输出已存在，将被覆盖".to_string());
+        }
+        task.output_path = Some(plan.main);
+
+        for ((i, variant), path) in global.export.variants.iter().enumerate().zip(plan.variants) {
+            let variant_target = calculate_variant_path(&plan.target, variant, i);
+            if path != variant_target {
+                task.warnings.push(format!("尺寸版本与本批次其他文件重名，将改为 {}", path.display()));
+            } else if variant_target.exists() {
+                task.warnings.push(format!("尺寸版本 {} 已存在，将被覆盖", path.display()));
+            }
+            task.variant_outputs.push(VariantOutput { name: variant_label(variant, i), path });
+        }
+        Ok(StepResult::Continue)
+    }
+}
+
+/// 预检流水线：标准流水线去掉加载、绘制和保存
+/// 不检查停止信号 (预检很快，也不应该受上一个批次的停止状态影响)
+fn build_dry_run_pipeline() -> Pipeline {
+    Pipeline::new()
+        .add_step(CheckFileSizeStep)
+        .add_step(CheckAlreadyFramedStep)
+        .add_step(CheckExifStep)
+        .add_step(ParseExifStep)
+        .add_step(CheckOverwriteStep)
+        .add_step(PlanOutputStep)
+}
+
+/// 预检结果 (每个文件一条)
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DryRunEntry {
+    pub source_path: String,
+    pub status: &'static str, // ok / skipped / error
+    pub brand: Option<String>,
+    pub model: Option<String>,
+    pub params: Option<String>, // 标准参数串 (如 "50mm f/1.8 1/800s ISO 100")
+    pub output_path: Option<PathBuf>,
+    pub variants: Vec<VariantOutput>, // 尺寸版本的输出路径 (未配置时为空)
+    pub message: Option<String>, // 跳过原因或错误信息
+    pub error_code: Option<&'static str>,
+    pub warnings: Vec<String>, // 重名 / 将覆盖
+}
 
 // =========================================================
 // 4. 管道执行器 (Runner)
 // =========================================================
 
-struct Pipeline {
+pub(super) struct Pipeline {
     steps: Vec<Box<dyn PipelineStep>>,
 }
 
@@ -247,64 +997,201 @@ impl Pipeline {
     }
 
     /// 运行单张图片的完整流程
-    fn run(&self, global: &GlobalContext, file_path: String) {
-        let mut task = TaskContext::new(file_path.clone());
-        let mut skip_reason = None;
-        let mut error_obj: Option<AppError> = None; // 🔴 变更：存储 AppError
-        let mut is_stopped = false;
+    fn run(&self, global: &GlobalContext, file_path: String, index: usize) {
+        let mut task = TaskContext::new(file_path, index);
+        if let Some(outcome) = self.execute(global, &mut task) {
+            self.report(global, &task, outcome);
+        }
+    }
 
-        // --- 核心循环 ---
-        for step in &self.steps {
-            match step.execute(global, &mut task) {
-                Ok(StepResult::Continue) => continue,
-                Ok(StepResult::Stop) => {
-                    is_stopped = true;
-                    break;
-                },
-                Ok(StepResult::Skip(reason)) => {
-                    skip_reason = Some(reason);
-                    break;
-                },
-                Err(e) => {
+    /// 带看门狗运行：在当前 (rayon 工作) 线程里处理，处理前向看门狗登记截止时间
+    /// 超时由看门狗线程直接上报并标记 abandoned，这里迟到的结果作废 (不会再写盘或上报)
+    pub(super) fn run_guarded(&self, global: &GlobalContext, watchdog: Option<&Watchdog>, file_path: String, index: usize) {
+        let Some(watchdog) = watchdog else {
+            return self.run(global, file_path, index);
+        };
+
+        let mut task = TaskContext::new(file_path, index);
+        let ticket = watchdog.arm(&task.file_path, index, task.abandoned.clone());
+        let result = panic::catch_unwind(AssertUnwindSafe(|| self.execute(global, &mut task)));
+        if !watchdog.disarm(ticket) {
+            return;
+        }
+        match result {
+            Ok(Some(outcome)) => self.report(global, &task, outcome),
+            Ok(None) => {},
+            Err(_) => {
+                // 某个步骤 panic 了，没有结果可用；按失败上报，批次里的其他文件照常继续
+                error!("❌ [Watchdog] 处理过程异常退出 [{}]", task.file_path);
+                let task = TaskContext::new(task.file_path.clone(), index);
+                self.report(global, &task, TaskOutcome::Failed(AppError::System("处理过程异常退出".to_string())));
+            },
+        }
+    }
+
+    /// 批次开始时启动看门狗 (未设置超时返回 None)；超时的文件由看门狗线程按失败上报
+    pub(super) fn spawn_watchdog(self: &Arc<Self>, global: &Arc<GlobalContext>) -> Option<Watchdog> {
+        let timeout = global.file_timeout?;
+        let (pipeline, ctx) = (self.clone(), global.clone());
+        let spawned = Watchdog::spawn(timeout, move |file_path, index| {
+            let task = TaskContext::new(file_path, index);
+            let err = AppError::System(format!("timeout: 处理超过 {} 秒", timeout.as_secs()));
+            pipeline.report(&ctx, &task, TaskOutcome::Failed(err));
+        });
+        match spawned {
+            Ok(watchdog) => Some(watchdog),
+            Err(e) => {
+                // 线程数耗尽等极端情况：本批次没有超时保护
+                warn!("⚠️ [Watchdog] 无法创建看门狗线程，本批次不做超时保护: {}", e);
+                None
+            },
+        }
+    }
+
+    /// 依次执行所有步骤；用户停止 / 被看门狗放弃时返回 None (不上报进度)
+    /// 执行期间取消令牌挂在当前线程上，步骤内部的耗时操作 (画布生成、编码前) 也会检查
+    fn execute(&self, global: &GlobalContext, task: &mut TaskContext) -> Option<TaskOutcome> {
+        let token = CancelToken::new(global.app_state.clone(), task.abandoned.clone());
+        cancel::with_token(&token, || {
+            // --- 核心循环 ---
+            for step in &self.steps {
+                if token.is_cancelled() {
+                    return None;
+                }
+                let t_step = Instant::now();
+                let result = step.execute(global, task);
+                if let Some(phase) = step.timing_phase() {
+                    task.timings.add(phase, t_step.elapsed());
+                }
+                match result {
+                    Ok(StepResult::Continue) => continue,
+                    Ok(StepResult::Stop) => return None,
+                    Ok(StepResult::Skip(reason)) => return Some(TaskOutcome::Skipped(reason)),
+                    // 中途取消不是错误，和 Stop 一样静默结束
+                    Err(AppError::Cancelled) => {
+                        debug!("🛑 [Pipeline] 处理中途取消: {}", task.file_path);
+                        return None;
+                    },
                     // 🟢 捕获结构化错误
-                    error_obj = Some(e);
-                    break;
+                    Err(e) => return Some(TaskOutcome::Failed(e)),
                 }
             }
+            Some(TaskOutcome::Done)
+        })
+    }
+
+    /// 预检用：依次执行所有步骤，不挂取消令牌、不上报进度
+    /// (预检流水线里没有会返回 Stop 的步骤，万一出现按跳过处理)
+    fn plan(&self, global: &GlobalContext, task: &mut TaskContext) -> TaskOutcome {
+        for step in &self.steps {
+            match step.execute(global, task) {
+                Ok(StepResult::Continue) => continue,
+                Ok(StepResult::Stop) => return TaskOutcome::Skipped(SkipReason::Stopped),
+                Ok(StepResult::Skip(reason)) => return TaskOutcome::Skipped(reason),
+                Err(e) => return TaskOutcome::Failed(e),
+            }
         }
+        TaskOutcome::Done
+    }
 
-        if is_stopped { return; }
+    /// 统一的进度报告 (进度事件 + 批次报告)
+    fn report(&self, global: &GlobalContext, task: &TaskContext, outcome: TaskOutcome) {
+        let file_path = &task.file_path;
+        let index = task.index;
+        let (skip_reason, error_obj) = match outcome {
+            TaskOutcome::Done => (None, None),
+            TaskOutcome::Skipped(reason) => (Some(reason), None),
+            TaskOutcome::Failed(e) => (None, Some(e)),
+        };
 
         // --- 统一的进度报告 ---
         let current = global.completed_count.fetch_add(1, Ordering::Relaxed) + 1;
         
+        // 批次报告 (状态与进度事件一致，成功记为 success)
+        if global.export.write_report {
+            let (status, error_code, message) = match (&error_obj, &skip_reason) {
+                (Some(err), _) => ("error", Some(err.code()), Some(err.to_string())),
+                (None, Some(reason)) => ("skipped", None, Some(reason.message().to_string())),
+                (None, None) => ("success", None, None),
+            };
+            global.reports.lock().unwrap().push(FileReport {
+                index,
+                source_path: file_path.clone(),
+                output_path: task.output_path.clone(),
+                original_copy_path: task.original_copy_path.clone(),
+                status,
+                error_code,
+                message,
+                processing_ms: task.started_at.elapsed().as_millis(),
+            });
+        }
+
+        // 🟢 失败时生成关联 ID，同时写进进度事件和日志，用户反馈时报 ID 就能在日志里定位
+        let correlation_id = error_obj.as_ref().map(|err| {
+            let id = next_correlation_id();
+            error!("❌ [Pipeline] [{}] #{} {}: {}", id, index, file_path, err);
+            id
+        });
+
         let (status, msg_payload) = if let Some(err) = error_obj {
+            global.storage.record_failed();
+            global.tally.lock().unwrap().record_failed();
             // 🟢 错误时，status="error"，message 是序列化后的 AppError 对象
             // 前端可以通过 msg_payload.code 判断错误类型
             ("error", json!(err)) 
         } else if let Some(reason) = skip_reason {
+            global.tally.lock().unwrap().record_skipped(reason.message());
+            // 跳过时 message 是 { code, message }，前端按 code 区分原因 (文字只用于展示)
             ("skipped", json!(reason))
         } else {
+            global.timing_stats.lock().unwrap().record(&task.timings);
+            let mut tally = global.tally.lock().unwrap();
+            tally.record_success();
+            if task.original_copy_path.is_some() {
+                tally.record_original_copied();
+            }
             ("processing", json!(null)) // 成功
         };
+        let succeeded = status == "processing";
 
         // 发送事件
-        let _ = global.window.emit("process-progress", json!({
+        let payload = json!({
             "current": current,
-            "total": global.total_files,
+            "index": index, // 原始提交顺序里的序号 (从 0 开始，断点续传过滤后也不变)，前端据此定位行
+            "total": (global.total_files > 0).then_some(global.total_files), // 监听模式没有总数，为 null
             "filepath": file_path,
             "status": status,
-            "message": msg_payload // 这里的 message 可能是一个字符串，也可能是一个 Error 对象
-        }));
-        
-        // 服务端最后一道日志防线
-        if status == "error" {
-            // 这里的 err 已经在各个 step 里由 log::error 记录过了，所以这里 debug 即可
-            debug!("❌ [Pipeline] 任务终止: {}", file_path);
+            "message": msg_payload, // 跳过时是 { code, message }，失败时是 Error 对象 { code, message }，成功为 null
+            "originalCopy": task.original_copy_path, // 复制出的原图路径 (未开启时为 null)
+            "warnings": task.warnings, // 成功但有提醒 (如位深降级)，没有时为空数组
+            "colorProfile": task.source_profile.as_ref().map(|p| &p.name), // 原图 ICC 配置文件名称 (未标记时为 null)
+            "thumbnail": task.thumbnail, // 成品缩略图 data URL (未开启或失败时为 null)
+            "variants": task.variant_outputs, // 尺寸版本 [{name, path}] (未配置或未成功时为空数组)
+            "timings": succeeded.then_some(task.timings), // 分步耗时 {load, parse, render, encode (ms), megapixels}，仅成功时有
+            "correlationId": correlation_id, // 失败时的关联 ID，与日志里 [..] 中的一致 (其他状态为 null)
+        });
+        let payload = match &global.ordered_progress {
+            Some(ordered) => ordered.send(index, payload),
+            None => Some(payload),
+        };
+        if let Some(payload) = payload {
+            global.emit("process-progress", payload);
         }
     }
 }
 
+// 关联 ID：Unix 毫秒时间戳 + 进程内自增序号 (十六进制)，同一台机器上不会重复
+static CORRELATION_SEQ: AtomicU64 = AtomicU64::new(0);
+
+fn next_correlation_id() -> String {
+    let millis = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0);
+    let seq = CORRELATION_SEQ.fetch_add(1, Ordering::Relaxed);
+    format!("{:x}-{:x}", millis, seq)
+}
+
 // =========================================================
 // 5. API 入口函数
 // =========================================================
@@ -315,58 +1202,543 @@ pub async fn start_batch_process_v3(
     state: State<'_, Arc<AppState>>,
     file_paths: Vec<String>,
     context: crate::models::BatchContext,
+    resume: bool, // 沿用上次中断批次的清单，跳过已完成的文件
 ) -> Result<String, AppError> { // 🔴 变更：返回 AppError
     
-    info!("🚀 [API V3] Pipeline Mode Started ({} files)", file_paths.len());
+    info!("🚀 [API V3] Pipeline Mode Started ({} files, resume={})", file_paths.len(), resume);
+
+    // 🟢 启动前校验：在创建线程池、写任何文件之前拒绝明显无效的请求
+    validate_batch_request(&file_paths, &context.export)?;
 
     let state_arc = (*state).clone();
     state_arc.should_stop.store(false, Ordering::Relaxed);
     
-    let total_files = file_paths.len();
     let batch_start = Instant::now();
+
+    // 自动导出调优 (只升不降)，调整记录写入日志和批次总结，生效配置随 sidecar 一起保存
+    let (export, export_adjustments) = if context.auto_export_tuning {
+        let (effective, adjustments) = context.export.tuned_for(&context.options);
+        for adj in &adjustments {
+            info!("🎛️ [API V3] 导出调优: {}", adj);
+        }
+        (effective, adjustments)
+    } else {
+        (context.export.clone(), Vec::new())
+    };
     let completed_count = Arc::new(AtomicUsize::new(0));
 
+    // 断点续传清单：参数与上次一致时过滤掉已完成的文件，否则重新开始记录 (不续传的任务不碰清单)
+    let (journal, done) = match window.path().app_data_dir() {
+        Ok(dir) => match ResumeJournal::open(dir.join(resume::MANIFEST_FILE_NAME), &context, &export, resume) {
+            Ok(opened) => opened,
+            Err(e) => {
+                warn!("⚠️ [API V3] 断点续传清单不可用: {}", e);
+                (None, HashSet::new())
+            },
+        },
+        Err(e) => {
+            warn!("⚠️ [API V3] 无法获取 app 数据目录，断点续传不可用: {}", e);
+            (None, HashSet::new())
+        },
+    };
+    // 原始序号在过滤前记下：进度事件、批次报告和 {counter} 命名都用它，续传时同一个文件序号不变
+    let submitted: Vec<(usize, String)> = file_paths.into_iter().enumerate().collect();
+    let (remaining, resumed) = resume::filter_completed(submitted, &done);
+    let (indices, file_paths): (Vec<usize>, Vec<String>) = remaining.into_iter().unzip();
+    if resumed > 0 {
+        info!("⏯️ [API V3] 断点续传：跳过已完成的 {} 张", resumed);
+    }
+    let total_files = file_paths.len();
+
+    // 目标磁盘预检：只读或空间明显不足直接报错，空间紧张只提示
+    let space_warning = storage::preflight_check(&file_paths, &context)?;
+
+    // 文件句柄压力：按系统上限决定输出并发数，上限偏低时在总结里提示
+    let fd_limit = io_limits::open_file_limit();
+    let output_slots = io_limits::output_slot_count(fd_limit);
+    let fd_warning = io_limits::fd_pressure_warning(fd_limit, total_files);
+    debug!("📂 [API V3] 文件句柄上限: {:?}, 输出并发槽位: {}", fd_limit, output_slots);
+    if let Some(w) = &fd_warning {
+        warn!("⚠️ [API V3] {}", w);
+    }
+
+    // AVIF 编码器是可选 feature，未启用时每张都会失败，不如直接报错
+    if export.output_formats().any(|f| matches!(f, ExportImageFormat::Avif)) && !avif::supported() {
+        return Err(AppError::System("当前版本未启用 AVIF 编码 (需要以 avif feature 编译)".to_string()));
+    }
+
+    // 色度抽样 / 渐进式 / 霍夫曼表优化：当前构建不支持时整批回退到 4:4:4 基线，在总结里提示一次
+    let jpeg_warning = export.output_formats().any(|f| matches!(f, ExportImageFormat::Jpg))
+        .then(|| jpeg::fallback_note(&jpeg::JpegOptions::from_export(&export)))
+        .flatten();
+    if let Some(w) = &jpeg_warning {
+        warn!("⚠️ [API V3] {}", w);
+    }
+    // 有损 WebP：未启用 libwebp 时整批回退为无损，同样在总结里提示一次
+    let webp_warning = webp::fallback_note(&export);
+    if let Some(w) = &webp_warning {
+        warn!("⚠️ [API V3] {}", w);
+    }
+
+    state_arc.decoded_images.set_capacity(context.decode_cache_size.unwrap_or(decode_cache::DEFAULT_CAPACITY));
+
     // 构建全局上下文
-    let global_ctx = Arc::new(GlobalContext {
-        window: window.clone(),
+    let mut global_ctx = GlobalContext {
+        window: Some(window.clone()),
         app_state: state_arc.clone(),
         options: context.options.clone(),
+        signature: context.signature.clone(),
         total_files,
         completed_count,
-        export: context.export.clone()
-    });
+        export,
+        deterministic: context.deterministic,
+        output_slots: IoSlots::new(output_slots),
+        storage: StorageBreaker::default(),
+        claimed_outputs: Mutex::new(OutputNames::default()),
+        planned_outputs: HashMap::new(),
+        resume: journal,
+        reports: Mutex::new(Vec::new()),
+        // 0 代表不限制
+        file_timeout: (context.file_timeout_secs > 0).then(|| Duration::from_secs(context.file_timeout_secs)),
+        max_file_size: context.max_file_size_mb.map(|mb| mb.saturating_mul(1_048_576)),
+        emit_thumbnails: context.emit_thumbnails,
+        overrides: context.overrides.clone(),
+        labels: context.labels.clone(),
+        allow_missing_exif: context.allow_missing_exif,
+        reframe_outputs: context.reframe_outputs,
+        timing_stats: Mutex::new(TimingStats::default()),
+        tally: Mutex::new(BatchTally::default()),
+        ordered_progress: context.ordered_progress.then(|| OrderedEmitter::spawn(window.clone(), indices.clone())),
+    };
+    global_ctx.planned_outputs = plan_outputs(&global_ctx, &indices, &file_paths);
+    let global_ctx = Arc::new(global_ctx);
+
+    // 每个批次重新开始统计 Logo 缩放缓存，避免跨批次无限增长
+    crate::resources::reset_scaled_logo_cache();
 
-    let processor_strategy = crate::processor::create_processor(&context.options);
+    let processor_strategy = crate::processor::create_context_processor(&context)?;
     let processor_arc = Arc::new(processor_strategy);
 
     // 组装流水线
-    let pipeline = Arc::new(Pipeline::new()
-        .add_step(CheckStopStep)
-        .add_step(CheckExifStep)
-        .add_step(LoadImageStep)
-        .add_step(ProcessFrameStep { processor: processor_arc })
-        .add_step(SaveImageStep)
-    );
+    let pipeline = Arc::new(build_pipeline(processor_arc));
+
+    // 并发数：按用户设置或内存估算限制同时处理的图片数量
+    // (确定性模式不降并发：处理器内部的并行都是逐行写入互不重叠的输出，结果与线程调度无关)
+    let concurrency = concurrency::choose_concurrency(context.max_concurrent, &file_paths);
+    info!("🧵 [API V3] 并发数: {}", concurrency);
+    let pool = build_pool(concurrency)?;
+
+    // 🟢 回显生效配置 (调优后)，前端据此显示 "以 PNG 导出到 D:\out，WhiteModern 样式"
+    let _ = window.emit("process-config", json!({
+        "total": total_files,
+        "style": global_ctx.options.filename_suffix(),
+        "format": global_ctx.export.format.extension(),
+        "quality": global_ctx.export.quality,
+        "targetDir": global_ctx.export.target_dir, // null 代表原图同级
+        "concurrency": concurrency,
+        "appVersion": env!("CARGO_PKG_VERSION"),
+    }));
 
-    // 启动线程池
+    // 初始进度事件：告诉前端总数和本批次的并发数
+    let _ = window.emit("process-progress", json!({
+        "current": 0,
+        "total": total_files,
+        "filepath": null,
+        "status": "started",
+        "concurrency": concurrency,
+        "resumed": resumed, // 断点续传跳过的数量 (不计入 total)
+    }));
+
+    let report_dir = report::report_dir(&global_ctx.export, &file_paths);
+
+    // 启动线程池 (整批共用一个看门狗线程)
+    let worker_ctx = global_ctx.clone();
+    let watchdog = pipeline.spawn_watchdog(&global_ctx);
     let result = tauri::async_runtime::spawn_blocking(move || {
-        file_paths.par_iter().for_each(|file_path| {
-            pipeline.run(&global_ctx, file_path.clone());
+        pool.install(|| {
+            let tasks = indices.par_iter().zip(file_paths.par_iter());
+            if worker_ctx.ordered_progress.is_some() {
+                // 有序模式：每个任务单独切分，工作线程基本按提交顺序领取，重排缓冲不会堆积太多
+                tasks.with_max_len(1).for_each(|(&index, file_path)| {
+                    pipeline.run_guarded(&worker_ctx, watchdog.as_ref(), file_path.clone(), index);
+                });
+            } else {
+                tasks.for_each(|(&index, file_path)| {
+                    pipeline.run_guarded(&worker_ctx, watchdog.as_ref(), file_path.clone(), index);
+                });
+            }
         });
+        // 所有任务都已注销，超时上报不会再有新的了
+        drop(watchdog);
+        // 等有序模式的剩余事件发完，再发批次状态 / 返回总结
+        if let Some(ordered) = &worker_ctx.ordered_progress {
+            ordered.finish();
+        }
     }).await;
 
     // 处理 spawn_blocking 的 JoinError
     result.map_err(|e| AppError::System(format!("线程池异常: {}", e)))?;
 
     let duration = batch_start.elapsed();
+    let logo_cache_hits = crate::resources::scaled_logo_cache_hits();
+    debug!("📦 [API V3] Logo 缩放缓存命中: {} 次", logo_cache_hits);
+
+    // 批次报告：停止 / 中止时也写 (正是最需要排查的时候)，写入失败只警告
+    let report_note = match (global_ctx.export.write_report, &report_dir) {
+        (true, Some(dir)) => {
+            let mut files = std::mem::take(&mut *global_ctx.reports.lock().unwrap());
+            match report::write_report(dir, &mut files, &global_ctx.options, &global_ctx.export, duration.as_millis(), global_ctx.export.report_csv) {
+                Ok(path) => {
+                    info!("📝 [API V3] 批次报告: {:?}", path);
+                    format!(" [report: {}]", path.display())
+                },
+                Err(e) => {
+                    warn!("⚠️ [API V3] 批次报告写入失败: {}", e);
+                    String::new()
+                },
+            }
+        },
+        _ => String::new(),
+    };
     
+    let notes: Vec<String> = [space_warning, fd_warning, jpeg_warning, webp_warning].into_iter().flatten().collect();
+    // 批次总结事件：计数、跳过原因、总耗时 (在 process-status 之后发出，前端用它覆盖状态栏文字)
+    let emit_summary = |status: &'static str| {
+        let mut summary = global_ctx.tally.lock().unwrap().summary(status, total_files, duration);
+        summary.export_adjustments = export_adjustments.clone();
+        summary.logo_cache_hits = logo_cache_hits;
+        summary.notes = notes.clone();
+        let _ = window.emit("process-summary", summary);
+    };
+
     if state_arc.should_stop.load(Ordering::Relaxed) {
         window.emit("process-status", "stopped").map_err(|e| AppError::System(e.to_string()))?;
-        return Ok("Stopped by user".to_string());
+        emit_summary("stopped");
+        return Ok(format!("Stopped by user{}", report_note));
+    }
+
+    // 存储中止：aborted_storage 事件已在熔断时发出，这里只返回总结
+    if let Some(summary) = global_ctx.storage.summary() {
+        warn!("💥 [API V3] 批次因存储问题中止: {}", summary);
+        emit_summary("aborted_storage");
+        return Ok(format!("Aborted (storage) after {:.2?}: {}{}", duration, summary, report_note));
     }
 
     info!("✨ [API V3] Batch Complete in {:.2?}", duration);
+    // 整批跑完，清单不再需要 (失败的文件重新生成即可，不需要续传)
+    if let Some(journal) = &global_ctx.resume {
+        journal.remove();
+    }
     window.emit("process-status", "finished").map_err(|e| AppError::System(e.to_string()))?;
+    emit_summary("finished");
+
+    // 分步耗时汇总 (没有成功的文件时不输出)
+    let timing_note = match global_ctx.timing_stats.lock().unwrap().summary() {
+        Some(summary) => {
+            info!("⏱️ [API V3] 分步耗时: {}", summary);
+            format!(" [timings: {}]", summary)
+        },
+        None => String::new(),
+    };
+
+    if notes.is_empty() {
+        Ok(format!("Done in {:.2?}{}{}", duration, timing_note, report_note))
+    } else {
+        Ok(format!("Done in {:.2?} (⚠️ {}){}{}", duration, notes.join("; "), timing_note, report_note))
+    }
+}
+
+/// 批次线程池
+pub(crate) fn build_pool(threads: usize) -> Result<rayon::ThreadPool, AppError> {
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build()
+        .map_err(|e| AppError::System(format!("线程池创建失败: {}", e)))
+}
+
+/// 并行阶段之前按提交顺序分配所有文件的输出路径：{counter} 用原始序号，批内重名的 _1、_2 按提交顺序分配，
+/// 与完成顺序无关，重跑时同一文件得到同一个名字 (skip_existing 才能认出带后缀的旧成品)
+/// 文件名模板引用 EXIF 时先并行读取；路径算不出来的文件不分配，处理到它时会报同样的错误
+fn plan_outputs(global: &GlobalContext, indices: &[usize], file_paths: &[String]) -> HashMap<usize, PlannedOutput> {
+    let targets: Vec<Option<PathBuf>> = indices.par_iter().zip(file_paths.par_iter())
+        .map(|(&index, path)| {
+            let exif = global.export.template_needs_exif().then(|| parse_exif(global, path).ok()).flatten();
+            let naming = NamingContext { exif: exif.as_ref(), counter: Some(index + 1) };
+            global.calculate_target_path(path, naming).ok()
+        })
+        .collect();
+
+    let mut names = global.claimed_outputs.lock().unwrap();
+    indices.iter().zip(targets)
+        .filter_map(|(&index, target)| Some((index, names.plan(target?, &global.export.variants))))
+        .collect()
+}
+
+/// 批次参数校验：空文件列表、不存在的自定义输出目录直接报错
+/// (输出目录来自文件夹选择器，不存在多半是移动硬盘已拔出或设置过期，不应该静默新建)
+fn validate_batch_request(file_paths: &[String], export: &ExportConfig) -> Result<(), AppError> {
+    if file_paths.is_empty() {
+        return Err(AppError::PathCalculation("没有需要处理的文件".to_string()));
+    }
+    if let Some(dir) = &export.target_dir {
+        if !Path::new(dir).is_dir() {
+            warn!("⚠️ [API V3] 输出目录不存在: {}", dir);
+            return Err(AppError::PathCalculation(format!("输出目录不存在: {}", dir)));
+        }
+    }
+    // 目标比例不合法时整批直接报错，不必每张图各失败一次
+    if let Some(ratio) = export.target_aspect {
+        aspect::validate_ratio(ratio)?;
+    }
+    Ok(())
+}
+
+/// 🟢 预检：按正式批次的配置走一遍检查、EXIF 解析和输出路径计算，不加载图片、不写任何文件
+/// 按文件顺序串行执行，批内重名的改名结果与顺序处理时一致
+#[tauri::command]
+pub async fn dry_run_batch(
+    window: Window,
+    state: State<'_, Arc<AppState>>,
+    file_paths: Vec<String>,
+    context: crate::models::BatchContext,
+) -> Result<Vec<DryRunEntry>, AppError> {
+    info!("🔍 [API V3] Dry run ({} files)", file_paths.len());
+
+    // 导出配置与正式批次一致 (调优可能改变格式，进而改变扩展名)
+    let export = if context.auto_export_tuning {
+        context.export.tuned_for(&context.options).0
+    } else {
+        context.export.clone()
+    };
+
+    // 正式批次会直接报错的配置，预检也直接报错
+    if export.output_formats().any(|f| matches!(f, ExportImageFormat::Avif)) && !avif::supported() {
+        return Err(AppError::System("当前版本未启用 AVIF 编码 (需要以 avif feature 编译)".to_string()));
+    }
+
+    let global_ctx = GlobalContext {
+        window: Some(window),
+        app_state: (*state).clone(),
+        options: context.options.clone(),
+        signature: context.signature.clone(),
+        total_files: file_paths.len(),
+        completed_count: Arc::new(AtomicUsize::new(0)),
+        export,
+        deterministic: context.deterministic,
+        output_slots: IoSlots::new(1),
+        storage: StorageBreaker::default(),
+        claimed_outputs: Mutex::new(OutputNames::default()),
+        planned_outputs: HashMap::new(),
+        resume: None,
+        reports: Mutex::new(Vec::new()),
+        file_timeout: None,
+        max_file_size: context.max_file_size_mb.map(|mb| mb.saturating_mul(1_048_576)),
+        emit_thumbnails: false,
+        overrides: context.overrides.clone(),
+        labels: context.labels.clone(),
+        allow_missing_exif: context.allow_missing_exif,
+        reframe_outputs: context.reframe_outputs,
+        timing_stats: Mutex::new(TimingStats::default()),
+        tally: Mutex::new(BatchTally::default()),
+        ordered_progress: None,
+    };
+
+    let pipeline = build_dry_run_pipeline();
+    let entries = tauri::async_runtime::spawn_blocking(move || {
+        file_paths.into_iter().enumerate().map(|(index, file_path)| {
+            let mut task = TaskContext::new(file_path, index);
+            let outcome = pipeline.plan(&global_ctx, &mut task);
+            let (status, message, error_code) = match outcome {
+                TaskOutcome::Done => ("ok", None, None),
+                TaskOutcome::Skipped(reason) => ("skipped", Some(reason.message().to_string()), None),
+                TaskOutcome::Failed(e) => ("error", Some(e.to_string()), Some(e.code())),
+            };
+            let parsed = task.parsed_ctx.as_ref();
+            DryRunEntry {
+                source_path: task.file_path,
+                status,
+                brand: parsed.map(|c| c.brand_label()),
+                model: parsed.map(|c| c.model_name.clone()),
+                params: parsed.map(|c| c.params.format_standard()),
+                output_path: task.output_path,
+                variants: task.variant_outputs,
+                message,
+                error_code,
+                warnings: task.warnings,
+            }
+        }).collect::<Vec<_>>()
+    }).await.map_err(|e| AppError::System(format!("预检线程异常: {}", e)))?;
+
+    let (ok, skipped) = entries.iter().fold((0, 0), |(ok, skipped), e| match e.status {
+        "ok" => (ok + 1, skipped),
+        "skipped" => (ok, skipped + 1),
+        _ => (ok, skipped),
+    });
+    info!("🔍 [API V3] Dry run 完成: {} 可处理, {} 跳过, {} 失败", ok, skipped, entries.len() - ok - skipped);
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::OriginalCopy;
+    use crate::test_support::{fixture, temp_dir};
+
+    #[test]
+    fn png_levels_round_trip_losslessly() {
+        let img = image::RgbImage::from_fn(64, 48, |x, y| image::Rgb([(x * 4) as u8, (y * 5) as u8, ((x ^ y) * 3) as u8]));
+        for level in [PngCompression::Fast, PngCompression::Default, PngCompression::Best] {
+            let (compression, filter) = png_settings(level);
+            let mut encoded = Vec::new();
+            PngEncoder::new_with_quality(&mut encoded, compression, filter)
+                .write_image(img.as_raw(), 64, 48, image::ExtendedColorType::Rgb8)
+                .unwrap();
+            let decoded = image::load_from_memory(&encoded).unwrap().to_rgb8();
+            assert_eq!(decoded, img, "{:?}", level);
+        }
+    }
+
+    #[test]
+    fn lossy_webp_request_is_accepted() {
+        // 有损 WebP 不再整批拒绝：启用 libwebp 时有损编码，否则回退为无损 (见 webp::fallback_note)
+        let files = vec![fixture("ev_spot.jpg")];
+        let webp = |quality| ExportConfig { format: ExportImageFormat::Webp, quality, ..ExportConfig::default() };
+        assert!(validate_batch_request(&files, &webp(90)).is_ok());
+        assert!(validate_batch_request(&files, &webp(100)).is_ok());
+    }
+
+    #[test]
+    fn empty_file_fails_as_truncated_before_size_limit() {
+        // 0 字节的文件 (读卡中断) 是错误而不是跳过，错误码让前端单独提示重新拷贝
+        for limit in [None, Some(1)] {
+            let err = file_size_skip_reason(&fixture("empty.jpg"), limit).unwrap_err();
+            assert_eq!(err.code(), "TRUNCATED_FILE");
+            assert!(err.to_string().contains("空文件 (0 字节)"), "{}", err);
+        }
+    }
+
+    #[test]
+    fn oversized_file_is_skipped_with_reason() {
+        // 截断的文件本身不为空，大小检查照常放行，交给解码前的结束标记检查
+        let path = fixture("truncated_60.jpg");
+        assert_eq!(file_size_skip_reason(&path, None).unwrap(), None);
+        assert_eq!(file_size_skip_reason(&path, Some(1_048_576)).unwrap(), None);
+        let reason = file_size_skip_reason(&path, Some(1_000)).unwrap().unwrap();
+        assert_eq!(reason, SkipReason::FileTooLarge { size_mb: 0 });
+        assert_eq!(reason.code(), "file_too_large");
+        // 原因文字固定，不同大小的文件在批次总结里归为同一类
+        assert_eq!(reason.message(), "文件过大");
+        assert_eq!(SkipReason::FileTooLarge { size_mb: 900 }.message(), reason.message());
+    }
+
+    #[test]
+    fn skip_reason_serializes_a_stable_code() {
+        assert_eq!(
+            serde_json::to_value(SkipReason::OutputExists).unwrap(),
+            json!({ "code": "output_exists", "message": "输出已存在" })
+        );
+        assert_eq!(serde_json::to_value(SkipReason::NoExif).unwrap()["code"], "no_exif");
+    }
+
+    #[test]
+    fn fast_png_skips_adaptive_filter() {
+        // fast 不能和 image 的默认设置 (Fast + Adaptive) 一样
+        assert_eq!(png_settings(PngCompression::Fast), (CompressionType::Fast, PngFilter::Up));
+        assert!(matches!(png_settings(PngCompression::Best).1, PngFilter::Adaptive));
+    }
+
+    #[test]
+    fn original_copy_renamed_to_match_output() {
+        let dir = temp_dir("pipeline_copy_rename");
+        let src = dir.join("DSC_0001.NEF");
+        std::fs::write(&src, b"raw bytes").unwrap();
+        let modified = std::time::SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        std::fs::File::options().write(true).open(&src).unwrap().set_modified(modified).unwrap();
+
+        let style: StyleOptions = serde_json::from_value(serde_json::json!({ "style": "WhiteClassic" })).unwrap();
+        let output = dir.join(format!("Trip_01_{}.jpg", style.filename_suffix()));
+        let cfg = OriginalCopy { subfolder: Some("originals".into()), rename_to_match: true };
+        let dest = calculate_original_copy_path(&src.to_string_lossy(), &output, &cfg, &style).unwrap();
+        // 取成品文件名去掉样式后缀，保留原图扩展名
+        assert_eq!(dest, dir.join("originals").join("Trip_01.NEF"));
+
+        assert!(copy_original_to(&src, &dest).unwrap());
+        assert_eq!(std::fs::read(&dest).unwrap(), b"raw bytes");
+        assert_eq!(std::fs::metadata(&dest).unwrap().modified().unwrap(), modified);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn original_copy_onto_itself_is_skipped() {
+        // 输出到原图目录且不改名：目标就是源文件本身
+        let dir = temp_dir("pipeline_copy_same");
+        let src = dir.join("DSC_0002.JPG");
+        std::fs::write(&src, b"jpeg bytes").unwrap();
+
+        let style: StyleOptions = serde_json::from_value(serde_json::json!({ "style": "WhiteClassic" })).unwrap();
+        let output = dir.join(format!("DSC_0002_{}.jpg", style.filename_suffix()));
+        let cfg = OriginalCopy { subfolder: None, rename_to_match: false };
+        let dest = calculate_original_copy_path(&src.to_string_lossy(), &output, &cfg, &style).unwrap();
+        assert_eq!(dest, src);
+
+        assert!(!copy_original_to(&src, &dest).unwrap());
+        // 同一文件经由不同写法的路径也能识别
+        assert!(!copy_original_to(&src, &dir.join(".").join("DSC_0002.JPG")).unwrap());
+        assert_eq!(std::fs::read(&src).unwrap(), b"jpeg bytes");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    // 走完整流水线 (检查 → 解码 → 绘制 → 编码写盘) 处理一张图，返回写出的文件内容
+    // 模糊背景样式：扩展画布和模糊背景都在 rayon 里逐行并行，最能暴露线程调度带来的差异
+    fn render_through_pipeline(source: &Path, out_dir: &Path, deterministic: bool, threads: usize) -> Vec<u8> {
+        let context: crate::models::BatchContext = serde_json::from_value(json!({
+            "style": "TransparentClassic",
+            "exportSettings": { "targetDir": out_dir, "format": "jpg", "quality": 95 },
+            "deterministic": deterministic,
+            "allowMissingExif": true,
+        })).unwrap();
+        let global = GlobalContext::for_test(&context, 1);
+        let processor = crate::processor::create_context_processor(&context).unwrap();
+        let pipeline = build_pipeline(Arc::new(processor));
+        build_pool(threads).unwrap().install(|| {
+            pipeline.run_guarded(&global, None, source.to_string_lossy().into_owned(), 0);
+        });
+        assert_eq!(global.tally.lock().unwrap().summary("finished", 1, Duration::ZERO).succeeded, 1);
+
+        let mut outputs = std::fs::read_dir(out_dir).unwrap().map(|e| e.unwrap().path());
+        let output = outputs.next().expect("没有写出成品");
+        assert!(outputs.next().is_none());
+        std::fs::read(output).unwrap()
+    }
+
+    fn gradient_source(dir: &Path) -> PathBuf {
+        let source = dir.join("source.png");
+        image::RgbImage::from_fn(640, 480, |x, y| {
+            image::Rgb([(x * 7 % 256) as u8, (y * 3 % 256) as u8, ((x * y) % 256) as u8])
+        }).save(&source).unwrap();
+        source
+    }
+
+    #[test]
+    fn deterministic_mode_is_byte_identical() {
+        let dir = temp_dir("pipeline_deterministic");
+        let source = gradient_source(&dir);
+        // 确定性模式照常并发：单线程和多线程池里的两次输出逐字节一致
+        let first = render_through_pipeline(&source, &dir.join("a"), true, 1);
+        let second = render_through_pipeline(&source, &dir.join("b"), true, 4);
+        assert!(image::load_from_memory(&first).is_ok());
+        assert!(first == second, "确定性模式两次输出不一致");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn normal_mode_renders() {
+        // 普通模式只要求成功出图
+        let dir = temp_dir("pipeline_normal_mode");
+        let source = gradient_source(&dir);
+        let bytes = render_through_pipeline(&source, &dir.join("out"), false, 4);
+        assert!(image::load_from_memory(&bytes).is_ok());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
 
-    Ok(format!("Done in {:.2?}", duration))
-}
\ No newline at end of file
@@ -0,0 +1,188 @@
+// src-tauri/src/batch/report.rs
+
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use log::debug;
+use serde::Serialize;
+
+use crate::error::AppError;
+use crate::models::{ExportConfig, StyleOptions};
+use crate::utils::write_file_atomic;
+
+// =========================================================
+// 🟢 批次报告：framer_report_{时间戳}.json (可选 .csv)
+// 记录每个文件的处理结果，大批量跑完后方便排查哪些失败了、为什么
+// =========================================================
+
+/// 单个文件的处理结果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileReport {
+    // 批次内序号，仅用于排序 (并发完成的顺序是乱的)
+    #[serde(skip)]
+    pub index: usize,
+    pub source_path: String,
+    pub output_path: Option<PathBuf>,
+    // copy_original 模式下原图副本的位置 (没有复制时为空)
+    pub original_copy_path: Option<PathBuf>,
+    pub status: &'static str, // success / skipped / error
+    pub error_code: Option<&'static str>,
+    pub message: Option<String>, // 跳过原因或错误信息
+    pub processing_ms: u128,
+}
+
+/// 报告文件的完整结构
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BatchReport<'a> {
+    app_version: &'static str,
+    generated_at: String, // UTC
+    style: &'a StyleOptions,
+    export: &'a ExportConfig,
+    duration_ms: u128,
+    total: usize,
+    succeeded: usize,
+    skipped: usize,
+    failed: usize,
+    originals_copied: usize,
+    files: &'a [FileReport],
+}
+
+/// 报告写到哪里：自定义输出目录，或第一个原图所在目录 (原图同级模式)
+pub fn report_dir(export: &ExportConfig, file_paths: &[String]) -> Option<PathBuf> {
+    match &export.target_dir {
+        Some(dir) => Some(PathBuf::from(dir)),
+        None => Path::new(file_paths.first()?).parent().map(Path::to_path_buf),
+    }
+}
+
+/// 写入报告，返回 JSON 报告的路径
+/// with_csv 时在旁边再写一份同名 .csv (方便用表格软件筛选)
+pub fn write_report(
+    dir: &Path,
+    files: &mut [FileReport],
+    style: &StyleOptions,
+    export: &ExportConfig,
+    duration_ms: u128,
+    with_csv: bool,
+) -> Result<PathBuf, AppError> {
+    files.sort_by_key(|f| f.index);
+
+    let count = |status: &str| files.iter().filter(|f| f.status == status).count();
+    let report = BatchReport {
+        app_version: env!("CARGO_PKG_VERSION"),
+        generated_at: utc_timestamp("-", " ", ":"),
+        style,
+        export,
+        duration_ms,
+        total: files.len(),
+        succeeded: count("success"),
+        skipped: count("skipped"),
+        failed: count("error"),
+        originals_copied: files.iter().filter(|f| f.original_copy_path.is_some()).count(),
+        files,
+    };
+
+    std::fs::create_dir_all(dir)?;
+    let path = dir.join(format!("framer_report_{}.json", utc_timestamp("", "_", "")));
+    let json = serde_json::to_vec_pretty(&report)
+        .map_err(|e| AppError::System(format!("报告序列化失败: {}", e)))?;
+    write_file_atomic(&path, &json)?;
+    debug!("📝 [Report] 已写入: {:?}", path);
+
+    if with_csv {
+        let csv_path = path.with_extension("csv");
+        write_file_atomic(&csv_path, to_csv(files).as_bytes())?;
+        debug!("📝 [Report] 已写入: {:?}", csv_path);
+    }
+
+    Ok(path)
+}
+
+fn to_csv(files: &[FileReport]) -> String {
+    let mut out = String::from("source_path,output_path,original_copy_path,status,error_code,message,processing_ms\n");
+    let path_field = |p: &Option<PathBuf>| csv_field(&p.as_ref().map(|p| p.to_string_lossy().into_owned()).unwrap_or_default());
+    for f in files {
+        let row = [
+            csv_field(&f.source_path),
+            path_field(&f.output_path),
+            path_field(&f.original_copy_path),
+            f.status.to_string(),
+            f.error_code.unwrap_or_default().to_string(),
+            csv_field(f.message.as_deref().unwrap_or_default()),
+            f.processing_ms.to_string(),
+        ];
+        out.push_str(&row.join(","));
+        out.push('\n');
+    }
+    out
+}
+
+// 含逗号、引号、换行的字段用双引号包裹，内部引号写两遍
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// 当前 UTC 时间，如 ("-", " ", ":") -> "2024-05-01 10:20:30"，("", "_", "") -> "20240501_102030"
+fn utc_timestamp(date_sep: &str, mid: &str, time_sep: &str) -> String {
+    let secs = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let (days, rem) = (secs / 86_400, secs % 86_400);
+
+    // 公历换算 (Howard Hinnant 的 civil_from_days 算法)
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}{ds}{:02}{ds}{:02}{mid}{:02}{ts}{:02}{ts}{:02}",
+        year, month, day, rem / 3600, (rem / 60) % 60, rem % 60,
+        ds = date_sep, mid = mid, ts = time_sep,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_lists_original_copies() {
+        let dir = std::env::temp_dir().join(format!("framer_report_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let file = |index, copy: Option<&str>| FileReport {
+            index,
+            source_path: format!("/in/DSC_{}.NEF", index),
+            output_path: Some(PathBuf::from(format!("/out/DSC_{}_WhiteClassic.jpg", index))),
+            original_copy_path: copy.map(PathBuf::from),
+            status: "success",
+            error_code: None,
+            message: None,
+            processing_ms: 10,
+        };
+        let mut files = vec![file(1, None), file(0, Some("/out/originals/DSC_0.NEF"))];
+        let style: StyleOptions = serde_json::from_str(r#"{"style":"WhiteClassic"}"#).unwrap();
+
+        let path = write_report(&dir, &mut files, &style, &ExportConfig::default(), 20, true).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+        let csv = std::fs::read_to_string(path.with_extension("csv")).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+
+        assert_eq!(json["originalsCopied"], 1);
+        assert_eq!(json["files"][0]["originalCopyPath"], "/out/originals/DSC_0.NEF");
+        assert!(json["files"][1]["originalCopyPath"].is_null());
+        let rows: Vec<&str> = csv.lines().collect();
+        assert!(rows[0].starts_with("source_path,output_path,original_copy_path,"));
+        assert_eq!(rows[1], "/in/DSC_0.NEF,/out/DSC_0_WhiteClassic.jpg,/out/originals/DSC_0.NEF,success,,,10");
+        assert_eq!(rows[2], "/in/DSC_1.NEF,/out/DSC_1_WhiteClassic.jpg,,success,,,10");
+    }
+}
@@ -0,0 +1,276 @@
+// src-tauri/src/batch/resume.rs
+
+use std::collections::HashSet;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::UNIX_EPOCH;
+
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::error::AppError;
+use crate::models::{BatchContext, ExportConfig};
+
+// =========================================================
+// 🟢 断点续传：记录本批次已完成的文件
+// =========================================================
+// 清单是 app 数据目录下的 JSON Lines 文件：
+//   第 1 行：批次参数 (样式 + 生效的导出配置 + 边框文字 / 签名等所有影响成品的设置)
+//   之后每行：一个已成功输出的源文件 (路径 + 修改时间)
+// 每完成一张追加一行，崩溃时最多丢最后一行；批次正常结束后删除。
+// 只有续传批次 (批处理按钮) 读写清单；单张处理等不续传的任务完全不碰它，
+// 否则中途处理一张预览就会把被中断批次的记录清空。
+
+pub const MANIFEST_FILE_NAME: &str = "batch_resume.jsonl";
+
+/// 清单头：影响成品内容的参数，任何一项变了之前的成品就不能算数
+/// 新增会改变输出的 BatchContext 字段时要同步加在这里 (缺项的清单解析失败，按损坏处理)
+#[derive(Serialize, Deserialize)]
+struct ManifestHeader {
+    style: Value,
+    export: Value,
+    // 🟢 边框固定文字 (换了语言的批次不能和之前的成品混在一起)
+    labels: Value,
+    // 🟢 布局覆盖 (已包含在 style 里，单独记录一份便于日志里看出是哪项变了)；为空时是 null
+    layout_overrides: Value,
+    // 🟢 叠加签名
+    signature: Value,
+    // 🟢 手动填写的边框文字 (机型 / 参数 / 作者)
+    overrides: Value,
+    // 🟢 是否给带成品标记的文件再加边框 (决定了哪些文件会有输出)
+    reframe_outputs: bool,
+}
+
+impl ManifestHeader {
+    fn new(context: &BatchContext, export: &ExportConfig) -> Result<Self, AppError> {
+        fn to_value<T: Serialize>(v: &T) -> Result<Value, AppError> {
+            serde_json::to_value(v).map_err(|e| AppError::System(format!("清单序列化失败: {}", e)))
+        }
+        Ok(Self {
+            style: to_value(&context.options)?,
+            export: to_value(export)?,
+            // None 和显式传默认值是同一套文字
+            labels: to_value(&context.labels.clone().unwrap_or_default())?,
+            layout_overrides: to_value(&context.options.layout_overrides().filter(|o| !o.is_empty()))?,
+            signature: to_value(&context.signature)?,
+            overrides: to_value(&context.overrides)?,
+            reframe_outputs: context.reframe_outputs,
+        })
+    }
+
+    /// 与期望的参数不一致时返回变化的项 (只用于日志)
+    fn mismatch(&self, expected: &ManifestHeader) -> Option<&'static str> {
+        [
+            ("样式", self.style == expected.style),
+            ("导出设置", self.export == expected.export),
+            ("边框文字", self.labels == expected.labels),
+            ("布局覆盖", self.layout_overrides == expected.layout_overrides),
+            ("签名", self.signature == expected.signature),
+            ("手动文字", self.overrides == expected.overrides),
+            ("成品重跑", self.reframe_outputs == expected.reframe_outputs),
+        ]
+        .into_iter()
+        .find(|(_, same)| !same)
+        .map(|(name, _)| name)
+    }
+}
+
+/// 已完成的源文件；修改时间变了说明原图被编辑过，需要重新处理
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct CompletedEntry {
+    pub path: String,
+    pub mtime: u64,
+}
+
+/// 正在写入的清单
+pub struct ResumeJournal {
+    path: PathBuf,
+    file: Mutex<File>,
+}
+
+impl ResumeJournal {
+    /// 打开清单
+    /// resume = false：不记录断点，也不读写清单文件 (返回 None)
+    /// resume = true：参数一致时沿用旧清单，返回其中已完成的文件；否则清空重写 (参数已变，旧记录失效)
+    pub fn open(
+        path: PathBuf,
+        context: &BatchContext,
+        export: &ExportConfig,
+        resume: bool,
+    ) -> Result<(Option<Self>, HashSet<CompletedEntry>), AppError> {
+        if !resume {
+            return Ok((None, HashSet::new()));
+        }
+
+        let header = ManifestHeader::new(context, export)?;
+
+        match read_completed(&path, &header) {
+            Some(done) => {
+                info!("⏯️ [Resume] 沿用清单，已完成 {} 张", done.len());
+                let file = OpenOptions::new().append(true).open(&path)?;
+                return Ok((Some(Self { path, file: Mutex::new(file) }), done));
+            },
+            None => debug!("⏯️ [Resume] 没有可用的清单，从头开始"),
+        }
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut file = File::create(&path)?;
+        let line = serde_json::to_string(&header).map_err(|e| AppError::System(format!("清单序列化失败: {}", e)))?;
+        writeln!(file, "{}", line)?;
+        Ok((Some(Self { path, file: Mutex::new(file) }), HashSet::new()))
+    }
+
+    /// 追加一条完成记录 (失败只警告，不影响成品)
+    pub fn record(&self, source_path: &str) {
+        let Some(mtime) = source_mtime(source_path) else { return };
+        let entry = CompletedEntry { path: source_path.to_string(), mtime };
+        let Ok(line) = serde_json::to_string(&entry) else { return };
+
+        let mut file = self.file.lock().unwrap();
+        if let Err(e) = writeln!(file, "{}", line).and_then(|_| file.flush()) {
+            warn!("⚠️ [Resume] 清单写入失败: {}", e);
+        }
+    }
+
+    /// 批次完成，删除清单
+    pub fn remove(&self) {
+        if let Err(e) = std::fs::remove_file(&self.path) {
+            debug!("⚠️ [Resume] 清单删除失败 {:?}: {}", self.path, e);
+        }
+    }
+}
+
+/// 读取旧清单；不存在、损坏或参数不一致时返回 None
+fn read_completed(path: &Path, expected: &ManifestHeader) -> Option<HashSet<CompletedEntry>> {
+    let mut lines = BufReader::new(File::open(path).ok()?).lines();
+
+    let header: ManifestHeader = serde_json::from_str(&lines.next()?.ok()?).ok()?;
+    if let Some(changed) = header.mismatch(expected) {
+        info!("⏯️ [Resume] {}已变更，旧清单作废", changed);
+        return None;
+    }
+
+    // 崩溃时最后一行可能只写了一半，解析失败的行直接忽略
+    Some(lines
+        .map_while(Result::ok)
+        .filter_map(|l| serde_json::from_str::<CompletedEntry>(&l).ok())
+        .collect())
+}
+
+/// 源文件修改时间 (毫秒)
+fn source_mtime(path: &str) -> Option<u64> {
+    let modified = std::fs::metadata(path).and_then(|m| m.modified()).ok()?;
+    Some(modified.duration_since(UNIX_EPOCH).ok()?.as_millis() as u64)
+}
+
+/// 去掉清单里已完成且未被修改过的文件，返回 (待处理列表, 跳过数量)
+/// 列表项是 (原始序号, 路径)，过滤后序号保持不变
+pub fn filter_completed(file_paths: Vec<(usize, String)>, done: &HashSet<CompletedEntry>) -> (Vec<(usize, String)>, usize) {
+    if done.is_empty() {
+        return (file_paths, 0);
+    }
+    let before = file_paths.len();
+    let remaining: Vec<(usize, String)> = file_paths
+        .into_iter()
+        .filter(|(_, p)| match source_mtime(p) {
+            Some(mtime) => !done.contains(&CompletedEntry { path: p.clone(), mtime }),
+            None => true,
+        })
+        .collect();
+    let skipped = before - remaining.len();
+    (remaining, skipped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::FrameLabels;
+
+    fn context(extra: Value) -> BatchContext {
+        let mut json = serde_json::json!({ "style": "WhiteClassic" });
+        if let (Some(obj), Value::Object(extra)) = (json.as_object_mut(), extra) {
+            obj.extend(extra);
+        }
+        serde_json::from_value(json).unwrap()
+    }
+
+    // 每个测试用独立的临时目录，避免并行测试互相覆盖清单
+    fn manifest_path(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("framer_resume_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir.join(MANIFEST_FILE_NAME)
+    }
+
+    // 新建清单并记录一张已完成的源图，返回其路径
+    fn start_batch(path: &Path, ctx: &BatchContext) -> String {
+        let (journal, done) = ResumeJournal::open(path.to_path_buf(), ctx, &ExportConfig::default(), true).unwrap();
+        assert!(done.is_empty());
+        let src = path.parent().unwrap().join("a.jpg");
+        std::fs::write(&src, b"x").unwrap();
+        let src = src.to_string_lossy().to_string();
+        journal.unwrap().record(&src);
+        src
+    }
+
+    fn resumed(path: &Path, ctx: &BatchContext) -> usize {
+        ResumeJournal::open(path.to_path_buf(), ctx, &ExportConfig::default(), true).unwrap().1.len()
+    }
+
+    #[test]
+    fn labels_change_invalidates_manifest() {
+        let path = manifest_path("labels");
+        start_batch(&path, &context(Value::Null));
+
+        // 参数不变：沿用清单
+        assert_eq!(resumed(&path, &context(Value::Null)), 1);
+        // 显式传默认文字与 None 等价
+        let defaults = serde_json::json!({ "labels": serde_json::to_value(FrameLabels::default()).unwrap() });
+        assert_eq!(resumed(&path, &context(defaults)), 1);
+        // 换成中文：旧清单作废
+        assert_eq!(resumed(&path, &context(serde_json::json!({ "labels": { "aperture": "光圈" } }))), 0);
+
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    // 影响成品的每一项设置变了，都不能沿用之前的成品
+    #[test]
+    fn output_affecting_settings_invalidate_manifest() {
+        let changes = [
+            serde_json::json!({ "signature": { "text": "Tom", "fontScale": 1.0, "bottomRatio": 0.1 } }),
+            serde_json::json!({ "layoutOverrides": { "border_ratio": 0.1 } }),
+            serde_json::json!({ "overrides": { "model": "Z 8" } }),
+            serde_json::json!({ "reframeOutputs": true }),
+        ];
+        for (i, change) in changes.into_iter().enumerate() {
+            let path = manifest_path(&format!("settings{}", i));
+            start_batch(&path, &context(Value::Null));
+            assert_eq!(resumed(&path, &context(change.clone())), 0, "{}", change);
+            let _ = std::fs::remove_dir_all(path.parent().unwrap());
+        }
+    }
+
+    // 单张处理 (不续传) 不能清空被中断批次的清单
+    #[test]
+    fn non_resume_run_leaves_manifest_untouched() {
+        let path = manifest_path("untouched");
+        let src = start_batch(&path, &context(Value::Null));
+        let before = std::fs::read(&path).unwrap();
+
+        // 换了样式的单张处理
+        let other: BatchContext = serde_json::from_value(serde_json::json!({ "style": "WhiteMaster" })).unwrap();
+        let (journal, done) = ResumeJournal::open(path.clone(), &other, &ExportConfig::default(), false).unwrap();
+        assert!(journal.is_none() && done.is_empty());
+        assert_eq!(std::fs::read(&path).unwrap(), before);
+
+        // 回到批处理仍然能续传
+        let (_, done) = ResumeJournal::open(path.clone(), &context(Value::Null), &ExportConfig::default(), true).unwrap();
+        assert!(done.iter().any(|e| e.path == src));
+
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+}
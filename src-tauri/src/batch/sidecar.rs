@@ -0,0 +1,256 @@
+// src-tauri/src/batch/sidecar.rs
+
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use tauri::{State, Window};
+
+use crate::error::AppError;
+use crate::models::{BatchContext, ExportConfig, FrameLabels, SignatureConfig, StyleOptions, TextOverrides};
+use crate::parser::models::ParsedImageContext;
+use crate::utils::write_file_atomic;
+use crate::AppState;
+
+// =========================================================
+// 🟢 Sidecar：每张输出旁边的 {output_stem}.framer.json
+// 记录“这张图是怎么生成的”，半年后也能用同样的参数重新生成
+// =========================================================
+
+/// 解析后的 EXIF 摘要 (只保留排版实际用到的字段)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SidecarExifSummary {
+    pub brand: String,
+    pub model: String,
+    pub lens: String,
+    pub params: String,
+    pub capture_time: String,
+    pub artist: Option<String>,
+}
+
+impl From<&ParsedImageContext> for SidecarExifSummary {
+    fn from(ctx: &ParsedImageContext) -> Self {
+        Self {
+            brand: ctx.brand.to_string(),
+            model: ctx.model_name.clone(),
+            lens: ctx.params.lens_model.clone(),
+            params: ctx.params.format_standard(),
+            capture_time: ctx.params.capture_time.clone(),
+            artist: ctx.artist_name.clone(),
+        }
+    }
+}
+
+/// Sidecar 文件的完整结构
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SidecarRecord {
+    pub app_version: String,
+    pub style: StyleOptions,
+    pub export: ExportConfig,
+    pub source_path: String,
+    pub source_hash: String,
+    pub exif: Option<SidecarExifSummary>,
+    pub processing_ms: u128,
+    #[serde(default)]
+    pub deterministic: bool,
+    // 叠加签名 (旧版本 sidecar 没有这个字段)
+    #[serde(default)]
+    pub signature: Option<SignatureConfig>,
+    // 🟢 手动填写的边框文字 (exif 摘要里已经是覆盖后的结果，这里记录原始输入以便重新生成)
+    #[serde(default)]
+    pub overrides: Option<TextOverrides>,
+    // 🟢 边框固定文字 (None 代表默认英文)
+    #[serde(default)]
+    pub labels: Option<FrameLabels>,
+}
+
+/// 根据输出路径计算 sidecar 路径: xxx_WhiteClassic.jpg -> xxx_WhiteClassic.framer.json
+pub fn sidecar_path_for(output_path: &Path) -> PathBuf {
+    output_path.with_extension("framer.json")
+}
+
+/// 写入 sidecar (原子写入：临时文件 + rename)
+pub fn write_sidecar(output_path: &Path, record: &SidecarRecord) -> Result<PathBuf, AppError> {
+    let path = sidecar_path_for(output_path);
+    let json = serde_json::to_vec_pretty(record)
+        .map_err(|e| AppError::System(format!("Sidecar 序列化失败: {}", e)))?;
+
+    write_file_atomic(&path, &json)?;
+    debug!("📝 [Sidecar] 已写入: {:?}", path);
+    Ok(path)
+}
+
+/// 从 sidecar 还原出当时的 BatchContext 和源文件路径
+/// 供 regenerate_from_sidecar (以及以后的“失败重试”、“仅更新元数据”) 复用原始参数
+pub fn load_context_from_sidecar(path: &Path) -> Result<(BatchContext, String), AppError> {
+    let content = std::fs::read_to_string(path)?;
+    let record: SidecarRecord = serde_json::from_str(&content).map_err(|e| {
+        warn!("⚠️ [Sidecar] 解析失败 [{:?}]: {}", path, e);
+        AppError::System(format!("Sidecar 解析失败: {}", e))
+    })?;
+
+    let context = BatchContext {
+        options: record.style,
+        export: record.export,
+        deterministic: record.deterministic,
+        // sidecar 里记录的已经是调优后的生效配置
+        auto_export_tuning: false,
+        // 并发数只影响速度和内存，不影响输出，重新按当前机器决定
+        max_concurrent: None,
+        // 超时与大小限制只是保护措施，同样按默认值
+        file_timeout_secs: crate::models::default_file_timeout_secs(),
+        max_file_size_mb: None,
+        signature: record.signature,
+        emit_thumbnails: true,
+        overrides: record.overrides,
+        labels: record.labels,
+        // 能写出 sidecar 说明当时已经处理过，按允许无 EXIF 还原
+        allow_missing_exif: true,
+        reframe_outputs: false,
+        decode_cache_size: None,
+        // 只影响事件顺序
+        ordered_progress: false,
+    };
+    Ok((context, record.source_path))
+}
+
+/// 🟢 按 sidecar 记录的参数重新生成这张图 (走普通批处理流程，只有一个文件)
+#[tauri::command]
+pub async fn regenerate_from_sidecar(
+    window: Window,
+    state: State<'_, Arc<AppState>>,
+    sidecar_path: String,
+) -> Result<String, AppError> {
+    let (context, source_path) = load_context_from_sidecar(Path::new(&sidecar_path))?;
+    if !Path::new(&source_path).exists() {
+        return Err(AppError::System(format!("Sidecar 记录的源文件已不存在: {}", source_path)));
+    }
+    info!("♻️ [Sidecar] 按 {} 重新生成 {}", sidecar_path, source_path);
+    super::start_batch_process_v3(window, state, vec![source_path], context, false).await
+}
+
+/// 计算源文件的稳定哈希 (FNV-1a 64)
+/// 只用于校验“源文件是否还是当初那一张”，不需要密码学强度
+pub fn hash_file(path: &str) -> Result<String, AppError> {
+    const FNV_OFFSET: u64 = 0xcbf29ce484222325;
+    const FNV_PRIME: u64 = 0x100000001b3;
+
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut buf = [0u8; 64 * 1024];
+    let mut hash = FNV_OFFSET;
+
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 { break; }
+        for byte in &buf[..n] {
+            hash ^= *byte as u64;
+            hash = hash.wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    Ok(format!("fnv1a64:{:016x}", hash))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_dir;
+
+    fn record(source_path: &str) -> SidecarRecord {
+        let style: StyleOptions = serde_json::from_value(serde_json::json!({
+            "style": "WhiteMaster", "showEv": true, "layoutOverrides": { "border_ratio": 0.12 }
+        }))
+        .unwrap();
+        SidecarRecord {
+            app_version: "test".to_string(),
+            style,
+            export: ExportConfig { quality: 88, ..ExportConfig::default() },
+            source_path: source_path.to_string(),
+            source_hash: hash_file(source_path).unwrap(),
+            exif: None,
+            processing_ms: 12,
+            deterministic: true,
+            signature: Some(SignatureConfig { text: "Tom".to_string(), font_scale: 0.05, bottom_ratio: 0.1 }),
+            overrides: Some(TextOverrides { model: Some("Z 8".to_string()), ..TextOverrides::default() }),
+            labels: Some(FrameLabels { aperture: "光圈".to_string(), ..FrameLabels::default() }),
+        }
+    }
+
+    fn json<T: Serialize>(v: &T) -> serde_json::Value {
+        serde_json::to_value(v).unwrap()
+    }
+
+    #[test]
+    fn sidecar_round_trips_into_batch_context() {
+        let dir = temp_dir("sidecar_roundtrip");
+        let source = dir.join("DSC_0001.jpg");
+        std::fs::write(&source, b"source bytes").unwrap();
+        let output = dir.join("DSC_0001_WhiteMaster.jpg");
+        let rec = record(&source.to_string_lossy());
+
+        let path = write_sidecar(&output, &rec).unwrap();
+        assert_eq!(path, dir.join("DSC_0001_WhiteMaster.framer.json"));
+        // 原子写入不留临时文件
+        assert!(!dir.join("DSC_0001_WhiteMaster.framer.json.tmp").exists());
+
+        let (ctx, source_path) = load_context_from_sidecar(&path).unwrap();
+        assert_eq!(source_path, rec.source_path);
+        assert_eq!(json(&ctx.options), json(&rec.style));
+        assert_eq!(json(&ctx.export), json(&rec.export));
+        assert_eq!(json(&ctx.signature), json(&rec.signature));
+        assert_eq!(json(&ctx.overrides), json(&rec.overrides));
+        assert_eq!(json(&ctx.labels), json(&rec.labels));
+        assert!(ctx.deterministic);
+        // 记录的是调优后的配置，不能再调一次
+        assert!(!ctx.auto_export_tuning);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    // 旧版本 sidecar 没有签名 / 覆盖 / 文字表字段
+    #[test]
+    fn legacy_sidecar_without_optional_fields_loads() {
+        let dir = temp_dir("sidecar_legacy");
+        let path = dir.join("a.framer.json");
+        let legacy = serde_json::json!({
+            "appVersion": "1.0.0",
+            "style": { "style": "WhiteClassic" },
+            "export": json(&ExportConfig::default()),
+            "sourcePath": "/photos/a.jpg",
+            "sourceHash": "fnv1a64:0000000000000000",
+            "exif": null,
+            "processingMs": 5,
+        });
+        std::fs::write(&path, legacy.to_string()).unwrap();
+
+        let (ctx, source_path) = load_context_from_sidecar(&path).unwrap();
+        assert_eq!(source_path, "/photos/a.jpg");
+        assert!(ctx.signature.is_none() && ctx.labels.is_none() && !ctx.deterministic);
+
+        std::fs::write(&path, b"{ not json").unwrap();
+        assert!(load_context_from_sidecar(&path).is_err());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn hash_file_is_stable_and_content_sensitive() {
+        let dir = temp_dir("sidecar_hash");
+        let (a, b) = (dir.join("a"), dir.join("b"));
+        std::fs::write(&a, b"").unwrap();
+        // FNV-1a 64 的空输入就是 offset basis
+        assert_eq!(hash_file(&a.to_string_lossy()).unwrap(), "fnv1a64:cbf29ce484222325");
+        std::fs::write(&a, b"frame").unwrap();
+        std::fs::write(&b, b"framf").unwrap();
+        let ha = hash_file(&a.to_string_lossy()).unwrap();
+        assert_eq!(ha, hash_file(&a.to_string_lossy()).unwrap());
+        assert_ne!(ha, hash_file(&b.to_string_lossy()).unwrap());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
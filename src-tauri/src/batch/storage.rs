@@ -0,0 +1,331 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use log::{debug, error, warn};
+
+use crate::error::AppError;
+use crate::models::{BatchContext, ExportImageFormat};
+use crate::utils::NamingContext;
+
+// =========================================================
+// 目标磁盘保护 (空间不足 / 只读)
+// =========================================================
+
+// 连续多少次存储类错误后中止整个批次
+const STORAGE_FAILURE_THRESHOLD: usize = 3;
+
+/// 是否是"继续写也没用"的存储类错误 (磁盘满 / 只读 / 配额)
+pub fn is_storage_error(e: &io::Error) -> bool {
+    matches!(
+        e.kind(),
+        io::ErrorKind::StorageFull | io::ErrorKind::ReadOnlyFilesystem | io::ErrorKind::QuotaExceeded
+    )
+}
+
+/// 成品写盘接口：批次里用 FsWriter，测试注入模拟磁盘错误的实现
+pub trait OutputWriter: Send + Sync {
+    fn write(&self, path: &Path, data: &[u8]) -> io::Result<()>;
+}
+
+/// 写到本地文件系统 (目录不存在时自动创建)
+pub struct FsWriter;
+
+impl OutputWriter for FsWriter {
+    fn write(&self, path: &Path, data: &[u8]) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            if !parent.exists() {
+                std::fs::create_dir_all(parent).inspect_err(|e| error!("❌ [Save] 创建目录失败 {:?}: {}", parent, e))?;
+            }
+        }
+        let mut file = File::create(path).inspect_err(|e| error!("❌ [Save] 创建文件句柄失败 {:?}: {}", path, e))?;
+        file.write_all(data).inspect_err(|e| {
+            error!("❌ [Save] 写入失败 {:?}: {}", path, e);
+            drop(file);
+            // 写了一半的文件没有意义 (磁盘满时还会占着空间)
+            let _ = std::fs::remove_file(path);
+        })
+    }
+}
+
+/// 批次级存储熔断器
+/// 连续 N 次存储错误后置位 aborted：不再调度新文件，已在计算的文件也不再写盘
+pub struct StorageBreaker {
+    writer: Box<dyn OutputWriter>,
+    consecutive_failures: AtomicUsize,
+    aborted: AtomicBool,
+    // 触发熔断的那个文件还没有计入 failed (它的失败在中止之前)
+    trip_pending: AtomicBool,
+    reason: Mutex<Option<String>>,
+    // 中止前失败的文件数 / 因中止而未处理的文件数 (用于批次总结)
+    failed: AtomicUsize,
+    not_attempted: AtomicUsize,
+}
+
+impl Default for StorageBreaker {
+    fn default() -> Self {
+        Self::with_writer(Box::new(FsWriter))
+    }
+}
+
+impl StorageBreaker {
+    pub fn with_writer(writer: Box<dyn OutputWriter>) -> Self {
+        Self {
+            writer,
+            consecutive_failures: AtomicUsize::new(0),
+            aborted: AtomicBool::new(false),
+            trip_pending: AtomicBool::new(false),
+            reason: Mutex::new(None),
+            failed: AtomicUsize::new(0),
+            not_attempted: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn is_aborted(&self) -> bool {
+        self.aborted.load(Ordering::Relaxed)
+    }
+
+    /// 写出一个文件：成功清零连续失败计数，存储类错误计入熔断
+    /// 这一次写入触发熔断时调用 on_abort (全批次只会调用一次)
+    pub fn write(&self, path: &Path, data: &[u8], on_abort: impl FnOnce()) -> io::Result<()> {
+        match self.writer.write(path, data) {
+            Ok(()) => {
+                self.consecutive_failures.store(0, Ordering::Relaxed);
+                Ok(())
+            },
+            Err(e) => {
+                if is_storage_error(&e) && self.record_storage_failure(&e) {
+                    on_abort();
+                }
+                Err(e)
+            },
+        }
+    }
+
+    /// 记录一次存储错误，返回 true 代表这一次触发了熔断 (全批次只会返回一次)
+    fn record_storage_failure(&self, e: &io::Error) -> bool {
+        let n = self.consecutive_failures.fetch_add(1, Ordering::Relaxed) + 1;
+        if n < STORAGE_FAILURE_THRESHOLD || self.aborted.swap(true, Ordering::Relaxed) {
+            return false;
+        }
+        self.trip_pending.store(true, Ordering::Relaxed);
+        let reason = format!("连续 {} 次写入失败，目标磁盘已满或只读: {}", n, e);
+        error!("💥 [Storage] {}", reason);
+        *self.reason.lock().unwrap() = Some(reason);
+        true
+    }
+
+    /// 记录一个失败的文件。中止之后才报告的失败 (触发熔断的那个除外) 不算写入失败，
+    /// 这些文件的成品反正不会写盘，归入"因中止未处理"
+    pub fn record_failed(&self) {
+        if !self.is_aborted() || self.trip_pending.swap(false, Ordering::Relaxed) {
+            self.failed.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.not_attempted.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn record_not_attempted(&self) {
+        self.not_attempted.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// 中止时的总结文本 (未中止返回 None)
+    pub fn summary(&self) -> Option<String> {
+        let reason = self.reason.lock().unwrap().clone()?;
+        Some(format!(
+            "{}；写入失败 {} 个，因存储中止未处理 {} 个",
+            reason,
+            self.failed.load(Ordering::Relaxed),
+            self.not_attempted.load(Ordering::Relaxed),
+        ))
+    }
+}
+
+// =========================================================
+// 预检 (Pre-flight)
+// =========================================================
+
+// 输出大小相对原图的粗略倍数 (加边框后面积增大，PNG/WebP 无损体积更大)
+fn size_factor(format: &ExportImageFormat) -> f64 {
+    match format {
+        ExportImageFormat::Jpg => 1.5,
+        ExportImageFormat::Png => 4.0,
+        ExportImageFormat::Webp => 3.0,
+        // AVIF 有损压缩，通常只有 JPG 的一半左右
+        ExportImageFormat::Avif => 0.8,
+        // TIFF 不压缩，16 位输出还要再翻倍
+        ExportImageFormat::Tiff => 8.0,
+    }
+}
+
+/// 批次开始前检查目标磁盘：只读或空间明显不足直接报错，空间紧张返回警告文本
+pub fn preflight_check(file_paths: &[String], context: &BatchContext) -> Result<Option<String>, AppError> {
+    let factor = size_factor(&context.export.format);
+
+    // 按目标目录汇总预计写入量 (原图同级模式下可能分布在多个目录)
+    let mut needed: HashMap<PathBuf, u64> = HashMap::new();
+    // 这里只关心目标目录，文件名模板里的 EXIF 字段不影响结果
+    for path in file_paths {
+        let Ok(target) = context.calculate_target_path(path, NamingContext::default()) else { continue };
+        let Some(dir) = target.parent() else { continue };
+        let src_size = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+        *needed.entry(dir.to_path_buf()).or_default() += (src_size as f64 * factor) as u64;
+    }
+
+    let mut warnings = Vec::new();
+    for (dir, bytes) in needed {
+        let Some(probe) = nearest_existing(&dir) else { continue };
+        let Some(space) = volume_space(probe) else {
+            debug!("📂 [Storage] 无法查询磁盘空间，跳过预检: {:?}", dir);
+            continue;
+        };
+
+        if space.read_only {
+            return Err(AppError::Storage(format!("目标目录所在磁盘为只读: {:?}", dir)));
+        }
+        if space.available < bytes / 2 {
+            return Err(AppError::Storage(format!(
+                "目标磁盘空间不足: {:?} 预计需要 {} MB，可用 {} MB",
+                dir, bytes / 1_048_576, space.available / 1_048_576
+            )));
+        }
+        if space.available < bytes {
+            let msg = format!(
+                "目标磁盘空间紧张: {:?} 预计需要 {} MB，可用 {} MB",
+                dir, bytes / 1_048_576, space.available / 1_048_576
+            );
+            warn!("⚠️ [Storage] {}", msg);
+            warnings.push(msg);
+        }
+    }
+
+    Ok(if warnings.is_empty() { None } else { Some(warnings.join("; ")) })
+}
+
+// 目标目录可能还不存在 (会自动创建)，向上找到第一个存在的祖先目录来查询
+fn nearest_existing(dir: &Path) -> Option<&Path> {
+    dir.ancestors().find(|p| p.exists())
+}
+
+struct VolumeSpace {
+    available: u64,
+    read_only: bool,
+}
+
+#[cfg(unix)]
+fn volume_space(path: &Path) -> Option<VolumeSpace> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = CString::new(path.as_os_str().as_bytes()).ok()?;
+    // SAFETY: statvfs 只写入我们传入的结构体，c_path 在调用期间有效
+    let mut st: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(c_path.as_ptr(), &mut st) } != 0 {
+        return None;
+    }
+    Some(VolumeSpace {
+        available: st.f_bavail as u64 * st.f_frsize as u64,
+        read_only: st.f_flag & libc::ST_RDONLY != 0,
+    })
+}
+
+// Windows 上暂不预检 (需要额外的系统 API 依赖)，依靠批次中的熔断器兜底
+#[cfg(not(unix))]
+fn volume_space(_path: &Path) -> Option<VolumeSpace> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    // 前 ok_writes 次写入成功，之后一直报磁盘已满
+    struct FullDisk {
+        ok_writes: usize,
+        calls: Arc<AtomicUsize>,
+    }
+
+    impl OutputWriter for FullDisk {
+        fn write(&self, _path: &Path, _data: &[u8]) -> io::Result<()> {
+            if self.calls.fetch_add(1, Ordering::Relaxed) < self.ok_writes {
+                Ok(())
+            } else {
+                Err(io::Error::from(io::ErrorKind::StorageFull))
+            }
+        }
+    }
+
+    #[test]
+    fn consecutive_enospc_trips_the_breaker_once() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let breaker = StorageBreaker::with_writer(Box::new(FullDisk { ok_writes: 2, calls: calls.clone() }));
+        let mut abort_events = 0;
+
+        // 与流水线一致：调度前检查熔断，写盘失败的文件记为失败
+        for i in 0..10 {
+            if breaker.is_aborted() {
+                breaker.record_not_attempted();
+                continue;
+            }
+            let path = PathBuf::from(format!("out_{}.jpg", i));
+            if breaker.write(&path, b"jpeg", || abort_events += 1).is_err() {
+                breaker.record_failed();
+            }
+        }
+
+        assert_eq!(abort_events, 1);
+        assert_eq!(calls.load(Ordering::Relaxed), 5, "熔断后不应再写盘");
+        assert_eq!(breaker.failed.load(Ordering::Relaxed), 3);
+        assert_eq!(breaker.not_attempted.load(Ordering::Relaxed), 5);
+        let summary = breaker.summary().unwrap();
+        assert!(summary.contains("写入失败 3 个") && summary.contains("未处理 5 个"), "{}", summary);
+    }
+
+    #[test]
+    fn failures_reported_after_abort_are_not_attempted() {
+        let breaker = StorageBreaker::with_writer(Box::new(FullDisk { ok_writes: 0, calls: Arc::default() }));
+        for _ in 0..3 {
+            assert!(breaker.write(Path::new("out.jpg"), b"", || {}).is_err());
+        }
+        assert!(breaker.is_aborted());
+        // 触发熔断的文件计入失败；之后才结束的在途文件 (解码出错等) 不算
+        breaker.record_failed();
+        breaker.record_failed();
+        assert_eq!(breaker.failed.load(Ordering::Relaxed), 1);
+        assert_eq!(breaker.not_attempted.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn success_resets_the_failure_streak() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        // 第 1、2 次失败，第 3 次成功，之后再失败两次：始终没有连续三次
+        struct Flaky(Arc<AtomicUsize>);
+        impl OutputWriter for Flaky {
+            fn write(&self, _path: &Path, _data: &[u8]) -> io::Result<()> {
+                match self.0.fetch_add(1, Ordering::Relaxed) {
+                    2 => Ok(()),
+                    _ => Err(io::Error::from(io::ErrorKind::ReadOnlyFilesystem)),
+                }
+            }
+        }
+        let breaker = StorageBreaker::with_writer(Box::new(Flaky(calls)));
+        for _ in 0..5 {
+            let _ = breaker.write(Path::new("out.jpg"), b"", || panic!("不应熔断"));
+        }
+        assert!(!breaker.is_aborted());
+        assert!(breaker.summary().is_none());
+    }
+
+    #[test]
+    fn other_io_errors_do_not_count() {
+        let breaker = StorageBreaker::default();
+        // 权限 / 路径类错误每个文件各不相同，不代表磁盘不可用
+        assert!(!is_storage_error(&io::Error::from(io::ErrorKind::PermissionDenied)));
+        assert!(is_storage_error(&io::Error::from(io::ErrorKind::QuotaExceeded)));
+        assert!(!breaker.is_aborted());
+    }
+}
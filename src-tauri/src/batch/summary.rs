@@ -0,0 +1,130 @@
+// src-tauri/src/batch/summary.rs
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use serde::Serialize;
+
+// =========================================================
+// 🟢 批次总结：成功 / 跳过 / 失败计数 + 跳过原因分布 + 总耗时
+// 每个文件上报时计数，批次结束 (完成 / 停止 / 存储中止) 时作为 process-summary 事件发给前端，
+// 不开批次报告也能看到 "跳过 12 张：输出已存在 10、无 EXIF 数据 2"
+// =========================================================
+
+/// 按文件累计的计数 (GlobalContext 里加锁共享)
+#[derive(Debug, Default)]
+pub struct BatchTally {
+    succeeded: usize,
+    failed: usize,
+    // 成功复制了原图副本的张数 (copy_original 模式)
+    originals_copied: usize,
+    // 跳过原因 -> 张数 (按原因排序，前端展示顺序稳定)
+    skipped: BTreeMap<String, usize>,
+}
+
+/// 一种跳过原因及其张数
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SkipReasonCount {
+    pub reason: String,
+    pub count: usize,
+}
+
+/// process-summary 事件的载荷
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchSummary {
+    pub status: &'static str, // finished / stopped / aborted_storage
+    pub total: usize,         // 本批次提交的数量 (不含续传跳过的)
+    pub processed: usize,     // 实际上报了结果的数量 (停止时小于 total)
+    pub succeeded: usize,
+    pub skipped: usize,
+    pub failed: usize,
+    pub skipped_reasons: Vec<SkipReasonCount>, // 张数多的在前
+    pub originals_copied: usize, // 复制了原图副本的张数 (每个副本的路径见进度事件和批次报告)
+    pub elapsed_ms: u128,
+    pub export_adjustments: Vec<String>, // 自动导出调优做的调整 (未开启或无调整时为空)
+    pub logo_cache_hits: usize,          // Logo 缩放缓存命中次数 (同尺寸的照片越多越高)
+    pub notes: Vec<String>,              // 磁盘空间 / 文件句柄 / JPG 回退等提示
+}
+
+impl BatchTally {
+    pub fn record_success(&mut self) {
+        self.succeeded += 1;
+    }
+
+    pub fn record_skipped(&mut self, reason: &str) {
+        *self.skipped.entry(reason.to_string()).or_default() += 1;
+    }
+
+    pub fn record_failed(&mut self) {
+        self.failed += 1;
+    }
+
+    pub fn record_original_copied(&mut self) {
+        self.originals_copied += 1;
+    }
+
+    pub fn summary(&self, status: &'static str, total: usize, elapsed: Duration) -> BatchSummary {
+        let mut skipped_reasons: Vec<SkipReasonCount> = self.skipped.iter()
+            .map(|(reason, &count)| SkipReasonCount { reason: reason.clone(), count })
+            .collect();
+        // 稳定排序：张数相同时保持原因的字典序
+        skipped_reasons.sort_by_key(|r| std::cmp::Reverse(r.count));
+        let skipped = self.skipped.values().sum();
+
+        BatchSummary {
+            status,
+            total,
+            processed: self.succeeded + skipped + self.failed,
+            succeeded: self.succeeded,
+            skipped,
+            failed: self.failed,
+            skipped_reasons,
+            originals_copied: self.originals_copied,
+            elapsed_ms: elapsed.as_millis(),
+            export_adjustments: Vec::new(),
+            logo_cache_hits: 0,
+            notes: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skip_reasons_are_grouped_and_sorted_by_count() {
+        let mut tally = BatchTally::default();
+        tally.record_success();
+        tally.record_skipped("无 EXIF 数据");
+        tally.record_skipped("输出已存在");
+        tally.record_skipped("输出已存在");
+        tally.record_skipped("输出已存在");
+        tally.record_failed();
+        tally.record_original_copied();
+
+        let s = tally.summary("finished", 8, Duration::from_millis(1234));
+        assert_eq!((s.succeeded, s.skipped, s.failed, s.processed), (1, 4, 1, 6));
+        assert_eq!(s.originals_copied, 1);
+        assert_eq!(s.skipped_reasons, vec![
+            SkipReasonCount { reason: "输出已存在".into(), count: 3 },
+            SkipReasonCount { reason: "无 EXIF 数据".into(), count: 1 },
+        ]);
+        assert_eq!(s.elapsed_ms, 1234);
+    }
+
+    #[test]
+    fn payload_uses_camel_case_fields() {
+        let mut tally = BatchTally::default();
+        tally.record_skipped("输出已存在");
+        let json = serde_json::to_value(tally.summary("stopped", 3, Duration::from_secs(2))).unwrap();
+        assert_eq!(json["status"], "stopped");
+        assert_eq!(json["elapsedMs"], 2000);
+        assert_eq!(json["skippedReasons"][0]["reason"], "输出已存在");
+        assert_eq!(json["skippedReasons"][0]["count"], 1);
+        assert!(json["exportAdjustments"].as_array().unwrap().is_empty());
+        assert_eq!(json["logoCacheHits"], 0);
+        assert_eq!(json["originalsCopied"], 0);
+    }
+}
@@ -0,0 +1,117 @@
+// src-tauri/src/batch/timings.rs
+
+use std::time::Duration;
+
+use serde::Serialize;
+
+// =========================================================
+// 🟢 分步耗时统计：加载 / 解析 / 绘制 / 编码
+// 每个文件的耗时随进度事件发给前端，整批的最小 / 平均 / 最大值写进批次总结，
+// 用来判断慢在解码、绘制还是编码，不必再去翻 debug 日志
+// =========================================================
+
+/// 耗时归到哪一段 (检查、复制原图、缩略图等零碎步骤不计)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimingPhase {
+    Load,   // 解码 + 色彩转换
+    Parse,  // 读取并解析 EXIF
+    Render, // 处理器绘制 + 补边 + 缩放
+    Encode, // 编码 + 写盘 (含尺寸版本)
+}
+
+impl TimingPhase {
+    const ALL: [TimingPhase; 4] = [TimingPhase::Load, TimingPhase::Parse, TimingPhase::Render, TimingPhase::Encode];
+
+    fn label(self) -> &'static str {
+        match self {
+            TimingPhase::Load => "load",
+            TimingPhase::Parse => "parse",
+            TimingPhase::Render => "render",
+            TimingPhase::Encode => "encode",
+        }
+    }
+}
+
+/// 单个文件的分步耗时 (毫秒，保留一位小数)，进度事件里的 timings 字段
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct StepTimings {
+    pub load: f64,
+    pub parse: f64,
+    pub render: f64,
+    pub encode: f64,
+    // 原图像素数 (百万)，未加载时为 null
+    pub megapixels: Option<f64>,
+}
+
+impl StepTimings {
+    /// 同一段可能由多个步骤组成，耗时累加
+    pub fn add(&mut self, phase: TimingPhase, elapsed: Duration) {
+        let slot = self.slot_mut(phase);
+        *slot = round1(*slot + elapsed.as_secs_f64() * 1000.0);
+    }
+
+    pub fn set_megapixels(&mut self, width: u32, height: u32) {
+        self.megapixels = Some((width as f64 * height as f64 / 10_000.0).round() / 100.0);
+    }
+
+    fn get(&self, phase: TimingPhase) -> f64 {
+        match phase {
+            TimingPhase::Load => self.load,
+            TimingPhase::Parse => self.parse,
+            TimingPhase::Render => self.render,
+            TimingPhase::Encode => self.encode,
+        }
+    }
+
+    fn slot_mut(&mut self, phase: TimingPhase) -> &mut f64 {
+        match phase {
+            TimingPhase::Load => &mut self.load,
+            TimingPhase::Parse => &mut self.parse,
+            TimingPhase::Render => &mut self.render,
+            TimingPhase::Encode => &mut self.encode,
+        }
+    }
+}
+
+fn round1(ms: f64) -> f64 {
+    (ms * 10.0).round() / 10.0
+}
+
+/// 整批成功文件的分步耗时汇总
+#[derive(Debug, Default)]
+pub struct TimingStats {
+    count: usize,
+    // 与 TimingPhase::ALL 顺序一致：(最小, 总和, 最大)
+    phases: [(f64, f64, f64); 4],
+}
+
+impl TimingStats {
+    pub fn record(&mut self, timings: &StepTimings) {
+        for (i, phase) in TimingPhase::ALL.into_iter().enumerate() {
+            let ms = timings.get(phase);
+            let (min, sum, max) = &mut self.phases[i];
+            if self.count == 0 {
+                (*min, *max) = (ms, ms);
+            } else {
+                *min = min.min(ms);
+                *max = max.max(ms);
+            }
+            *sum += ms;
+        }
+        self.count += 1;
+    }
+
+    /// 批次总结里的一行，例如 "load 120/180/260ms, parse 2/3/5ms, … (min/mean/max, 12 files)"
+    /// 没有成功的文件时返回 None
+    pub fn summary(&self) -> Option<String> {
+        if self.count == 0 {
+            return None;
+        }
+        let parts: Vec<String> = TimingPhase::ALL.into_iter().zip(&self.phases)
+            .map(|(phase, &(min, sum, max))| {
+                format!("{} {:.0}/{:.0}/{:.0}ms", phase.label(), min, sum / self.count as f64, max)
+            })
+            .collect();
+        Some(format!("{} (min/mean/max, {} files)", parts.join(", "), self.count))
+    }
+}
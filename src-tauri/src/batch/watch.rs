@@ -0,0 +1,239 @@
+// src-tauri/src/batch/watch.rs
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc, Mutex, atomic::{AtomicUsize, Ordering}};
+use std::time::{Duration, Instant};
+
+use log::{debug, error, info, warn};
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use serde_json::json;
+use tauri::{Emitter, State, Window};
+
+use crate::error::AppError;
+use crate::models::{BatchContext, ExportImageFormat};
+use crate::AppState;
+use crate::utils::{variant_label, OutputNames};
+use super::avif;
+use super::io_limits::{self, IoSlots};
+use super::pipeline::{build_pipeline, GlobalContext};
+use super::report;
+use super::storage::StorageBreaker;
+use super::summary::BatchTally;
+use super::timings::TimingStats;
+use super::webp;
+
+// =========================================================
+// 🟢 监听模式：联机拍摄时把相机输出目录交给我们，新照片落盘后自动加框
+// =========================================================
+// 1. notify 监听目录 (不递归)，只关心新建 / 修改的 jpg/png
+// 2. 相机或传输软件写文件需要时间，文件大小连续 STABLE_FOR 不变才开始处理
+// 3. 自己写出的成品 (claimed_outputs 或带样式后缀) 一律忽略，避免输出目录就是监听目录时无限套娃
+// 4. 用户点停止 (should_stop) 后退出，进度事件与批处理相同，只是 total 为 null
+
+// 轮询间隔 (同时也是检查停止信号的间隔)
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+// 文件大小保持不变多久才认为写完
+const STABLE_FOR: Duration = Duration::from_secs(1);
+// 监听模式接受的格式 (联机拍摄一般只有 JPG，RAW 不处理)
+const WATCH_EXTENSIONS: [&str; 3] = ["jpg", "jpeg", "png"];
+
+/// 等待写完的文件
+struct PendingFile {
+    size: u64,
+    changed_at: Instant,
+}
+
+#[tauri::command]
+pub async fn start_watch_mode(
+    window: Window,
+    state: State<'_, Arc<AppState>>,
+    folder: String,
+    context: BatchContext,
+) -> Result<String, AppError> {
+    let folder = PathBuf::from(folder);
+    if !folder.is_dir() {
+        return Err(AppError::PathCalculation(format!("监听目录不存在: {:?}", folder)));
+    }
+    if context.export.output_formats().any(|f| matches!(f, ExportImageFormat::Avif)) && !avif::supported() {
+        return Err(AppError::System("当前版本未启用 AVIF 编码 (需要以 avif feature 编译)".to_string()));
+    }
+    info!("👀 [Watch] 开始监听: {:?}", folder);
+
+    let state_arc = (*state).clone();
+    state_arc.should_stop.store(false, Ordering::Relaxed);
+
+    let export = if context.auto_export_tuning {
+        context.export.tuned_for(&context.options).0
+    } else {
+        context.export.clone()
+    };
+    if let Some(w) = webp::fallback_note(&export) {
+        warn!("⚠️ [Watch] {}", w);
+    }
+
+    let global = Arc::new(GlobalContext {
+        window: Some(window.clone()),
+        app_state: state_arc.clone(),
+        options: context.options.clone(),
+        signature: context.signature.clone(),
+        total_files: 0, // 0 代表总数未知，进度事件里 total 为 null
+        completed_count: Arc::new(AtomicUsize::new(0)),
+        export,
+        deterministic: context.deterministic,
+        output_slots: IoSlots::new(io_limits::output_slot_count(io_limits::open_file_limit())),
+        storage: StorageBreaker::default(),
+        claimed_outputs: Mutex::new(OutputNames::default()),
+        // 文件逐个到达，处理到时再分配
+        planned_outputs: HashMap::new(),
+        // 监听没有“批次完成”的概念，不做断点续传
+        resume: None,
+        reports: Mutex::new(Vec::new()),
+        file_timeout: (context.file_timeout_secs > 0).then(|| Duration::from_secs(context.file_timeout_secs)),
+        max_file_size: context.max_file_size_mb.map(|mb| mb.saturating_mul(1_048_576)),
+        emit_thumbnails: context.emit_thumbnails,
+        overrides: context.overrides.clone(),
+        labels: context.labels.clone(),
+        allow_missing_exif: context.allow_missing_exif,
+        reframe_outputs: context.reframe_outputs,
+        timing_stats: Mutex::new(TimingStats::default()),
+        tally: Mutex::new(BatchTally::default()),
+        // 监听模式没有提交顺序，来一张报一张
+        ordered_progress: None,
+    });
+
+    let processor = crate::processor::create_context_processor(&context)?;
+    let pipeline = Arc::new(build_pipeline(Arc::new(processor)));
+
+    // notify 的回调在它自己的线程里，转发到 channel 由下面的循环统一处理
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<Event>| {
+        let _ = tx.send(res);
+    })
+    .map_err(|e| AppError::System(format!("无法创建目录监听: {}", e)))?;
+    watcher
+        .watch(&folder, RecursiveMode::NonRecursive)
+        .map_err(|e| AppError::System(format!("无法监听目录 {:?}: {}", folder, e)))?;
+
+    let _ = window.emit("process-progress", json!({
+        "current": 0,
+        "total": null,
+        "filepath": null,
+        "status": "started",
+        "watching": folder,
+    }));
+
+    let watchdog = pipeline.spawn_watchdog(&global);
+
+    let started = Instant::now();
+    let worker_ctx = global.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        // watcher 必须活到循环结束，drop 即停止监听
+        let _watcher = watcher;
+        let mut pending: HashMap<PathBuf, PendingFile> = HashMap::new();
+        let mut processed: HashSet<PathBuf> = HashSet::new();
+        let mut index = 0;
+
+        loop {
+            match rx.recv_timeout(POLL_INTERVAL) {
+                Ok(Ok(event)) => {
+                    if !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+                        continue;
+                    }
+                    for path in event.paths {
+                        if processed.contains(&path) || !is_candidate(&path, &worker_ctx) {
+                            continue;
+                        }
+                        // 有新动静就重新计时
+                        let size = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+                        pending.insert(path, PendingFile { size, changed_at: Instant::now() });
+                    }
+                },
+                Ok(Err(e)) => warn!("⚠️ [Watch] 监听事件错误: {}", e),
+                Err(mpsc::RecvTimeoutError::Timeout) => {},
+                Err(mpsc::RecvTimeoutError::Disconnected) => {
+                    error!("❌ [Watch] 监听已断开");
+                    break;
+                },
+            }
+
+            if worker_ctx.app_state.should_stop.load(Ordering::Relaxed) {
+                info!("🛑 [Watch] 用户停止监听");
+                break;
+            }
+            if worker_ctx.storage.summary().is_some() {
+                warn!("💥 [Watch] 目标磁盘不可用，停止监听");
+                break;
+            }
+
+            for path in take_stable(&mut pending) {
+                debug!("👀 [Watch] 文件已稳定，开始处理: {:?}", path);
+                processed.insert(path.clone());
+                let file_path = path.to_string_lossy().into_owned();
+                pipeline.run_guarded(&worker_ctx, watchdog.as_ref(), file_path, index);
+                index += 1;
+            }
+        }
+    }).await;
+
+    result.map_err(|e| AppError::System(format!("监听线程异常: {}", e)))?;
+
+    let count = global.completed_count.load(Ordering::Relaxed);
+    info!("👀 [Watch] 监听结束，共处理 {} 张，历时 {:.2?}", count, started.elapsed());
+
+    // 报告写到自定义输出目录或监听目录
+    let mut report_note = String::new();
+    if global.export.write_report && count > 0 {
+        let dir = global.export.target_dir.as_ref().map(PathBuf::from).unwrap_or(folder);
+        let mut files = std::mem::take(&mut *global.reports.lock().unwrap());
+        match report::write_report(&dir, &mut files, &global.options, &global.export, started.elapsed().as_millis(), global.export.report_csv) {
+            Ok(path) => report_note = format!(" [report: {}]", path.display()),
+            Err(e) => warn!("⚠️ [Watch] 报告写入失败: {}", e),
+        }
+    }
+
+    window.emit("process-status", "stopped").map_err(|e| AppError::System(e.to_string()))?;
+    // 监听没有总数，total 记为实际处理的张数
+    let _ = window.emit("process-summary", global.tally.lock().unwrap().summary("stopped", count, started.elapsed()));
+    Ok(format!("Watch stopped after {} files{}", count, report_note))
+}
+
+/// 是否值得处理：格式对得上，且不是我们自己写出的成品
+fn is_candidate(path: &Path, global: &GlobalContext) -> bool {
+    let Some(ext) = path.extension().and_then(|e| e.to_str()) else { return false };
+    if !WATCH_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()) {
+        return false;
+    }
+    if global.claimed_outputs.lock().unwrap().contains(path) {
+        return false;
+    }
+    // 默认命名的成品是 {stem}_{style} (尺寸版本再接 _{版本名})，即使是上次运行留下的也不能再处理
+    let suffix = format!("_{}", global.options.filename_suffix());
+    let variant_suffixes: Vec<String> = global.export.variants.iter().enumerate()
+        .map(|(i, v)| format!("{}_{}", suffix, variant_label(v, i)))
+        .collect();
+    !path.file_stem().and_then(|s| s.to_str()).is_some_and(|stem| {
+        stem.ends_with(&suffix) || variant_suffixes.iter().any(|s| stem.ends_with(s.as_str()))
+    })
+}
+
+/// 取出大小已稳定的文件；仍在变化的更新记录，已消失的丢弃
+fn take_stable(pending: &mut HashMap<PathBuf, PendingFile>) -> Vec<PathBuf> {
+    let mut ready = Vec::new();
+    pending.retain(|path, file| {
+        let Ok(meta) = std::fs::metadata(path) else { return false };
+        if meta.len() != file.size || meta.len() == 0 {
+            file.size = meta.len();
+            file.changed_at = Instant::now();
+            return true;
+        }
+        if file.changed_at.elapsed() < STABLE_FOR {
+            return true;
+        }
+        ready.push(path.clone());
+        false
+    });
+    // 按文件名排序，连拍时按拍摄顺序处理
+    ready.sort();
+    ready
+}
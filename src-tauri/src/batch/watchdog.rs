@@ -0,0 +1,188 @@
+// src/batch/watchdog.rs
+// 🟢 单文件超时看门狗：整个批次只有一个看门狗线程，按截止时间表巡检
+// 图片仍在 rayon 工作线程里处理；处理前登记截止时间，处理完注销。
+// 过了截止时间还没注销的任务：标记 abandoned (后续步骤不再执行、结果不再写盘)，并立即回调上报超时。
+// Rust 无法强行终止线程，卡住的解码会占着这个工作线程直到自行结束，其他文件在其余工作线程上照常继续
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use log::{error, warn};
+
+// 巡检间隔上限：截止时间表为空时按这个间隔醒来，也保证退出及时
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// 一个登记中的任务
+struct Entry {
+    deadline: Instant,
+    abandoned: Arc<AtomicBool>,
+    file_path: String,
+    index: usize,
+}
+
+type Deadlines = Arc<Mutex<HashMap<u64, Entry>>>;
+
+pub struct Watchdog {
+    timeout: Duration,
+    deadlines: Deadlines,
+    next_ticket: Mutex<u64>,
+    // drop 掉发送端即通知看门狗线程退出
+    shutdown: Option<Sender<()>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Watchdog {
+    /// on_timeout(file_path, index)：在看门狗线程里调用，负责上报超时
+    pub fn spawn<F>(timeout: Duration, on_timeout: F) -> Result<Self, std::io::Error>
+    where
+        F: Fn(String, usize) + Send + 'static,
+    {
+        let deadlines: Deadlines = Arc::new(Mutex::new(HashMap::new()));
+        let (shutdown, rx) = mpsc::channel::<()>();
+        let table = deadlines.clone();
+        let handle = std::thread::Builder::new()
+            .name("framer-watchdog".to_string())
+            .spawn(move || loop {
+                // 睡到最早的截止时间 (最多 POLL_INTERVAL)
+                let now = Instant::now();
+                let wait = table.lock().unwrap().values()
+                    .map(|e| e.deadline.saturating_duration_since(now))
+                    .min()
+                    .map_or(POLL_INTERVAL, |d| d.min(POLL_INTERVAL));
+                match rx.recv_timeout(wait) {
+                    Err(mpsc::RecvTimeoutError::Timeout) => {},
+                    // 发送端已 drop：批次结束
+                    _ => return,
+                }
+
+                // 先在锁内摘出过期任务，再在锁外上报 (上报要 emit 事件，不能拖住登记 / 注销)
+                let now = Instant::now();
+                let expired: Vec<Entry> = {
+                    let mut table = table.lock().unwrap();
+                    let keys: Vec<u64> = table.iter()
+                        .filter(|(_, e)| e.deadline <= now)
+                        .map(|(k, _)| *k)
+                        .collect();
+                    keys.iter().filter_map(|k| table.remove(k)).collect()
+                };
+                for entry in expired {
+                    entry.abandoned.store(true, Ordering::Relaxed);
+                    error!("⏱️ [Watchdog] 处理超时 ({:?})，跳过: {}", timeout, entry.file_path);
+                    on_timeout(entry.file_path, entry.index);
+                }
+            })?;
+
+        Ok(Self {
+            timeout,
+            deadlines,
+            next_ticket: Mutex::new(0),
+            shutdown: Some(shutdown),
+            handle: Some(handle),
+        })
+    }
+
+    /// 任务开始处理时登记，返回注销用的票号
+    pub fn arm(&self, file_path: &str, index: usize, abandoned: Arc<AtomicBool>) -> u64 {
+        let ticket = {
+            let mut next = self.next_ticket.lock().unwrap();
+            *next += 1;
+            *next
+        };
+        let entry = Entry {
+            deadline: Instant::now() + self.timeout,
+            abandoned,
+            file_path: file_path.to_string(),
+            index,
+        };
+        self.deadlines.lock().unwrap().insert(ticket, entry);
+        ticket
+    }
+
+    /// 任务处理完时注销；返回 false 代表已经按超时上报过，调用方不能再上报结果
+    pub fn disarm(&self, ticket: u64) -> bool {
+        self.deadlines.lock().unwrap().remove(&ticket).is_some()
+    }
+}
+
+impl Drop for Watchdog {
+    fn drop(&mut self) {
+        self.shutdown.take();
+        if let Some(handle) = self.handle.take() {
+            if handle.join().is_err() {
+                warn!("⚠️ [Watchdog] 看门狗线程异常退出");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Fired = Arc<Mutex<Vec<(String, usize)>>>;
+
+    fn recorder() -> (Fired, impl Fn(String, usize) + Send + 'static) {
+        let fired = Arc::new(Mutex::new(Vec::new()));
+        let sink = fired.clone();
+        (fired, move |path, index| sink.lock().unwrap().push((path, index)))
+    }
+
+    #[test]
+    fn expired_task_is_abandoned_and_reported_once() {
+        let (fired, on_timeout) = recorder();
+        let dog = Watchdog::spawn(Duration::from_millis(50), on_timeout).unwrap();
+        let abandoned = Arc::new(AtomicBool::new(false));
+        let ticket = dog.arm("slow.tif", 3, abandoned.clone());
+
+        std::thread::sleep(Duration::from_millis(400));
+        assert!(abandoned.load(Ordering::Relaxed));
+        assert_eq!(*fired.lock().unwrap(), vec![("slow.tif".to_string(), 3)]);
+        // 已按超时上报，处理线程迟到的结果要丢弃
+        assert!(!dog.disarm(ticket));
+    }
+
+    #[test]
+    fn finished_task_is_never_reported() {
+        let (fired, on_timeout) = recorder();
+        let dog = Watchdog::spawn(Duration::from_millis(100), on_timeout).unwrap();
+        let abandoned = Arc::new(AtomicBool::new(false));
+        let ticket = dog.arm("fast.jpg", 0, abandoned.clone());
+        assert!(dog.disarm(ticket));
+
+        std::thread::sleep(Duration::from_millis(300));
+        assert!(!abandoned.load(Ordering::Relaxed));
+        assert!(fired.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn one_thread_tracks_many_deadlines() {
+        let (fired, on_timeout) = recorder();
+        let dog = Watchdog::spawn(Duration::from_millis(200), on_timeout).unwrap();
+        let tickets: Vec<u64> = (0..20)
+            .map(|i| dog.arm(&format!("{}.jpg", i), i, Arc::new(AtomicBool::new(false))))
+            .collect();
+        // 偶数号按时完成，奇数号卡住
+        for t in tickets.iter().step_by(2) {
+            assert!(dog.disarm(*t));
+        }
+
+        std::thread::sleep(Duration::from_millis(800));
+        let mut indices: Vec<usize> = fired.lock().unwrap().iter().map(|(_, i)| *i).collect();
+        indices.sort_unstable();
+        assert_eq!(indices, (1..20).step_by(2).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn drop_stops_the_thread_promptly() {
+        let (_, on_timeout) = recorder();
+        let dog = Watchdog::spawn(Duration::from_secs(120), on_timeout).unwrap();
+        dog.arm("stuck.tif", 0, Arc::new(AtomicBool::new(false)));
+        let t = Instant::now();
+        drop(dog);
+        assert!(t.elapsed() < Duration::from_secs(1));
+    }
+}
@@ -0,0 +1,114 @@
+use image::codecs::webp::WebPEncoder;
+use image::{DynamicImage, ImageEncoder};
+use log::{debug, error};
+
+use crate::error::AppError;
+use crate::models::ExportConfig;
+
+// =========================================================
+// WebP 编码
+// =========================================================
+// image 自带的 WebP 编码器只有无损模式；quality < 100 的有损编码走 libwebp (可选 feature)。
+// 未启用时有损请求回退为无损 (体积更大但画质不降)，在批次总结里提示一次。
+
+/// 当前构建能否输出有损 WebP
+pub fn lossy_supported() -> bool {
+    cfg!(feature = "libwebp")
+}
+
+/// 有损请求在当前构建下会回退为无损时返回提示，否则 None；用于批次总结里提示一次
+pub fn fallback_note(export: &ExportConfig) -> Option<String> {
+    if lossy_supported() {
+        return None;
+    }
+    export.lossy_webp_quality()
+        .map(|q| format!("当前版本未启用 libwebp，WebP 质量 {} 已回退为无损编码", q))
+}
+
+/// 编码为 WebP (输入应为 8 位 RGB/RGBA，保留 Alpha)：quality < 100 为有损，100 为无损
+pub fn encode(img: &DynamicImage, quality: u8) -> Result<Vec<u8>, AppError> {
+    #[cfg(feature = "libwebp")]
+    if quality < 100 {
+        return encode_lossy(img, quality);
+    }
+    if quality < 100 {
+        debug!("  -> 当前构建不支持有损 WebP (quality={})，使用无损编码", quality);
+    }
+
+    let mut encoded = Vec::new();
+    WebPEncoder::new_lossless(&mut encoded)
+        .write_image(img.as_bytes(), img.width(), img.height(), img.color().into())
+        .map_err(|e| {
+            error!("❌ [Save] WebP 编码失败: {}", e);
+            AppError::Image(e)
+        })?;
+    Ok(encoded)
+}
+
+#[cfg(feature = "libwebp")]
+fn encode_lossy(img: &DynamicImage, quality: u8) -> Result<Vec<u8>, AppError> {
+    let layout = match img {
+        DynamicImage::ImageRgb8(_) => ::webp::PixelLayout::Rgb,
+        DynamicImage::ImageRgba8(_) => ::webp::PixelLayout::Rgba,
+        other => return Err(AppError::System(format!("WebP 有损编码不支持 {:?} 像素格式", other.color()))),
+    };
+    debug!("  -> WebP 有损编码 (quality={})", quality);
+    ::webp::Encoder::new(img.as_bytes(), layout, img.width(), img.height())
+        .encode_simple(false, quality as f32)
+        .map(|memory| memory.to_vec())
+        .map_err(|e| {
+            error!("❌ [Save] WebP 有损编码失败: {:?}", e);
+            AppError::System(format!("WebP 编码失败: {:?}", e))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ExportImageFormat, ExportVariant};
+
+    // 渐变 + 细节纹理，接近真实照片的熵
+    fn photo() -> DynamicImage {
+        DynamicImage::ImageRgb8(image::RgbImage::from_fn(320, 240, |x, y| {
+            image::Rgb([(x * 255 / 319) as u8, (y * 255 / 239) as u8, ((x * 7 + y * 13) % 64 + 96) as u8])
+        }))
+    }
+
+    #[test]
+    fn quality_100_is_lossless() {
+        let img = photo();
+        let encoded = encode(&img, 100).unwrap();
+        assert_eq!(image::load_from_memory(&encoded).unwrap().to_rgb8(), img.to_rgb8());
+    }
+
+    #[test]
+    fn lower_quality_is_lossy_when_libwebp_is_enabled() {
+        let img = photo();
+        let lossless = encode(&img, 100).unwrap();
+        let lossy = encode(&img, 75).unwrap();
+        let decoded = image::load_from_memory(&lossy).unwrap().to_rgb8();
+        assert_eq!(decoded.dimensions(), (320, 240));
+        if lossy_supported() {
+            // VP8 (有损) 而不是 VP8L，体积明显小于无损
+            assert_eq!(lossy.get(12..16), Some(&b"VP8 "[..]));
+            assert!(lossy.len() < lossless.len() / 2, "{} vs {}", lossy.len(), lossless.len());
+        } else {
+            // 回退为无损：像素完全一致
+            assert_eq!(decoded, img.to_rgb8());
+        }
+    }
+
+    #[test]
+    fn fallback_note_follows_feature_and_covers_variants() {
+        let webp = |quality| ExportConfig { format: ExportImageFormat::Webp, quality, ..ExportConfig::default() };
+        assert_eq!(fallback_note(&webp(100)), None);
+        assert_eq!(fallback_note(&webp(80)).is_some(), !lossy_supported());
+
+        let variant = ExportVariant { name: "web".into(), max_long_edge: Some(2048), format: ExportImageFormat::Webp, quality: 70 };
+        let export = ExportConfig { variants: vec![variant], ..ExportConfig::default() };
+        assert_eq!(fallback_note(&export).is_some(), !lossy_supported());
+        if let Some(note) = fallback_note(&export) {
+            assert!(note.contains("70"), "{}", note);
+        }
+    }
+}
@@ -0,0 +1,65 @@
+// src-tauri/src/cancel.rs
+
+use std::cell::RefCell;
+use std::sync::{Arc, atomic::{AtomicBool, Ordering}};
+
+use crate::error::AppError;
+use crate::state::AppState;
+
+// =========================================================
+// 🟢 取消令牌：让正在处理中的文件也能响应停止
+// =========================================================
+// 管线只在步骤之间检查停止信号，一张 61MP 的大图在绘制 / 编码里一跑就是好几秒。
+// 处理器接口 (process(img, ctx)) 不带令牌，改签名会波及所有样式，
+// 所以由管线在执行期间把令牌挂到当前线程上，create_expanded_canvas 这类耗时函数自己取来检查。
+// 没有挂令牌的调用 (预览等) 永远不会被取消。
+
+/// 用户停止 (全局) 或看门狗放弃 (单个任务) 任一发生即视为取消
+#[derive(Clone)]
+pub struct CancelToken {
+    app_state: Arc<AppState>,
+    task: Arc<AtomicBool>,
+}
+
+impl CancelToken {
+    pub fn new(app_state: Arc<AppState>, task: Arc<AtomicBool>) -> Self {
+        Self { app_state, task }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.task.load(Ordering::Relaxed) || self.app_state.should_stop.load(Ordering::Relaxed)
+    }
+}
+
+thread_local! {
+    static CURRENT: RefCell<Option<CancelToken>> = const { RefCell::new(None) };
+}
+
+/// 在 f 执行期间把 token 设为当前线程的令牌，结束后恢复原值
+/// (rayon 在等待嵌套任务时可能在同一线程上插入执行别的文件，所以必须按栈的方式恢复)
+pub fn with_token<R>(token: &CancelToken, f: impl FnOnce() -> R) -> R {
+    // 用 Drop 恢复，f 里 panic 时也不会把令牌留在线程上
+    struct Restore(Option<CancelToken>);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            let previous = self.0.take();
+            CURRENT.with(|c| *c.borrow_mut() = previous);
+        }
+    }
+
+    let _restore = Restore(CURRENT.with(|c| c.replace(Some(token.clone()))));
+    f()
+}
+
+/// 当前线程的令牌；需要在 rayon 并行区里检查时，先在调用线程上取出再带进闭包
+pub fn current() -> Option<CancelToken> {
+    CURRENT.with(|c| c.borrow().clone())
+}
+
+/// 当前任务已被取消时返回 AppError::Cancelled
+pub fn check() -> Result<(), AppError> {
+    match current() {
+        Some(token) if token.is_cancelled() => Err(AppError::Cancelled),
+        _ => Ok(()),
+    }
+}
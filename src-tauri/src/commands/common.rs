@@ -2,19 +2,23 @@
 
 use exif::{In, Reader, Tag, Value};
 use log::{debug, error, info, warn}; // 引入日志宏
-use tauri::State;
-use std::{fs::{self, File}, io::BufReader, sync::{Arc, atomic::Ordering}};
+use tauri::{Emitter, State, Window};
+use rayon::prelude::*;
+use serde_json::json;
+use std::{fs::{self, File}, io::{BufReader, Read, Seek, SeekFrom}, path::Path, sync::{Arc, atomic::Ordering}};
 use std::io::Cursor;
-use image::ImageFormat;
+use image::{ImageEncoder, ImageFormat};
+use image::codecs::jpeg::JpegEncoder;
 use base64::{Engine as _, engine::general_purpose};
 
 // 🟢 引入我们的新错误类型
-use crate::{error::AppError, parser::models::RawExifData};
+use crate::{error::AppError, parser::models::{ParsedImageContext, RawExifData}};
 use crate::{
-    graphics::load_image_auto_rotate, 
-    models::{BatchContext, ExportConfig, StyleOptions}, 
+    graphics::{load_image_auto_rotate, color::SourceProfile, decode_cache::{self, load_image_cached, FileStamp}}, 
+    models::{BatchContext, ExportConfig, ExportHints, ExportImageFormat, StyleOptions}, 
+    resources,
     state::AppState, 
-    utils::calculate_target_path_core,
+    utils::{calculate_target_path_core, NamingContext, OutputNames},
 };
 
 // ==========================================
@@ -26,10 +30,50 @@ pub fn check_file_exif(path: String) -> bool {
     has_exif(&path)
 }
 
+/// 列出所有样式及其推荐导出参数
+#[tauri::command]
+pub fn list_styles() -> Vec<StyleInfo> {
+    StyleOptions::catalog()
+        .iter()
+        .map(|style| StyleInfo {
+            id: style.filename_suffix(),
+            export_hints: style.recommended_export(),
+        })
+        .collect()
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StyleInfo {
+    pub id: &'static str,
+    pub export_hints: ExportHints,
+}
+
+/// 🟢 清空 Logo 缓存并重新扫描用户 Logo 目录 (放入新文件后无需重启)，返回找到的文件数
+#[tauri::command]
+pub fn reload_logos() -> usize {
+    info!("🔄 重新加载 Logo...");
+    resources::reload_logos()
+}
+
+/// 🟢 日志文件的完整路径 (前端"打开日志文件夹"用)；文件可能还没创建
+/// 按天轮转只在启动时做一次 (日志文件由插件一直持有，运行中无法改名重开)：
+/// 跨过午夜一直开着的会话仍写在这个文件里，下次启动时才改名为 nikon-framer_<最后写入时间>.log。
+/// 会话中按大小 (10 MB) 的轮转不受影响
+#[tauri::command]
+pub fn get_log_path(app: tauri::AppHandle) -> Result<String, AppError> {
+    use tauri::Manager;
+    let dir = app.path().app_log_dir()
+        .map_err(|e| AppError::System(format!("无法解析日志目录: {}", e)))?;
+    Ok(dir.join(format!("{}.log", crate::setup::LOG_FILE_NAME)).to_string_lossy().to_string())
+}
+
 #[tauri::command]
 pub fn stop_batch_process(state: State<'_, Arc<AppState>>) {
     info!("🛑 收到停止指令...");
     state.should_stop.store(true, Ordering::Relaxed);
+    // 同时取消正在进行的预览
+    state.preview_generation.fetch_add(1, Ordering::Relaxed);
 }
 
 // ==========================================
@@ -91,6 +135,67 @@ pub async fn generate_thumbnail(file_path: String) -> Result<String, AppError> {
     inner_result
 }
 
+/// 单张预览：按当前样式渲染一张，不写盘，返回 base64 JPG
+/// 返回 Ok(None) 代表预览已被取消 (有更新的预览请求，或收到停止指令)
+#[tauri::command]
+pub async fn preview_frame(
+    file_path: String,
+    context: BatchContext,
+    state: State<'_, Arc<AppState>>,
+) -> Result<Option<String>, AppError> {
+    const PREVIEW_MAX_EDGE: u32 = 1200;
+
+    let state = state.inner().clone();
+    let generation = state.preview_generation.fetch_add(1, Ordering::Relaxed) + 1;
+    state.decoded_images.set_capacity(context.decode_cache_size.unwrap_or(decode_cache::DEFAULT_CAPACITY));
+
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        let cancelled = || state.preview_generation.load(Ordering::Relaxed) != generation;
+
+        // 1. 加载并先缩小原图 (边框按短边比例计算，缩小后排版比例不变，速度快很多)
+        // 完整解码结果进缓存，切换样式再预览或随后批处理时直接复用
+        let source = load_image_cached(&state.decoded_images, &file_path, true)?;
+        let mut img = source.image.thumbnail(PREVIEW_MAX_EDGE, PREVIEW_MAX_EDGE);
+        // 与批处理一致：非 sRGB 原图先转换 (缩小后再转，开销很小)
+        if context.export.color_management {
+            if let Some(profile) = source.icc.and_then(SourceProfile::parse).filter(SourceProfile::needs_conversion) {
+                img = profile.to_srgb(&img)?;
+            }
+        }
+        if cancelled() {
+            debug!("⏭️ [Preview] 已取消 (加载后): {}", file_path);
+            return Ok(None);
+        }
+
+        // 2. 与批处理相同的解析 + 绘制路径
+        let mut parsed_ctx = crate::parser::parse(get_exif_data(&file_path)?);
+        if let Some(overrides) = &context.overrides {
+            crate::parser::apply_overrides(&mut parsed_ctx, overrides);
+        }
+        let processor = crate::processor::create_context_processor(&context)?;
+        let framed = processor.process(&img, &parsed_ctx)?;
+        if cancelled() {
+            debug!("⏭️ [Preview] 已取消 (绘制后): {}", file_path);
+            return Ok(None);
+        }
+
+        // 3. 加边框后尺寸变大，再缩回预览尺寸并编码
+        let preview = framed.thumbnail(PREVIEW_MAX_EDGE, PREVIEW_MAX_EDGE).to_rgb8();
+        let mut buffer = Vec::new();
+        JpegEncoder::new_with_quality(&mut buffer, 85).write_image(
+            preview.as_raw(),
+            preview.width(),
+            preview.height(),
+            image::ExtendedColorType::Rgb8,
+        )?;
+
+        let b64 = general_purpose::STANDARD.encode(&buffer);
+        Ok::<Option<String>, AppError>(Some(format!("data:image/jpeg;base64,{}", b64)))
+    }).await;
+
+    result.map_err(|e| AppError::System(format!("线程池异常: {}", e)))?
+}
+
 // ==========================================
 // 4. 保持原有签名但增强日志的函数
 // ==========================================
@@ -102,8 +207,10 @@ pub fn check_output_exists(
     export_config: ExportConfig, 
 ) -> Option<String> {
     
-    // 1. 路径计算
-    let target_path = match calculate_target_path_core(&file_path, &export_config, &style_options) {
+    // 1. 路径计算 (文件名模板引用了 EXIF 时需要先读一次)
+    let exif = naming_exif(&file_path, &export_config);
+    let naming = NamingContext { exif: exif.as_ref(), counter: None };
+    let target_path = match calculate_target_path_core(&file_path, &export_config, &style_options, naming) {
         Ok(p) => p,
         Err(e) => {
             // 🟢 使用 error! 记录
@@ -122,8 +229,8 @@ pub fn check_output_exists(
     match load_and_resize_blob(target_path_str, 1000) {
         Ok(buffer) => {
             let b64 = general_purpose::STANDARD.encode(&buffer);
-            let mime = export_config.format.mime_type();
-            Some(format!("data:{};base64,{}", mime, b64))
+            // 预览总是重新编码为 JPG (见 load_and_resize_blob)，与成品格式无关
+            Some(format!("data:{};base64,{}", ExportImageFormat::Jpg.mime_type(), b64))
         },
         Err(e) => {
             // 🟢 使用 warn! 记录 (这属于非致命错误，可能是文件损坏或占用)
@@ -133,7 +240,17 @@ pub fn check_output_exists(
     }
 }
 
+// 文件名模板需要 EXIF 字段时才读取 (默认命名不需要，避免无谓的 IO)
+fn naming_exif(path: &str, export: &ExportConfig) -> Option<ParsedImageContext> {
+    if !export.template_needs_exif() {
+        return None;
+    }
+    get_exif_data(path).ok().map(crate::parser::parse)
+}
+
 // 批量过滤函数 (保持逻辑，日志已在之前步骤优化过，这里确认一下引用没问题)
+// 命名与正式批次一致：{counter} 按 paths 里的顺序编号，批内重名按同样的顺序追加 _1、_2，
+// 所以前端要把同一个完整列表交给批处理 (由 skip_existing 跳过)，不能只传过滤后的部分
 #[tauri::command]
 pub fn filter_unprocessed_files(
     paths: Vec<String>, 
@@ -149,11 +266,14 @@ pub fn filter_unprocessed_files(
     let mut to_process = Vec::new();
     let mut skipped_count = 0;
     let mut error_count = 0;
+    let mut names = OutputNames::default();
 
-    for path_str in &paths {
-        match context.calculate_target_path(path_str) {
+    for (index, path_str) in paths.iter().enumerate() {
+        let exif = naming_exif(path_str, &context.export);
+        let naming = NamingContext { exif: exif.as_ref(), counter: Some(index + 1) };
+        match context.calculate_target_path(path_str, naming) {
             Ok(target_path) => {
-                if target_path.exists() {
+                if names.plan(target_path, &context.export.variants).all_exist() {
                     skipped_count += 1;
                 } else {
                     to_process.push(path_str.clone());
@@ -181,23 +301,27 @@ pub fn filter_unprocessed_files(
 /// 🟢 变更：返回值从 RawExifData 改为 Result<RawExifData, AppError>
 /// 这样调用者可以区分是“文件不存在”还是“单纯没有EXIF”
 pub fn get_exif_data(path: &str) -> Result<RawExifData, AppError> {
-    // 1. 尝试打开文件 (IO 错误应该抛出)
-    let file = File::open(path).map_err(|e| {
-        error!("❌ [Metadata] 无法打开文件 [{}]: {}", path, e);
-        AppError::Io(e)
-    })?;
-
-    // 2. 读取 EXIF
-    let mut reader = BufReader::new(file);
-    
-    // 🟢 策略调整：如果读取 EXIF 失败（比如是 PNG 或 纯文本文件），
-    // 这不算系统错误，而是“无数据”。所以我们记录警告，但返回默认空数据。
-    let exif = match Reader::new().read_from_container(&mut reader) {
-        Ok(e) => e,
-        Err(e) => {
-            // debug! 级别即可，因为很多图片确实没有 EXIF，不需要刷屏 error
-            debug!("ℹ️ [Metadata] 未找到 EXIF 信息 [{}]: {}", path, e);
-            return Ok(RawExifData::default());
+    // 🟢 文件句柄只在这个块内存活：EXIF 读进内存后立即关闭，
+    // 后续的字段解析和图片解码都不再占用这个句柄 (大批量时 fd 上限很紧)
+    let exif = {
+        // 1. 尝试打开文件 (IO 错误应该抛出)
+        let file = File::open(path).map_err(|e| {
+            error!("❌ [Metadata] 无法打开文件 [{}]: {}", path, e);
+            AppError::Io(e)
+        })?;
+
+        // 2. 读取 EXIF
+        let mut reader = BufReader::new(file);
+
+        // 🟢 策略调整：如果读取 EXIF 失败（比如是 PNG 或 纯文本文件），
+        // 这不算系统错误，而是“无数据”。所以我们记录警告，但返回默认空数据。
+        match Reader::new().read_from_container(&mut reader) {
+            Ok(e) => e,
+            Err(e) => {
+                // debug! 级别即可，因为很多图片确实没有 EXIF，不需要刷屏 error
+                debug!("ℹ️ [Metadata] 未找到 EXIF 信息 [{}]: {}", path, e);
+                return Ok(RawExifData::default());
+            }
         }
     };
 
@@ -210,6 +334,8 @@ pub fn get_exif_data(path: &str) -> Result<RawExifData, AppError> {
             .trim()
             .to_string()
     };
+    // 可选文本：没有该标签 (或内容为空) 时为 None
+    let get_opt_text = |tag| Some(get_text(tag)).filter(|s| !s.is_empty());
 
     // --- 辅助闭包：获取 u32 ---
     let get_u32 = |tag| {
@@ -235,22 +361,36 @@ pub fn get_exif_data(path: &str) -> Result<RawExifData, AppError> {
             })
     };
 
-    // GPS 预留位置
-    let lat = None;
-    let long = None;
+    // GPS：度分秒有理数 + 半球参考 (N/S, E/W) -> 带符号的十进制度
+    let lat = read_gps_coord(&exif, Tag::GPSLatitude, Tag::GPSLatitudeRef, 90.0);
+    let long = read_gps_coord(&exif, Tag::GPSLongitude, Tag::GPSLongitudeRef, 180.0);
+    // 部分机型没有定位时也会写入全 0 的坐标 (0°N 0°E 在海上)，视为无数据
+    let (lat, long) = match (lat, long) {
+        (Some(a), Some(b)) if a == 0.0 && b == 0.0 => {
+            debug!("ℹ️ [Metadata] GPS 坐标为 (0, 0)，视为无定位 [{}]", path);
+            (None, None)
+        },
+        other => other,
+    };
+
+    let focal_35mm = get_u32(Tag::FocalLengthIn35mmFilm);
 
     let data = RawExifData {
         make: get_text(Tag::Make),
         model: get_text(Tag::Model),
-        lens: get_text(Tag::LensModel),
-        iso: get_u32(Tag::PhotographicSensitivity),
+        lens: get_opt_text(Tag::LensModel),
+        iso: read_iso(&exif),
         aperture: get_f32(Tag::FNumber),
         shutter_speed: get_text(Tag::ExposureTime),
-        focal_length: get_u32(Tag::FocalLengthIn35mmFilm)
-            .or_else(|| get_u32(Tag::FocalLength)),
-        datetime: get_text(Tag::DateTimeOriginal),
-        artist: Some(get_text(Tag::Artist)),
-        copyright: Some(get_text(Tag::Copyright)),
+        // 物理焦距是有理数 (如 235/10)，按浮点读取保留小数
+        focal_length: focal_35mm.map(|f| f as f32).or_else(|| get_f32(Tag::FocalLength)),
+        focal_length_35mm: focal_35mm,
+        // 曝光补偿是有符号有理数 (如 -4/3)
+        exposure_bias: get_f32(Tag::ExposureBiasValue),
+        metering_mode: get_u32(Tag::MeteringMode).and_then(|v| u16::try_from(v).ok()),
+        datetime: get_opt_text(Tag::DateTimeOriginal),
+        artist: get_opt_text(Tag::Artist),
+        copyright: get_opt_text(Tag::Copyright),
         gps_latitude: lat,
         gps_longitude: long,
     };
@@ -260,9 +400,75 @@ pub fn get_exif_data(path: &str) -> Result<RawExifData, AppError> {
     Ok(data)
 }
 
+// PhotographicSensitivity 是 SHORT，扩展 ISO 超过 65535 时只能写这个标记值
+const EXTENDED_ISO_MARKER: u32 = 65535;
+
+/// 读取 ISO：PhotographicSensitivity -> ISOSpeed -> RecommendedExposureIndex -> StandardOutputSensitivity
+/// 部分 Canon 和老机型只写后面几个标签；扩展 ISO 时 PhotographicSensitivity 是 65535，
+/// 真实值在后面几个 LONG 标签里，都没有时保留 65535 (至少不低于实际值)。0 视为无数据
+fn read_iso(exif: &exif::Exif) -> Option<u32> {
+    let get = |tag| exif.get_field(tag, In::PRIMARY).and_then(|f| f.value.get_uint(0)).filter(|&v| v > 0);
+    let photographic = get(Tag::PhotographicSensitivity);
+    if let Some(iso) = photographic.filter(|&v| v < EXTENDED_ISO_MARKER) {
+        return Some(iso);
+    }
+    let fallback = [Tag::ISOSpeed, Tag::RecommendedExposureIndex, Tag::StandardOutputSensitivity]
+        .into_iter()
+        .find_map(|tag| get(tag).map(|v| (tag, v)));
+    match fallback {
+        Some((tag, iso)) => {
+            debug!("ℹ️ [Metadata] ISO 取自 {}: {}", tag, iso);
+            Some(iso)
+        },
+        None => photographic,
+    }
+}
+
+/// 读取一个 GPS 坐标分量 (GPSLatitude / GPSLongitude)
+/// 值是 [度, 分, 秒] 三个有理数；参考标签为 "S" / "W" 时取负。
+/// 缺少参考标签、分母为 0 或超出 `max_abs` 的坐标都视为无效
+fn read_gps_coord(exif: &exif::Exif, value_tag: Tag, ref_tag: Tag, max_abs: f64) -> Option<f64> {
+    let field = exif.get_field(value_tag, In::PRIMARY)?;
+    let Value::Rational(parts) = &field.value else { return None };
+    if parts.is_empty() {
+        return None;
+    }
+
+    // 个别软件只写 [度] 或 [度, 分]，缺的部分按 0 处理
+    let mut dms = [0.0f64; 3];
+    for (slot, r) in dms.iter_mut().zip(parts.iter()) {
+        if r.denom == 0 {
+            return None;
+        }
+        *slot = r.num as f64 / r.denom as f64;
+    }
+    let decimal = dms[0] + dms[1] / 60.0 + dms[2] / 3600.0;
+
+    let hemisphere = match &exif.get_field(ref_tag, In::PRIMARY)?.value {
+        Value::Ascii(v) => v.first().and_then(|s| s.first()).copied(),
+        _ => None,
+    }?;
+    let signed = match hemisphere.to_ascii_uppercase() {
+        b'N' | b'E' => decimal,
+        b'S' | b'W' => -decimal,
+        _ => return None,
+    };
+
+    if signed.abs() > max_abs {
+        warn!("⚠️ [Metadata] GPS 坐标超出范围，已忽略: {}", signed);
+        return None;
+    }
+    Some(signed)
+}
+
+// 🟢 EXIF 探测只读文件开头这么多字节 (JPG 的 APP1、TIFF / RAW 的 IFD0 几乎都在这里)
+const EXIF_PROBE_BYTES: u64 = 64 * 1024;
+
 /// 快速检查是否存在 EXIF
+/// 先只读前 64KB 解析；EXIF 跨过了这个范围 (带大缩略图的 APP1、RAW 里偏移较远的 MakerNote)
+/// 导致解析失败时再完整读取一次。明确"没有 EXIF"的结论不需要回退
 pub fn has_exif(path: &str) -> bool {
-    let file = match File::open(path) {
+    let mut file = match File::open(path) {
         Ok(f) => f,
         Err(e) => {
             // 这里我们只关心“有没有”，所以打开失败也算 false，但记录一下 debug 日志
@@ -270,8 +476,22 @@ pub fn has_exif(path: &str) -> bool {
             return false;
         }
     };
-    let mut bufreader = BufReader::new(&file);
-    exif::Reader::new().read_from_container(&mut bufreader).is_ok()
+    let mut head = Vec::new();
+    if let Err(e) = (&mut file).take(EXIF_PROBE_BYTES).read_to_end(&mut head) {
+        debug!("⚠️ [HasExif] 文件读取失败 [{}]: {}", path, e);
+        return false;
+    }
+    match exif::Reader::new().read_from_container(&mut Cursor::new(&head)) {
+        Ok(_) => return true,
+        Err(exif::Error::NotFound(_)) => return false,
+        // 文件本身不到 64KB：已经是完整内容，解析失败就是没有 (或损坏)
+        Err(_) if (head.len() as u64) < EXIF_PROBE_BYTES => return false,
+        Err(_) => {}
+    }
+    if file.seek(SeekFrom::Start(0)).is_err() {
+        return false;
+    }
+    exif::Reader::new().read_from_container(&mut BufReader::new(&file)).is_ok()
 }
 
 // 🟢 [Command] 批量过滤：只保留文件
@@ -298,15 +518,32 @@ pub fn filter_files(paths: Vec<String>) -> Vec<String> {
     filtered
 }
 
-// 🟢 [Command] 扫描文件夹
-// 🟢 变更：返回 Result<Vec<String>, AppError> 以便前端捕获“文件夹无权限”等错误
-#[tauri::command]
-pub fn scan_folder(folder_path: String) -> Result<Vec<String>, AppError> {
-    let allowed_exts = ["jpg", "jpeg", "png", "nef", "arw", "dng", "tif", "tiff", "webp"];
+// 没有扩展名的文件按文件头识别，只收能处理的格式 (RAW 靠扩展名判断，不在此列)
+fn sniff_image_file(path: &Path) -> bool {
+    let mut head = [0u8; 32];
+    let n = match File::open(path).and_then(|mut f| f.read(&mut head)) {
+        Ok(n) => n,
+        Err(e) => {
+            debug!("⚠️ [Scan] 无法读取文件头 [{:?}]: {}", path, e);
+            return false;
+        }
+    };
+    matches!(
+        image::guess_format(&head[..n]),
+        Ok(ImageFormat::Jpeg | ImageFormat::Png | ImageFormat::Tiff | ImageFormat::WebP)
+    )
+}
+
+// 🟢 扫描进度事件 (scan-progress) 的节流：每找到 / 检查这么多个文件发一次
+const SCAN_PROGRESS_STEP: usize = 200;
+
+// 列出目录里能处理的图片 (不递归)，每找到 SCAN_PROGRESS_STEP 个回调一次
+pub(crate) fn collect_image_paths(folder_path: &str, sniff_extensionless: bool, mut on_found: impl FnMut(usize)) -> Result<Vec<String>, AppError> {
+    let allowed_exts = ["jpg", "jpeg", "png", "nef", "arw", "dng", "cr3", "tif", "tiff", "webp"];
     let mut image_paths = Vec::new();
 
     // read_dir 可能会失败（权限不足、路径不存在），这里应该用 ? 抛出
-    let entries = fs::read_dir(&folder_path).map_err(|e| {
+    let entries = fs::read_dir(folder_path).map_err(|e| {
         error!("❌ [Scan] 无法读取目录 [{}]: {}", folder_path, e);
         AppError::Io(e)
     })?;
@@ -323,12 +560,15 @@ pub fn scan_folder(folder_path: String) -> Result<Vec<String>, AppError> {
 
         let path = entry.path();
         if path.is_file() {
-            if let Some(ext) = path.extension() {
-                if let Some(ext_str) = ext.to_str() {
-                    if allowed_exts.contains(&ext_str.to_lowercase().as_str()) {
-                        if let Some(path_str) = path.to_str() {
-                            image_paths.push(path_str.to_string());
-                        }
+            let admitted = match path.extension() {
+                Some(ext) => ext.to_str().is_some_and(|e| allowed_exts.contains(&e.to_lowercase().as_str())),
+                None => sniff_extensionless && sniff_image_file(&path),
+            };
+            if admitted {
+                if let Some(path_str) = path.to_str() {
+                    image_paths.push(path_str.to_string());
+                    if image_paths.len() % SCAN_PROGRESS_STEP == 0 {
+                        on_found(image_paths.len());
                     }
                 }
             }
@@ -337,4 +577,213 @@ pub fn scan_folder(folder_path: String) -> Result<Vec<String>, AppError> {
 
     debug!("📂 [Scan] 扫描目录 [{}] 完成，找到 {} 张图片", folder_path, image_paths.len());
     Ok(image_paths)
-}
\ No newline at end of file
+}
+
+// 🟢 [Command] 扫描文件夹
+// 🟢 变更：返回 Result<Vec<String>, AppError> 以便前端捕获“文件夹无权限”等错误
+// 🟢 sniff_extensionless：没有扩展名的文件按文件头识别 (不传时为 false，只看扩展名)
+// 🟢 异步执行，扫描期间发送 scan-progress 事件 { phase: "scanning", found }，几千张的目录界面不再卡住
+#[tauri::command]
+pub async fn scan_folder(window: Window, folder_path: String, sniff_extensionless: Option<bool>) -> Result<Vec<String>, AppError> {
+    let sniff_extensionless = sniff_extensionless.unwrap_or(false);
+    tauri::async_runtime::spawn_blocking(move || {
+        collect_image_paths(&folder_path, sniff_extensionless, |found| {
+            let _ = window.emit("scan-progress", json!({ "phase": "scanning", "found": found }));
+        })
+    })
+    .await
+    .map_err(|e| AppError::System(format!("扫描线程异常: {}", e)))?
+}
+
+/// 扫描结果：文件路径 + 是否带 EXIF
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScannedFile {
+    pub path: String,
+    pub has_exif: bool,
+}
+
+// 🟢 [Command] 扫描文件夹并同时检查 EXIF
+// 两个阶段都发送 scan-progress 事件：
+// - { phase: "scanning", found }：列目录中
+// - { phase: "exif", checked, total, withExif, files: [ScannedFile] }：每检查完一块就把这一块的结果发出去，
+//   前端可以边扫边加入列表，开始处理前就能显示 "3000 张里 2481 张有 EXIF"
+// 结果同时记入 AppState，批处理的 EXIF 检查步骤直接复用，不再逐张重新读取
+#[tauri::command]
+pub async fn scan_folder_with_exif(
+    window: Window,
+    state: State<'_, Arc<AppState>>,
+    folder_path: String,
+    sniff_extensionless: Option<bool>,
+) -> Result<Vec<ScannedFile>, AppError> {
+    let sniff_extensionless = sniff_extensionless.unwrap_or(false);
+    let state = (*state).clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let paths = collect_image_paths(&folder_path, sniff_extensionless, |found| {
+            let _ = window.emit("scan-progress", json!({ "phase": "scanning", "found": found }));
+        })?;
+
+        let total = paths.len();
+        let mut scanned = Vec::with_capacity(total);
+        let mut stamps = Vec::with_capacity(total);
+        let mut with_exif = 0;
+        // 按块并行检查，块内保持原顺序，每块结束发一次事件
+        for chunk in paths.chunks(SCAN_PROGRESS_STEP) {
+            // 先记下文件身份再检查：检查期间文件被改写的话，批处理时身份对不上会重新检查
+            let (files, chunk_stamps): (Vec<ScannedFile>, Vec<Option<FileStamp>>) = chunk
+                .par_iter()
+                .map(|path| {
+                    let stamp = FileStamp::read(Path::new(path));
+                    (ScannedFile { path: path.clone(), has_exif: has_exif(path) }, stamp)
+                })
+                .unzip();
+            stamps.extend(chunk_stamps);
+            with_exif += files.iter().filter(|f| f.has_exif).count();
+            let _ = window.emit("scan-progress", json!({
+                "phase": "exif",
+                "checked": scanned.len() + files.len(),
+                "total": total,
+                "withExif": with_exif,
+                "files": files,
+            }));
+            scanned.extend(files);
+        }
+
+        state.record_exif_presence(scanned.iter().zip(stamps).map(|(f, stamp)| (f.path.clone(), stamp, f.has_exif)));
+        info!("📂 [Scan] [{}] 共 {} 张，其中 {} 张有 EXIF", folder_path, total, with_exif);
+        Ok(scanned)
+    })
+    .await
+    .map_err(|e| AppError::System(format!("扫描线程异常: {}", e)))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{fixture, temp_dir};
+
+    fn coords(name: &str) -> (f64, f64) {
+        let raw = get_exif_data(&fixture(name)).unwrap();
+        (raw.gps_latitude.expect("缺少纬度"), raw.gps_longitude.expect("缺少经度"))
+    }
+
+    #[test]
+    fn gps_rationals_become_signed_degrees() {
+        // 35°41'22.2"N 139°41'30.12"E
+        let (lat, lon) = coords("gps_tokyo.jpg");
+        assert!((lat - 35.6895).abs() < 1e-6, "{}", lat);
+        assert!((lon - 139.6917).abs() < 1e-6, "{}", lon);
+        // 22°54'30"S 43°11'47"W：南纬 / 西经为负
+        let (lat, lon) = coords("gps_rio.jpg");
+        assert!((lat + 22.908333).abs() < 1e-5, "{}", lat);
+        assert!((lon + 43.196389).abs() < 1e-5, "{}", lon);
+    }
+
+    #[test]
+    fn gps_formats_as_dms_in_both_hemispheres() {
+        let format = |name| {
+            let raw = get_exif_data(&fixture(name)).unwrap();
+            crate::parser::parse(raw).gps.expect("GPS 丢失").format_dms()
+        };
+        assert_eq!(format("gps_tokyo.jpg"), "35°41'22\"N 139°41'30\"E");
+        assert_eq!(format("gps_rio.jpg"), "22°54'30\"S 43°11'47\"W");
+    }
+
+    #[test]
+    fn file_without_gps_has_no_coordinates() {
+        let raw = get_exif_data(&fixture("orientation_1.jpg")).unwrap();
+        assert_eq!((raw.gps_latitude, raw.gps_longitude), (None, None));
+    }
+
+    #[test]
+    fn iso_falls_back_to_later_tags() {
+        // 5D 时代的 Canon 只写 ISOSpeed / RecommendedExposureIndex
+        let raw = get_exif_data(&fixture("iso_canon_5d.jpg")).unwrap();
+        assert_eq!(raw.model, "Canon EOS 5D");
+        assert_eq!(raw.iso, Some(400));
+    }
+
+    #[test]
+    fn extended_iso_marker_uses_real_value() {
+        // PhotographicSensitivity 为 65535 标记值，真实感光度在 RecommendedExposureIndex
+        let raw = get_exif_data(&fixture("iso_extended.jpg")).unwrap();
+        assert_eq!(raw.iso, Some(102_400));
+        // 没有任何 ISO 标签
+        assert_eq!(get_exif_data(&fixture("gps_tokyo.jpg")).unwrap().iso, None);
+    }
+
+    #[test]
+    fn exposure_bias_and_metering_mode_are_read() {
+        // ExposureBiasValue = -4/3 (SRational)，MeteringMode = 3 (点测光)
+        let raw = get_exif_data(&fixture("ev_spot.jpg")).unwrap();
+        assert_eq!(raw.metering_mode, Some(3));
+        assert!((raw.exposure_bias.unwrap() + 4.0 / 3.0).abs() < 1e-6);
+
+        let params = crate::parser::parse(raw).params;
+        assert_eq!(params.metering_mode, Some(crate::parser::models::MeteringMode::Spot));
+        assert_eq!(params.format_ev().as_deref(), Some("-1.3"));
+        // 没有这两个标签的文件
+        let raw = get_exif_data(&fixture("gps_tokyo.jpg")).unwrap();
+        assert_eq!((raw.exposure_bias, raw.metering_mode), (None, None));
+    }
+
+    #[test]
+    fn existing_output_is_found_by_style_suffix() {
+        // 前端传来的 style 与批处理用同一个枚举解析，后缀不会各算各的
+        let dir = temp_dir("common_existing");
+        let src = dir.join("DSC_0001.jpg");
+        std::fs::copy(fixture("gps_tokyo.jpg"), &src).unwrap();
+        std::fs::copy(fixture("gps_tokyo.jpg"), dir.join("DSC_0001_WhiteModern.jpg")).unwrap();
+
+        let style = |name: &str| serde_json::from_value::<StyleOptions>(json!({ "style": name })).unwrap();
+        let src = src.to_string_lossy().into_owned();
+        let preview = check_output_exists(src.clone(), style("WhiteModern"), ExportConfig::default());
+        assert!(preview.is_some_and(|p| p.starts_with("data:image/jpeg;base64,")));
+        assert!(check_output_exists(src, style("WhiteClassic"), ExportConfig::default()).is_none());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn blank_text_tags_read_as_none() {
+        // Artist 全是空格、LensModel 为空、没有拍摄时间，只有 Copyright 有内容
+        let raw = get_exif_data(&fixture("artist_blank.jpg")).unwrap();
+        assert_eq!(raw.artist, None);
+        assert_eq!(raw.copyright.as_deref(), Some("Aurora"));
+        assert_eq!(raw.lens, None);
+        assert_eq!(raw.datetime, None);
+        // 署名回退到 Copyright
+        assert_eq!(crate::parser::parse(raw).artist_name.as_deref(), Some("Aurora"));
+    }
+
+    fn filter_context(out: &std::path::Path, template: &str) -> BatchContext {
+        serde_json::from_value(serde_json::json!({
+            "style": "WhiteClassic",
+            "exportSettings": { "targetDir": out, "format": "jpg", "quality": 95, "nameTemplate": template },
+        })).unwrap()
+    }
+
+    #[test]
+    fn filter_numbers_counter_over_the_submitted_list() {
+        let out = temp_dir("filter_counter");
+        std::fs::write(out.join("0002.jpg"), b"").unwrap();
+        let paths: Vec<String> = ["/in/a.NEF", "/in/b.NEF", "/in/c.NEF"].iter().map(|s| s.to_string()).collect();
+
+        let kept = filter_unprocessed_files(paths, filter_context(&out, "{counter}"));
+        let _ = std::fs::remove_dir_all(&out);
+        // 第 2 个文件的成品已存在；编号按提交顺序，与批处理收到同一列表时一致
+        assert_eq!(kept, vec!["/in/a.NEF".to_string(), "/in/c.NEF".to_string()]);
+    }
+
+    #[test]
+    fn filter_recognises_deduped_outputs() {
+        let out = temp_dir("filter_dedupe");
+        // 两个目录里的同名原图输出到同一个文件夹：第二个上次被改名为 DSC_0001_1.jpg
+        std::fs::write(out.join("DSC_0001.jpg"), b"").unwrap();
+        std::fs::write(out.join("DSC_0001_1.jpg"), b"").unwrap();
+        let paths: Vec<String> = ["/a/DSC_0001.NEF", "/b/DSC_0001.NEF", "/c/DSC_0001.NEF"].iter().map(|s| s.to_string()).collect();
+
+        let kept = filter_unprocessed_files(paths, filter_context(&out, "{stem}"));
+        let _ = std::fs::remove_dir_all(&out);
+        assert_eq!(kept, vec!["/c/DSC_0001.NEF".to_string()]);
+    }
+}
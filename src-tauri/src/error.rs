@@ -17,6 +17,43 @@ pub enum AppError {
     
     #[error("路径计算失败: {0}")]
     PathCalculation(String),
+
+    // 目标磁盘空间不足 / 只读 (批次预检)
+    #[error("存储不可用: {0}")]
+    Storage(String),
+
+    // 字体文件缺失或无法解析
+    #[error("字体加载失败: {0}")]
+    Font(String),
+
+    // 用户停止 / 看门狗放弃了正在处理的文件 (管线把它当作静默停止，不上报错误)
+    #[error("已取消")]
+    Cancelled,
+}
+
+// 🟢 文件不完整 (读卡中断、拷贝到一半) 的消息前缀
+// 仍然归在 System 下，错误码单独区分 (TRUNCATED_FILE)，前端和批次报告据此归类
+pub const TRUNCATED_FILE: &str = "truncated file";
+
+impl AppError {
+    /// 文件不完整：detail 说明是怎么发现的 (空文件 / 缺少结束标记 / 解码器读到文件末尾)
+    pub fn truncated(detail: impl std::fmt::Display) -> Self {
+        AppError::System(format!("{}: {}", TRUNCATED_FILE, detail))
+    }
+
+    /// 错误码 (用于前端判断类型，批次报告里也按这个归类)
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::Io(_) => "IO_ERROR",
+            AppError::Image(_) => "IMAGE_ERROR",
+            AppError::System(msg) if msg.starts_with(TRUNCATED_FILE) => "TRUNCATED_FILE",
+            AppError::System(_) => "SYSTEM_ERROR",
+            AppError::PathCalculation(_) => "PATH_ERROR",
+            AppError::Storage(_) => "STORAGE_ERROR",
+            AppError::Font(_) => "FONT_ERROR",
+            AppError::Cancelled => "CANCELLED",
+        }
+    }
 }
 
 // 核心：实现 Serialize，让前端接收到的是 JSON 对象而不是报错字符串
@@ -28,12 +65,7 @@ impl Serialize for AppError {
         use serde::ser::SerializeStruct;
         let mut state = serializer.serialize_struct("AppError", 2)?;
         // 1. 错误码 (用于前端判断类型)
-        state.serialize_field("code", match self {
-            AppError::Io(_) => "IO_ERROR",
-            AppError::Image(_) => "IMAGE_ERROR",
-            AppError::System(_) => "SYSTEM_ERROR",
-            AppError::PathCalculation(_) => "PATH_ERROR",
-        })?;
+        state.serialize_field("code", self.code())?;
         // 2. 错误信息 (用于展示)
         state.serialize_field("message", &self.to_string())?;
         state.end()
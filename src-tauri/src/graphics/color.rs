@@ -0,0 +1,139 @@
+// src-tauri/src/graphics/color.rs
+
+use image::{DynamicImage, ImageBuffer};
+use log::{debug, warn};
+use moxcms::{CmsError, ColorProfile, DataColorSpace, Layout, ProfileText, TransformOptions};
+
+use crate::error::AppError;
+
+// =========================================================
+// 🟢 色彩管理：原图 ICC 配置文件 -> sRGB
+// =========================================================
+// 边框是按 sRGB 画的，输出也不带配置文件 (浏览器按 sRGB 显示)。
+// Adobe RGB / Display P3 的原图如果直接拿原始像素值处理，成品会明显发灰。
+// 转换走 moxcms (image 本身已经依赖它，纯 Rust)。
+
+// JPG APP2 段里 ICC 的标识，后面跟 序号 (从 1 开始) + 总段数
+const APP2_ICC_HEADER: &[u8] = b"ICC_PROFILE\0";
+// 单个 APP2 段能放下的 ICC 数据 (65535 - 长度字段 2 - 标识 12 - 序号 2)
+const MAX_ICC_CHUNK: usize = 65519;
+
+/// 原图内嵌的 ICC 配置文件
+pub struct SourceProfile {
+    /// 配置文件描述 (如 "Adobe RGB (1998)")，随进度事件发给前端
+    pub name: String,
+    profile: ColorProfile,
+    icc: Vec<u8>,
+}
+
+impl SourceProfile {
+    /// 解析 ICC 数据；解析失败时按未标记 (sRGB) 处理
+    pub fn parse(icc: Vec<u8>) -> Option<Self> {
+        match ColorProfile::new_from_slice(&icc) {
+            Ok(profile) => {
+                let name = profile.description.as_ref().and_then(text_of).unwrap_or_else(|| "未命名 ICC".to_string());
+                Some(Self { name, profile, icc })
+            },
+            Err(e) => {
+                warn!("⚠️ [Color] ICC 配置文件解析失败，按 sRGB 处理: {:?}", e);
+                None
+            },
+        }
+    }
+
+    /// 原始 ICC 数据 (关闭色彩管理时原样嵌入输出)
+    pub fn icc(&self) -> &[u8] {
+        &self.icc
+    }
+
+    /// 是否需要转换：只处理 RGB 配置文件，sRGB 本身跳过
+    pub fn needs_conversion(&self) -> bool {
+        self.profile.color_space == DataColorSpace::Rgb && !self.name.to_ascii_lowercase().contains("srgb")
+    }
+
+    /// 转换到 sRGB，保持原位深和 Alpha (灰度图原样返回)
+    pub fn to_srgb(&self, img: &DynamicImage) -> Result<DynamicImage, AppError> {
+        let srgb = ColorProfile::new_srgb();
+        let options = TransformOptions::default();
+        let cms_err = |e: CmsError| AppError::System(format!("色彩转换失败 ({}): {:?}", self.name, e));
+        debug!("🎨 [Color] {} -> sRGB", self.name);
+
+        let converted = match img {
+            DynamicImage::ImageRgb8(buf) => {
+                let t = self.profile.create_transform_8bit(Layout::Rgb, &srgb, Layout::Rgb, options).map_err(cms_err)?;
+                let mut out = ImageBuffer::new(buf.width(), buf.height());
+                t.transform(buf.as_raw(), &mut out).map_err(cms_err)?;
+                DynamicImage::ImageRgb8(out)
+            },
+            DynamicImage::ImageRgba8(buf) => {
+                let t = self.profile.create_transform_8bit(Layout::Rgba, &srgb, Layout::Rgba, options).map_err(cms_err)?;
+                let mut out = ImageBuffer::new(buf.width(), buf.height());
+                t.transform(buf.as_raw(), &mut out).map_err(cms_err)?;
+                DynamicImage::ImageRgba8(out)
+            },
+            DynamicImage::ImageRgb16(buf) => {
+                let t = self.profile.create_transform_16bit(Layout::Rgb, &srgb, Layout::Rgb, options).map_err(cms_err)?;
+                let mut out = ImageBuffer::new(buf.width(), buf.height());
+                t.transform(buf.as_raw(), &mut out).map_err(cms_err)?;
+                DynamicImage::ImageRgb16(out)
+            },
+            DynamicImage::ImageRgba16(buf) => {
+                let t = self.profile.create_transform_16bit(Layout::Rgba, &srgb, Layout::Rgba, options).map_err(cms_err)?;
+                let mut out = ImageBuffer::new(buf.width(), buf.height());
+                t.transform(buf.as_raw(), &mut out).map_err(cms_err)?;
+                DynamicImage::ImageRgba16(out)
+            },
+            DynamicImage::ImageLuma8(_) | DynamicImage::ImageLumaA8(_)
+            | DynamicImage::ImageLuma16(_) | DynamicImage::ImageLumaA16(_) => img.clone(),
+            // 浮点等其他格式先转为 16 位再转换
+            other if other.color().has_alpha() => self.to_srgb(&DynamicImage::ImageRgba16(other.to_rgba16()))?,
+            other => self.to_srgb(&DynamicImage::ImageRgb16(other.to_rgb16()))?,
+        };
+        Ok(converted)
+    }
+}
+
+/// 取配置文件描述文本 (多语言时优先英文)
+fn text_of(text: &ProfileText) -> Option<String> {
+    let s = match text {
+        ProfileText::PlainString(s) => s.clone(),
+        ProfileText::Description(d) => d.ascii_string.clone(),
+        ProfileText::Localizable(list) => list
+            .iter()
+            .find(|l| l.language.eq_ignore_ascii_case("en"))
+            .or_else(|| list.first())
+            .map(|l| l.value.clone())?,
+    };
+    let s = s.trim_matches(char::from(0)).trim().to_string();
+    (!s.is_empty()).then_some(s)
+}
+
+/// 把 ICC 配置文件以 APP2 段嵌入 JPG (超过 64KB 时分段)，放在 SOI / JFIF APP0 之后
+pub fn inject_icc_into_jpeg(jpeg: &[u8], icc: &[u8]) -> Result<Vec<u8>, String> {
+    if jpeg.len() < 4 || jpeg[0] != 0xFF || jpeg[1] != 0xD8 {
+        return Err("不是有效的 JPG 数据".to_string());
+    }
+    let chunks: Vec<&[u8]> = icc.chunks(MAX_ICC_CHUNK).collect();
+    if chunks.is_empty() || chunks.len() > 255 {
+        return Err(format!("ICC 配置文件大小异常 ({} 字节)", icc.len()));
+    }
+
+    let mut insert_at = 2;
+    if jpeg[2] == 0xFF && jpeg[3] == 0xE0 && jpeg.len() >= 6 {
+        let app0_len = u16::from_be_bytes([jpeg[4], jpeg[5]]) as usize;
+        insert_at = (4 + app0_len).min(jpeg.len());
+    }
+
+    let mut out = Vec::with_capacity(jpeg.len() + icc.len() + chunks.len() * 18);
+    out.extend_from_slice(&jpeg[..insert_at]);
+    for (i, chunk) in chunks.iter().enumerate() {
+        let seg_len = 2 + APP2_ICC_HEADER.len() + 2 + chunk.len();
+        out.extend_from_slice(&[0xFF, 0xE2]);
+        out.extend_from_slice(&(seg_len as u16).to_be_bytes());
+        out.extend_from_slice(APP2_ICC_HEADER);
+        out.extend_from_slice(&[(i + 1) as u8, chunks.len() as u8]);
+        out.extend_from_slice(chunk);
+    }
+    out.extend_from_slice(&jpeg[insert_at..]);
+    Ok(out)
+}
@@ -0,0 +1,251 @@
+// src-tauri/src/graphics/decode_cache.rs
+
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use image::{DynamicImage, GenericImageView};
+use log::debug;
+
+use crate::error::AppError;
+use super::effects::load_image_with_icc;
+
+// =========================================================
+// 🟢 解码结果缓存 (LRU)
+// =========================================================
+// 同一张 60MP 照片先预览三个样式、再批处理，会完整解码四次 (每次 1-2 秒)。
+// 这里缓存摆正方向后的解码结果 (色彩转换之前)，按 路径 + 修改时间 + 大小 识别，
+// 文件在磁盘上变了就视为未命中并丢弃旧条目。
+// 预览负责写入和读取；批处理只读取 (每张只处理一次，写进来只会挤掉用户刚预览的图)。
+
+/// 默认缓存张数
+pub const DEFAULT_CAPACITY: usize = 3;
+// 内存上限按 w * h * 4 累计：物理内存的 1/8，查询不到时 1 GiB
+const FALLBACK_MAX_BYTES: u64 = 1 << 30;
+const MEMORY_DIVISOR: u64 = 8;
+
+/// 命中的解码结果
+#[derive(Clone)]
+pub struct CachedImage {
+    pub image: Arc<DynamicImage>,
+    pub icc: Option<Vec<u8>>,
+}
+
+/// 文件身份：修改时间 + 大小 (同一秒内覆盖写入的情况靠大小兜底)
+/// 扫描时记录的 EXIF 检查结果也按它判断文件是否还是原来那个
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileStamp {
+    modified: SystemTime,
+    len: u64,
+}
+
+impl FileStamp {
+    /// 读不到元数据 (文件不存在等) 时返回 None
+    pub fn read(path: &Path) -> Option<Self> {
+        let meta = std::fs::metadata(path).ok()?;
+        Some(Self { modified: meta.modified().ok()?, len: meta.len() })
+    }
+}
+
+struct Entry {
+    path: PathBuf,
+    stamp: FileStamp,
+    bytes: u64,
+    cached: CachedImage,
+}
+
+struct Inner {
+    // 最近使用的在队尾
+    entries: VecDeque<Entry>,
+    capacity: usize,
+}
+
+pub struct DecodedImageCache {
+    inner: Mutex<Inner>,
+    max_bytes: u64,
+}
+
+impl Default for DecodedImageCache {
+    fn default() -> Self {
+        let max_bytes = crate::batch::concurrency::total_memory()
+            .map_or(FALLBACK_MAX_BYTES, |total| total / MEMORY_DIVISOR);
+        Self {
+            inner: Mutex::new(Inner { entries: VecDeque::new(), capacity: DEFAULT_CAPACITY }),
+            max_bytes,
+        }
+    }
+}
+
+impl DecodedImageCache {
+    /// 调整缓存张数 (0 代表关闭并清空)，多出的旧条目立即淘汰
+    pub fn set_capacity(&self, capacity: usize) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.capacity = capacity;
+        while inner.entries.len() > capacity {
+            inner.entries.pop_front();
+        }
+    }
+
+    /// 查询缓存；文件已变化 (或已删除) 时移除旧条目并返回 None
+    pub fn get(&self, path: &str) -> Option<CachedImage> {
+        let path = Path::new(path);
+        let stamp = FileStamp::read(path);
+        let mut inner = self.inner.lock().unwrap();
+        let pos = inner.entries.iter().position(|e| e.path == path)?;
+        let entry = inner.entries.remove(pos)?;
+        if Some(entry.stamp) != stamp {
+            debug!("🗑️ [DecodeCache] 文件已变化，丢弃缓存: {:?}", path);
+            return None;
+        }
+        let cached = entry.cached.clone();
+        inner.entries.push_back(entry);
+        Some(cached)
+    }
+
+    /// 写入解码结果；单张超过内存上限时不缓存，否则从最久未用的开始淘汰到满足张数和内存限制
+    fn insert(&self, path: &str, stamp: FileStamp, cached: CachedImage) {
+        let (w, h) = cached.image.dimensions();
+        let bytes = w as u64 * h as u64 * 4;
+        let mut inner = self.inner.lock().unwrap();
+        if inner.capacity == 0 || bytes > self.max_bytes {
+            return;
+        }
+
+        let path = PathBuf::from(path);
+        inner.entries.retain(|e| e.path != path);
+        let mut total: u64 = inner.entries.iter().map(|e| e.bytes).sum();
+        while !inner.entries.is_empty() && (inner.entries.len() >= inner.capacity || total + bytes > self.max_bytes) {
+            if let Some(old) = inner.entries.pop_front() {
+                total -= old.bytes;
+            }
+        }
+        inner.entries.push_back(Entry { path, stamp, bytes, cached });
+    }
+}
+
+/// 带缓存的 load_image_with_icc：命中直接返回，未命中时解码，store 为 true 时写入缓存
+/// 修改时间在解码之前读取，解码期间文件被改写的话下次查询会因时间不符而失效
+pub fn load_image_cached(cache: &DecodedImageCache, path: &str, store: bool) -> Result<CachedImage, AppError> {
+    if let Some(hit) = cache.get(path) {
+        debug!("♻️ [DecodeCache] 命中: {}", path);
+        return Ok(hit);
+    }
+    let stamp = FileStamp::read(Path::new(path));
+    let (img, icc) = load_image_with_icc(path)?;
+    let cached = CachedImage { image: Arc::new(img), icc };
+    if let (true, Some(stamp)) = (store, stamp) {
+        cache.insert(path, stamp, cached.clone());
+    }
+    Ok(cached)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use crate::test_support::{fixture, temp_dir};
+
+    fn cache(capacity: usize, max_bytes: u64) -> DecodedImageCache {
+        DecodedImageCache {
+            inner: Mutex::new(Inner { entries: VecDeque::new(), capacity }),
+            max_bytes,
+        }
+    }
+
+    fn image(w: u32, h: u32) -> CachedImage {
+        CachedImage { image: Arc::new(DynamicImage::new_rgba8(w, h)), icc: None }
+    }
+
+    /// 临时目录里的文件 (缓存只看修改时间和大小，内容不需要是图片)
+    fn temp_files(tag: &str, names: &[&str]) -> (PathBuf, Vec<String>) {
+        let dir = temp_dir(&format!("decode_cache_{}", tag));
+        let paths = names.iter().map(|name| {
+            let path = dir.join(name);
+            std::fs::write(&path, name.as_bytes()).unwrap();
+            path.to_string_lossy().into_owned()
+        }).collect();
+        (dir, paths)
+    }
+
+    fn put(cache: &DecodedImageCache, path: &str, img: CachedImage) {
+        cache.insert(path, FileStamp::read(Path::new(path)).unwrap(), img);
+    }
+
+    #[test]
+    fn least_recently_used_entry_is_evicted_first() {
+        let (dir, paths) = temp_files("lru", &["a", "b", "c"]);
+        let cache = cache(2, u64::MAX);
+        put(&cache, &paths[0], image(4, 4));
+        put(&cache, &paths[1], image(4, 4));
+        // 读一次 a，b 就成了最久未用的
+        assert!(cache.get(&paths[0]).is_some());
+        put(&cache, &paths[2], image(4, 4));
+
+        assert!(cache.get(&paths[1]).is_none());
+        assert!(cache.get(&paths[0]).is_some());
+        assert!(cache.get(&paths[2]).is_some());
+
+        // 缩小容量时同样从最久未用的开始淘汰 (刚读过 c，剩下的是 c)
+        cache.set_capacity(1);
+        assert!(cache.get(&paths[0]).is_none());
+        assert!(cache.get(&paths[2]).is_some());
+        cache.set_capacity(0);
+        assert!(cache.get(&paths[2]).is_none());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn memory_bound_evicts_and_rejects_oversized_images() {
+        let (dir, paths) = temp_files("memory", &["a", "b", "c", "huge"]);
+        // 够放两张 10x10 (每张 400 字节)
+        let cache = cache(10, 800);
+        put(&cache, &paths[0], image(10, 10));
+        put(&cache, &paths[1], image(10, 10));
+        put(&cache, &paths[2], image(10, 10));
+        assert!(cache.get(&paths[0]).is_none());
+        assert!(cache.get(&paths[1]).is_some());
+        assert!(cache.get(&paths[2]).is_some());
+
+        // 单张超过上限：不缓存，也不挤掉已有的
+        put(&cache, &paths[3], image(20, 20));
+        assert!(cache.get(&paths[3]).is_none());
+        assert!(cache.get(&paths[1]).is_some());
+        assert!(cache.get(&paths[2]).is_some());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn changed_file_stamp_invalidates_the_entry() {
+        let (dir, paths) = temp_files("stamp", &["size", "mtime"]);
+        let cache = cache(4, u64::MAX);
+        put(&cache, &paths[0], image(4, 4));
+        put(&cache, &paths[1], image(4, 4));
+        assert!(cache.get(&paths[0]).is_some());
+
+        // 大小变了
+        std::fs::write(&paths[0], b"rewritten").unwrap();
+        assert!(cache.get(&paths[0]).is_none());
+
+        // 大小不变，只有修改时间变了
+        let file = std::fs::File::options().write(true).open(&paths[1]).unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(60)).unwrap();
+        drop(file);
+        assert!(cache.get(&paths[1]).is_none());
+
+        // 失效的条目已从缓存里移除
+        assert!(cache.inner.lock().unwrap().entries.is_empty());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn batch_reads_do_not_store() {
+        let path = fixture("ev_spot.jpg");
+        let cache = cache(2, u64::MAX);
+        load_image_cached(&cache, &path, false).unwrap();
+        assert!(cache.get(&path).is_none());
+        let first = load_image_cached(&cache, &path, true).unwrap();
+        let hit = cache.get(&path).expect("预览解码后应命中");
+        assert!(Arc::ptr_eq(&first.image, &hit.image));
+    }
+}
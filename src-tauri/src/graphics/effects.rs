@@ -1,14 +1,17 @@
-use std::{fs::File, io::BufReader};
+use std::{fs::File, io::BufReader, time::Instant};
 
 use exif::{In, Reader, Tag};
-use image::{DynamicImage, Rgba, imageops, GenericImageView, RgbaImage};
+use image::{DynamicImage, Rgba, imageops, GenericImageView, RgbaImage, ImageBuffer, ImageDecoder, ImageReader, ImageError, ImageFormat, Pixel};
+use image::error::{DecodingError, ImageFormatHint};
 use imageproc::rect::Rect;
 use log::{debug}; // 🟢 引入日志
+use rayon::prelude::*;
 
 // 🟢 引入我们的错误类型
 use crate::error::AppError;
 // 引用同级目录下的 shapes 模块
 use super::shapes::draw_rounded_rect_mut;
+use super::integrity;
 
 
 /// 辅助：简单的 Alpha Blending (Src Over Dst)
@@ -26,37 +29,221 @@ fn blend_pixel(bg: Rgba<u8>, fg: Rgba<u8>) -> Rgba<u8> {
     Rgba([r, g, b, new_alpha])
 }
 
-pub fn make_image_white(img: &DynamicImage) -> DynamicImage {
+/// 🎨 单色着色：RGB 全部替换为目标颜色，Alpha 保持原样 (目标颜色的 Alpha 作为整体不透明度相乘)
+/// 用于把黑色字标变成白色 / 浅灰 / 品牌色；Logo 的抗锯齿边缘完全靠 Alpha，所以边缘不会发虚
+pub fn tint_image(img: &DynamicImage, color: Rgba<u8>) -> DynamicImage {
     let mut new_img = img.to_rgba8();
-    
+    let opacity = color[3] as u16;
+
     for pixel in new_img.pixels_mut() {
-        if pixel[3] > 0 {
-            pixel[0] = 255;
-            pixel[1] = 255;
-            pixel[2] = 255;
-        }
+        pixel[0] = color[0];
+        pixel[1] = color[1];
+        pixel[2] = color[2];
+        // 四舍五入的整数乘法，opacity = 255 时 Alpha 不变
+        pixel[3] = ((pixel[3] as u16 * opacity + 127) / 255) as u8;
     }
-    
+
     DynamicImage::ImageRgba8(new_img)
 }
 
-// 🟢 高性能模糊背景生成器
+/// 着色为纯白 (兼容旧调用)
+#[allow(dead_code)]
+pub fn make_image_white(img: &DynamicImage) -> DynamicImage {
+    tint_image(img, Rgba([255, 255, 255, 255]))
+}
+
+/// 🎨 主色提取：先缩到 64px 以内，再按 RGB 各取高 4 位分桶 (4096 桶) 统计，取像素最多的桶内平均色
+/// 缩放和统计都是确定性的 (不做随机采样)，同一张图永远得到同一个颜色
+/// 完全透明的像素不参与统计；整张图都透明时返回白色
+pub fn dominant_color(img: &DynamicImage) -> Rgba<u8> {
+    let small = img.thumbnail(64, 64).to_rgba8();
+
+    // 每个桶：[像素数, R 总和, G 总和, B 总和]
+    let mut buckets = vec![[0u32; 4]; 4096];
+    for p in small.pixels().filter(|p| p[3] > 0) {
+        let idx = ((p[0] as usize >> 4) << 8) | ((p[1] as usize >> 4) << 4) | (p[2] as usize >> 4);
+        let b = &mut buckets[idx];
+        b[0] += 1;
+        b[1] += p[0] as u32;
+        b[2] += p[1] as u32;
+        b[3] += p[2] as u32;
+    }
+
+    // 数量相同时取序号小的桶，保证结果稳定
+    let best = buckets.iter().fold(&[0u32; 4], |best, b| if b[0] > best[0] { b } else { best });
+    if best[0] == 0 {
+        return Rgba([255, 255, 255, 255]);
+    }
+    let avg = |sum: u32| ((sum + best[0] / 2) / best[0]) as u8;
+    Rgba([avg(best[1]), avg(best[2]), avg(best[3]), 255])
+}
+
+/// 🎨 按背景亮度选择文字颜色：亮背景用黑字，暗背景用白字
+/// 亮度按 sRGB 相对亮度 (WCAG) 计算，0.179 是黑白两种文字对比度相等的分界点
+pub fn contrast_text_color(bg: Rgba<u8>) -> Rgba<u8> {
+    if relative_luminance(bg) > 0.179 {
+        Rgba([0, 0, 0, 255])
+    } else {
+        Rgba([255, 255, 255, 255])
+    }
+}
+
+/// sRGB 相对亮度 (0 = 黑，1 = 白)
+pub fn relative_luminance(c: Rgba<u8>) -> f32 {
+    let linear = |v: u8| {
+        let v = v as f32 / 255.0;
+        if v <= 0.04045 { v / 12.92 } else { ((v + 0.055) / 1.055).powf(2.4) }
+    };
+    0.2126 * linear(c[0]) + 0.7152 * linear(c[1]) + 0.0722 * linear(c[2])
+}
+
+/// 两种颜色的 WCAG 对比度 (1 ~ 21)
+pub fn contrast_ratio(a: Rgba<u8>, b: Rgba<u8>) -> f32 {
+    let (la, lb) = (relative_luminance(a), relative_luminance(b));
+    (la.max(lb) + 0.05) / (la.min(lb) + 0.05)
+}
+
+/// 🎨 保证颜色在背景上的对比度：不够时逐步向黑色 (亮背景) / 白色 (暗背景) 混合，
+/// 直到达到 min_ratio。尼康黄这类浅色在白底上会被压成偏暗的芥末黄
+pub fn ensure_contrast(color: Rgba<u8>, bg: Rgba<u8>, min_ratio: f32) -> Rgba<u8> {
+    let target = contrast_text_color(bg);
+    let mix = |t: f32| {
+        let ch = |i: usize| (color[i] as f32 + (target[i] as f32 - color[i] as f32) * t).round() as u8;
+        Rgba([ch(0), ch(1), ch(2), color[3]])
+    };
+    (0..=20)
+        .map(|step| mix(step as f32 / 20.0))
+        .find(|c| contrast_ratio(*c, bg) >= min_ratio)
+        .unwrap_or(target)
+}
+
+/// 🟢 模糊背景的亮度调整方式
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BgBrightness {
+    /// 固定偏移 (-255 ~ 255，与 imageops::brighten 一致)
+    Fixed(i32),
+    /// 按原图平均亮度自动选择偏移，让背景平均亮度落在 [min, max] (0-255) 内
+    /// 夜景本来就暗，不再被固定偏移压成纯黑
+    Auto { min: u8, max: u8 },
+}
+
+/// 🟢 模糊背景的色调：亮度 + 饱和度 (1.0 = 不变，0.0 = 灰度，大于 1 更浓)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BackgroundTone {
+    pub brightness: BgBrightness,
+    pub saturation: f32,
+}
+
+impl BackgroundTone {
+    /// 自动模式下按图片实际的平均亮度算出偏移
+    fn brightness_for(&self, img: &DynamicImage) -> i32 {
+        match self.brightness {
+            BgBrightness::Fixed(adj) => adj,
+            BgBrightness::Auto { min, max } => {
+                let mean = mean_luma(img);
+                let target = mean.clamp(min as f32, max.max(min) as f32);
+                (target - mean).round() as i32
+            },
+        }
+    }
+}
+
+/// 平均亮度 (Rec.709 权重，0-255)，只用于缩小后的图
+/// 浮点求和结果依赖累加顺序：保持单线程按像素顺序累加，确定性模式依赖这一点
+fn mean_luma(img: &DynamicImage) -> f32 {
+    let rgb = img.to_rgb8();
+    let count = (rgb.width() as u64 * rgb.height() as u64).max(1);
+    let sum: f64 = rgb.pixels()
+        .map(|p| 0.2126 * p[0] as f64 + 0.7152 * p[1] as f64 + 0.0722 * p[2] as f64)
+        .sum();
+    (sum / count as f64) as f32
+}
+
+/// 🎨 饱和度调整：每个像素向自身亮度 (Rec.709) 插值，factor 1.0 不变，0.0 为灰度
+/// 只处理 8 位 RGB / RGBA，其他格式先转成 RGBA8 (用在缩小后的图上，转换开销可以忽略)
+pub fn adjust_saturation(img: &mut DynamicImage, factor: f32) {
+    let factor = factor.max(0.0);
+    if (factor - 1.0).abs() < f32::EPSILON {
+        return;
+    }
+    fn lerp_pixels(data: &mut [u8], channels: usize, factor: f32) {
+        for px in data.chunks_exact_mut(channels) {
+            let luma = 0.2126 * px[0] as f32 + 0.7152 * px[1] as f32 + 0.0722 * px[2] as f32;
+            for c in &mut px[..3] {
+                *c = (luma + (*c as f32 - luma) * factor).round().clamp(0.0, 255.0) as u8;
+            }
+        }
+    }
+    match img {
+        DynamicImage::ImageRgb8(buf) => lerp_pixels(buf, 3, factor),
+        DynamicImage::ImageRgba8(buf) => lerp_pixels(buf, 4, factor),
+        other => {
+            let mut rgba = DynamicImage::ImageRgba8(other.to_rgba8());
+            adjust_saturation(&mut rgba, factor);
+            *other = rgba;
+        },
+    }
+}
+
+/// 按 8 位尺度调整亮度：brighten 的偏移是子像素单位，16 位图要放大 257 倍，浮点图按 1/255 换算
+/// 否则同一个设置在 16 位原图上几乎看不出效果
+fn brighten_scaled(img: &DynamicImage, adj: i32) -> DynamicImage {
+    fn shift_f32<P: Pixel<Subpixel = f32>>(buf: &mut ImageBuffer<P, Vec<f32>>, adj: i32) {
+        let delta = adj as f32 / 255.0;
+        let color_channels = P::CHANNEL_COUNT.min(3) as usize;
+        for px in buf.pixels_mut() {
+            for c in &mut px.channels_mut()[..color_channels] {
+                *c = (*c + delta).clamp(0.0, 1.0);
+            }
+        }
+    }
+    match img {
+        DynamicImage::ImageRgb32F(buf) => {
+            let mut buf = buf.clone();
+            shift_f32(&mut buf, adj);
+            DynamicImage::ImageRgb32F(buf)
+        },
+        DynamicImage::ImageRgba32F(buf) => {
+            let mut buf = buf.clone();
+            shift_f32(&mut buf, adj);
+            DynamicImage::ImageRgba32F(buf)
+        },
+        _ if img.color().bytes_per_pixel() / img.color().channel_count() == 2 => img.brighten(adj * 257),
+        _ => img.brighten(adj),
+    }
+}
+
+// 🟢 高性能模糊背景生成器 (所有模糊背景样式和补边共用这一份实现)
+// 先缩到短边约 300px (且不超过原图的 1/5) 再模糊，8000px 的原图也只在几百像素上做高斯模糊
 // 这属于图形计算，一般不会失败（除非内存耗尽 panic），所以保持不返回 Result
 pub fn generate_blurred_background(
     img: &DynamicImage,
     target_w: u32,
     target_h: u32,
     blur_radius: f32,
-    brightness_adj: i32, 
+    tone: BackgroundTone,
+) -> DynamicImage {
+    let blurred = blur_tiny_background(img, target_w, target_h, blur_radius, tone);
+
+    // 6. 放大回目标尺寸
+    // 8 位图走并行双线性 (放大时 Triangle 滤波就是双线性，结果一致)，
+    // image 的 resize_exact 单线程，放大到 8000px 级画布要好几秒，占了整个背景生成的绝大部分时间
+    upscale_bilinear(&blurred, target_w, target_h)
+        .unwrap_or_else(|| blurred.resize_exact(target_w, target_h, imageops::FilterType::Triangle))
+}
+
+/// 模糊背景的小图部分 (缩小、按目标比例裁切、调色、模糊)，返回放大之前的小图
+fn blur_tiny_background(
+    img: &DynamicImage,
+    target_w: u32,
+    target_h: u32,
+    blur_radius: f32,
+    tone: BackgroundTone,
 ) -> DynamicImage {
     let (src_w, src_h) = img.dimensions();
     
     // 1. 定义极小的处理尺寸
-    let min_dimension = 300.0;
-    let scale_factor = (min_dimension / (src_w.min(src_h) as f64)).min(0.2); 
-    
-    let tiny_w = (src_w as f64 * scale_factor) as u32;
-    let tiny_h = (src_h as f64 * scale_factor) as u32;
+    let (tiny_w, tiny_h, scale_factor) = blur_work_size(src_w, src_h);
 
     // 2. 快速缩小
     let tiny_img = img.resize_exact(tiny_w, tiny_h, imageops::FilterType::Nearest);
@@ -66,29 +253,218 @@ pub fn generate_blurred_background(
     let ratio_tiny = tiny_w as f64 / tiny_h as f64;
 
     let (crop_w, crop_h) = if ratio_target > ratio_tiny {
-        (tiny_w, (tiny_w as f64 / ratio_target) as u32)
+        (tiny_w, ((tiny_w as f64 / ratio_target) as u32).max(1))
     } else {
-        ((tiny_h as f64 * ratio_target) as u32, tiny_h)
+        (((tiny_h as f64 * ratio_target) as u32).max(1), tiny_h)
     };
 
     let crop_x = (tiny_w - crop_w) / 2;
     let crop_y = (tiny_h - crop_h) / 2;
 
-    let cropped_tiny = tiny_img.crop_imm(crop_x, crop_y, crop_w, crop_h);
+    let mut cropped_tiny = tiny_img.crop_imm(crop_x, crop_y, crop_w, crop_h);
+
+    // 🟢 饱和度在模糊之前调整 (几百像素的小图，几乎不花时间)
+    adjust_saturation(&mut cropped_tiny, tone.saturation);
+    // 自动亮度按背景实际用到的区域测量 (模糊不改变平均亮度，在模糊前测即可)
+    let brightness_adj = tone.brightness_for(&cropped_tiny);
 
     // 4. 应用等效模糊
     let effective_blur = blur_radius * (scale_factor as f32);
     let mut blurred = cropped_tiny.blur(effective_blur);
 
     // 5. 调整亮度
+    // 🔴 原来调用的 imageops::colorops::brighten 返回新图，结果被丢弃，亮度参数从未生效
     if brightness_adj != 0 {
-        imageops::colorops::brighten(&mut blurred, brightness_adj);
+        blurred = brighten_scaled(&blurred, brightness_adj);
     }
+    blurred
+}
 
-    // 6. 放大回目标尺寸
-    blurred.resize_exact(target_w, target_h, imageops::FilterType::Triangle)
+/// 模糊背景实际做高斯模糊的尺寸：短边约 300px，且不超过原图的 1/5
+/// 返回 (宽, 高, 缩放比例)；极小原图缩放后可能为 0，至少保留 1px
+fn blur_work_size(src_w: u32, src_h: u32) -> (u32, u32, f64) {
+    let min_dimension = 300.0;
+    let scale_factor = (min_dimension / (src_w.min(src_h) as f64)).min(0.2);
+    let tiny_w = ((src_w as f64 * scale_factor) as u32).max(1);
+    let tiny_h = ((src_h as f64 * scale_factor) as u32).max(1);
+    (tiny_w, tiny_h, scale_factor)
 }
 
+/// 🟢 径向暗角：画面中心不变，从 radius 开始向四角逐渐压暗 (smoothstep 过渡，没有可见的边界)
+/// * `strength`: 四角的压暗比例 0-1，0 代表不处理
+/// * `radius`: 开始压暗的位置 (0 = 中心，1 = 四角)，按画布宽高归一化，横竖图都是椭圆形
+///
+/// 只改颜色通道，Alpha 不变；应在亮度调整之后调用 (先整体压暗，再叠加暗角)
+/// NaN / 无穷大的 strength 视为不处理，radius 非有限值时按 0 (从中心开始) 处理
+pub fn apply_vignette(img: &mut DynamicImage, strength: f32, radius: f32) {
+    // clamp 对 NaN 原样返回，不先拦下的话整张图会乘成黑色
+    if !strength.is_finite() {
+        return;
+    }
+    let strength = strength.clamp(0.0, 1.0);
+    if strength <= 0.0 {
+        return;
+    }
+    let radius = if radius.is_finite() { radius.clamp(0.0, 0.99) } else { 0.0 };
+    match img {
+        DynamicImage::ImageLuma8(buf) => vignette_buffer(buf, strength, radius, |v, f| (v as f32 * f).round() as u8),
+        DynamicImage::ImageLumaA8(buf) => vignette_buffer(buf, strength, radius, |v, f| (v as f32 * f).round() as u8),
+        DynamicImage::ImageRgb8(buf) => vignette_buffer(buf, strength, radius, |v, f| (v as f32 * f).round() as u8),
+        DynamicImage::ImageRgba8(buf) => vignette_buffer(buf, strength, radius, |v, f| (v as f32 * f).round() as u8),
+        DynamicImage::ImageLuma16(buf) => vignette_buffer(buf, strength, radius, |v, f| (v as f32 * f).round() as u16),
+        DynamicImage::ImageLumaA16(buf) => vignette_buffer(buf, strength, radius, |v, f| (v as f32 * f).round() as u16),
+        DynamicImage::ImageRgb16(buf) => vignette_buffer(buf, strength, radius, |v, f| (v as f32 * f).round() as u16),
+        DynamicImage::ImageRgba16(buf) => vignette_buffer(buf, strength, radius, |v, f| (v as f32 * f).round() as u16),
+        DynamicImage::ImageRgb32F(buf) => vignette_buffer(buf, strength, radius, |v, f| v * f),
+        DynamicImage::ImageRgba32F(buf) => vignette_buffer(buf, strength, radius, |v, f| v * f),
+        // DynamicImage 是 non_exhaustive，未来新增的格式先转成 RGBA8 再处理
+        other => {
+            let mut rgba = DynamicImage::ImageRgba8(other.to_rgba8());
+            apply_vignette(&mut rgba, strength, radius);
+            *other = rgba;
+        },
+    }
+}
+
+/// 按行并行：每行先算好纵向分量，行内逐像素乘以压暗系数
+fn vignette_buffer<P: Pixel>(
+    buf: &mut ImageBuffer<P, Vec<P::Subpixel>>,
+    strength: f32,
+    radius: f32,
+    scale: impl Fn(P::Subpixel, f32) -> P::Subpixel + Sync,
+) where
+    P::Subpixel: Send + Sync,
+{
+    let (w, h) = buf.dimensions();
+    if w == 0 || h == 0 {
+        return;
+    }
+    let channels = P::CHANNEL_COUNT as usize;
+    let color_channels = if P::HAS_ALPHA { channels - 1 } else { channels };
+    let (cx, cy) = (w as f32 / 2.0, h as f32 / 2.0);
+
+    // 横向分量每列都一样，先算好
+    let dx2: Vec<f32> = (0..w).map(|x| ((x as f32 + 0.5 - cx) / cx).powi(2)).collect();
+
+    buf.par_chunks_mut(w as usize * channels).enumerate().for_each(|(y, row)| {
+        let dy2 = ((y as f32 + 0.5 - cy) / cy).powi(2);
+        for (px, &dx2) in row.chunks_exact_mut(channels).zip(&dx2) {
+            // 归一化距离：中心 0，四角 1
+            let d = ((dx2 + dy2) / 2.0).sqrt();
+            if d <= radius {
+                continue;
+            }
+            let t = ((d - radius) / (1.0 - radius)).min(1.0);
+            let factor = 1.0 - strength * t * t * (3.0 - 2.0 * t);
+            for v in &mut px[..color_channels] {
+                *v = scale(*v, factor);
+            }
+        }
+    });
+}
+
+/// 8 位图的并行双线性缩放 (保持原像素格式)；16 位 / 浮点图返回 None
+fn upscale_bilinear(img: &DynamicImage, target_w: u32, target_h: u32) -> Option<DynamicImage> {
+    Some(match img {
+        DynamicImage::ImageLuma8(buf) => DynamicImage::ImageLuma8(bilinear(buf, target_w, target_h)),
+        DynamicImage::ImageLumaA8(buf) => DynamicImage::ImageLumaA8(bilinear(buf, target_w, target_h)),
+        DynamicImage::ImageRgb8(buf) => DynamicImage::ImageRgb8(bilinear(buf, target_w, target_h)),
+        DynamicImage::ImageRgba8(buf) => DynamicImage::ImageRgba8(bilinear(buf, target_w, target_h)),
+        _ => return None,
+    })
+}
+
+fn bilinear<P: Pixel<Subpixel = u8>>(src: &ImageBuffer<P, Vec<u8>>, target_w: u32, target_h: u32) -> ImageBuffer<P, Vec<u8>> {
+    let channels = P::CHANNEL_COUNT as usize;
+    let (src_w, src_h) = src.dimensions();
+    let src_raw = src.as_raw();
+    let src_stride = src_w as usize * channels;
+
+    // 目标像素中心映射回原图坐标：(左/上索引, 右/下索引, 8 位定点权重 0-256)
+    let taps = |src_len: u32, dst_len: u32| -> Vec<(usize, usize, u32)> {
+        let scale = src_len as f32 / dst_len as f32;
+        (0..dst_len)
+            .map(|i| {
+                let pos = ((i as f32 + 0.5) * scale - 0.5).clamp(0.0, (src_len - 1) as f32);
+                let lo = pos.floor() as u32;
+                let hi = (lo + 1).min(src_len - 1);
+                (lo as usize, hi as usize, ((pos - lo as f32) * 256.0).round() as u32)
+            })
+            .collect()
+    };
+    // 横向直接存成字节偏移，内层循环只剩整数乘加
+    let x_taps: Vec<(usize, usize, u32)> = taps(src_w, target_w)
+        .into_iter()
+        .map(|(lo, hi, w)| (lo * channels, hi * channels, w))
+        .collect();
+    let y_taps = taps(src_h, target_h);
+
+    let row_len = target_w as usize * channels;
+    let mut out = vec![0u8; row_len * target_h as usize];
+    out.par_chunks_mut(row_len).zip(y_taps.par_iter()).for_each(|(row, &(y0, y1, fy))| {
+        let top = &src_raw[y0 * src_stride..(y0 + 1) * src_stride];
+        let bottom = &src_raw[y1 * src_stride..(y1 + 1) * src_stride];
+        for (px, &(a, b, fx)) in row.chunks_exact_mut(channels).zip(&x_taps) {
+            for (c, value) in px.iter_mut().enumerate() {
+                let t = top[a + c] as u32 * (256 - fx) + top[b + c] as u32 * fx;
+                let m = bottom[a + c] as u32 * (256 - fx) + bottom[b + c] as u32 * fx;
+                *value = ((t * (256 - fy) + m * fy + 32768) >> 16) as u8;
+            }
+        }
+    });
+    ImageBuffer::from_raw(target_w, target_h, out).expect("缓冲区大小与尺寸一致")
+}
+
+
+/// 🟢 玻璃前景参数 (圆角 + 半透明描边)
+/// 默认值与旧版硬编码一致：圆角为短边的 3%，描边为长边的 0.2% (限制在 3-8px)，白色 130 透明度
+#[derive(Debug, Clone, Copy)]
+pub struct GlassConfig {
+    pub radius_ratio: f32,     // 圆角半径 (相对原图短边)
+    pub border_ratio: f32,     // 描边厚度 (相对原图长边)
+    pub border_min: f32,       // 描边厚度下限 (px)
+    pub border_max: f32,       // 描边厚度上限 (px)
+    pub border_color: Rgba<u8>,
+}
+
+impl Default for GlassConfig {
+    fn default() -> Self {
+        Self {
+            radius_ratio: 0.03,
+            border_ratio: 0.002,
+            border_min: 3.0,
+            border_max: 8.0,
+            border_color: Rgba([255, 255, 255, 130]),
+        }
+    }
+}
+
+impl GlassConfig {
+    /// 按 StyleOptions 里的可选项覆盖默认值
+    /// - `thickness`: 固定描边厚度 (px)，0 = 不画描边；None 沿用自适应厚度
+    /// - `color`: 描边颜色 [R, G, B, A]；None 沿用默认的半透明白
+    pub fn with_overrides(thickness: Option<u32>, color: Option<[u8; 4]>) -> Self {
+        let mut cfg = Self::default();
+        if let Some(px) = thickness {
+            cfg.border_min = px as f32;
+            cfg.border_max = px as f32;
+        }
+        if let Some(c) = color {
+            cfg.border_color = Rgba(c);
+        }
+        cfg
+    }
+
+    /// 圆角半径 (px)
+    pub fn radius(&self, w: u32, h: u32) -> u32 {
+        (w.min(h) as f32 * self.radius_ratio) as u32
+    }
+
+    /// 描边厚度 (px)，排版时计算玻璃整体尺寸也要用它
+    pub fn border_thickness(&self, w: u32, h: u32) -> u32 {
+        (w.max(h) as f32 * self.border_ratio).clamp(self.border_min, self.border_max) as u32
+    }
+}
 
 /// 🟢 [高性能] 绘制玻璃前景
 pub fn draw_glass_foreground_on(
@@ -96,102 +472,147 @@ pub fn draw_glass_foreground_on(
     img: &DynamicImage,
     dest_x: i64,
     dest_y: i64,
+    glass: &GlassConfig,
 ) {
     let (w, h) = img.dimensions();
     let (canvas_w, canvas_h) = canvas.dimensions();
 
     // 1. 参数计算
-    let radius_ratio = 0.03;
-    let radius = (w.min(h) as f32 * radius_ratio) as i32;
-    let r_sq = (radius * radius) as f32;
-    
-    let border_thickness = (w.max(h) as f32 * 0.002).clamp(3.0, 8.0) as u32;
-    let glass_border_color = Rgba([255, 255, 255, 130]);
+    let radius = glass.radius(w, h);
+    let border_thickness = glass.border_thickness(w, h);
+
+    // 2. 绘制边框底座 (厚度为 0 或完全透明时不画，免得把背景覆盖成透明)
+    if border_thickness > 0 && glass.border_color[3] > 0 {
+        let border_x = dest_x - border_thickness as i64;
+        let border_y = dest_y - border_thickness as i64;
+        let border_w = w + border_thickness * 2;
+        let border_h = h + border_thickness * 2;
+
+        let border_rect = Rect::at(border_x as i32, border_y as i32)
+            .of_size(border_w, border_h);
+
+        draw_rounded_rect_mut(
+            canvas,
+            border_rect,
+            (radius + border_thickness) as i32,
+            glass.border_color,
+        );
+    }
 
-    // 2. 绘制边框底座
-    let border_x = dest_x - border_thickness as i64;
-    let border_y = dest_y - border_thickness as i64;
-    let border_w = w + border_thickness * 2;
-    let border_h = h + border_thickness * 2;
+    // 3. 按行并行绘制原图
+    // 圆角的 alpha 计算只落在四个 radius x radius 的角块里；其余像素不透明时整段拷贝，半透明时才逐像素混合
+    // 常见的 Rgb8 / Rgba8 直接按行读取，省掉整图 to_rgba8 的一次拷贝
+    let t_fg = Instant::now();
+    let converted;
+    let (src_raw, channels) = match img {
+        DynamicImage::ImageRgb8(buf) => (buf.as_raw().as_slice(), 3),
+        DynamicImage::ImageRgba8(buf) => (buf.as_raw().as_slice(), 4),
+        _ => {
+            converted = img.to_rgba8();
+            (converted.as_raw().as_slice(), 4)
+        },
+    };
 
-    let border_rect = Rect::at(border_x as i32, border_y as i32)
-        .of_size(border_w, border_h);
-    
-    draw_rounded_rect_mut(
-        canvas,
-        border_rect,
-        radius + border_thickness as i32,
-        glass_border_color,
-    );
-
-    // 3. 逐像素绘制原图
-    let src_buf = img.to_rgba8();
-    
-    let safe_x_start = radius as u32;
-    let safe_x_end = w - radius as u32;
-    let safe_y_start = radius as u32;
-    let safe_y_end = h - radius as u32;
+    let safe_x_start = radius;
+    let safe_x_end = w - radius;
+    let safe_y_start = radius;
+    let safe_y_end = h - radius;
+
+    // 可见范围 (原图坐标)，完全在画布外时直接返回
+    let start_x = (-dest_x).clamp(0, w as i64) as u32;
+    let start_y = (-dest_y).clamp(0, h as i64) as u32;
+    let end_x = (canvas_w as i64 - dest_x).clamp(start_x as i64, w as i64) as u32;
+    let end_y = (canvas_h as i64 - dest_y).clamp(start_y as i64, h as i64) as u32;
+    if start_x >= end_x || start_y >= end_y {
+        return;
+    }
+
+    let stride = canvas_w as usize * 4;
+    let src_stride = w as usize * channels;
+    let first_row = (dest_y + start_y as i64) as usize;
+    let last_row = (dest_y + end_y as i64) as usize;
+    let cx0 = (dest_x + start_x as i64) as usize * 4;
+    let cx1 = (dest_x + end_x as i64) as usize * 4;
 
-    let start_x = 0.max(-dest_x) as u32;
-    let start_y = 0.max(-dest_y) as u32;
-    let end_x = w.min((canvas_w as i64 - dest_x) as u32);
-    let end_y = h.min((canvas_h as i64 - dest_y) as u32);
+    let rows = &mut (**canvas)[first_row * stride..last_row * stride];
+    rows.par_chunks_mut(stride).enumerate().for_each(|(i, row)| {
+        let y = start_y + i as u32;
+        let src_row = &src_raw[y as usize * src_stride..(y as usize + 1) * src_stride];
+        let src = &src_row[start_x as usize * channels..end_x as usize * channels];
+        let dst = &mut row[cx0..cx1];
 
-    for y in start_y..end_y {
         let is_y_in_corner = y < safe_y_start || y >= safe_y_end;
-        let cy = (dest_y + y as i64) as u32;
-        
-        for x in start_x..end_x {
-            let mut p = *src_buf.get_pixel(x, y);
-            
+        if !is_y_in_corner {
+            if channels == 3 {
+                for (s, d) in src.chunks_exact(3).zip(dst.chunks_exact_mut(4)) {
+                    d[..3].copy_from_slice(s);
+                    d[3] = 255;
+                }
+                return;
+            }
+            if src.chunks_exact(4).all(|p| p[3] == 255) {
+                dst.copy_from_slice(src);
+                return;
+            }
+        }
+
+        for (x, (s, d)) in (start_x..end_x).zip(src.chunks_exact(channels).zip(dst.chunks_exact_mut(4))) {
+            let mut p = Rgba([s[0], s[1], s[2], if channels == 4 { s[3] } else { 255 }]);
+
             // --- 圆角逻辑 ---
             if is_y_in_corner && (x < safe_x_start || x >= safe_x_end) {
-                let dx = if x < safe_x_start {
-                    (safe_x_start as f32 - x as f32) - 0.5
-                } else {
-                    (x as f32 - safe_x_end as f32) + 0.5
-                };
-                let dy = if y < safe_y_start {
-                    (safe_y_start as f32 - y as f32) - 0.5
-                } else {
-                    (y as f32 - safe_y_end as f32) + 0.5
-                };
-                let dist_sq = dx * dx + dy * dy;
-
-                if dist_sq > r_sq {
-                    continue; 
-                } else if dist_sq > (radius - 1) as f32 * (radius - 1) as f32 {
-                    // 抗锯齿
-                    let dist = dist_sq.sqrt();
-                    let alpha_factor = (radius as f32 - dist).clamp(0.0, 1.0);
-                    let new_alpha = (p[3] as f32 * alpha_factor) as u8;
-                    p = Rgba([p[0], p[1], p[2], new_alpha]);
+                match corner_alpha_factor(x, y, radius, (safe_x_start, safe_x_end, safe_y_start, safe_y_end)) {
+                    None => continue,
+                    Some(factor) => p[3] = (p[3] as f32 * factor) as u8,
                 }
             }
-            
+
             // --- 写入画布 ---
-            let cx = (dest_x + x as i64) as u32;
-            
             if p[3] == 255 {
-                canvas.put_pixel(cx, cy, p);
+                d.copy_from_slice(&p.0);
             } else if p[3] > 0 {
-                let bg = canvas.get_pixel(cx, cy);
-                let blended = blend_pixel(*bg, p);
-                canvas.put_pixel(cx, cy, blended);
+                let bg = Rgba([d[0], d[1], d[2], d[3]]);
+                d.copy_from_slice(&blend_pixel(bg, p).0);
             }
         }
+    });
+    debug!("  -> [PERF] Glass foreground {}x{}: {:.2?}", w, h, t_fg.elapsed());
+}
+
+/// 角块内像素的 alpha 系数：圆外返回 None (不绘制)，圆内为 1.0，最外一圈像素做抗锯齿
+#[inline(always)]
+fn corner_alpha_factor(x: u32, y: u32, radius: u32, (safe_x_start, safe_x_end, safe_y_start, safe_y_end): (u32, u32, u32, u32)) -> Option<f32> {
+    let dx = if x < safe_x_start {
+        (safe_x_start as f32 - x as f32) - 0.5
+    } else {
+        (x as f32 - safe_x_end as f32) + 0.5
+    };
+    let dy = if y < safe_y_start {
+        (safe_y_start as f32 - y as f32) - 0.5
+    } else {
+        (y as f32 - safe_y_end as f32) + 0.5
+    };
+    let dist_sq = dx * dx + dy * dy;
+    let r = radius as f32;
+
+    if dist_sq > r * r {
+        None
+    } else if dist_sq > (r - 1.0) * (r - 1.0) {
+        Some((r - dist_sq.sqrt()).clamp(0.0, 1.0))
+    } else {
+        Some(1.0)
     }
 }
 
 
-/// ⚡️ 轻量级：仅读取 EXIF 方向信息
+/// ⚡️ 轻量级：仅读取 EXIF 方向信息 (以及记录的像素尺寸，用于截断检查)
 /// 🟢 修改：不返回错误，默认返回 1。如果打开失败，记录 debug 日志。
-fn get_orientation(path: &str) -> u32 {
+fn get_orientation(path: &str) -> (u32, Option<(u32, u32)>) {
     let file = match File::open(path) {
         Ok(f) => f,
         Err(e) => {
             debug!("⚠️ [Orientation] 无法读取方向信息 (IO error): {} -> {}", path, e);
-            return 1;
+            return (1, None);
         }
     };
     
@@ -200,40 +621,316 @@ fn get_orientation(path: &str) -> u32 {
 
     match reader.read_from_container(&mut bufreader) {
         Ok(exif) => {
-            if let Some(field) = exif.get_field(Tag::Orientation, In::PRIMARY) {
-                field.value.get_uint(0).unwrap_or(1)
-            } else {
-                1
-            }
+            let uint = |tag| exif.get_field(tag, In::PRIMARY).and_then(|f| f.value.get_uint(0));
+            let orientation = uint(Tag::Orientation).unwrap_or(1);
+            let dims = uint(Tag::PixelXDimension).zip(uint(Tag::PixelYDimension));
+            (orientation, dims)
         },
-        Err(_) => 1, // 解析失败也是 1
+        Err(_) => (1, None), // 解析失败也是 1
     }
 }
 
 /// 加载图片并自动旋转
 /// 🔴 修改：返回 Result<DynamicImage, AppError>
 pub fn load_image_auto_rotate(path: &str) -> Result<DynamicImage, AppError> {
+    load_image_with_icc(path).map(|(img, _)| img)
+}
+
+/// 同 load_image_auto_rotate，并返回原图内嵌的 ICC 配置文件 (没有时为 None，RAW 的内嵌预览按 sRGB 处理)
+/// 配置文件和像素来自同一次解码，不会重复读文件
+pub fn load_image_with_icc(path: &str) -> Result<(DynamicImage, Option<Vec<u8>>), AppError> {
+    // 🟢 空文件 (读卡中断) 直接报不完整，不交给解码器报一个看不懂的格式错误
+    integrity::check_not_empty(path)?;
+
     // 1. 获取方向
-    let orientation = get_orientation(path);
+    let (orientation, exif_dims) = get_orientation(path);
 
-    // 2. 解码图片
-    // 🟢 这里使用了 ?，所以如果 image::open 失败，ImageError 会自动转为 AppError::Image 并返回
-    let mut img = image::open(path)?;
+    // 2. 解码图片 (RAW 走内嵌预览 / 反马赛克，方向同样由 EXIF 决定)
+    // 🟢 这里使用了 ?，所以如果解码失败，ImageError 会自动转为 AppError::Image 并返回
+    let (mut img, icc) = if super::raw::is_raw_path(path) {
+        (super::raw::decode_raw(path)?, None)
+    } else {
+        let (img, icc) = decode_sniffed(path)?;
+        integrity::check_pixel_dimensions(img.dimensions(), exif_dims)?;
+        (img, icc)
+    };
 
     // 3. 根据方向调整
     if orientation != 1 {
         // debug!("🔄 [Load] 检测到方向 {}, 正在自动旋转...", orientation);
-        img = match orientation {
-            2 => img.fliph(),
-            3 => img.rotate180(),
-            4 => img.flipv(),
-            5 => img.rotate90().fliph(),
-            6 => img.rotate90(),
-            7 => img.rotate270().fliph(),
-            8 => img.rotate270(),
-            _ => img,
-        };
-    }
-
-    Ok(img)
-}
\ No newline at end of file
+        img = apply_orientation(img, orientation);
+    }
+
+    Ok((img, icc))
+}
+
+/// 按文件内容选择解码器 (扩展名可能是错的，比如 .webp 改名成 .jpg)
+/// 内容识别不出来时沿用扩展名；解码失败时错误信息里带上两者，方便用户对照
+fn decode_sniffed(path: &str) -> Result<(DynamicImage, Option<Vec<u8>>), AppError> {
+    let expected = ImageFormat::from_path(path).ok();
+    let reader = ImageReader::open(path)?.with_guessed_format()?;
+    let guessed = reader.format();
+    if guessed != expected {
+        debug!("🔎 [Load] 文件内容为 {:?}，与扩展名 ({:?}) 不符，按内容解码: {}", guessed, expected, path);
+    }
+
+    // 🟢 截断的 JPEG 解码器会补灰照常返回，解码前先看有没有结束标记
+    if guessed == Some(ImageFormat::Jpeg) && integrity::jpeg_is_truncated(path)? {
+        return Err(AppError::truncated("JPEG 缺少结束标记"));
+    }
+
+    let describe = |f: Option<ImageFormat>| f.map_or_else(|| "未知格式".to_string(), |f| format!("{:?}", f));
+    let with_formats = |e: ImageError| {
+        if integrity::is_truncation_error(&e) {
+            return AppError::truncated(format!("按内容识别为 {}，扩展名对应 {}，解码器读到文件末尾: {}", describe(guessed), describe(expected), e));
+        }
+        AppError::Image(ImageError::Decoding(DecodingError::new(
+            guessed.map_or(ImageFormatHint::Unknown, ImageFormatHint::Exact),
+            format!("按内容识别为 {}，扩展名对应 {}: {}", describe(guessed), describe(expected), e),
+        )))
+    };
+
+    let mut decoder = reader.into_decoder().map_err(with_formats)?;
+    // 读不到配置文件不影响解码，按未标记处理
+    let icc = decoder.icc_profile().unwrap_or_else(|e| {
+        debug!("⚠️ [Load] 读取 ICC 失败，按 sRGB 处理: {}", e);
+        None
+    });
+    Ok((DynamicImage::from_decoder(decoder).map_err(with_formats)?, icc))
+}
+
+/// 按 EXIF Orientation 把像素摆正 (完整 8 种情况)
+///
+/// | 值 | 含义                         | 变换                    |
+/// |----|------------------------------|-------------------------|
+/// | 1  | 正常                         | 无                      |
+/// | 2  | 水平镜像                     | 水平翻转                |
+/// | 3  | 旋转 180°                    | 旋转 180°               |
+/// | 4  | 垂直镜像                     | 垂直翻转                |
+/// | 5  | 主对角线镜像 (transpose)     | 顺时针 90° + 水平翻转   |
+/// | 6  | 需顺时针 90°                 | 顺时针 90°              |
+/// | 7  | 副对角线镜像 (transverse)    | 逆时针 90° + 水平翻转   |
+/// | 8  | 需逆时针 90°                 | 逆时针 90°              |
+///
+/// 镜像类 (2/4/5/7) 常见于手机全景、自拍等应用导出的文件。未知值按 1 处理。
+pub fn apply_orientation(img: DynamicImage, orientation: u32) -> DynamicImage {
+    match orientation {
+        2 => img.fliph(),
+        3 => img.rotate180(),
+        4 => img.flipv(),
+        5 => img.rotate90().fliph(),
+        6 => img.rotate90(),
+        7 => img.rotate270().fliph(),
+        8 => img.rotate270(),
+        _ => img,
+    }
+}
+
+/// 🎲 随机效果 (颗粒、抖动) 的种子：文件路径的 FNV-1a 64 哈希
+/// 确定性模式 (BatchContext.deterministic) 下随机效果必须用它播种，同一文件每次运行都得到同一个种子；
+/// 自己实现哈希而不用 DefaultHasher，保证跨平台、跨 Rust 版本结果不变
+#[allow(dead_code)] // 颗粒 / 抖动效果落地前还没有调用方
+pub fn seed_for(path: &str) -> u64 {
+    const FNV_OFFSET: u64 = 0xcbf29ce484222325;
+    const FNV_PRIME: u64 = 0x100000001b3;
+    path.bytes().fold(FNV_OFFSET, |hash, byte| (hash ^ byte as u64).wrapping_mul(FNV_PRIME))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seed_is_a_stable_hash_of_the_path() {
+        assert_eq!(seed_for(""), 0xcbf29ce484222325);
+        // FNV-1a 64 的公开测试向量
+        assert_eq!(seed_for("a"), 0xaf63dc4c8601ec8c);
+        assert_eq!(seed_for("D:/shoot/DSC_0001.NEF"), seed_for("D:/shoot/DSC_0001.NEF"));
+        assert_ne!(seed_for("D:/shoot/DSC_0001.NEF"), seed_for("D:/shoot/DSC_0002.NEF"));
+    }
+
+    fn gray(w: u32, h: u32) -> DynamicImage {
+        DynamicImage::ImageRgb8(image::RgbImage::from_pixel(w, h, image::Rgb([200, 200, 200])))
+    }
+
+    #[test]
+    fn vignette_ignores_non_finite_strength() {
+        for strength in [f32::NAN, f32::INFINITY, f32::NEG_INFINITY, 0.0, -1.0] {
+            let mut img = gray(40, 30);
+            apply_vignette(&mut img, strength, 0.5);
+            assert_eq!(img, gray(40, 30), "{}", strength);
+        }
+    }
+
+    #[test]
+    fn vignette_clamps_strength_and_radius() {
+        // 超过 1 的强度按 1 处理
+        let mut over = gray(40, 30);
+        apply_vignette(&mut over, 5.0, 0.5);
+        let mut full = gray(40, 30);
+        apply_vignette(&mut full, 1.0, 0.5);
+        assert_eq!(over, full);
+
+        // radius 为 NaN 时按 0 处理，中心不变、四角变暗，不会出现全黑
+        let mut img = gray(40, 30);
+        apply_vignette(&mut img, 0.5, f32::NAN);
+        let rgb = img.to_rgb8();
+        assert_eq!(rgb.get_pixel(20, 15)[0], 200);
+        let corner = rgb.get_pixel(0, 0)[0];
+        assert!(corner > 0 && corner < 200, "{}", corner);
+    }
+
+    // tests/fixtures/orientation_{1..8}.jpg：按各自的 EXIF 方向存储像素，摆正后都应显示为
+    // 32x16，左上红、右上绿、左下蓝、右下白
+    #[test]
+    fn every_orientation_loads_upright() {
+        let expected = [
+            ((8, 4), [255, 0, 0]),
+            ((24, 4), [0, 255, 0]),
+            ((8, 12), [0, 0, 255]),
+            ((24, 12), [255, 255, 255]),
+        ];
+        for o in 1..=8 {
+            let path = crate::test_support::fixture(&format!("orientation_{}.jpg", o));
+            assert_eq!(get_orientation(&path).0, o);
+
+            let img = load_image_auto_rotate(&path).unwrap().to_rgb8();
+            assert_eq!(img.dimensions(), (32, 16), "orientation {}", o);
+            for ((x, y), want) in expected {
+                let got = img.get_pixel(x, y).0;
+                let close = got.iter().zip(want).all(|(a, b)| a.abs_diff(b) < 40);
+                assert!(close, "orientation {}: ({}, {}) = {:?}, 应为 {:?}", o, x, y, got, want);
+            }
+        }
+    }
+
+    #[test]
+    fn tint_keeps_antialiased_edge_alpha() {
+        // 字标边缘的半透明像素：只换颜色，Alpha 原样保留
+        let edge = RgbaImage::from_fn(4, 1, |x, _| Rgba([0, 0, 0, [0, 64, 200, 255][x as usize]]));
+        let gray = tint_image(&DynamicImage::ImageRgba8(edge.clone()), Rgba([230, 230, 230, 255])).to_rgba8();
+        for (x, p) in gray.pixels().enumerate() {
+            assert_eq!(*p, Rgba([230, 230, 230, edge.get_pixel(x as u32, 0)[3]]));
+        }
+
+        // 目标色带透明度时按比例缩放 Alpha
+        let half = tint_image(&DynamicImage::ImageRgba8(edge), Rgba([255, 0, 0, 128])).to_rgba8();
+        let alphas: Vec<u8> = half.pixels().map(|p| p[3]).collect();
+        assert_eq!(alphas, vec![0, 32, 100, 128]);
+
+        // 真实字标：着色前后 Alpha 通道逐像素一致
+        let logo = crate::resources::get_logo(crate::resources::Brand::Nikon, crate::resources::LogoType::Wordmark).unwrap();
+        let white = make_image_white(&logo).to_rgba8();
+        let original = logo.to_rgba8();
+        assert!(original.pixels().zip(white.pixels()).all(|(a, b)| a[3] == b[3]));
+        assert!(white.pixels().all(|p| p[0] == 255 && p[1] == 255 && p[2] == 255));
+    }
+
+    #[test]
+    fn huge_source_blurs_on_a_tiny_copy() {
+        // 8000px 原图只在短边约 300px 的小图上模糊，模糊半径同比缩小
+        let (w, h, scale) = blur_work_size(8000, 5333);
+        assert_eq!(h, 300);
+        assert_eq!(w, 450);
+        assert!((scale - 300.0 / 5333.0).abs() < 1e-9);
+        // 本来就小的图也至少缩到 1/5，极端长条至少保留 1px
+        assert_eq!(blur_work_size(1000, 800), (200, 160, 0.2));
+        assert_eq!(blur_work_size(4, 2000).0, 1);
+
+        // 整体走一遍：高斯模糊只作用在 400x300 缩小后按 3:2 裁出的 400x266 上 (不比较耗时，按像素数判断)
+        let src = gray(8000, 6000);
+        let tone = BackgroundTone { brightness: BgBrightness::Fixed(0), saturation: 1.0 };
+        let tiny = blur_tiny_background(&src, 1200, 800, 80.0, tone);
+        assert_eq!(tiny.dimensions(), (400, 266));
+        assert!(tiny.width() as u64 * tiny.height() as u64 * 400 < 8000 * 6000);
+        let bg = generate_blurred_background(&src, 1200, 800, 80.0, tone);
+        assert_eq!(bg.dimensions(), (1200, 800));
+        // 纯色原图模糊后仍是同一颜色
+        assert_eq!(bg.to_rgb8().get_pixel(600, 400).0, [200, 200, 200]);
+    }
+
+    /// 优化前的逐像素实现 (get_pixel / put_pixel，每个像素都判断圆角)，只作对照
+    fn glass_foreground_reference(canvas: &mut RgbaImage, img: &DynamicImage, dest_x: i64, dest_y: i64, glass: &GlassConfig) {
+        let (w, h) = img.dimensions();
+        let (canvas_w, canvas_h) = canvas.dimensions();
+        let radius = glass.radius(w, h) as i32;
+        let r_sq = (radius * radius) as f32;
+        let border_thickness = glass.border_thickness(w, h);
+        if border_thickness > 0 && glass.border_color[3] > 0 {
+            let rect = Rect::at((dest_x - border_thickness as i64) as i32, (dest_y - border_thickness as i64) as i32)
+                .of_size(w + border_thickness * 2, h + border_thickness * 2);
+            draw_rounded_rect_mut(canvas, rect, radius + border_thickness as i32, glass.border_color);
+        }
+
+        let src_buf = img.to_rgba8();
+        let (safe_x_start, safe_x_end) = (radius as u32, w - radius as u32);
+        let (safe_y_start, safe_y_end) = (radius as u32, h - radius as u32);
+        let start_x = 0.max(-dest_x) as u32;
+        let start_y = 0.max(-dest_y) as u32;
+        let end_x = w.min((canvas_w as i64 - dest_x) as u32);
+        let end_y = h.min((canvas_h as i64 - dest_y) as u32);
+
+        for y in start_y..end_y {
+            let is_y_in_corner = y < safe_y_start || y >= safe_y_end;
+            let cy = (dest_y + y as i64) as u32;
+            for x in start_x..end_x {
+                let mut p = *src_buf.get_pixel(x, y);
+                if is_y_in_corner && (x < safe_x_start || x >= safe_x_end) {
+                    let dx = if x < safe_x_start { (safe_x_start as f32 - x as f32) - 0.5 } else { (x as f32 - safe_x_end as f32) + 0.5 };
+                    let dy = if y < safe_y_start { (safe_y_start as f32 - y as f32) - 0.5 } else { (y as f32 - safe_y_end as f32) + 0.5 };
+                    let dist_sq = dx * dx + dy * dy;
+                    if dist_sq > r_sq {
+                        continue;
+                    } else if dist_sq > (radius - 1) as f32 * (radius - 1) as f32 {
+                        let alpha_factor = (radius as f32 - dist_sq.sqrt()).clamp(0.0, 1.0);
+                        p = Rgba([p[0], p[1], p[2], (p[3] as f32 * alpha_factor) as u8]);
+                    }
+                }
+                let cx = (dest_x + x as i64) as u32;
+                if p[3] == 255 {
+                    canvas.put_pixel(cx, cy, p);
+                } else if p[3] > 0 {
+                    let blended = blend_pixel(*canvas.get_pixel(cx, cy), p);
+                    canvas.put_pixel(cx, cy, blended);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn glass_foreground_matches_per_pixel_reference() {
+        // 背景带渐变，半透明像素的混合结果才看得出差异
+        let background = RgbaImage::from_fn(260, 200, |x, y| Rgba([(x % 256) as u8, (y % 256) as u8, 90, 255]));
+        let photo = |x: u32, y: u32| [(x * 2 % 256) as u8, (y * 3 % 256) as u8, ((x + y) % 256) as u8];
+        let sources = [
+            DynamicImage::ImageRgb8(image::RgbImage::from_fn(200, 140, |x, y| image::Rgb(photo(x, y)))),
+            // 不透明 PNG 走整段拷贝，带透明度的逐像素混合
+            DynamicImage::ImageRgba8(RgbaImage::from_fn(200, 140, |x, y| { let [r, g, b] = photo(x, y); Rgba([r, g, b, 255]) })),
+            DynamicImage::ImageRgba8(RgbaImage::from_fn(200, 140, |x, y| { let [r, g, b] = photo(x, y); Rgba([r, g, b, ((x * 7 + y) % 256) as u8]) })),
+            DynamicImage::ImageLuma8(image::GrayImage::from_fn(200, 140, |x, y| image::Luma([photo(x, y)[0]]))),
+        ];
+        // 居中、左上越界、右下越界
+        let placements = [(30, 30), (-25, -12), (90, 100)];
+        // 默认圆角只有几像素，再加一个大圆角，抗锯齿的那一圈才有足够多的像素
+        let glasses = [
+            GlassConfig::default(),
+            GlassConfig::with_overrides(Some(0), None),
+            GlassConfig { radius_ratio: 0.15, ..GlassConfig::with_overrides(None, Some([0, 0, 0, 60])) },
+        ];
+
+        for (i, img) in sources.iter().enumerate() {
+            for &(dx, dy) in &placements {
+                for glass in &glasses {
+                    let mut fast = background.clone();
+                    draw_glass_foreground_on(&mut fast, img, dx, dy, glass);
+                    let mut reference = background.clone();
+                    glass_foreground_reference(&mut reference, img, dx, dy, glass);
+                    let diff = fast.enumerate_pixels()
+                        .find(|&(x, y, p)| p != reference.get_pixel(x, y))
+                        .map(|(x, y, p)| (x, y, *p, *reference.get_pixel(x, y)));
+                    assert_eq!(diff, None, "source {} at ({}, {})", i, dx, dy);
+                }
+            }
+        }
+    }
+}
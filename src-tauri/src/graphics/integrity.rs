@@ -0,0 +1,180 @@
+// src/graphics/integrity.rs
+// 🟢 源文件完整性检查：读卡中断 / 拷贝到一半的文件
+// 解码器对截断的 JPEG 很宽容，缺失的部分会补成灰色照常返回，不检查的话会悄悄存出下半截是灰色的成品
+
+use std::fs::File;
+use std::io::{ErrorKind, Read, Seek, SeekFrom};
+
+use image::ImageError;
+
+use crate::error::AppError;
+
+// JPEG 标记
+const MARKER_SOI: u8 = 0xD8;
+const MARKER_EOI: u8 = 0xD9;
+const MARKER_SOS: u8 = 0xDA;
+
+/// 空文件直接判为不完整 (读不到元数据时不在这里报错，交给后面的解码)
+pub fn check_not_empty(path: &str) -> Result<(), AppError> {
+    match std::fs::metadata(path) {
+        Ok(meta) if meta.len() == 0 => Err(AppError::truncated("空文件 (0 字节)")),
+        _ => Ok(()),
+    }
+}
+
+/// JPEG 缺少结束标记 (EOI)
+/// 先看最后两个字节 (绝大多数完整文件到这里就结束)；不是的话从第一个扫描段 (SOS) 开始找 EOI，
+/// 动态照片在 EOI 后面拼接的视频、厂商追加的尾部数据都不影响判断
+/// 文件结构看不懂时返回 false，交给解码器自己报错
+pub fn jpeg_is_truncated(path: &str) -> Result<bool, AppError> {
+    let mut file = File::open(path)?;
+    let len = file.metadata()?.len();
+    if len >= 4 {
+        let mut tail = [0u8; 2];
+        file.seek(SeekFrom::End(-2))?;
+        file.read_exact(&mut tail)?;
+        if tail == [0xFF, MARKER_EOI] {
+            return Ok(false);
+        }
+        file.seek(SeekFrom::Start(0))?;
+    }
+    let mut bytes = Vec::with_capacity(len as usize);
+    file.read_to_end(&mut bytes)?;
+    Ok(jpeg_bytes_truncated(&bytes))
+}
+
+fn jpeg_bytes_truncated(bytes: &[u8]) -> bool {
+    if bytes.len() < 2 || bytes[0] != 0xFF || bytes[1] != MARKER_SOI {
+        return false;
+    }
+    // 逐段跳过文件头 (APP1 里的 EXIF 缩略图自带 EOI，不能从头直接搜)
+    let mut pos = 2;
+    let scan_start = loop {
+        if pos >= bytes.len() {
+            return true; // 文件头还没读完就结束了
+        }
+        if bytes[pos] != 0xFF {
+            return false;
+        }
+        // 标记前允许有填充的 0xFF
+        while pos + 1 < bytes.len() && bytes[pos + 1] == 0xFF {
+            pos += 1;
+        }
+        let Some(&marker) = bytes.get(pos + 1) else {
+            return true;
+        };
+        match marker {
+            MARKER_EOI => return false,
+            // 没有长度字段的标记
+            0x01 | 0xD0..=0xD7 => pos += 2,
+            _ => {
+                let Some(len) = bytes.get(pos + 2..pos + 4).map(|b| u16::from_be_bytes([b[0], b[1]]) as usize) else {
+                    return true;
+                };
+                if marker == MARKER_SOS {
+                    break pos + 2 + len;
+                }
+                pos += 2 + len;
+            },
+        }
+    };
+    // 熵编码数据里的 0xFF 后面只会跟 0x00 或 RST，出现 FF D9 就是结束标记
+    match bytes.get(scan_start..) {
+        Some(data) => !data.windows(2).any(|w| w == [0xFF, MARKER_EOI]),
+        None => true,
+    }
+}
+
+/// 解码器的 "读到文件末尾" 类错误
+pub fn is_truncation_error(e: &ImageError) -> bool {
+    if let ImageError::IoError(io) = e {
+        return io.kind() == ErrorKind::UnexpectedEof;
+    }
+    let msg = e.to_string().to_lowercase();
+    ["unexpected eof", "unexpected end", "end of file", "no more bytes", "not enough data"]
+        .iter()
+        .any(|pat| msg.contains(pat))
+}
+
+/// 解码尺寸与 EXIF 记录的像素尺寸对照 (方向校正之前)
+/// 只有宽度一致而高度偏小才算截断 (缺的是下半部分的行)；
+/// 其他不一致多半是编辑软件裁剪 / 缩放后没更新 EXIF，不当作错误
+pub fn check_pixel_dimensions(decoded: (u32, u32), exif: Option<(u32, u32)>) -> Result<(), AppError> {
+    match exif {
+        Some((w, h)) if decoded.0 == w && decoded.1 < h => Err(AppError::truncated(format!(
+            "解码只得到 {}x{}，EXIF 记录为 {}x{}",
+            decoded.0, decoded.1, w, h
+        ))),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::graphics::effects::load_image_with_icc;
+    use crate::test_support::fixture;
+
+    fn encode_jpeg() -> Vec<u8> {
+        let img = image::RgbImage::from_fn(64, 48, |x, y| image::Rgb([(x * 4) as u8, (y * 5) as u8, 90]));
+        let mut bytes = Vec::new();
+        image::codecs::jpeg::JpegEncoder::new_with_quality(&mut bytes, 90).encode_image(&img).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn empty_file_is_reported_as_truncated() {
+        let path = fixture("empty.jpg");
+        let err = check_not_empty(&path).unwrap_err();
+        assert_eq!(err.code(), "TRUNCATED_FILE");
+        // 加载时也在解码之前拦下，不是看不懂的格式错误
+        let err = load_image_with_icc(&path).unwrap_err();
+        assert_eq!(err.code(), "TRUNCATED_FILE");
+        assert!(err.to_string().contains("空文件 (0 字节)"), "{}", err);
+    }
+
+    #[test]
+    fn cut_jpeg_is_reported_as_truncated() {
+        let path = fixture("truncated_60.jpg");
+        check_not_empty(&path).unwrap();
+        assert!(jpeg_is_truncated(&path).unwrap());
+        // 解码器会把缺的下半截补成灰色照常返回，必须在解码前拦下
+        let err = load_image_with_icc(&path).unwrap_err();
+        assert_eq!(err.code(), "TRUNCATED_FILE");
+        assert!(err.to_string().contains("JPEG 缺少结束标记"), "{}", err);
+    }
+
+    #[test]
+    fn complete_jpeg_with_trailing_data_is_not_truncated() {
+        let full = encode_jpeg();
+        assert!(!jpeg_bytes_truncated(&full));
+        // 动态照片等在 EOI 之后拼接的数据不影响判断
+        let mut with_trailer = full.clone();
+        with_trailer.extend_from_slice(b"ftypmp42 motion photo payload");
+        assert!(!jpeg_bytes_truncated(&with_trailer));
+        // 同一张图截到文件头之后 / 扫描数据中途都算截断
+        assert!(jpeg_bytes_truncated(&full[..full.len() * 6 / 10]));
+        assert!(jpeg_bytes_truncated(&full[..4]));
+        // 不是 JPEG 的交给解码器报错
+        assert!(!jpeg_bytes_truncated(b"\x89PNG\r\n"));
+    }
+
+    #[test]
+    fn decoder_eof_errors_count_as_truncation() {
+        let eof = ImageError::IoError(std::io::Error::new(ErrorKind::UnexpectedEof, "eof"));
+        assert!(is_truncation_error(&eof));
+        let other = ImageError::IoError(std::io::Error::new(ErrorKind::PermissionDenied, "denied"));
+        assert!(!is_truncation_error(&other));
+    }
+
+    #[test]
+    fn only_short_height_counts_as_truncated() {
+        let err = check_pixel_dimensions((6000, 2400), Some((6000, 4000))).unwrap_err();
+        assert_eq!(err.code(), "TRUNCATED_FILE");
+        // 裁剪 / 缩放后没更新 EXIF 的不算
+        assert!(check_pixel_dimensions((3000, 2000), Some((6000, 4000))).is_ok());
+        assert!(check_pixel_dimensions((6000, 4000), Some((6000, 4000))).is_ok());
+        assert!(check_pixel_dimensions((6000, 2400), None).is_ok());
+    }
+}
@@ -2,6 +2,10 @@ pub mod shapes;
 pub mod text;
 pub mod effects;
 pub mod shadow;
+pub mod raw;
+pub mod color;
+pub mod decode_cache;
+pub mod integrity;
 
 // 重新导出所有内容，保持对外 API 兼容性
 pub use effects::*;
@@ -0,0 +1,302 @@
+// src-tauri/src/graphics/raw.rs
+
+use std::path::Path;
+
+use image::DynamicImage;
+use log::{debug, warn};
+
+use crate::error::AppError;
+
+// =========================================================
+// 🟢 RAW 解码 (NEF / ARW / CR2 / CR3 / DNG ...)
+// =========================================================
+// 1. 快速路径：几乎所有 RAW 都内嵌了一张机内直出的全尺寸 JPG 预览，直接扫出来解码
+//    (只认 baseline / progressive，DNG/CR2 里的无损 JPEG 是传感器数据，image 解不了)
+// 2. 没有够大的预览时：以 raw feature 编译则走 rawloader 半尺寸反马赛克，否则退而求其次用最大的预览
+// EXIF 仍然由 get_exif_data 从 RAW 本身读取，这里只负责像素
+
+/// 按扩展名判断是否为 RAW
+pub const RAW_EXTENSIONS: [&str; 8] = ["nef", "nrw", "arw", "cr2", "cr3", "dng", "raf", "orf"];
+
+// 预览长边达到这个尺寸才算“全尺寸”，否则优先反马赛克
+const MIN_FULL_PREVIEW_EDGE: u32 = 2000;
+
+pub fn is_raw_path(path: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|ext| RAW_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+}
+
+/// 解码 RAW 文件 (方向由调用方按 EXIF 处理)
+pub fn decode_raw(path: &str) -> Result<DynamicImage, AppError> {
+    let data = std::fs::read(path)?;
+    let preview = largest_embedded_jpeg(&data);
+
+    if let Some((offset, w, h)) = preview {
+        if w.max(h) >= MIN_FULL_PREVIEW_EDGE || !cfg!(feature = "raw") {
+            debug!("📷 [RAW] 使用内嵌预览 {}x{}: {}", w, h, path);
+            if w.max(h) < MIN_FULL_PREVIEW_EDGE {
+                warn!("⚠️ [RAW] 内嵌预览只有 {}x{} (未启用 raw feature，无法反马赛克): {}", w, h, path);
+            }
+            match image::load_from_memory_with_format(&data[offset..], image::ImageFormat::Jpeg) {
+                Ok(img) => return Ok(img),
+                Err(e) => warn!("⚠️ [RAW] 内嵌预览解码失败，尝试其他方式: {}", e),
+            }
+        }
+    }
+
+    #[cfg(feature = "raw")]
+    {
+        debug!("📷 [RAW] 半尺寸反马赛克: {}", path);
+        demosaic_half(path)
+    }
+
+    #[cfg(not(feature = "raw"))]
+    Err(AppError::System(format!("RAW 文件没有可用的内嵌预览 (需要以 raw feature 编译): {}", path)))
+}
+
+/// 在文件里找出尺寸最大的内嵌 JPG，返回 (起始偏移, 宽, 高)
+/// 只读 JPG 头部到 SOF 为止，不做解码
+fn largest_embedded_jpeg(data: &[u8]) -> Option<(usize, u32, u32)> {
+    let mut best: Option<(usize, u32, u32)> = None;
+    let mut pos = 0;
+    while pos + 3 < data.len() {
+        // SOI 紧跟着一个标记 (FF D8 FF)
+        let Some(found) = data[pos..].windows(3).position(|w| w == [0xFF, 0xD8, 0xFF]) else { break };
+        let start = pos + found;
+        if let Some((w, h)) = jpeg_dimensions(&data[start..]) {
+            if best.is_none_or(|(_, bw, bh)| (w as u64 * h as u64) > (bw as u64 * bh as u64)) {
+                best = Some((start, w, h));
+            }
+        }
+        pos = start + 2;
+    }
+    best
+}
+
+/// 解析 JPG 头部，返回 SOF 里的 (宽, 高)；不是可解码的 baseline / progressive JPG 时返回 None
+fn jpeg_dimensions(jpg: &[u8]) -> Option<(u32, u32)> {
+    let be16 = |i: usize| -> Option<u16> { Some(u16::from_be_bytes([*jpg.get(i)?, *jpg.get(i + 1)?])) };
+    let mut i = 2; // 跳过 SOI
+    loop {
+        if *jpg.get(i)? != 0xFF {
+            return None;
+        }
+        let marker = *jpg.get(i + 1)?;
+        match marker {
+            // 填充字节
+            0xFF => { i += 1; continue; },
+            // SOF0 / SOF1 / SOF2：长度(2) 精度(1) 高(2) 宽(2)
+            0xC0..=0xC2 => {
+                let (h, w) = (be16(i + 5)?, be16(i + 7)?);
+                return (w > 0 && h > 0).then_some((w as u32, h as u32));
+            },
+            // 其他 SOF (无损 / 算术编码) 或者在 SOF 之前就遇到了扫描数据 / 结束
+            0xC3 | 0xC5..=0xC7 | 0xC9..=0xCB | 0xCD..=0xCF | 0xDA | 0xD9 => return None,
+            _ => i += 2 + be16(i + 2)? as usize,
+        }
+    }
+}
+
+/// rawloader 解码 + 2x2 合并的半尺寸反马赛克 (每个 Bayer 单元直接得到一个 RGB 像素)
+/// 相机色彩矩阵转到 sRGB，再做 sRGB gamma；不做降噪和锐化，够加边框用
+#[cfg(feature = "raw")]
+fn demosaic_half(path: &str) -> Result<DynamicImage, AppError> {
+    use rawloader::RawImageData;
+
+    let raw = rawloader::decode_file(path)
+        .map_err(|e| AppError::System(format!("RAW 解码失败 [{}]: {:?}", path, e)))?;
+    if raw.cpp != 1 {
+        return Err(AppError::System(format!("不支持的 RAW 格式 (cpp={}): {}", raw.cpp, path)));
+    }
+
+    let sample = |idx: usize| -> f32 {
+        match &raw.data {
+            RawImageData::Integer(v) => v[idx] as f32,
+            RawImageData::Float(v) => v[idx],
+        }
+    };
+
+    // 有效区域 (crops: 上 右 下 左)，对齐到 2 保证 Bayer 单元完整
+    let [top, right, bottom, left] = raw.crops;
+    let (x0, y0) = (left & !1, top & !1);
+    let out_w = raw.width.saturating_sub(left + right) / 2;
+    let out_h = raw.height.saturating_sub(top + bottom) / 2;
+    if out_w == 0 || out_h == 0 {
+        return Err(AppError::System(format!("RAW 有效区域为空: {}", path)));
+    }
+
+    // 白平衡以绿色为 1 归一化
+    let wb = raw.wb_coeffs;
+    let wb = if wb[1].is_finite() && wb[1] > 0.0 {
+        [wb[0] / wb[1], 1.0, wb[2] / wb[1], wb[3] / wb[1]]
+    } else {
+        [1.0; 4]
+    };
+    let wb = wb.map(|c| if c.is_finite() && c > 0.0 { c } else { 1.0 });
+    let cam_to_xyz = raw.cam_to_xyz_normalized();
+
+    // XYZ (D65) -> 线性 sRGB
+    const XYZ_TO_SRGB: [[f32; 3]; 3] = [
+        [3.240_454_2, -1.537_138_5, -0.498_531_4],
+        [-0.969_266, 1.876_010_8, 0.041_556],
+        [0.055_643_4, -0.204_025_9, 1.057_225_2],
+    ];
+
+    let mut out = image::RgbImage::new(out_w as u32, out_h as u32);
+    for (ox, oy, px) in out.enumerate_pixels_mut() {
+        let (bx, by) = (x0 + ox as usize * 2, y0 + oy as usize * 2);
+        // 一个 2x2 单元：按 CFA 颜色累加 (两个绿色取平均)
+        let mut cam = [0.0f32; 4];
+        let mut count = [0u32; 4];
+        for (dy, dx) in [(0, 0), (0, 1), (1, 0), (1, 1)] {
+            let (row, col) = (by + dy, bx + dx);
+            let color = raw.cfa.color_at(row, col).min(3);
+            let black = raw.blacklevels[color] as f32;
+            let white = raw.whitelevels[color] as f32;
+            let v = (sample(row * raw.width + col) - black) / (white - black).max(1.0);
+            cam[color] += v.clamp(0.0, 1.0) * wb[color];
+            count[color] += 1;
+        }
+        for c in 0..4 {
+            if count[c] > 0 { cam[c] /= count[c] as f32; }
+        }
+        // 没有第四色 (E) 的传感器用绿色补上，矩阵第 4 列通常为 0
+        if count[3] == 0 { cam[3] = cam[1]; }
+
+        let xyz: [f32; 3] = std::array::from_fn(|r| (0..4).map(|c| cam_to_xyz[r][c] * cam[c]).sum());
+        let rgb: [f32; 3] = std::array::from_fn(|r| (0..3).map(|c| XYZ_TO_SRGB[r][c] * xyz[c]).sum());
+        *px = image::Rgb(rgb.map(|v| (srgb_gamma(v.clamp(0.0, 1.0)) * 255.0).round() as u8));
+    }
+
+    Ok(DynamicImage::ImageRgb8(out))
+}
+
+#[cfg(feature = "raw")]
+fn srgb_gamma(v: f32) -> f32 {
+    if v <= 0.0031308 { v * 12.92 } else { 1.055 * v.powf(1.0 / 2.4) - 0.055 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::fixture;
+
+    /// 只有头部的 JPG：SOI + APP0 + 给定的 SOF 标记 (宽高) + SOS
+    fn jpeg_header(sof: u8, w: u16, h: u16) -> Vec<u8> {
+        let mut v = vec![0xFF, 0xD8];
+        v.extend_from_slice(&[0xFF, 0xE0, 0x00, 0x06, b'J', b'F', b'I', b'F']);
+        v.extend_from_slice(&[0xFF, sof, 0x00, 0x0B, 0x08]);
+        v.extend_from_slice(&h.to_be_bytes());
+        v.extend_from_slice(&w.to_be_bytes());
+        v.extend_from_slice(&[0x01, 0x01, 0x11, 0x00]);
+        v.extend_from_slice(&[0xFF, 0xDA, 0x00, 0x02]);
+        v
+    }
+
+    /// 固定种子的伪随机字节 (模拟 RAW 里的传感器数据)
+    fn noise(len: usize, mut seed: u32) -> Vec<u8> {
+        (0..len).map(|_| {
+            seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            (seed >> 24) as u8
+        }).collect()
+    }
+
+    #[test]
+    fn sof_dimensions_are_parsed() {
+        assert_eq!(jpeg_dimensions(&jpeg_header(0xC0, 6048, 4024)), Some((6048, 4024)));
+        assert_eq!(jpeg_dimensions(&jpeg_header(0xC2, 160, 120)), Some((160, 120)));
+        // 与真实文件的解码尺寸一致
+        let real = std::fs::read(fixture("ev_spot.jpg")).unwrap();
+        let (w, h) = image::image_dimensions(fixture("ev_spot.jpg")).unwrap();
+        assert_eq!(jpeg_dimensions(&real), Some((w, h)));
+    }
+
+    #[test]
+    fn undecodable_or_empty_sof_is_rejected() {
+        // 无损 (DNG/CR2 的传感器数据) 和算术编码解不了
+        assert_eq!(jpeg_dimensions(&jpeg_header(0xC3, 6048, 4024)), None);
+        assert_eq!(jpeg_dimensions(&jpeg_header(0xC9, 6048, 4024)), None);
+        assert_eq!(jpeg_dimensions(&jpeg_header(0xC0, 0, 4024)), None);
+        // 填充字节不影响解析
+        let mut padded = jpeg_header(0xC0, 640, 480);
+        padded.insert(2, 0xFF);
+        assert_eq!(jpeg_dimensions(&padded), Some((640, 480)));
+    }
+
+    #[test]
+    fn truncated_or_garbage_headers_do_not_panic() {
+        let header = jpeg_header(0xC0, 6048, 4024);
+        for len in 0..header.len() {
+            let (cut, found) = (&header[..len], jpeg_dimensions(&header[..len]));
+            // 宽高 (SOF 的第 5~8 字节) 完整之前一律 None
+            if len < 19 {
+                assert_eq!(found, None, "截断到 {} 字节", len);
+            }
+            let _ = largest_embedded_jpeg(cut);
+        }
+        // 段长度指向文件之外
+        assert_eq!(jpeg_dimensions(&[0xFF, 0xD8, 0xFF, 0xE1, 0xFF, 0xFF, 0x00]), None);
+        for seed in 0..32 {
+            let data = noise(4096, seed);
+            let _ = jpeg_dimensions(&data);
+            let _ = largest_embedded_jpeg(&data);
+        }
+        // 结尾只剩 SOI
+        assert_eq!(largest_embedded_jpeg(&[0x00, 0xFF, 0xD8, 0xFF]), None);
+        assert_eq!(largest_embedded_jpeg(&[]), None);
+    }
+
+    #[test]
+    fn largest_of_several_previews_is_picked() {
+        // 缩略图 -> 全尺寸预览 -> 中等预览，中间夹着传感器数据
+        let mut data = noise(512, 1);
+        data.extend(jpeg_header(0xC0, 160, 120));
+        data.extend(noise(512, 2));
+        let full_at = data.len();
+        data.extend(jpeg_header(0xC0, 6048, 4024));
+        data.extend(noise(512, 3));
+        data.extend(jpeg_header(0xC0, 1620, 1080));
+        // 更大的无损数据不算
+        data.extend(jpeg_header(0xC3, 8000, 6000));
+        assert_eq!(largest_embedded_jpeg(&data), Some((full_at, 6048, 4024)));
+    }
+
+    #[test]
+    fn garbage_raw_file_is_an_error() {
+        let path = std::env::temp_dir().join(format!("framer_raw_garbage_{}.nef", std::process::id()));
+        std::fs::write(&path, noise(8192, 7)).unwrap();
+        let result = decode_raw(path.to_str().unwrap());
+        let _ = std::fs::remove_file(&path);
+        assert!(result.is_err());
+    }
+
+    #[cfg(not(feature = "raw"))]
+    #[test]
+    fn embedded_preview_is_decoded() {
+        let jpg = std::fs::read(fixture("ev_spot.jpg")).unwrap();
+        // 前面的小缩略图只有头部，解码时必须选中后面的真实预览
+        let mut data = noise(1024, 9);
+        data.extend(jpeg_header(0xC0, 1, 1));
+        data.extend(&jpg);
+        let path = std::env::temp_dir().join(format!("framer_raw_preview_{}.nef", std::process::id()));
+        std::fs::write(&path, data).unwrap();
+        let result = decode_raw(path.to_str().unwrap());
+        let _ = std::fs::remove_file(&path);
+        let img = result.unwrap();
+        let (w, h) = image::image_dimensions(fixture("ev_spot.jpg")).unwrap();
+        assert_eq!((img.width(), img.height()), (w, h));
+    }
+
+    #[cfg(feature = "raw")]
+    #[test]
+    fn demosaic_rejects_garbage_without_panicking() {
+        let path = std::env::temp_dir().join(format!("framer_raw_demosaic_{}.dng", std::process::id()));
+        std::fs::write(&path, noise(8192, 11)).unwrap();
+        let result = demosaic_half(path.to_str().unwrap());
+        let _ = std::fs::remove_file(&path);
+        assert!(result.is_err());
+        assert!(demosaic_half(&fixture("empty.jpg")).is_err());
+    }
+}
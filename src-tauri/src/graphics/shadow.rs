@@ -30,6 +30,37 @@ impl ShadowProfile {
         Self { sigma: 25.0, offset_x: 0, offset_y: 30, spread: -8, color: Rgba([0, 0, 0, 210]) }
     }
     
+    /// 🟢 按强度取阴影 (StyleOptions 里的 shadow_intensity)
+    /// - None: 沿用默认的 standard 预设 (旧版本输出不变)
+    /// - 0 及以下: 不画阴影
+    /// - (0, 1]: 在 subtle 与 floating 之间线性插值
+    pub fn for_intensity(intensity: Option<f32>) -> Option<Self> {
+        match intensity {
+            None => Some(Self::preset_standard()),
+            Some(t) if t.is_nan() || t <= 0.0 => None,
+            Some(t) => Some(Self::lerp(&Self::preset_subtle(), &Self::preset_floating(), t.min(1.0))),
+        }
+    }
+
+    /// 两个模板之间线性插值 (t=0 为 a，t=1 为 b)
+    pub fn lerp(a: &Self, b: &Self, t: f32) -> Self {
+        let mix = |x: f32, y: f32| x + (y - x) * t;
+        let mix_i = |x: i32, y: i32| mix(x as f32, y as f32).round() as i32;
+        let mix_u8 = |x: u8, y: u8| mix(x as f32, y as f32).round().clamp(0.0, 255.0) as u8;
+        Self {
+            sigma: mix(a.sigma, b.sigma),
+            offset_x: mix_i(a.offset_x, b.offset_x),
+            offset_y: mix_i(a.offset_y, b.offset_y),
+            spread: mix_i(a.spread, b.spread),
+            color: Rgba([
+                mix_u8(a.color[0], b.color[0]),
+                mix_u8(a.color[1], b.color[1]),
+                mix_u8(a.color[2], b.color[2]),
+                mix_u8(a.color[3], b.color[3]),
+            ]),
+        }
+    }
+    
     // 如果需要自定义，可以使用 new
     pub fn new(sigma: f32, offset: (i32, i32), spread: i32, color: Rgba<u8>) -> Self {
         Self { sigma, offset_x: offset.0, offset_y: offset.1, spread, color }
@@ -47,6 +78,17 @@ impl ShadowProfile {
         target: &mut RgbaImage,
         src_dims: (u32, u32),
         center_pos: (i64, i64),
+    ) {
+        self.draw_adaptive_rounded_shadow_on(target, src_dims, center_pos, 0);
+    }
+
+    /// 🟢 圆角版本：阴影轮廓与圆角照片一致 (radius 为原图尺度下的圆角半径，像素)
+    pub fn draw_adaptive_rounded_shadow_on(
+        &self,
+        target: &mut RgbaImage,
+        src_dims: (u32, u32),
+        center_pos: (i64, i64),
+        radius: u32,
     ) {
         let (canvas_w, canvas_h) = target.dimensions();
         const REF_SIZE: f32 = 1000.0;
@@ -65,7 +107,7 @@ impl ShadowProfile {
             color: self.color,
         };
 
-        effective_profile.draw_raw_shadow_on(target, src_dims, center_pos.0, center_pos.1);
+        effective_profile.draw_raw_shadow_on(target, src_dims, center_pos.0, center_pos.1, radius);
     }
 
     /// 🔒 [底层 API] 原始绘制 (Raw Drawing)
@@ -75,7 +117,8 @@ impl ShadowProfile {
         target: &mut RgbaImage, 
         src_dims: (u32, u32), 
         center_x: i64, 
-        center_y: i64
+        center_y: i64,
+        radius: u32,
     ) {
         let (src_w, src_h) = src_dims;
         
@@ -98,6 +141,10 @@ impl ShadowProfile {
         let shadow_rect_h = (tiny_h as f32 + tiny_spread * 2.0).max(1.0).ceil() as u32;
         let padding = (tiny_sigma * 3.0).ceil() as u32;
         
+        // 圆角跟随扩散收缩，且不能超过阴影块短边的一半
+        let tiny_radius = ((radius as f32 + self.spread as f32) * scale_factor)
+            .clamp(0.0, shadow_rect_w.min(shadow_rect_h) as f32 / 2.0);
+
         let canvas_w = shadow_rect_w + padding * 2;
         let canvas_h = shadow_rect_h + padding * 2;
 
@@ -110,6 +157,16 @@ impl ShadowProfile {
         
         for y in fill_y..(fill_y + shadow_rect_h) {
             for x in fill_x..(fill_x + shadow_rect_w) {
+                // 圆角外的像素不涂 (之后还要模糊，不需要抗锯齿)
+                if tiny_radius > 0.0 {
+                    let lx = (x - fill_x) as f32 + 0.5;
+                    let ly = (y - fill_y) as f32 + 0.5;
+                    let cx = lx.clamp(tiny_radius, shadow_rect_w as f32 - tiny_radius);
+                    let cy = ly.clamp(tiny_radius, shadow_rect_h as f32 - tiny_radius);
+                    if (lx - cx).powi(2) + (ly - cy).powi(2) > tiny_radius * tiny_radius {
+                        continue;
+                    }
+                }
                 tiny_map.put_pixel(x, y, paint_pixel);
             }
         }
@@ -134,4 +191,63 @@ impl ShadowProfile {
 
         imageops::overlay(target, &shadow_layer, paste_x.round() as i64, paste_y.round() as i64);
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn intensity_interpolates_between_presets() {
+        let (subtle, floating) = (ShadowProfile::preset_subtle(), ShadowProfile::preset_floating());
+
+        // 0.5 落在两个预设之间
+        let mid = ShadowProfile::for_intensity(Some(0.5)).unwrap();
+        assert!((mid.sigma - (subtle.sigma + floating.sigma) / 2.0).abs() < 1e-4);
+        assert_eq!(mid.offset_y, 20);
+        assert_eq!(mid.spread, -5);
+        assert_eq!(mid.color[3], 185);
+        assert!(subtle.sigma < mid.sigma && mid.sigma < floating.sigma);
+
+        // 端点与预设一致，超过 1 按 1 处理
+        let low = ShadowProfile::for_intensity(Some(f32::MIN_POSITIVE)).unwrap();
+        assert_eq!((low.offset_y, low.spread, low.color), (subtle.offset_y, subtle.spread, subtle.color));
+        for t in [1.0, 3.0, f32::INFINITY] {
+            let high = ShadowProfile::for_intensity(Some(t)).unwrap();
+            assert_eq!((high.sigma, high.offset_y, high.color), (floating.sigma, floating.offset_y, floating.color));
+        }
+    }
+
+    #[test]
+    fn zero_or_invalid_intensity_disables_shadow() {
+        for t in [0.0, -0.5, f32::NAN, f32::NEG_INFINITY] {
+            assert!(ShadowProfile::for_intensity(Some(t)).is_none(), "{}", t);
+        }
+        // 不传沿用默认 standard 预设
+        let standard = ShadowProfile::preset_standard();
+        let none = ShadowProfile::for_intensity(None).unwrap();
+        assert_eq!((none.sigma, none.offset_y, none.color), (standard.sigma, standard.offset_y, standard.color));
+    }
+
+    #[test]
+    fn shadow_near_corners_is_clipped() {
+        let profile = ShadowProfile::preset_floating();
+        // 照片中心在画布左上角：贴图坐标为负，只画落在画布内的部分
+        let mut canvas = RgbaImage::new(300, 200);
+        profile.draw_adaptive_shadow_on(&mut canvas, (200, 150), (0, 0));
+        assert!(canvas.get_pixel(10, 10)[3] > 0);
+        assert_eq!(canvas.get_pixel(299, 199)[3], 0);
+
+        // 右下角同理，圆角版本也一样
+        let mut canvas = RgbaImage::new(300, 200);
+        profile.draw_adaptive_rounded_shadow_on(&mut canvas, (200, 150), (300, 200), 24);
+        assert!(canvas.get_pixel(290, 190)[3] > 0);
+        assert_eq!(canvas.get_pixel(0, 0)[3], 0);
+
+        // 完全在画布外：什么都不画
+        let mut canvas = RgbaImage::new(300, 200);
+        profile.draw_adaptive_shadow_on(&mut canvas, (200, 150), (-5000, -5000));
+        assert!(canvas.pixels().all(|p| p[3] == 0));
+    }
+}
+
@@ -1,25 +1,182 @@
-use image::{ImageBuffer, Rgba};
-use imageproc::drawing::{draw_filled_rect_mut, draw_filled_circle_mut};
+use std::f32::consts::PI;
+
+use image::Rgba;
+use imageproc::drawing::{draw_filled_circle_mut, draw_filled_rect_mut, draw_polygon_mut, Canvas};
+use imageproc::point::Point;
 use imageproc::rect::Rect;
+use log::warn;
+
+/// 🛡️ 判断一个区域是否与画布有交集
+///
+/// 极端配置 (小图大字号、超大边框比例) 可能算出负坐标或完全落在画布外的区域。
+/// 部分越界由 imageproc 自动裁剪；完全越界的直接跳过，避免无意义的绘制。
+pub fn overlaps_canvas<C: Canvas>(canvas: &C, x: i32, y: i32, w: u32, h: u32) -> bool {
+    let (canvas_w, canvas_h) = canvas.dimensions();
+    let right = x as i64 + w as i64;
+    let bottom = y as i64 + h as i64;
+    w > 0 && h > 0
+        && right > 0 && bottom > 0
+        && (x as i64) < canvas_w as i64 && (y as i64) < canvas_h as i64
+}
 
-// 🟢 绘制实心圆角矩形
-pub fn draw_rounded_rect_mut(
-    image: &mut ImageBuffer<Rgba<u8>, Vec<u8>>,
+// 🟢 绘制实心圆角矩形 (四个实心圆 + 两个矩形拼接)
+// 玻璃边框底座用它；角上的像素与多边形版本不同，换掉会改变 TransparentClassic 的输出
+pub fn draw_rounded_rect_mut<C: Canvas<Pixel = Rgba<u8>>>(
+    canvas: &mut C,
     rect: Rect,
     radius: i32,
     color: Rgba<u8>
 ) {
     let (x, y) = (rect.left(), rect.top());
     let (w, h) = (rect.width(), rect.height());
-    let r = radius.min((w as i32) / 2).min((h as i32) / 2);
+    // r 恰好等于半宽/半高时中间矩形会退化为 0 尺寸 (Rect 会 panic)，这里多留 1px
+    let r = radius.min((w as i32 - 1) / 2).min((h as i32 - 1) / 2).max(0);
+    if w == 0 || h == 0 { return; }
 
     let rect_h = Rect::at(x + r, y).of_size(w - (2 * r as u32), h);
     let rect_v = Rect::at(x, y + r).of_size(w, h - (2 * r as u32));
-    draw_filled_rect_mut(image, rect_h, color);
-    draw_filled_rect_mut(image, rect_v, color);
-
-    draw_filled_circle_mut(image, (x + r, y + r), r, color);
-    draw_filled_circle_mut(image, (x + (w as i32) - r - 1, y + r), r, color);
-    draw_filled_circle_mut(image, (x + r, y + (h as i32) - r - 1), r, color);
-    draw_filled_circle_mut(image, (x + (w as i32) - r - 1, y + (h as i32) - r - 1), r, color);
-}
\ No newline at end of file
+    draw_filled_rect_mut(canvas, rect_h, color);
+    draw_filled_rect_mut(canvas, rect_v, color);
+
+    draw_filled_circle_mut(canvas, (x + r, y + r), r, color);
+    draw_filled_circle_mut(canvas, (x + (w as i32) - r - 1, y + r), r, color);
+    draw_filled_circle_mut(canvas, (x + r, y + (h as i32) - r - 1), r, color);
+    draw_filled_circle_mut(canvas, (x + (w as i32) - r - 1, y + (h as i32) - r - 1), r, color);
+}
+
+/// 🔷 绘制实心圆角矩形 (Polyfill)
+///
+/// 使用多边形拟合圆角，比像素扫描质量更高。
+/// 用于绘制徽章、标签背景等；RgbaImage 和 DynamicImage 画布都可以用。
+pub fn draw_rounded_rect_polyfill<C: Canvas<Pixel = Rgba<u8>>>(
+    canvas: &mut C,
+    rect: Rect,
+    radius: i32,
+    color: Rgba<u8>
+) {
+    if !overlaps_canvas(canvas, rect.left(), rect.top(), rect.width(), rect.height()) {
+        warn!(
+            "⚠️ [Draw] 矩形完全超出画布，已跳过: ({}, {}) {}x{} canvas={:?}",
+            rect.left(), rect.top(), rect.width(), rect.height(), canvas.dimensions()
+        );
+        return;
+    }
+
+    let x = rect.left() as f32;
+    let y = rect.top() as f32;
+    let w = rect.width() as f32;
+    let h = rect.height() as f32;
+    
+    let r = (radius as f32).min(w / 2.0).min(h / 2.0);
+
+    if r <= 0.5 {
+        draw_filled_rect_mut(canvas, rect, color);
+        return;
+    }
+
+    let segments = 16; 
+    let mut points: Vec<Point<i32>> = Vec::with_capacity(4 * (segments + 1)); 
+
+    let mut add_arc = |cx: f32, cy: f32, start_angle: f32| {
+        for i in 0..=segments {
+            let angle = start_angle + (i as f32 / segments as f32) * (PI / 2.0);
+            let px = cx + r * angle.cos();
+            let py = cy + r * angle.sin();
+            points.push(Point::new(px.round() as i32, py.round() as i32));
+        }
+    };
+
+    add_arc(x + w - r, y + r, -PI / 2.0);     // 右上
+    add_arc(x + w - r, y + h - r, 0.0);       // 右下
+    add_arc(x + r, y + h - r, PI / 2.0);      // 左下
+    add_arc(x + r, y + r, PI);                // 左上
+
+    // 极小矩形四舍五入后会产生大量相邻重复点，先去重
+    points.dedup();
+
+    // imageproc 要求首尾点不能相同，否则会 panic。
+    // 在绘制胶囊形状（完全圆角）时，数学计算会导致首尾点重合，必须手动去重。
+    if let (Some(first), Some(last)) = (points.first(), points.last()) {
+        if first == last {
+            points.pop(); // 移除最后一个重复的点
+        }
+    }
+
+    // 去重后不足 3 个点 (退化成点或线)，多边形无意义，退回普通矩形
+    if points.len() < 3 {
+        draw_filled_rect_mut(canvas, rect, color);
+        return;
+    }
+
+    draw_polygon_mut(canvas, &points, color);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbaImage;
+
+    const RED: Rgba<u8> = Rgba([255, 0, 0, 255]);
+
+    #[test]
+    fn overlap_check_rejects_only_fully_outside_rects() {
+        let canvas = RgbaImage::new(100, 50);
+        assert!(overlaps_canvas(&canvas, -10, -10, 20, 20));
+        assert!(overlaps_canvas(&canvas, 90, 40, 500, 500));
+        assert!(!overlaps_canvas(&canvas, -20, 0, 20, 10)); // 右边缘正好贴着左边界
+        assert!(!overlaps_canvas(&canvas, 100, 0, 10, 10));
+        assert!(!overlaps_canvas(&canvas, 0, 50, 10, 10));
+        assert!(!overlaps_canvas(&canvas, 10, 10, 0, 10));
+        // 极端坐标不能溢出
+        assert!(!overlaps_canvas(&canvas, i32::MAX, i32::MAX, u32::MAX, u32::MAX));
+        assert!(overlaps_canvas(&canvas, i32::MIN, i32::MIN, u32::MAX, u32::MAX));
+    }
+
+    type DrawFn = fn(&mut RgbaImage, Rect, i32, Rgba<u8>);
+    const BOTH: [DrawFn; 2] = [draw_rounded_rect_mut, draw_rounded_rect_polyfill];
+
+    #[test]
+    fn partially_outside_rect_is_clipped() {
+        for draw in BOTH {
+            let mut canvas = RgbaImage::new(100, 50);
+            draw(&mut canvas, Rect::at(-30, -30).of_size(60, 60), 20, RED);
+            // 画布内的部分照常填充，左上角那段圆角落在画布外
+            assert_eq!(*canvas.get_pixel(10, 10), RED);
+            assert_eq!(*canvas.get_pixel(50, 40), Rgba([0, 0, 0, 0]));
+        }
+    }
+
+    #[test]
+    fn fully_outside_rect_is_skipped() {
+        for draw in BOTH {
+            let mut canvas = RgbaImage::new(100, 50);
+            draw(&mut canvas, Rect::at(-80, 10).of_size(40, 20), 8, RED);
+            draw(&mut canvas, Rect::at(10, 60).of_size(40, 20), 8, RED);
+            assert!(canvas.pixels().all(|p| p[3] == 0));
+        }
+    }
+
+    #[test]
+    fn degenerate_rects_do_not_panic() {
+        for draw in BOTH {
+            let mut canvas = RgbaImage::new(20, 20);
+            for (w, h, r) in [(1, 1, 5), (2, 1, 5), (3, 3, 100), (20, 2, 10), (2, 20, 10)] {
+                draw(&mut canvas, Rect::at(5, 5).of_size(w, h), r, RED);
+            }
+            assert_eq!(*canvas.get_pixel(5, 5), RED);
+        }
+    }
+
+    #[test]
+    fn circle_corners_differ_from_polygon_corners() {
+        // 两种圆角的角上像素不同：玻璃边框必须继续用四圆拼接，否则 TransparentClassic 的输出会变
+        let rect = Rect::at(0, 0).of_size(60, 40);
+        let (mut circles, mut polygon) = (RgbaImage::new(60, 40), RgbaImage::new(60, 40));
+        draw_rounded_rect_mut(&mut circles, rect, 15, RED);
+        draw_rounded_rect_polyfill(&mut polygon, rect, 15, RED);
+        assert_ne!(circles, polygon);
+        // 四角之外的区域完全一致
+        assert_eq!(*circles.get_pixel(30, 20), *polygon.get_pixel(30, 20));
+        assert_eq!(circles.get_pixel(0, 0)[3], 0);
+    }
+}
@@ -1,33 +1,12 @@
-// 🟢 4. 修复 text_size 的 Option 报错
-// (你可以把这个放在 src/graphics/mod.rs 里，或者暂时放在这里)
-use ab_glyph::{Font, FontArc, PxScale, ScaleFont}; // 需要引入 Font trait
-
-pub fn text_size(text: &str, scale: PxScale, font: &FontArc) -> (u32, u32) {
-    let scaled_font = font.as_scaled(scale);
-    let mut width = 0.0;
-    let mut height = 0.0;
-
-    for c in text.chars() {
-        // 🟢 修复：with_scale_and_position 直接返回 Glyph，不需要 if let Some
-        let glyph = scaled_font.glyph_id(c).with_scale_and_position(scale, ab_glyph::point(0.0, 0.0));
-        
-        // outline_glyph 才会返回 Option，这里需要 if let Some
-        if let Some(outlined) = scaled_font.outline_glyph(glyph) {
-            let bounds = outlined.px_bounds();
-            width += scaled_font.h_advance(outlined.glyph().id);
-            if bounds.height() > height {
-                height = bounds.height();
-            }
-        }
-    }
-    
-    if text.is_empty() {
-        return (0, 0);
-    }
-
-    (width.ceil() as u32, height.ceil() as u32)
-}
+use ab_glyph::{point, Font, FontArc, GlyphId, PxScale, ScaleFont}; // 需要引入 Font trait
+use image::Pixel;
+use imageproc::definitions::Clamp;
+use imageproc::drawing::{draw_text_mut, Canvas};
+use imageproc::pixelops::weighted_sum;
+use log::warn;
+use once_cell::sync::OnceCell;
 
+use crate::resources::{self, FontFamily, FontWeight};
 
 /// 计算经过 DPI 校准后的字体大小 (物理像素)
 ///
@@ -58,4 +37,343 @@ pub fn calculate_browser_baseline_offset(font_size: f32) -> f32 {
     const BROWSER_BASELINE_RATIO: f32 = 0.121;
     
     font_size * BROWSER_BASELINE_RATIO
-}
\ No newline at end of file
+}
+
+// =========================================================
+// 🟢 缺字回退：署名 / 镜头 / 签名里的中日文
+// =========================================================
+// InterDisplay 等主字体没有 CJK 字形，draw_text_mut 会直接画出 notdef 方块。
+// 逐字符检查主字体是否有字形 (glyph_id != 0)，缺字的连续片段改用 CJK 回退字体绘制。
+// 回退字体只在第一次遇到缺字时加载，纯拉丁文字走原来的单次绘制。
+
+// None 表示回退字体加载失败 (文件缺失等)，之后不再重试，缺字照旧画 notdef
+static FALLBACK_FONT: OnceCell<Option<FontArc>> = OnceCell::new();
+
+fn fallback_font() -> Option<&'static FontArc> {
+    FALLBACK_FONT
+        .get_or_init(|| match resources::get_font(FontFamily::CjkFallback, FontWeight::Regular) {
+            Ok(font) => Some(font),
+            Err(e) => {
+                warn!("⚠️ [Font] CJK 回退字体不可用，缺字将显示为方块: {}", e);
+                None
+            }
+        })
+        .as_ref()
+}
+
+/// 按字体切分文字：(是否使用回退字体, 片段)。主字体不缺字时返回 None
+fn fallback_runs<F: Font>(font: &F, text: &str) -> Option<Vec<(bool, String)>> {
+    if text.chars().all(|c| font.glyph_id(c).0 != 0) {
+        return None;
+    }
+    let mut runs: Vec<(bool, String)> = Vec::new();
+    for c in text.chars() {
+        // 空白各字体都有，跟随前一段，避免把 "张 三" 拆成三段
+        let missing = font.glyph_id(c).0 == 0;
+        let use_fallback = match runs.last() {
+            Some((prev, _)) if c.is_whitespace() => *prev,
+            _ => missing,
+        };
+        match runs.last_mut() {
+            Some((prev, run)) if *prev == use_fallback => run.push(c),
+            _ => runs.push((use_fallback, c.to_string())),
+        }
+    }
+    Some(runs)
+}
+
+/// 与 imageproc 的 text_size 相同，缺字部分按回退字体计算宽高
+pub fn text_size_with_fallback<F: Font>(scale: PxScale, font: &F, text: &str) -> (u32, u32) {
+    let (Some(runs), Some(fallback)) = (fallback_runs(font, text), fallback_font()) else {
+        return imageproc::drawing::text_size(scale, font, text);
+    };
+    runs.iter().fold((0, 0), |(w, h), (use_fallback, run)| {
+        let (rw, rh) = if *use_fallback {
+            imageproc::drawing::text_size(scale, fallback, run)
+        } else {
+            imageproc::drawing::text_size(scale, font, run)
+        };
+        (w + rw, h.max(rh))
+    })
+}
+
+/// 与 draw_text_mut 相同 (y 为文字框顶部)，缺字部分用回退字体绘制
+///
+/// draw_text_mut 按各自字体的 ascent 放基线，回退片段额外下移两者 ascent 之差，保证基线一致。
+pub fn draw_text_with_fallback<C, F>(canvas: &mut C, color: C::Pixel, x: i32, y: i32, scale: PxScale, font: &F, text: &str)
+where
+    C: Canvas,
+    <C::Pixel as Pixel>::Subpixel: Into<f32> + Clamp<f32>,
+    F: Font,
+{
+    let (Some(runs), Some(fallback)) = (fallback_runs(font, text), fallback_font()) else {
+        draw_text_mut(canvas, color, x, y, scale, font, text);
+        return;
+    };
+    let baseline_shift = (font.as_scaled(scale).ascent() - fallback.as_scaled(scale).ascent()).round() as i32;
+
+    let mut cursor_x = x;
+    for (use_fallback, run) in &runs {
+        let (run_w, _) = if *use_fallback {
+            draw_text_mut(canvas, color, cursor_x, y + baseline_shift, scale, fallback, run);
+            imageproc::drawing::text_size(scale, fallback, run)
+        } else {
+            draw_text_mut(canvas, color, cursor_x, y, scale, font, run);
+            imageproc::drawing::text_size(scale, font, run)
+        };
+        cursor_x += run_w as i32;
+    }
+}
+
+// =========================================================
+// 🟢 真实字形范围 (墨迹)
+// =========================================================
+// imageproc 的 text_size 返回的高度是各字形墨迹高度的最大值，不带位置信息：
+// "1/250" 因为斜杠伸到基线以下，比 "100" 高一截，按它居中就会整体上移，
+// 所以各处理器只能靠 *_y_nudge 手调。这里按基线测量，基线以上 / 以下分开返回。
+
+/// 测量文字：(宽度, 基线以上的墨迹高度, 基线以下的墨迹深度)
+///
+/// 宽度与 text_size 一致 (按字宽累加)；高度 / 深度来自字形轮廓，均为非负像素值。
+/// 主字体缺字的片段按回退字体测量。
+pub fn measure_text_bounds<F: Font>(font: &F, text: &str, scale: PxScale) -> (u32, f32, f32) {
+    let (width, _) = text_size_with_fallback(scale, font, text);
+    let mut bounds = (0.0f32, 0.0f32);
+    match (fallback_runs(font, text), fallback_font()) {
+        (Some(runs), Some(fallback)) => {
+            for (use_fallback, run) in &runs {
+                let (a, d) = if *use_fallback { ink_extent(fallback, run, scale) } else { ink_extent(font, run, scale) };
+                bounds = (bounds.0.max(a), bounds.1.max(d));
+            }
+        }
+        _ => bounds = ink_extent(font, text, scale),
+    }
+    (width, bounds.0, bounds.1)
+}
+
+// 把字形放在 y = 0 的基线上，取轮廓包围盒的上下沿
+fn ink_extent<F: Font>(font: &F, text: &str, scale: PxScale) -> (f32, f32) {
+    let scaled = font.as_scaled(scale);
+    let (mut ascent, mut descent, mut x) = (0.0f32, 0.0f32, 0.0f32);
+    for c in text.chars() {
+        let id = scaled.glyph_id(c);
+        if let Some(outline) = scaled.outline_glyph(id.with_scale_and_position(scale, point(x, 0.0))) {
+            let bb = outline.px_bounds();
+            ascent = ascent.max(-bb.min.y);
+            descent = descent.max(bb.max.y);
+        }
+        x += scaled.h_advance(id);
+    }
+    (ascent, descent)
+}
+
+/// 字体的 ascent (像素)：draw_text_mut 的 y 是文字框顶部，基线在 y + ascent
+pub fn font_ascent<F: Font>(font: &F, scale: PxScale) -> f32 {
+    font.as_scaled(scale).ascent()
+}
+
+// =========================================================
+// 🟢 字间距 (tracking)
+// =========================================================
+// "P H O T O G R A P H" 这类宽字距标题：一次排版，字符按 ab_glyph 的字宽 (含同字体相邻字符的字距调整)
+// 排列，每两个字符之间再加 tracking 像素。按 char 遍历，多字节字符 (中文) 按一个字符计；
+// 主字体缺字时该字符改用 CJK 回退字体，基线与主字体保持一致。
+
+// 单个字符排版结果：使用的字体 (true = 回退字体)、字形 ID、起点 x
+struct TrackedGlyph {
+    fallback: bool,
+    id: GlyphId,
+    x: f32,
+}
+
+/// 排好版的一行带字间距文字：先算宽度做对齐，再用同一份结果绘制，不必排两遍
+pub struct TrackedLine {
+    glyphs: Vec<TrackedGlyph>,
+    scale: PxScale,
+    /// 加字间距后的整行宽度 (像素)
+    pub width: f32,
+}
+
+impl TrackedLine {
+    pub fn layout<F: Font>(font: &F, text: &str, scale: PxScale, tracking: f32) -> Self {
+        let primary = font.as_scaled(scale);
+        let fallback = if text.chars().any(|c| font.glyph_id(c).0 == 0) { fallback_font() } else { None };
+
+        let mut glyphs: Vec<TrackedGlyph> = Vec::with_capacity(text.len());
+        let mut x = 0.0f32;
+        for c in text.chars() {
+            let use_fallback = fallback.is_some() && font.glyph_id(c).0 == 0;
+            let (id, advance) = match (use_fallback, fallback) {
+                (true, Some(fb)) => {
+                    let fb = fb.as_scaled(scale);
+                    let id = fb.glyph_id(c);
+                    (id, fb.h_advance(id))
+                }
+                _ => {
+                    let id = primary.glyph_id(c);
+                    if let Some(prev) = glyphs.last().filter(|g| !g.fallback) {
+                        x += primary.kern(prev.id, id);
+                    }
+                    (id, primary.h_advance(id))
+                }
+            };
+            if !glyphs.is_empty() {
+                x += tracking;
+            }
+            glyphs.push(TrackedGlyph { fallback: use_fallback, id, x });
+            x += advance;
+        }
+        Self { glyphs, scale, width: x.max(0.0) }
+    }
+}
+
+// 🔴 已移除 tracked_text_width (改用 TrackedLine::layout(..).width，排版结果可以直接拿去绘制)
+
+/// 绘制排好版的一行 (x, y 为文字框左上角，与 draw_text_mut 一致)；font 必须是排版时用的主字体
+pub fn draw_tracked_text<C, F>(canvas: &mut C, color: C::Pixel, x: f32, y: i32, font: &F, line: &TrackedLine)
+where
+    C: Canvas,
+    <C::Pixel as Pixel>::Subpixel: Into<f32> + Clamp<f32>,
+    F: Font,
+{
+    let scale = line.scale;
+    let ascent = font.as_scaled(scale).ascent();
+    let (canvas_w, canvas_h) = (canvas.width() as i32, canvas.height() as i32);
+
+    for glyph in &line.glyphs {
+        let positioned = glyph.id.with_scale_and_position(scale, point(x + glyph.x, y as f32 + ascent));
+        let outline = match (glyph.fallback, fallback_font()) {
+            (true, Some(fb)) => fb.outline_glyph(positioned),
+            _ => font.outline_glyph(positioned),
+        };
+        let Some(outline) = outline else { continue };
+        let bb = outline.px_bounds();
+        outline.draw(|gx, gy, coverage| {
+            let px = gx as i32 + bb.min.x as i32;
+            let py = gy as i32 + bb.min.y as i32;
+            if (0..canvas_w).contains(&px) && (0..canvas_h).contains(&py) {
+                let coverage = coverage.clamp(0.0, 1.0);
+                let pixel = canvas.get_pixel(px as u32, py as u32);
+                canvas.draw_pixel(px as u32, py as u32, weighted_sum(pixel, color, 1.0 - coverage, coverage));
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cjk_text_measures_with_fallback_font() {
+        let font = resources::get_font(FontFamily::InterDisplay, FontWeight::Medium).unwrap();
+        let scale = PxScale::from(48.0);
+        assert!(fallback_runs(&font, "Shot by 山田太郎").is_some());
+        let fallback = fallback_font().expect("回退字体必须随资源一起提供");
+        // 假名和中日共用的汉字都由回退字体提供
+        assert!("山田ひらがなカタカナ写真".chars().all(|c| fallback.glyph_id(c).0 != 0));
+
+        let (w, h) = text_size_with_fallback(scale, &font, "山田");
+        assert!(w > 0 && h > 0, "{}x{}", w, h);
+        // 两个汉字的墨迹宽度超过一个全角字
+        assert!(w > 48, "{}", w);
+    }
+
+    // 前端中文边框文字预设用到的字 (光圈 / 快门 / 大师系列 ...)，主字体缺的都要能在回退字体里找到
+    // 补/偿/师/间/摄 是简体专用字，只有日文字形的回退字体会把它们画成方块
+    #[test]
+    fn chinese_frame_labels_render() {
+        let font = resources::get_font(FontFamily::InterDisplay, FontWeight::Medium).unwrap();
+        let fallback = fallback_font().expect("回退字体必须随资源一起提供");
+        let text = "光圈快门焦距曝光补偿大师系列决定性瞬间摄影作品摄影：";
+        for c in text.chars() {
+            assert!(font.glyph_id(c).0 != 0 || fallback.glyph_id(c).0 != 0, "缺字: {}", c);
+        }
+        // "大师 系列" 里的空格跟随前一段由回退字体绘制
+        assert!(fallback.glyph_id(' ').0 != 0);
+
+        // 真的画出了墨迹 (不是空白或 notdef 方块的固定宽度)
+        let scale = PxScale::from(32.0);
+        let (w, h) = text_size_with_fallback(scale, &font, "光圈");
+        let mut canvas = image::RgbaImage::from_pixel(w + 8, h + 16, image::Rgba([255, 255, 255, 255]));
+        draw_text_with_fallback(&mut canvas, image::Rgba([0, 0, 0, 255]), 4, 4, scale, &font, "光圈");
+        let inked = canvas.pixels().filter(|p| p[0] < 128).count();
+        assert!(inked > 50, "{}", inked);
+    }
+
+    // 前端的边框文字预设 (src/src/utils/frameLabelPresets.json) 每一条都要能画出来：
+    // 大师系列 / WhiteModern 用到的每种字体里，逐字要么主字体有、要么回退字体有，不能落到 notdef 方块
+    #[test]
+    fn frame_label_presets_render_without_tofu() {
+        use std::collections::HashMap;
+        use crate::models::FrameLabels;
+
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../src/src/utils/frameLabelPresets.json");
+        let presets: HashMap<String, FrameLabels> = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert!(presets.contains_key("zh"), "{:?}", presets.keys());
+
+        let fallback = fallback_font().expect("回退字体必须随资源一起提供");
+        let fonts = [
+            (FontFamily::InterDisplay, FontWeight::Medium),
+            (FontFamily::InterDisplay, FontWeight::SemiBold),
+            (FontFamily::AbhayaLibre, FontWeight::Medium),
+            (FontFamily::MrDafoe, FontWeight::Regular),
+            (FontFamily::Birthstone, FontWeight::Regular),
+        ];
+        let scale = PxScale::from(32.0);
+        for (name, labels) in &presets {
+            let serde_json::Value::Object(fields) = serde_json::to_value(labels).unwrap() else { unreachable!() };
+            for (field, text) in fields {
+                let text = text.as_str().unwrap().replace("{artist}", "张三");
+                for (family, weight) in fonts {
+                    let font = resources::get_font(family, weight).unwrap();
+                    for c in text.chars() {
+                        assert!(font.glyph_id(c).0 != 0 || fallback.glyph_id(c).0 != 0, "{}.{} 缺字: {} ({:?})", name, field, c, family);
+                    }
+
+                    let (w, h) = text_size_with_fallback(scale, &font, &text);
+                    let mut canvas = image::RgbaImage::from_pixel(w + 8, h + 16, image::Rgba([255, 255, 255, 255]));
+                    draw_text_with_fallback(&mut canvas, image::Rgba([0, 0, 0, 255]), 4, 4, scale, &font, &text);
+                    assert!(canvas.pixels().any(|p| p[0] < 128), "{}.{} 没有画出墨迹 ({:?})", name, field, family);
+                }
+            }
+        }
+    }
+
+    // 画布上有墨迹的行 (首行, 末行)
+    fn inked_rows(canvas: &image::RgbaImage) -> (u32, u32) {
+        let rows: Vec<u32> = canvas.enumerate_rows()
+            .filter(|(_, row)| row.clone().any(|(_, _, p)| p[0] < 160))
+            .map(|(y, _)| y)
+            .collect();
+        (*rows.first().expect("没有墨迹"), *rows.last().unwrap())
+    }
+
+    #[test]
+    fn ink_bounds_include_descenders() {
+        let font = resources::get_font(FontFamily::InterDisplay, FontWeight::Medium).unwrap();
+        let scale = PxScale::from(100.0);
+
+        // "g" 伸到基线以下，数字不会
+        let (_, g_ascent, g_descent) = measure_text_bounds(&font, "g", scale);
+        assert!(g_descent > 15.0, "{}", g_descent);
+        let (w, ascent, descent) = measure_text_bounds(&font, "100", scale);
+        assert!(descent < 1.5, "{}", descent);
+        assert!(ascent > g_ascent, "{} vs {}", ascent, g_ascent);
+        // 宽度沿用 text_size 的字宽累加
+        assert_eq!(w, imageproc::drawing::text_size(scale, &font, "100").0);
+        // "1/250" 的斜杠在基线以下，基线以上与 "100" 同高
+        let (_, slash_ascent, slash_descent) = measure_text_bounds(&font, "1/250", scale);
+        assert!(slash_descent > 1.0, "{}", slash_descent);
+        assert!((slash_ascent - ascent).abs() <= 1.0, "{} vs {}", slash_ascent, ascent);
+
+        // 与实际绘制的墨迹一致：文字框顶部 + ascent 为基线，"g" 的下沿在基线下 descent 处
+        let top = 20;
+        let mut canvas = image::RgbaImage::from_pixel(200, 200, image::Rgba([255, 255, 255, 255]));
+        draw_text_mut(&mut canvas, image::Rgba([0, 0, 0, 255]), 10, top, scale, &font, "g");
+        let (first, last) = inked_rows(&canvas);
+        let baseline = top as f32 + font_ascent(&font, scale);
+        assert!((first as f32 - (baseline - g_ascent)).abs() <= 1.0, "{} vs {}", first, baseline - g_ascent);
+        assert!((last as f32 + 1.0 - (baseline + g_descent)).abs() <= 1.0, "{} vs {}", last, baseline + g_descent);
+    }
+}
@@ -1,6 +1,7 @@
+use std::collections::HashMap;
 use std::path::{ PathBuf};
-use serde::Deserialize;
-use crate::utils::calculate_target_path_core;
+use serde::{Deserialize, Serialize};
+use crate::utils::{calculate_target_path_core, NamingContext};
 
 // 字体配置（公用）
 #[derive(Debug, Clone, Deserialize)]
@@ -11,33 +12,206 @@ pub struct FontConfig {
     pub weight: String,
 }
 
+// 🟢 布局参数覆盖：键为处理器配置里的字段名 (如 "border_ratio")，值覆盖默认值
+// 各样式支持的键见对应处理器的配置结构体注释，未知键会被忽略并记录警告
+pub type LayoutOverrides = HashMap<String, f32>;
+
+// 🟢 白底系列的配色主题：Dark 为近黑底 + 浅色文字 (类似画廊卡纸)，版式不变
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FrameTheme {
+    #[default]
+    Light,
+    Dark,
+}
+
+// 🟢 拍立得日期的字体：Script 为手写体 (MrDafoe)，Regular 与参数行字体相同
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DateFont {
+    #[default]
+    Script,
+    Regular,
+}
+
+// 🟢 拍立得底部标题：LogoOnly 只画品牌字标 (没有字标素材的品牌退回 "品牌 机型" 文字)，
+// TextOnly 总是画 "品牌 机型" 文字，LogoAndModel 在字标下方再加一行机型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PolaroidCaption {
+    #[default]
+    LogoOnly,
+    TextOnly,
+    LogoAndModel,
+}
+
+// 🟢 参数行分隔符 (WhiteClassic / AccentBar / WhitePolaroid)
+// Space 为双空格，与旧版本输出一致；Pipe 与 Classic 底栏的分隔线呼应，Dot 适合拍立得居中的单行
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ParamSeparator {
+    #[default]
+    Space,
+    Pipe,
+    Dot,
+}
+
+impl ParamSeparator {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Space => "  ",
+            Self::Pipe => "  |  ",
+            Self::Dot => " · ",
+        }
+    }
+}
+
 // 🟢 核心改变：使用 Enum 定义样式配置
 // Serde 的 tag = "style" 会自动根据 JSON 里的 "style" 字段决定解析成哪个变体
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "style", rename_all = "PascalCase")] 
 pub enum StyleOptions {
     // 变体 1：白底模式 (只关心字体)
     #[serde(rename_all = "camelCase")] // 🟢 必须加在这里！
-    WhiteClassic,
+    WhiteClassic {
+        // 🟢 品牌徽标 (目前只有尼康小黄块)：开启后底栏左侧绘制徽标，机型文字随之右移
+        // 缺少对应素材的品牌会自动忽略 (记录警告)
+        #[serde(default)]
+        brand_badge: bool,
+        // 🟢 署名：EXIF 里有作者/版权信息时绘制 "© {artist}"，没有则布局保持不变
+        #[serde(default)]
+        show_artist: bool,
+        #[serde(default)]
+        theme: FrameTheme,
+        #[serde(default)]
+        params_separator: ParamSeparator,
+        #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+        layout_overrides: LayoutOverrides,
+    },
 
+    // 🟢 photo_offset：照片在留白内的位移 (x, y)，取值 -1..1，x 正向右、y 正向上
+    // 不会移出画布，也不会压住底部文字区。None 代表默认居中位置
     #[serde(rename_all = "camelCase")] // 🟢 必须加在这里！
-    WhitePolaroid,
+    WhitePolaroid {
+        #[serde(default)]
+        photo_offset: Option<(f32, f32)>,
+        #[serde(default)]
+        theme: FrameTheme,
+        // 🟢 拍摄日期：底部留白右下角写上 "2024.03.15"，没有拍摄时间的照片不画
+        #[serde(default)]
+        show_date: bool,
+        #[serde(default)]
+        date_font: DateFont,
+        #[serde(default)]
+        caption_mode: PolaroidCaption,
+        #[serde(default)]
+        params_separator: ParamSeparator,
+        #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+        layout_overrides: LayoutOverrides,
+    },
 
     // 大师白底 (WhiteMaster)
     #[serde(rename_all = "camelCase")]
-    WhiteMaster,
+    WhiteMaster {
+        #[serde(default)]
+        photo_offset: Option<(f32, f32)>,
+        #[serde(default)]
+        theme: FrameTheme,
+        // 🟢 曝光补偿：参数区追加 "EV" 一列 (没有补偿的照片不显示)，关闭时四列布局不变
+        #[serde(default)]
+        show_ev: bool,
+        // 🟢 署名：PHOTOGRAPH 下方追加一行 "Shot by {artist}"，EXIF 没有作者/版权信息时不画
+        #[serde(default)]
+        show_artist: bool,
+        #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+        layout_overrides: LayoutOverrides,
+    },
 
     // 变体 2：高斯模糊 (关心字体 + 阴影)
     #[serde(rename_all = "camelCase")] // 🟢 必须加在这里！
-    TransparentClassic,
+    TransparentClassic {
+        // 🟢 镜头型号：参数行下方追加一行 (如 "NIKKOR Z 24-70mm f/2.8 S")，EXIF 没有镜头信息时不占位
+        #[serde(default)]
+        show_lens: bool,
+        // 🟢 阴影强度 0.0-1.0 (0 = 不画阴影)，None 代表默认阴影
+        #[serde(default)]
+        shadow_intensity: Option<f32>,
+        // 🟢 玻璃描边厚度 (px，0 = 不画描边)，None 代表按原图尺寸自适应 (3-8px)
+        #[serde(default)]
+        glass_border_thickness: Option<u32>,
+        // 🟢 玻璃描边颜色 [R, G, B, A]，None 代表默认的半透明白 [255, 255, 255, 130]
+        #[serde(default)]
+        glass_border_color: Option<[u8; 4]>,
+        // 🟢 背景亮度偏移 (-255 ~ 255)，None 代表不调整；负值压暗背景，让白字更清楚
+        #[serde(default)]
+        bg_brightness: Option<i32>,
+        // 🟢 背景饱和度 (1.0 = 不变，0.0 = 灰度)，None 代表不调整
+        #[serde(default)]
+        bg_saturation: Option<f32>,
+        // 🟢 自动背景亮度：按原图平均亮度选择偏移 (开启时忽略 bg_brightness)
+        #[serde(default)]
+        auto_bg_brightness: bool,
+        #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+        layout_overrides: LayoutOverrides,
+    },
 
     // 🟢 [新增] 大师模式
     // 参数几乎和 GaussianBlur 一样，因为它们都是模糊背景
     #[serde(rename_all = "camelCase")]
-    TransparentMaster,
+    TransparentMaster {
+        // 🟢 署名：同 WhiteMaster
+        #[serde(default)]
+        show_artist: bool,
+        // 🟢 背景亮度 / 饱和度 / 自动亮度：同 TransparentClassic
+        #[serde(default)]
+        bg_brightness: Option<i32>,
+        #[serde(default)]
+        bg_saturation: Option<f32>,
+        #[serde(default)]
+        auto_bg_brightness: bool,
+        #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+        layout_overrides: LayoutOverrides,
+    },
 
     #[serde(rename_all = "camelCase")]
-    WhiteModern, // 🟢 新增
+    WhiteModern {
+        #[serde(default)]
+        photo_offset: Option<(f32, f32)>,
+        #[serde(default)]
+        theme: FrameTheme,
+        // 🟢 阴影强度 0.0-1.0 (0 = 不画阴影)，None 代表默认阴影
+        #[serde(default)]
+        shadow_intensity: Option<f32>,
+        // 🟢 曝光补偿：追加 "EV" 胶囊 (没有补偿的照片不显示)，关闭时四个胶囊布局不变
+        #[serde(default)]
+        show_ev: bool,
+        // 🟢 品牌色胶囊：描边和参数标签改用品牌强调色 (尼康黄、索尼橙等)，自动压暗保证对比度
+        #[serde(default)]
+        accent_badges: bool,
+        #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+        layout_overrides: LayoutOverrides,
+    }, // 🟢 新增
+
+    // 🟢 旅行白底：经典底栏，右侧显示拍摄地坐标和日期 (没有 GPS 时只显示日期)
+    #[serde(rename_all = "camelCase")]
+    TravelWhite {
+        #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+        layout_overrides: LayoutOverrides,
+    },
+
+    // 🟢 主色底栏：经典白底的布局，底栏填充照片主色 (低饱和)，文字按底色亮度自动选黑 / 白
+    // layout_overrides 的键与 WhiteClassic 相同
+    #[serde(rename_all = "camelCase")]
+    AccentBar {
+        #[serde(default)]
+        brand_badge: bool,
+        #[serde(default)]
+        show_artist: bool,
+        #[serde(default)]
+        params_separator: ParamSeparator,
+        #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+        layout_overrides: LayoutOverrides,
+    },
     // ===================================
     // 2. 🟢 带参数模式 (Struct Variants)
     // ===================================
@@ -54,20 +228,40 @@ pub enum StyleOptions {
 
 // 🟢 新增：为枚举实现方法
 impl StyleOptions {
+    // 输出文件名后缀 (xxx_WhiteClassic.jpg)，同时也是 list_styles 返回给前端的样式 id
+    // 已有成品的"已处理"检测、skip_existing、断点续传都靠它匹配旧文件，已发布的值不能再改 (包括大小写)
+    // 新增样式的后缀只用 ASCII 字母数字，且不能与已有后缀重复
     pub fn filename_suffix(&self) -> &'static str {
         match self {
-            Self::WhiteClassic => "WhiteClassic",      // 对应生成 xxx_White.jpg
-            Self::TransparentClassic => "TransparentClassic", // 对应生成 xxx_Blur.jpg
-            Self::TransparentMaster => "TransparentMaster",// 对应生成 xxx_Master.jpg
-            Self::WhitePolaroid => "WhitePolaroid",
-            Self::WhiteMaster => "WhiteMaster",
-            Self::WhiteModern => "WhiteModern",
+            Self::WhiteClassic { .. } => "WhiteClassic",      // 对应生成 xxx_WhiteClassic.jpg
+            Self::TransparentClassic { .. } => "TransparentClassic", // 对应生成 xxx_TransparentClassic.jpg
+            Self::TransparentMaster { .. } => "TransparentMaster",// 对应生成 xxx_TransparentMaster.jpg
+            Self::WhitePolaroid { .. } => "WhitePolaroid",
+            Self::WhiteMaster { .. } => "WhiteMaster",
+            Self::WhiteModern { .. } => "WhiteModern",
+            Self::TravelWhite { .. } => "TravelWhite",
+            Self::AccentBar { .. } => "AccentBar",
             // 🟢 签名模式的后缀
             Self::Signature { .. } => "Signature",
             // 以后新增样式，只需要在这里加一行
         }
     }
 
+    // 🟢 布局覆盖 (签名模式没有)
+    pub fn layout_overrides(&self) -> Option<&LayoutOverrides> {
+        match self {
+            Self::WhiteClassic { layout_overrides, .. }
+            | Self::WhitePolaroid { layout_overrides, .. }
+            | Self::WhiteMaster { layout_overrides, .. }
+            | Self::TransparentClassic { layout_overrides, .. }
+            | Self::TransparentMaster { layout_overrides, .. }
+            | Self::WhiteModern { layout_overrides, .. }
+            | Self::TravelWhite { layout_overrides, .. }
+            | Self::AccentBar { layout_overrides, .. } => Some(layout_overrides),
+            Self::Signature { .. } => None,
+        }
+    }
+
     // 🟢 新增：判断该模式是否“可编辑/参数敏感”
     // 如果是可编辑模式，就不应该进行“跳过重复文件”的检查，
     // 因为用户可能改了签名内容，即使文件名没变，也需要重新生成。
@@ -77,10 +271,92 @@ impl StyleOptions {
             _ => false,                     // 其他模式是静态的
         }
     }
+
+    // 🟢 新增：每个样式推荐的导出参数
+    // 模糊背景有大面积细腻渐变，低质量 JPG 会出现明显色带；纯白底压缩效果很好，质量下限可以低一些，
+    // 但白色底栏的小字在 4:2:0 下会发糊 (红色品牌字尤其明显)，所以同样要求 4:4:4。
+    // 这里故意不写 `_ =>` 兜底：新增样式时编译器会强制作者考虑它的导出参数。
+    pub fn recommended_export(&self) -> ExportHints {
+        match self {
+            Self::WhiteClassic { .. } | Self::WhitePolaroid { .. } | Self::WhiteMaster { .. } | Self::WhiteModern { .. } | Self::TravelWhite { .. } | Self::AccentBar { .. } => ExportHints {
+                min_quality: 85,
+                prefer_subsampling: ChromaSubsampling::Yuv444,
+                prefer_format: ExportImageFormat::Jpg,
+            },
+            Self::TransparentClassic { .. } | Self::TransparentMaster { .. } => ExportHints {
+                min_quality: 92,
+                prefer_subsampling: ChromaSubsampling::Yuv444,
+                prefer_format: ExportImageFormat::Jpg,
+            },
+            Self::Signature { .. } => ExportHints {
+                min_quality: 90,
+                prefer_subsampling: ChromaSubsampling::Yuv444,
+                prefer_format: ExportImageFormat::Jpg,
+            },
+        }
+    }
+
+    // 所有样式的代表实例 (带参数的样式使用默认参数)，用于向前端列出样式信息
+    pub fn catalog() -> Vec<StyleOptions> {
+        vec![
+            Self::WhiteClassic { brand_badge: false, show_artist: false, theme: FrameTheme::Light, params_separator: ParamSeparator::Space, layout_overrides: LayoutOverrides::new() },
+            Self::WhitePolaroid { photo_offset: None, theme: FrameTheme::Light, show_date: false, date_font: DateFont::Script, caption_mode: PolaroidCaption::LogoOnly, params_separator: ParamSeparator::Space, layout_overrides: LayoutOverrides::new() },
+            Self::WhiteMaster { photo_offset: None, theme: FrameTheme::Light, show_ev: false, show_artist: false, layout_overrides: LayoutOverrides::new() },
+            Self::WhiteModern { photo_offset: None, theme: FrameTheme::Light, shadow_intensity: None, show_ev: false, accent_badges: false, layout_overrides: LayoutOverrides::new() },
+            Self::TravelWhite { layout_overrides: LayoutOverrides::new() },
+            Self::AccentBar { brand_badge: false, show_artist: false, params_separator: ParamSeparator::Space, layout_overrides: LayoutOverrides::new() },
+            Self::TransparentClassic { show_lens: false, shadow_intensity: None, glass_border_thickness: None, glass_border_color: None, bg_brightness: None, bg_saturation: None, auto_bg_brightness: false, layout_overrides: LayoutOverrides::new() },
+            Self::TransparentMaster { show_artist: false, bg_brightness: None, bg_saturation: None, auto_bg_brightness: false, layout_overrides: LayoutOverrides::new() },
+            Self::Signature { text: String::new(), font_scale: 0.05, bottom_ratio: 0.1 },
+        ]
+    }
+}
+
+// 🟢 样式推荐的导出参数 (下限，不会降低用户的选择)
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportHints {
+    pub min_quality: u8,
+    pub prefer_subsampling: ChromaSubsampling,
+    pub prefer_format: ExportImageFormat,
+}
+
+// 色度抽样方式 (JPG)
+// 默认 4:4:4：image 自带的 JPG 编码器所有分量都是 1x1 采样，即旧版本的输出
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChromaSubsampling {
+    #[default]
+    #[serde(alias = "444")]
+    Yuv444,
+    #[serde(alias = "422")]
+    Yuv422,
+    #[serde(alias = "420")]
+    Yuv420,
+}
+
+impl ChromaSubsampling {
+    // 色度分辨率从高到低：4:4:4 > 4:2:2 > 4:2:0
+    fn detail_rank(self) -> u8 {
+        match self {
+            Self::Yuv444 => 2,
+            Self::Yuv422 => 1,
+            Self::Yuv420 => 0,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Yuv444 => "4:4:4",
+            Self::Yuv422 => "4:2:2",
+            Self::Yuv420 => "4:2:0",
+        }
+    }
 }
 
+
 // 总配置
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")] // 🟢 必须加在这里！
 pub struct BatchContext {
     // 🟢 这里不再是 String，而是上面定义的枚举
@@ -91,44 +367,358 @@ pub struct BatchContext {
     // 🟢 [新增] 导出配置
     // 对应前端 JSON: { "options": { ... }, "export": { ... } }
     // 注意：前端传参时，建议把 exportSettings 改名为 export 传过来，或者这里用 #[serde(rename="exportSettings")]
-    #[serde(rename="exportSettings")]
+    // 🟢 旧版前端不传导出配置：按默认值 (原图同级、JPG、质量 95) 处理
+    #[serde(rename="exportSettings", default)]
     pub export: ExportConfig,
+
+    // 🟢 [新增] 确定性渲染：同一输入多次运行输出逐字节一致 (用于版本化归档/黄金图比对)
+    // 影响范围：
+    // 1. 随机效果 (颗粒、抖动) 的种子取 graphics::seed_for(文件路径)，关闭时可以每次随机
+    // 2. 结果依赖求和顺序的归约必须串行：目前只有 mean_luma (自动背景亮度) 和 dominant_color (主色)，
+    //    两者本来就是单线程累加，不受并发影响；模糊 / 暗角 / 毛玻璃 / 扩展画布的 par_chunks 逐行写入互不重叠，
+    //    结果与线程调度无关，所以批次照常并发
+    // 3. 以后引入带平台相关 SIMD 路径的缩放滤镜 (fast_image_resize) 时，开启后要固定到标量实现
+    #[serde(default)]
+    pub deterministic: bool,
+
+    // 🟢 [新增] 自动导出调优：按样式推荐值抬高导出参数 (只升不降)
+    #[serde(default)]
+    pub auto_export_tuning: bool,
+
+    // 🟢 [新增] 最大并发处理数 (同时在内存中的图片数量)
+    // None 代表自动：min(CPU 核心数, 内存预算 / 单张估算)，避免大批量高像素原图把内存吃光
+    #[serde(default)]
+    pub max_concurrent: Option<usize>,
+
+    // 🟢 [新增] 单文件处理超时 (秒)，超时的文件记为失败，批次继续；0 代表不限制
+    // 损坏的超大 TIFF 可能让解码器空转几分钟，不能让一个文件拖住整个批次
+    #[serde(default = "default_file_timeout_secs")]
+    pub file_timeout_secs: u64,
+
+    // 🟢 [新增] 源文件大小上限 (MB)，超过的文件直接跳过不解码；None 代表不限制
+    #[serde(default)]
+    pub max_file_size_mb: Option<u64>,
+
+    // 🟢 [新增] 叠加签名：边框绘制完成后再在同一张图上写一行签名 (None 代表不叠加)
+    #[serde(default)]
+    pub signature: Option<SignatureConfig>,
+
+    // 🟢 [新增] 进度事件附带 ~300px 宽的成品缩略图 (JPG base64)，前端显示实时胶片条
+    // 每张多十几 KB 的事件负载，超大批次可以关闭
+    #[serde(default = "default_true")]
+    pub emit_thumbnails: bool,
+
+    // 🟢 [新增] 手动填写的边框文字，在解析 EXIF 之后、处理器绘制之前覆盖 (所有样式行为一致)
+    #[serde(default)]
+    pub overrides: Option<TextOverrides>,
+
+    // 🟢 [新增] 胶片扫描模式：没有 EXIF 的文件也照常加边框 (品牌 Other、机型和参数为空，通常配合 overrides 手动填写)
+    #[serde(default)]
+    pub allow_missing_exif: bool,
+
+    // 🟢 [新增] 允许处理带成品标记的文件 (默认跳过，防止输出目录就是原图目录时重跑给成品再加一圈边框)
+    #[serde(default)]
+    pub reframe_outputs: bool,
+
+    // 🟢 [新增] 解码缓存张数 (预览过的照片再预览 / 批处理时复用解码结果)；None 代表默认 3 张，0 关闭
+    // 另有内存上限 (物理内存的 1/8)，超大原图可能缓存不了这么多张
+    #[serde(default)]
+    pub decode_cache_size: Option<usize>,
+
+    // 🟢 [新增] 边框上的固定文字 (参数标签、大师系列标题)，None 代表默认英文
+    // 目前作用于 WhiteMaster / TransparentMaster / WhiteModern
+    #[serde(default)]
+    pub labels: Option<FrameLabels>,
+
+    // 🟢 [新增] 有序进度：完成事件按提交顺序发出 (前端列表从上到下依次完成)
+    // 实际处理仍然并行，先完成的文件会等前面的文件完成后才上报；默认关闭 (按完成顺序即时上报)
+    #[serde(default)]
+    pub ordered_progress: bool,
+}
+
+// 边框文字覆盖：EXIF 不对或没有时手动填写 (转接手动镜头没有光圈、扫描底片没有 EXIF)
+// None 沿用 EXIF；空字符串清空该项，对应的参数 / 徽章不再绘制
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextOverrides {
+    pub model: Option<String>,
+    pub aperture: Option<String>, // "2.8" / "f/2.8"
+    pub shutter: Option<String>,  // "1/125" / "2s"
+    pub iso: Option<String>,      // "400" / "ISO 400"
+    pub focal: Option<String>,    // "50" / "50mm"
+    pub lens: Option<String>,
+    pub artist: Option<String>,
+}
+
+// 🟢 边框固定文字表：默认值就是原来写死的英文，可整体或逐项替换 (如 "光圈" / "快门")
+// 缺省的字段沿用默认值；主字体缺字的部分 (中日文) 自动改用 CJK 回退字体绘制
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct FrameLabels {
+    pub iso: String,          // "ISO"
+    pub aperture: String,     // "F"
+    pub focal: String,        // "mm"
+    pub shutter: String,      // "S"
+    pub ev: String,           // "EV"
+    pub series_title: String, // "MASTER SERIES" (大师系列第一行)
+    pub tagline: String,      // "The decisive moment" (大师系列手写体)
+    pub photograph: String,   // "PHOTOGRAPH" (大师系列宽字距行)
+    pub shot_by: String,      // 🟢 "Shot by {artist}" (大师系列署名行，{artist} 替换为作者名)
+}
+
+impl Default for FrameLabels {
+    fn default() -> Self {
+        Self {
+            iso: "ISO".to_string(),
+            aperture: "F".to_string(),
+            focal: "mm".to_string(),
+            shutter: "S".to_string(),
+            ev: "EV".to_string(),
+            series_title: "MASTER SERIES".to_string(),
+            tagline: "The decisive moment".to_string(),
+            photograph: "PHOTOGRAPH".to_string(),
+            shot_by: "Shot by {artist}".to_string(),
+        }
+    }
+}
+
+impl FrameLabels {
+    /// 🟢 大师系列署名行：把 {artist} 换成作者名；名字为空白时返回 None (不画这一行)
+    pub fn shot_by_line(&self, artist: &str) -> Option<String> {
+        let artist = artist.trim();
+        (!artist.is_empty()).then(|| self.shot_by.replace("{artist}", artist))
+    }
+}
+
+// 叠加签名配置 (与 Signature 样式的参数一致)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignatureConfig {
+    pub text: String,
+    pub font_scale: f32,
+    pub bottom_ratio: f32,
+}
+
+pub fn default_file_timeout_secs() -> u64 {
+    120
 }
 
 // 🟢 3. 统一路径计算逻辑 (Single Source of Truth)
 impl BatchContext {
-    pub fn calculate_target_path(&self, original_file_path: &str) -> Result<PathBuf, String> {
+    pub fn calculate_target_path(&self, original_file_path: &str, naming: NamingContext) -> Result<PathBuf, String> {
         // 🟢 直接调用核心函数，传入自己的字段
         calculate_target_path_core(
             original_file_path, 
             &self.export, 
-            &self.options
+            &self.options,
+            naming,
         )
     }
 }
 
 
 // 🟢 [新增] 导出配置结构体
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ExportConfig {
     // 目标文件夹：Some(路径) 代表自定义，None 代表原图同级
     pub target_dir: Option<String>, 
-    // 格式：jpg, png
+    // 格式：jpg, png, webp, tiff, avif
     pub format: ExportImageFormat, 
-    // 质量：1-100 (仅 JPG 有效)
+    // 质量：1-100 (JPG / AVIF / WebP 有效；WebP 100 为无损，低于 100 需要 libwebp feature，否则回退为无损)
     pub quality: u8,
+    // 🟢 [新增] AVIF 编码速度 1-10 (越小越慢、体积越小)，默认 6；批量处理时不宜太低
+    #[serde(default = "default_avif_speed")]
+    pub avif_speed: u8,
+    // 🟢 [新增] 色度抽样 (仅 JPG 有效)，默认 4:4:4 与旧版本一致
+    // 4:2:0 / 4:2:2 需要以 mozjpeg feature 编译，否则回退到 4:4:4
+    #[serde(default)]
+    pub subsampling: ChromaSubsampling,
+    // 🟢 [新增] 渐进式 JPG (慢速网络上先显示整体再变清晰) / 优化霍夫曼表 (体积小 5-10%，画质不变)
+    // 同样需要 mozjpeg feature，否则回退为基线编码
+    #[serde(default)]
+    pub progressive: bool,
+    #[serde(default)]
+    pub optimize_coding: bool,
+    // 🟢 [新增] PNG 压缩级别 (仅 PNG 有效)，默认 fast
+    #[serde(default)]
+    pub png_compression: PngCompression,
+    // 是否在输出旁写入 {output_stem}.framer.json (记录完整处理参数，便于日后复现)
+    #[serde(default)]
+    pub write_sidecar: bool,
+    // 🟢 [新增] 批次结束后在输出目录写入 framer_report_{时间戳}.json (每个文件的结果/错误/耗时)
+    #[serde(default)]
+    pub write_report: bool,
+    // 报告同时输出一份 CSV (需要 write_report)
+    #[serde(default)]
+    pub report_csv: bool,
+    // 🟢 [新增] 同时导出一份未处理的原图 (None 代表不复制)
+    #[serde(default)]
+    pub copy_original: Option<OriginalCopy>,
+    // 🟢 [新增] 保留原图 EXIF (目前仅 JPG)，默认开启；需要干净文件的用户可以关闭
+    #[serde(default = "default_true")]
+    pub preserve_exif: bool,
+    // 🟢 [新增] 文件名模板，如 "{stem}_{style}_{date}_{model}"；None 代表默认的 "{stem}_{style}"
+    // 占位符: {stem} {style} {date} {model} {iso} {counter}
+    #[serde(default)]
+    pub name_template: Option<String>,
+    // 🟢 [新增] 跳过成品已存在的文件 (重跑批次时只处理新增的照片)
+    #[serde(default)]
+    pub skip_existing: bool,
+    // 🟢 [新增] 输出长边上限 (像素)，超出时整体等比缩小 (含边框)；None 代表保持原尺寸
+    #[serde(default)]
+    pub max_long_edge: Option<u32>,
+    // 🟢 [新增] 成品补边到固定宽高比 (宽, 高)，如 (4, 5)、(1, 1)、(9, 16)；按样式背景对称延伸，只补不裁
+    // None 代表保持样式本身的比例
+    #[serde(default)]
+    pub target_aspect: Option<(u32, u32)>,
+    // 🟢 [新增] 色彩管理：带 ICC 的非 sRGB 原图 (Adobe RGB / Display P3) 先转换为 sRGB 再处理，输出不带配置文件
+    // 关闭时像素原样保留，并把原配置文件嵌入输出 (JPG / PNG)，给自己做色彩管理的用户
+    #[serde(default = "default_true")]
+    pub color_management: bool,
+    // 🟢 [新增] 额外导出的尺寸版本 (如存档用原尺寸 + 分享用 2048px)，共用同一次解码和绘制；空代表只输出主文件
+    #[serde(default)]
+    pub variants: Vec<ExportVariant>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_avif_speed() -> u8 {
+    6
+}
+
+// 🟢 默认导出：原图同级、JPG、质量 95 (与旧版本的固定行为一致)，其余选项与 serde 缺省值一致
+impl Default for ExportConfig {
+    fn default() -> Self {
+        Self {
+            target_dir: None,
+            format: ExportImageFormat::default(),
+            quality: 95,
+            avif_speed: default_avif_speed(),
+            subsampling: ChromaSubsampling::default(),
+            progressive: false,
+            optimize_coding: false,
+            png_compression: PngCompression::default(),
+            write_sidecar: false,
+            write_report: false,
+            report_csv: false,
+            copy_original: None,
+            preserve_exif: true,
+            name_template: None,
+            skip_existing: false,
+            max_long_edge: None,
+            target_aspect: None,
+            color_management: true,
+            variants: Vec::new(),
+        }
+    }
+}
+
+// 原图副本配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OriginalCopy {
+    // 放到目标目录下的子文件夹 (如 "originals")，None 代表与成品同目录
+    pub subfolder: Option<String>,
+    // 是否改名为与成品同名 (去掉样式后缀)，方便客户一一对应
+    #[serde(default)]
+    pub rename_to_match: bool,
+}
+
+// 🟢 尺寸版本：在主输出之外再写一份，文件名为 {主输出文件名}_{name}.{ext}
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportVariant {
+    // 版本名 (如 "web")，追加在文件名末尾
+    pub name: String,
+    // 长边上限 (像素)，None 代表与主输出同尺寸；只缩小不放大
+    #[serde(default)]
+    pub max_long_edge: Option<u32>,
+    pub format: ExportImageFormat,
+    // 质量：1-100 (JPG / AVIF / WebP 有效；WebP 100 为无损，低于 100 需要 libwebp feature，否则回退为无损)
+    pub quality: u8,
+}
+
+
+impl ExportConfig {
+    /// 文件名模板是否引用了 EXIF 字段 (需要先读 EXIF 才能算出输出路径)
+    pub fn template_needs_exif(&self) -> bool {
+        self.name_template.as_deref().is_some_and(|tpl| {
+            ["{date}", "{model}", "{iso}"].iter().any(|p| tpl.contains(p))
+        })
+    }
+
+    /// 主输出和所有尺寸版本用到的格式 (AVIF / JPG 编码选项的批次级检查要覆盖全部)
+    pub fn output_formats(&self) -> impl Iterator<Item = &ExportImageFormat> {
+        std::iter::once(&self.format).chain(self.variants.iter().map(|v| &v.format))
+    }
+
+    /// 主输出或尺寸版本里 quality 低于 100 的 WebP (返回其质量)
+    /// 这些输出需要 libwebp 有损编码，未启用时回退为无损并在批次总结里提示
+    pub fn lossy_webp_quality(&self) -> Option<u8> {
+        std::iter::once((self.format, self.quality))
+            .chain(self.variants.iter().map(|v| (v.format, v.quality)))
+            .find(|&(format, quality)| format == ExportImageFormat::Webp && quality < 100)
+            .map(|(_, quality)| quality)
+    }
+
+    /// 按样式推荐值调优，返回 (生效配置, 调整记录)
+    /// 只会抬高质量和色度抽样，不会降低用户的选择；
+    /// 格式不擅自更换 (会改变输出文件名)，与推荐不同时只在调整记录里提示
+    pub fn tuned_for(&self, style: &StyleOptions) -> (ExportConfig, Vec<String>) {
+        let hints = style.recommended_export();
+        let suffix = style.filename_suffix();
+        let mut effective = self.clone();
+        let mut adjustments = Vec::new();
+
+        if matches!(self.format, ExportImageFormat::Jpg) {
+            if self.quality < hints.min_quality {
+                effective.quality = hints.min_quality;
+                adjustments.push(format!("quality {} -> {} ({} 推荐下限)", self.quality, hints.min_quality, suffix));
+            }
+            if self.subsampling.detail_rank() < hints.prefer_subsampling.detail_rank() {
+                effective.subsampling = hints.prefer_subsampling;
+                adjustments.push(format!(
+                    "subsampling {} -> {} ({} 推荐)",
+                    self.subsampling.label(), hints.prefer_subsampling.label(), suffix
+                ));
+            }
+        }
+
+        if self.format != hints.prefer_format {
+            adjustments.push(format!(
+                "format {} 保持不变 ({} 推荐 {})",
+                self.format.extension(), suffix, hints.prefer_format.extension()
+            ));
+        }
+
+        (effective, adjustments)
+    }
 }
 
+// 🟢 PNG 压缩级别 (滤波的选择见 pipeline::png_settings)
+// fast 换最快的 Up 滤波，体积比 image 的默认设置大约两成；default / best 体积更小，但编码时间是 fast 的 20~60 倍
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PngCompression {
+    #[default]
+    Fast,
+    Default,
+    Best,
+}
 
 // 1. 定义支持的格式枚举
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")] 
 pub enum ExportImageFormat {
+    #[default]
     Jpg,
     Png,
-    // 未来想支持 WebP，只需在这里加一行：
-    // Webp, 
+    Webp,
+    Tiff,
+    Avif,
 }
 
 impl ExportImageFormat {
@@ -137,7 +727,9 @@ impl ExportImageFormat {
         match self {
             Self::Jpg => "jpg",
             Self::Png => "png",
-            // Self::Webp => "webp",
+            Self::Webp => "webp",
+            Self::Tiff => "tiff",
+            Self::Avif => "avif",
         }
     }
 
@@ -146,14 +738,181 @@ impl ExportImageFormat {
         match self {
             Self::Jpg => false, // JPG 不支持，需要转 RGB
             Self::Png => true,
+            Self::Webp => true,
+            Self::Tiff => true,
+            Self::Avif => true,
         }
     }
+
+    // 🟢 判断格式本身能否保存 16 位/通道 (WebP 编码器只接受 8 位，JPG 本身就是 8 位)
+    // 多数样式的边框合成只有 8 位，成品实际位深以处理器输出为准 (见 SaveImageStep 的降级提醒)
+    pub fn supports_16bit(&self) -> bool {
+        matches!(self, Self::Png | Self::Tiff)
+    }
     
     // 可以在这里封装 MIME type
     pub fn mime_type(&self) -> &'static str {
          match self {
             Self::Jpg => "image/jpeg",
             Self::Png => "image/png",
+            Self::Webp => "image/webp",
+            Self::Tiff => "image/tiff",
+            Self::Avif => "image/avif",
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn params_separator_defaults_to_previous_format() {
+        let st: StyleOptions = serde_json::from_str(r#"{"style":"WhitePolaroid"}"#).unwrap();
+        assert!(matches!(st, StyleOptions::WhitePolaroid { params_separator: ParamSeparator::Space, .. }));
+        assert_eq!(ParamSeparator::default().as_str(), "  ");
+
+        let st: StyleOptions = serde_json::from_str(r#"{"style":"WhiteClassic","paramsSeparator":"pipe"}"#).unwrap();
+        assert!(matches!(st, StyleOptions::WhiteClassic { params_separator: ParamSeparator::Pipe, .. }));
+        let st: StyleOptions = serde_json::from_str(r#"{"style":"AccentBar","paramsSeparator":"dot"}"#).unwrap();
+        assert!(matches!(st, StyleOptions::AccentBar { params_separator: ParamSeparator::Dot, .. }));
+    }
+
+    #[test]
+    fn auto_tuning_lifts_transparent_quality() {
+        let ctx: BatchContext = serde_json::from_value(serde_json::json!({
+            "style": "TransparentClassic",
+            "exportSettings": { "targetDir": null, "format": "jpg", "quality": 70 },
+            "autoExportTuning": true,
+        })).unwrap();
+        assert!(ctx.auto_export_tuning);
+
+        let (effective, adjustments) = ctx.export.tuned_for(&ctx.options);
+        assert!(effective.quality >= 90, "q{}", effective.quality);
+        assert_eq!(effective.quality, ctx.options.recommended_export().min_quality);
+        // 调整记录进批次总结
+        assert_eq!(adjustments.len(), 1);
+        assert!(adjustments[0].starts_with("quality 70 -> "), "{}", adjustments[0]);
+    }
+
+    #[test]
+    fn auto_tuning_lifts_subsampling_for_white_styles() {
+        // 白色底栏的小字在 4:2:0 下发糊：推荐 4:4:4
+        let style: StyleOptions = serde_json::from_str(r#"{"style":"WhiteClassic"}"#).unwrap();
+        assert_eq!(style.recommended_export().prefer_subsampling, ChromaSubsampling::Yuv444);
+
+        let export = ExportConfig { subsampling: ChromaSubsampling::Yuv420, ..ExportConfig::default() };
+        let (effective, adjustments) = export.tuned_for(&style);
+        assert_eq!(effective.subsampling, ChromaSubsampling::Yuv444);
+        assert_eq!(adjustments, vec!["subsampling 4:2:0 -> 4:4:4 (WhiteClassic 推荐)".to_string()]);
+
+        // 用户已经选了 4:4:4 时不产生调整
+        let (effective, adjustments) = ExportConfig::default().tuned_for(&style);
+        assert_eq!(effective.subsampling, ChromaSubsampling::Yuv444);
+        assert!(adjustments.is_empty());
+    }
+
+    #[test]
+    fn auto_tuning_reports_but_keeps_a_different_format() {
+        let style: StyleOptions = serde_json::from_str(r#"{"style":"WhiteModern"}"#).unwrap();
+        let export = ExportConfig { format: ExportImageFormat::Webp, quality: 100, ..ExportConfig::default() };
+        let (effective, adjustments) = export.tuned_for(&style);
+        assert_eq!(effective.format, ExportImageFormat::Webp);
+        assert_eq!(adjustments, vec!["format webp 保持不变 (WhiteModern 推荐 jpg)".to_string()]);
+    }
+
+    #[test]
+    fn lossy_webp_is_detected_in_main_output_and_variants() {
+        let webp = |quality| ExportConfig { format: ExportImageFormat::Webp, quality, ..ExportConfig::default() };
+        assert_eq!(webp(90).lossy_webp_quality(), Some(90));
+        assert_eq!(webp(100).lossy_webp_quality(), None);
+        // JPG 的质量与此无关
+        assert_eq!(ExportConfig { quality: 80, ..ExportConfig::default() }.lossy_webp_quality(), None);
+
+        let variant = ExportVariant { name: "web".into(), max_long_edge: Some(2048), format: ExportImageFormat::Webp, quality: 80 };
+        let export = ExportConfig { variants: vec![variant], ..ExportConfig::default() };
+        assert_eq!(export.lossy_webp_quality(), Some(80));
+    }
+
+    #[test]
+    fn auto_tuning_never_lowers_quality() {
+        let style: StyleOptions = serde_json::from_str(r#"{"style":"TransparentMaster"}"#).unwrap();
+        let export = ExportConfig { quality: 98, ..ExportConfig::default() };
+        let (effective, adjustments) = export.tuned_for(&style);
+        assert_eq!(effective.quality, 98);
+        assert!(adjustments.is_empty());
+
+        // 无损格式不看质量下限和色度抽样 (只提示格式与推荐不同)
+        let export = ExportConfig { quality: 50, format: ExportImageFormat::Png, subsampling: ChromaSubsampling::Yuv420, ..ExportConfig::default() };
+        let (effective, adjustments) = export.tuned_for(&style);
+        assert_eq!(effective.quality, 50);
+        assert_eq!(effective.subsampling, ChromaSubsampling::Yuv420);
+        assert_eq!(adjustments.len(), 1);
+        assert!(adjustments[0].starts_with("format png "), "{}", adjustments[0]);
+    }
+
+    #[test]
+    fn payload_without_export_block_uses_defaults() {
+        // 旧版前端只传样式参数
+        let ctx: BatchContext = serde_json::from_str(r#"{"style":"WhiteClassic"}"#).unwrap();
+        let export = &ctx.export;
+        assert!(export.target_dir.is_none());
+        assert!(matches!(export.format, ExportImageFormat::Jpg));
+        assert_eq!(export.quality, 95);
+        assert!(export.preserve_exif && export.color_management);
+        assert!(export.variants.is_empty() && export.copy_original.is_none());
+
+        // 最早的导出块只有这三项，其余字段的 serde 缺省值与 Default 一致
+        let minimal: ExportConfig = serde_json::from_str(r#"{"targetDir":null,"format":"jpg","quality":95}"#).unwrap();
+        assert_eq!(serde_json::to_value(&minimal).unwrap(), serde_json::to_value(ExportConfig::default()).unwrap());
+    }
+
+    #[test]
+    fn export_block_round_trips() {
+        let payload = serde_json::json!({
+            "style": "TransparentClassic",
+            "exportSettings": {
+                "targetDir": "/tmp/framed",
+                "format": "png",
+                "quality": 80,
+                "skipExisting": true,
+                "maxLongEdge": 4096,
+                "targetAspect": [4, 5],
+                "copyOriginal": { "subfolder": "originals", "renameToMatch": true },
+                "variants": [{ "name": "web", "maxLongEdge": 2048, "format": "webp", "quality": 85 }],
+            },
+            "deterministic": true,
+        });
+        let ctx: BatchContext = serde_json::from_value(payload).unwrap();
+        assert_eq!(ctx.export.target_dir.as_deref(), Some("/tmp/framed"));
+        assert!(matches!(ctx.export.format, ExportImageFormat::Png));
+        assert_eq!(ctx.export.format.extension(), "png");
+        assert!(ctx.export.format.supports_alpha());
+        assert_eq!(ctx.export.quality, 80);
+        assert_eq!(ctx.export.target_aspect, Some((4, 5)));
+        assert_eq!(ctx.export.variants[0].max_long_edge, Some(2048));
+        assert!(ctx.deterministic);
+
+        // 序列化后再读回，内容不变
+        let json = serde_json::to_value(&ctx).unwrap();
+        assert_eq!(json["style"], "TransparentClassic");
+        assert_eq!(json["exportSettings"]["format"], "png");
+        let again: BatchContext = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&again).unwrap(), json);
+    }
+
+    #[test]
+    fn filename_suffixes_are_unique_and_filesystem_safe() {
+        let catalog = StyleOptions::catalog();
+        let mut seen = std::collections::HashSet::new();
+        for style in &catalog {
+            let suffix = style.filename_suffix();
+            // 已发布的后缀是驼峰式，不能改成小写 (会认不出旧成品)；只要求 ASCII 字母数字
+            assert!(!suffix.is_empty() && suffix.chars().all(|c| c.is_ascii_alphanumeric()), "{}", suffix);
+            // 大小写不敏感的文件系统 (macOS / Windows) 上也不能撞名
+            assert!(seen.insert(suffix.to_ascii_lowercase()), "重复的后缀: {}", suffix);
+            // 后缀与前端传来的 style 标签一致，check_output_exists 和批处理算出的文件名相同
+            assert_eq!(serde_json::to_value(style).unwrap()["style"], suffix);
+        }
+    }
+}
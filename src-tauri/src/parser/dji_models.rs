@@ -0,0 +1,39 @@
+// src/parser/dji_models.rs
+
+// =========================================================
+// 🟢 DJI 相机模组对照表：EXIF 模组编号 -> 机型名
+// =========================================================
+// 无人机的 EXIF Model 是相机模组编号 ("FC3582")，对观众没有意义，边框上要显示机型名 ("Mini 3 Pro")。
+// 同一台飞机的多个摄像头编号不同 (如 Mavic 3 Pro 的两颗长焦)，都映射到同一个机型。
+// 哈苏主摄 (L1D-20c / L2D-20c) 的 Make 是 "Hasselblad"，走 Hasselblad 解析器，不在这里。
+// 未收录的编号显示为 "DJI FC1234" (见 parser::map_dji_model)
+
+/// (模组编号, 机型名)，编号统一大写
+pub(crate) const DJI_MODELS: &[(&str, &str)] = &[
+    // --- Mavic ---
+    ("FC220",   "Mavic Pro"),
+    ("FC2204",  "Mavic 2 Zoom"),
+    ("FC4170",  "Mavic 3"),     // 长焦
+    ("FC4370",  "Mavic 3 Pro"), // 中长焦
+    ("FC4382",  "Mavic 3 Pro"), // 长焦
+    // --- Air ---
+    ("FC3170",  "Mavic Air 2"),
+    ("FC3411",  "Air 2S"),
+    ("FC8282",  "Air 3"),
+    ("FC8284",  "Air 3"),       // 中长焦
+    // --- Mini ---
+    ("FC7203",  "Mavic Mini"),
+    ("FC7303",  "Mini 2"),
+    ("FC3682",  "Mini 3"),
+    ("FC3582",  "Mini 3 Pro"),
+    ("FC8482",  "Mini 4 Pro"),
+    // --- Phantom ---
+    ("FC6310",  "Phantom 4 Pro"),
+    ("FC6310S", "Phantom 4 Pro V2.0"),
+];
+
+/// 按模组编号查机型名 (不区分大小写，忽略首尾空格)
+pub(crate) fn lookup(module_id: &str) -> Option<&'static str> {
+    let key = module_id.trim().to_uppercase();
+    DJI_MODELS.iter().find(|(k, _)| *k == key).map(|(_, name)| *name)
+}
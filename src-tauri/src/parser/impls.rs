@@ -1,7 +1,7 @@
 // src/parser/impls.rs
 
 use crate::resources::Brand;
-use crate::parser::models::{RawExifData, ParsedImageContext, ShootingParams, GeoLocation};
+use crate::parser::models::{RawExifData, ParsedImageContext, ShootingParams, ShutterSpeed, MeteringMode, GeoLocation};
 use crate::parser::traits::BrandParser;
 
 // 🟢 引入刚才在 mod.rs 里定义的清洗函数
@@ -31,8 +31,7 @@ impl BrandParser for SonyParser {
         raw.make.to_uppercase().contains("SONY")
     }
     fn parse(&self, raw: &RawExifData) -> ParsedImageContext {
-        // 🟢 直接调用通用清洗 -> 得到 "α7R V"
-        // 删掉这里原来那一长串 match，逻辑已移至 mod.rs
+        // 🟢 直接调用通用清洗 -> 得到 "α7R V" (对照表在 sony_models.rs)
         let model_clean = clean_model_name_logic(&raw.make, &raw.model);
         build_context(Brand::Sony, model_clean, raw)
     }
@@ -53,15 +52,163 @@ impl BrandParser for CanonParser {
     }
 }
 
+// ==========================================
+// 4. Fujifilm 解析器
+// ==========================================
+pub struct FujiParser;
+impl BrandParser for FujiParser {
+    fn can_parse(&self, raw: &RawExifData) -> bool {
+        raw.make.to_uppercase().contains("FUJIFILM")
+    }
+    fn parse(&self, raw: &RawExifData) -> ParsedImageContext {
+        // 🟢 直接调用通用清洗 -> 得到 "GFX100 II" / "X-T5"
+        let model_clean = clean_model_name_logic(&raw.make, &raw.model);
+        build_context(Brand::Fujifilm, model_clean, raw)
+    }
+}
+
+// ==========================================
+// 5. Leica 解析器
+// ==========================================
+pub struct LeicaParser;
+impl BrandParser for LeicaParser {
+    fn can_parse(&self, raw: &RawExifData) -> bool {
+        raw.make.to_uppercase().contains("LEICA")
+    }
+    fn parse(&self, raw: &RawExifData) -> ParsedImageContext {
+        // 🟢 直接调用通用清洗 -> 得到 "Q3" / "M11 Monochrom"
+        let model_clean = clean_model_name_logic(&raw.make, &raw.model);
+        build_context(Brand::Leica, model_clean, raw)
+    }
+}
+
+// ==========================================
+// 6. Olympus / OM System 解析器
+// ==========================================
+pub struct OlympusParser;
+impl BrandParser for OlympusParser {
+    fn can_parse(&self, raw: &RawExifData) -> bool {
+        let make = raw.make.to_uppercase();
+        make.contains("OLYMPUS") || make.contains("OM DIGITAL")
+    }
+    fn parse(&self, raw: &RawExifData) -> ParsedImageContext {
+        // 🟢 Make 是 "OLYMPUS IMAGING CORP." 的是老奥林巴斯机身，"OM Digital Solutions" 的是 OM System
+        let brand = if raw.make.to_uppercase().contains("OLYMPUS") { Brand::Olympus } else { Brand::OmSystem };
+        // 🟢 直接调用通用清洗 -> 得到 "E-M1 Mark III" / "OM-1"
+        let model_clean = clean_model_name_logic(&raw.make, &raw.model);
+        build_context(brand, model_clean, raw)
+    }
+}
+
+// ==========================================
+// 7. Panasonic 解析器
+// ==========================================
+pub struct PanasonicParser;
+impl BrandParser for PanasonicParser {
+    fn can_parse(&self, raw: &RawExifData) -> bool {
+        raw.make.to_uppercase().contains("PANASONIC")
+    }
+    fn parse(&self, raw: &RawExifData) -> ParsedImageContext {
+        // 🟢 直接调用通用清洗 -> 得到 "LUMIX S5 II"
+        let model_clean = clean_model_name_logic(&raw.make, &raw.model);
+        build_context(Brand::Panasonic, model_clean, raw)
+    }
+}
+
+// ==========================================
+// 8. Hasselblad 解析器
+// ==========================================
+pub struct HasselbladParser;
+impl BrandParser for HasselbladParser {
+    fn can_parse(&self, raw: &RawExifData) -> bool {
+        raw.make.to_uppercase().contains("HASSELBLAD")
+    }
+    fn parse(&self, raw: &RawExifData) -> ParsedImageContext {
+        // 🟢 直接调用通用清洗 -> 得到 "X1D II 50C" / "907X"
+        let model_clean = clean_model_name_logic(&raw.make, &raw.model);
+        build_context(Brand::Hasselblad, model_clean, raw)
+    }
+}
+
+// ==========================================
+// 9. Ricoh / Pentax 解析器
+// ==========================================
+pub struct RicohPentaxParser;
+impl BrandParser for RicohPentaxParser {
+    fn can_parse(&self, raw: &RawExifData) -> bool {
+        let make = raw.make.to_uppercase();
+        make.contains("RICOH") || make.contains("PENTAX")
+    }
+    fn parse(&self, raw: &RawExifData) -> ParsedImageContext {
+        // 🟢 理光收购宾得后，新宾得机身的 Make 也是 "RICOH IMAGING COMPANY, LTD."，
+        // 品牌要看 Model 前缀 ("PENTAX K-3 Mark III")；老机身的 Make 是 "PENTAX Corporation"
+        let is_pentax = raw.make.to_uppercase().contains("PENTAX")
+            || raw.model.trim().to_uppercase().starts_with("PENTAX");
+        let brand = if is_pentax { Brand::Pentax } else { Brand::Ricoh };
+        // 🟢 直接调用通用清洗 -> 得到 "GR IIIx" / "K-1 Mark II"
+        let model_clean = clean_model_name_logic(&raw.make, &raw.model);
+        build_context(brand, model_clean, raw)
+    }
+}
+
+// ==========================================
+// 10. DJI 无人机解析器
+// ==========================================
+pub struct DjiParser;
+impl BrandParser for DjiParser {
+    fn can_parse(&self, raw: &RawExifData) -> bool {
+        raw.make.trim().eq_ignore_ascii_case("DJI")
+    }
+    fn parse(&self, raw: &RawExifData) -> ParsedImageContext {
+        // 🟢 直接调用通用清洗 -> "FC3582" 得到 "Mini 3 Pro"
+        let model_clean = clean_model_name_logic(&raw.make, &raw.model);
+        let mut ctx = build_context(Brand::Dji, model_clean, raw);
+        // 🟢 与手机相同：小底相机的物理焦距只有几毫米，只用等效焦距
+        ctx.params.focal_length = raw.focal_length_35mm.map(|f| f as f32);
+        ctx
+    }
+}
+
+// ==========================================
+// 11. 手机解析器 (Apple / Google / Samsung)
+// ==========================================
+pub struct SmartphoneParser;
+impl SmartphoneParser {
+    fn brand_of(make: &str) -> Option<Brand> {
+        let make = make.to_uppercase();
+        if make.contains("APPLE") {
+            Some(Brand::Apple)
+        } else if make.contains("GOOGLE") {
+            Some(Brand::Google)
+        } else if make.contains("SAMSUNG") {
+            Some(Brand::Samsung)
+        } else {
+            None
+        }
+    }
+}
+impl BrandParser for SmartphoneParser {
+    fn can_parse(&self, raw: &RawExifData) -> bool {
+        Self::brand_of(&raw.make).is_some()
+    }
+    fn parse(&self, raw: &RawExifData) -> ParsedImageContext {
+        let brand = Self::brand_of(&raw.make).unwrap_or(Brand::Other);
+        // 🟢 手机的 Model 本身就是商品名 ("iPhone 15 Pro" / "Pixel 8")，原样保留
+        let mut ctx = build_context(brand, raw.model.trim().to_string(), raw);
+        // 🟢 手机的物理焦距只有几毫米 ("7mm" 没有参考价值)，只用等效焦距；
+        // 没有等效焦距时留空，各样式会省略焦距一栏
+        ctx.params.focal_length = raw.focal_length_35mm.map(|f| f as f32);
+        ctx
+    }
+}
+
 // ... (build_context 辅助函数保持不变) ...
 fn build_context(brand: Brand, model_clean: String, raw: &RawExifData) -> ParsedImageContext {
     // ... (保持原样) ...
-    let clean_time = raw.datetime.replace(":", ".");
+    let clean_time = raw.datetime.as_deref().map(|t| t.replace(":", ".")).unwrap_or_default();
     
     // ... GPS 逻辑 ...
-    let gps_data = if let (Some(lat), Some(long)) = (raw.gps_latitude, raw.gps_longitude) {
-       Some(GeoLocation { latitude: lat, longitude: long })
-    } else { None };
+    let gps_data = GeoLocation::from_coords(raw.gps_latitude, raw.gps_longitude);
 
     ParsedImageContext {
         brand,
@@ -69,12 +216,14 @@ fn build_context(brand: Brand, model_clean: String, raw: &RawExifData) -> Parsed
         params: ShootingParams {
             iso: raw.iso,
             aperture: raw.aperture,
-            shutter_speed: raw.shutter_speed.clone(),
+            shutter_speed: ShutterSpeed::parse(&raw.shutter_speed),
             focal_length: raw.focal_length,
-            lens_model: raw.lens.clone(),
+            lens_model: raw.lens.clone().unwrap_or_default(),
+            exposure_bias: raw.exposure_bias,
+            metering_mode: raw.metering_mode.and_then(MeteringMode::from_exif),
             capture_time: clean_time,
         },
-        artist_name: raw.artist.clone().or(raw.copyright.clone()),
+        artist_name: super::artist_from_raw(raw),
         gps: gps_data,
     }
 }
\ No newline at end of file
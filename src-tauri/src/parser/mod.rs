@@ -2,33 +2,197 @@
 pub mod models;
 pub mod traits;
 pub(crate) mod impls; // 内部实现细节，对外隐藏，对内可见
+pub(crate) mod sony_models;
+pub(crate) mod dji_models;
 
+use log::warn;
+
+use crate::models::TextOverrides;
 use crate::resources::Brand;
-use models::{RawExifData, ParsedImageContext, ShootingParams};
+use models::{RawExifData, ParsedImageContext, ShootingParams, ShutterSpeed, MeteringMode, GeoLocation};
 use traits::BrandParser;
 // 引入具体的解析器实现
-use impls::{NikonParser, SonyParser, CanonParser};
+use impls::{
+    NikonParser, SonyParser, CanonParser, FujiParser, LeicaParser, OlympusParser, PanasonicParser,
+    HasselbladParser, RicohPentaxParser, DjiParser, SmartphoneParser,
+};
 
-// 🟢 1. 定义 Sony 映射表 (放在这里，作为通用工具)
+// 🟢 1. Sony 映射 (对照表在 sony_models.rs)
 fn map_sony_model(internal_name: &str) -> String {
-    match internal_name.to_uppercase().as_str() {
-        "ILCE-1"    => "α1".to_string(),
-        "ILCE-9M3"  => "α9 III".to_string(),
-        "ILCE-9M2"  => "α9 II".to_string(),
-        "ILCE-7RM5" => "α7R V".to_string(),
-        "ILCE-7RM4" => "α7R IV".to_string(),
-        "ILCE-7RM3" => "α7R III".to_string(),
-        "ILCE-7SM3" => "α7S III".to_string(),
-        "ILCE-7SM2" => "α7S II".to_string(),
-        "ILCE-7M5"  => "α7 V".to_string(),
-        "ILCE-7M4"  => "α7 IV".to_string(),
-        "ILCE-7M3"  => "α7 III".to_string(),
-        "ILCE-7C"   => "α7C".to_string(),
-        "ILCE-7CM2" => "α7C II".to_string(),
-        "ILCE-7CR"  => "α7CR".to_string(),
-        "ILCE-6700" => "α6700".to_string(),
-        "ZV-E1"     => "ZV-E1".to_string(),
-        _ => internal_name.replace("ILCE-", "α").trim().to_string(),
+    match sony_models::lookup(internal_name) {
+        Some(name) => name.to_string(),
+        None => internal_name.replace("ILCE-", "α").trim().to_string(),
+    }
+}
+
+// 🟢 1.1 定义 Fujifilm 映射表
+// 富士的 EXIF Model 写法不统一 ("GFX100II" / "GFX 50S II" / "FUJIFILM X-H2S")，
+// 这里统一成官网的标准写法；未收录的机型保留原始大小写 (如 "X-Pro3")
+fn map_fuji_model(model: &str) -> String {
+    let stripped = model.trim();
+    let stripped = if stripped.to_uppercase().starts_with("FUJIFILM") {
+        stripped[8..].trim()
+    } else {
+        stripped
+    };
+
+    match stripped.to_uppercase().as_str() {
+        // --- GFX 中画幅 ---
+        "GFX100II" | "GFX100 II" | "GFX 100 II"   => "GFX100 II".to_string(),
+        "GFX100RF" | "GFX 100RF"                  => "GFX100RF".to_string(),
+        "GFX100SII" | "GFX100S II" | "GFX 100S II" => "GFX100S II".to_string(),
+        "GFX100S" | "GFX 100S"                    => "GFX100S".to_string(),
+        "GFX100" | "GFX 100"                      => "GFX100".to_string(),
+        "GFX50SII" | "GFX50S II" | "GFX 50S II"   => "GFX50S II".to_string(),
+        "GFX50S" | "GFX 50S"                      => "GFX50S".to_string(),
+        "GFX50R" | "GFX 50R"                      => "GFX50R".to_string(),
+        // --- X 系列 ---
+        "X100VI"  => "X100VI".to_string(),
+        "X100V"   => "X100V".to_string(),
+        "X-PRO3"  => "X-Pro3".to_string(),
+        "X-PRO2"  => "X-Pro2".to_string(),
+        _ => stripped.to_string(),
+    }
+}
+
+// 🟢 1.2 定义 Leica 映射表
+// 徕卡的 Model 通常带品牌前缀且全大写 ("LEICA Q3" / "LEICA M11 Monochrom" / "LEICA SL3-S")，
+// 这里去掉前缀，并把后缀单词还原成官方写法
+fn map_leica_model(model: &str) -> String {
+    let stripped = model.trim();
+    let stripped = if stripped.to_uppercase().starts_with("LEICA") {
+        stripped[5..].trim()
+    } else {
+        stripped
+    };
+
+    stripped
+        .split_whitespace()
+        .map(|word| match word.to_uppercase().as_str() {
+            "MONOCHROM" => "Monochrom".to_string(),
+            "REPORTER"  => "Reporter".to_string(),
+            "CAMERA"    => "Camera".to_string(),
+            // 机型代号统一大写: Q3 / M11-P / SL3-S / Q3 43
+            upper => upper.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// 🟢 1.3 定义 Olympus / OM System 映射表
+// 奥林巴斯的 Model 里 Mark 和机型连写 ("E-M1MarkIII")，这里还原成官方写法 "E-M1 Mark III"；
+// 未收录的机型按同样规则在 Mark 前后补空格
+fn map_olympus_model(model: &str) -> String {
+    // 去掉空格后统一比较，兼容 "E-M1MarkIII" / "E-M1 Mark III" 两种写法
+    let compact = model.trim().to_uppercase().replace(' ', "");
+
+    match compact.as_str() {
+        // --- OM System ---
+        "OM-1"         => "OM-1".to_string(),
+        "OM-1MARKII"   => "OM-1 Mark II".to_string(),
+        "OM-3"         => "OM-3".to_string(),
+        "OM-5"         => "OM-5".to_string(),
+        // --- OM-D ---
+        "E-M1X"        => "E-M1X".to_string(),
+        "E-M1MARKIII"  => "E-M1 Mark III".to_string(),
+        "E-M1MARKII"   => "E-M1 Mark II".to_string(),
+        "E-M5MARKIII"  => "E-M5 Mark III".to_string(),
+        "E-M5MARKII"   => "E-M5 Mark II".to_string(),
+        "E-M10MARKIV"  => "E-M10 Mark IV".to_string(),
+        "E-M10MARKIII" => "E-M10 Mark III".to_string(),
+        // --- PEN ---
+        "PEN-F"        => "PEN-F".to_string(),
+        _ => match compact.find("MARK") {
+            Some(idx) => format!("{} Mark {}", &compact[..idx], &compact[idx + 4..]).trim().to_string(),
+            None => model.trim().to_string(),
+        },
+    }
+}
+
+// 🟢 1.4 定义 Panasonic 映射表
+// Lumix 的 Model 是内部型号 ("DC-S5M2" / "DMC-GH4")，这里换成机身上印的名字；
+// 未收录的机型去掉 "DC-" / "DMC-" 前缀，至少不会把内部编号印到边框上
+fn map_panasonic_model(model: &str) -> String {
+    let upper = model.trim().to_uppercase();
+
+    match upper.as_str() {
+        // --- S 全画幅 ---
+        "DC-S1"     => "LUMIX S1".to_string(),
+        "DC-S1R"    => "LUMIX S1R".to_string(),
+        "DC-S1H"    => "LUMIX S1H".to_string(),
+        "DC-S1M2"   => "LUMIX S1 II".to_string(),
+        "DC-S1M2E"  => "LUMIX S1 IIE".to_string(),
+        "DC-S1RM2"  => "LUMIX S1R II".to_string(),
+        "DC-S5"     => "LUMIX S5".to_string(),
+        "DC-S5M2"   => "LUMIX S5 II".to_string(),
+        "DC-S5M2X"  => "LUMIX S5 IIX".to_string(),
+        "DC-S9"     => "LUMIX S9".to_string(),
+        // --- G 系列 M43 ---
+        "DC-GH5"    => "LUMIX GH5".to_string(),
+        "DC-GH5S"   => "LUMIX GH5S".to_string(),
+        "DC-GH5M2"  => "LUMIX GH5 II".to_string(),
+        "DC-GH6"    => "LUMIX GH6".to_string(),
+        "DC-GH7"    => "LUMIX GH7".to_string(),
+        "DC-G9"     => "LUMIX G9".to_string(),
+        "DC-G9M2"   => "LUMIX G9 II".to_string(),
+        "DC-G100"   => "LUMIX G100".to_string(),
+        "DC-GX9"    => "LUMIX GX9".to_string(),
+        _ => {
+            let stripped = upper
+                .strip_prefix("DC-")
+                .or_else(|| upper.strip_prefix("DMC-"))
+                .unwrap_or(&upper);
+            format!("LUMIX {}", stripped.trim())
+        },
+    }
+}
+
+// 🟢 1.5 Hasselblad 型号清洗
+// Make 是 "Hasselblad"，Model 有时带品牌前缀 ("HASSELBLAD X1D II 50C") 或多余空格，
+// 去掉前缀并合并空格；型号本身的大小写保持原样 ("H6D-100c" 的小写 c 是官方写法)
+fn map_hasselblad_model(model: &str) -> String {
+    let stripped = model.trim();
+    let stripped = match stripped.get(..10) {
+        Some(prefix) if prefix.eq_ignore_ascii_case("HASSELBLAD") => &stripped[10..],
+        _ => stripped,
+    };
+    stripped.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+// 🟢 1.6 Ricoh / Pentax 型号清洗
+// 两个品牌共用理光的 EXIF：Model 带品牌前缀 ("RICOH GR III" / "PENTAX K-3 Mark III")，
+// 大小写也不统一 ("GR IIIX" / "K-1 MARK II")，这里还原成官方写法
+fn map_ricoh_pentax_model(model: &str) -> String {
+    let stripped = model.trim();
+    let stripped = ["RICOH", "PENTAX"]
+        .iter()
+        .find(|prefix| stripped.get(..prefix.len()).is_some_and(|p| p.eq_ignore_ascii_case(prefix)))
+        .map_or(stripped, |prefix| stripped[prefix.len()..].trim());
+
+    stripped
+        .split_whitespace()
+        .map(|word| match word.to_uppercase().as_str() {
+            "MARK" => "Mark".to_string(),
+            "IIIX" => "IIIx".to_string(), // GR IIIx
+            "MONOCHROME" => "Monochrome".to_string(),
+            // 机型代号统一大写: GR III / K-1 / K-3 / KP / 645Z
+            upper => upper.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// 🟢 1.7 DJI 映射 (对照表在 dji_models.rs)
+// 未收录的模组编号显示为 "DJI FC1234"，只有编号的话观众认不出是大疆
+fn map_dji_model(model: &str) -> String {
+    let stripped = model.trim();
+    let stripped = match stripped.get(..3) {
+        Some(prefix) if prefix.eq_ignore_ascii_case("DJI") => stripped[3..].trim(),
+        _ => stripped,
+    };
+    match dji_models::lookup(stripped) {
+        Some(name) => name.to_string(),
+        None => format!("DJI {}", stripped.to_uppercase()),
     }
 }
 
@@ -43,6 +207,41 @@ pub(crate) fn clean_model_name_logic(make: &str, model: &str) -> String {
         return map_sony_model(&model_upper);
     }
 
+    // Fujifilm 特殊处理
+    if make_clean.contains("FUJIFILM") {
+        return map_fuji_model(model);
+    }
+
+    // Leica 特殊处理 (Make 是 "LEICA CAMERA AG"，通用前缀匹配不到)
+    if make_clean.contains("LEICA") {
+        return map_leica_model(model);
+    }
+
+    // Olympus / OM System 特殊处理 (Make 是公司名，型号里不带品牌前缀)
+    if make_clean.contains("OLYMPUS") || make_clean.contains("OM DIGITAL") {
+        return map_olympus_model(model);
+    }
+
+    // Panasonic 特殊处理 (内部型号 -> LUMIX 商品名)
+    if make_clean.contains("PANASONIC") {
+        return map_panasonic_model(model);
+    }
+
+    // Hasselblad 特殊处理 (前缀 + 空格)
+    if make_clean.contains("HASSELBLAD") {
+        return map_hasselblad_model(model);
+    }
+
+    // Ricoh / Pentax 特殊处理 (Make 是 "RICOH IMAGING COMPANY, LTD."，通用前缀匹配不到)
+    if make_clean.contains("RICOH") || make_clean.contains("PENTAX") {
+        return map_ricoh_pentax_model(model);
+    }
+
+    // DJI 特殊处理 (相机模组编号 -> 机型名)
+    if make_clean == "DJI" {
+        return map_dji_model(model);
+    }
+
     // 通用处理：移除品牌前缀 (如 "Canon EOS R5" -> "EOS R5")
     let mut model_base = if let Some(idx) = model_upper.find(&make_clean) {
         let start = idx + make_clean.len();
@@ -66,11 +265,19 @@ pub fn parse(raw: RawExifData) -> ParsedImageContext {
     
     // A. 组建解析器团队 (注册中心)
     // 使用 Box<dyn BrandParser> 实现动态分发 (Polymorphism)
-    // 如果以后想支持 Fuji，就在这里加一行 Box::new(FujiParser)
+    // 如果以后想支持新品牌，就在这里加一行 Box::new(XxxParser)
     let parsers: Vec<Box<dyn BrandParser>> = vec![
         Box::new(NikonParser),
         Box::new(SonyParser),
         Box::new(CanonParser),
+        Box::new(FujiParser),
+        Box::new(LeicaParser),
+        Box::new(OlympusParser),
+        Box::new(PanasonicParser),
+        Box::new(HasselbladParser),
+        Box::new(RicohPentaxParser),
+        Box::new(DjiParser),
+        Box::new(SmartphoneParser),
     ];
 
     // B. 职责链模式：遍历寻找能处理的解析器
@@ -84,11 +291,77 @@ pub fn parse(raw: RawExifData) -> ParsedImageContext {
     default_parse(raw)
 }
 
+/// 🟢 把手动填写的文字覆盖到解析结果上 (在处理器之前调用，所有样式共用)
+/// 空字符串清空该项；填了但解析不出数值的保留 EXIF 原值并记录警告
+pub fn apply_overrides(ctx: &mut ParsedImageContext, overrides: &TextOverrides) {
+    // 去掉用户顺手带上的前后缀 ("f/2.8" / "ISO 400" / "50mm")
+    fn number<T: std::str::FromStr>(field: &str, value: &str, prefixes: &[&str], suffix: &str) -> Option<Option<T>> {
+        let mut v = value.trim();
+        if v.is_empty() {
+            return Some(None);
+        }
+        // get() 而不是直接切片：用户可能输入中文，按字节切会落在字符中间
+        if let Some(p) = prefixes.iter().find(|p| v.get(..p.len()).is_some_and(|h| h.eq_ignore_ascii_case(p))) {
+            v = v[p.len()..].trim_start();
+        }
+        let tail = v.len().saturating_sub(suffix.len());
+        if v.get(tail..).is_some_and(|t| t.eq_ignore_ascii_case(suffix)) {
+            v = v[..tail].trim_end();
+        }
+        match v.parse() {
+            Ok(n) => Some(Some(n)),
+            Err(_) => {
+                warn!("⚠️ [Parser] 无法识别手动填写的{}: {:?}，沿用 EXIF", field, value);
+                None
+            }
+        }
+    }
+
+    if let Some(model) = &overrides.model {
+        ctx.model_name = model.trim().to_string();
+    }
+    if let Some(v) = overrides.aperture.as_deref().and_then(|v| number::<f32>("光圈", v, &["f/", "f"], "")) {
+        ctx.params.aperture = v;
+    }
+    if let Some(v) = &overrides.shutter {
+        if v.trim().is_empty() {
+            ctx.params.shutter_speed = None;
+        } else if let Some(speed) = ShutterSpeed::parse(v) {
+            ctx.params.shutter_speed = Some(speed);
+        } else {
+            warn!("⚠️ [Parser] 无法识别手动填写的快门: {:?}，沿用 EXIF", v);
+        }
+    }
+    if let Some(v) = overrides.iso.as_deref().and_then(|v| number::<u32>("ISO", v, &["ISO"], "")) {
+        ctx.params.iso = v;
+    }
+    if let Some(v) = overrides.focal.as_deref().and_then(|v| number::<f32>("焦距", v, &[], "mm")) {
+        ctx.params.focal_length = v;
+    }
+    if let Some(lens) = &overrides.lens {
+        ctx.params.lens_model = lens.trim().to_string();
+    }
+    if let Some(artist) = &overrides.artist {
+        let artist = artist.trim();
+        ctx.artist_name = (!artist.is_empty()).then(|| artist.to_string());
+    }
+}
+
+/// 🟢 署名：Artist 优先，其次 Copyright；空白字符串视为没有 (不少相机会写入全空格的 Artist)
+pub(crate) fn artist_from_raw(raw: &RawExifData) -> Option<String> {
+    [&raw.artist, &raw.copyright]
+        .into_iter()
+        .filter_map(|v| v.as_deref().map(str::trim))
+        .find(|v| !v.is_empty())
+        .map(str::to_string)
+}
+
 /// 默认解析逻辑 (Fallback)
-/// 用于处理未适配的品牌 (如 Leica, Fuji 等尚未编写专门解析器的情况)
+/// 用于处理未适配的品牌 (尚未编写专门解析器的情况)
 fn default_parse(raw: RawExifData) -> ParsedImageContext {
     // 简单的清洗逻辑：把时间里的冒号换成点
-    let clean_time = raw.datetime.replace(":", ".");
+    let clean_time = raw.datetime.as_deref().map(|t| t.replace(":", ".")).unwrap_or_default();
+    let artist_name = artist_from_raw(&raw);
     
     // 尝试简单的品牌猜测
     let make_upper = raw.make.to_uppercase();
@@ -110,12 +383,167 @@ fn default_parse(raw: RawExifData) -> ParsedImageContext {
         params: ShootingParams {
             iso: raw.iso,
             aperture: raw.aperture,
-            shutter_speed: raw.shutter_speed,
+            shutter_speed: ShutterSpeed::parse(&raw.shutter_speed),
             focal_length: raw.focal_length,
-            lens_model: raw.lens,
+            lens_model: raw.lens.unwrap_or_default(),
+            exposure_bias: raw.exposure_bias,
+            metering_mode: raw.metering_mode.and_then(MeteringMode::from_exif),
             capture_time: clean_time,
         },
-        artist_name: raw.artist.or(raw.copyright),
-        gps: None, // 默认不尝试解析 GPS，除非你写了通用的 GPS 解析逻辑
+        artist_name,
+        gps: GeoLocation::from_coords(raw.gps_latitude, raw.gps_longitude),
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dji(model: &str) -> ParsedImageContext {
+        parse(RawExifData { make: "DJI".into(), model: model.into(), ..Default::default() })
+    }
+
+    #[test]
+    fn known_dji_code_maps_to_model_name() {
+        let c = dji("FC8482");
+        assert_eq!(c.brand, Brand::Dji);
+        assert_eq!(c.model_name, "Mini 4 Pro");
+        assert_eq!(c.title(), "DJI Mini 4 Pro");
+        // 带 "DJI" 前缀、大小写不一也能查到
+        assert_eq!(dji("dji fc8482").model_name, "Mini 4 Pro");
+    }
+
+    #[test]
+    fn unknown_dji_code_keeps_brand_prefix() {
+        let c = dji("fc9999");
+        assert_eq!(c.model_name, "DJI FC9999");
+        // 标题不会变成 "DJI DJI FC9999"
+        assert_eq!(c.title(), "DJI FC9999");
+        assert_eq!(dji("DJI FC9999").model_name, "DJI FC9999");
+    }
+
+    fn fuji(model: &str) -> ParsedImageContext {
+        parse(RawExifData { make: "FUJIFILM".into(), model: model.into(), ..Default::default() })
+    }
+
+    #[test]
+    fn fujifilm_bodies_render_cleanly() {
+        // (EXIF Model 原文, 期望机型名)
+        let cases = [
+            ("X-T5", "X-T5"),
+            ("FUJIFILM X-H2S", "X-H2S"),
+            ("X100VI", "X100VI"),
+            ("GFX100II", "GFX100 II"),
+            ("GFX 50S II", "GFX50S II"),
+            ("X-PRO3", "X-Pro3"),
+        ];
+        for (model, expected) in cases {
+            let c = fuji(model);
+            assert_eq!(c.brand, Brand::Fujifilm, "{}", model);
+            assert_eq!(c.model_name, expected, "{}", model);
+            assert_eq!(c.title(), format!("Fujifilm {}", expected));
+        }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn fujifilm_parser_keeps_artist_and_gps() {
+        // 走 build_context：作者与定位的处理和其它品牌一致
+        let c = parse(RawExifData {
+            make: "FUJIFILM".into(),
+            model: "X-T5".into(),
+            artist: Some("  Aurora  ".into()),
+            gps_latitude: Some(35.6895),
+            gps_longitude: Some(139.6917),
+            ..Default::default()
+        });
+        assert_eq!(c.artist_name.as_deref(), Some("Aurora"));
+        let gps = c.gps.expect("GPS 丢失");
+        assert_eq!((gps.latitude, gps.longitude), (35.6895, 139.6917));
+    }
+
+    // iPhone 15 Pro 主摄的 EXIF (exiftool 导出后按 RawExifData 字段整理)
+    fn iphone_15_pro() -> RawExifData {
+        RawExifData {
+            make: "Apple".into(),
+            model: "iPhone 15 Pro".into(),
+            lens: Some("iPhone 15 Pro back triple camera 6.765mm f/1.78".into()),
+            iso: Some(80),
+            aperture: Some(1.78),
+            shutter_speed: "1/120".into(),
+            focal_length: Some(24.0),
+            focal_length_35mm: Some(24),
+            exposure_bias: Some(0.0),
+            datetime: Some("2023:10:14 16:02:11".into()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn iphone_keeps_model_and_uses_equivalent_focal_length() {
+        let c = parse(iphone_15_pro());
+        assert_eq!(c.brand, Brand::Apple);
+        assert_eq!(c.model_name, "iPhone 15 Pro");
+        assert_eq!(c.title(), "Apple iPhone 15 Pro");
+        assert_eq!(c.params.focal_length, Some(24.0));
+        assert_eq!(c.params.format_standard(), "24mm  f/1.8  1/120s  ISO 80");
+    }
+
+    #[test]
+    fn phone_without_equivalent_focal_length_drops_mm() {
+        // 没有等效焦距时不显示物理焦距 ("7mm" 没有参考价值)
+        let c = parse(RawExifData {
+            make: "Google".into(),
+            model: "Pixel 8".into(),
+            aperture: Some(1.68),
+            focal_length: Some(6.9),
+            iso: Some(50),
+            ..Default::default()
+        });
+        assert_eq!(c.brand, Brand::Google);
+        assert_eq!(c.params.focal_length, None);
+        assert!(!c.params.format_standard().contains("mm"));
+
+        let c = parse(RawExifData { make: "samsung".into(), model: "SM-S918B".into(), ..Default::default() });
+        assert_eq!((c.brand, c.model_name.as_str()), (Brand::Samsung, "SM-S918B"));
+    }
+
+    #[test]
+    fn ricoh_and_pentax_strip_maker_prefix() {
+        const RICOH: &str = "RICOH IMAGING COMPANY, LTD.";
+        // (Make, Model, 品牌, 机型名)
+        let cases = [
+            (RICOH, "RICOH GR III", Brand::Ricoh, "GR III"),
+            (RICOH, "RICOH GR IIIx", Brand::Ricoh, "GR IIIx"),
+            (RICOH, "PENTAX K-3 Mark III", Brand::Pentax, "K-3 Mark III"),
+            (RICOH, "PENTAX K-1 MARK II", Brand::Pentax, "K-1 Mark II"),
+            ("PENTAX Corporation", "PENTAX K-5", Brand::Pentax, "K-5"),
+        ];
+        for (make, model, brand, expected) in cases {
+            let c = parse(RawExifData { make: make.into(), model: model.into(), ..Default::default() });
+            assert_eq!((c.brand, c.model_name.as_str()), (brand, expected), "{}", model);
+        }
+        assert_eq!(Brand::Ricoh.to_string(), "Ricoh");
+        assert_eq!(Brand::Pentax.to_string(), "Pentax");
+    }
+
+    #[test]
+    fn copyright_is_used_when_artist_is_empty() {
+        // 品牌解析器和兜底逻辑都要回退：没有 Artist、Artist 为空串、全是空格
+        for make in ["NIKON CORPORATION", "SIGMA"] {
+            for artist in [None, Some(""), Some("   ")] {
+                let c = parse(RawExifData {
+                    make: make.into(),
+                    model: "fp".into(),
+                    artist: artist.map(String::from),
+                    copyright: Some("© Aurora".into()),
+                    ..Default::default()
+                });
+                assert_eq!(c.artist_name.as_deref(), Some("© Aurora"), "{} {:?}", make, artist);
+            }
+            // 两者都有时 Artist 优先，都没有时为 None
+            let both = RawExifData { make: make.into(), artist: Some("Aurora".into()), copyright: Some("© Studio".into()), ..Default::default() };
+            assert_eq!(parse(both).artist_name.as_deref(), Some("Aurora"));
+            let neither = RawExifData { make: make.into(), copyright: Some(" ".into()), ..Default::default() };
+            assert_eq!(parse(neither).artist_name, None);
+        }
+    }
+}
@@ -7,16 +7,28 @@ use crate::resources::Brand;
 pub struct RawExifData {
     pub make: String,
     pub model: String,
-    pub lens: String,
+    // 🟢 没有镜头 / 拍摄时间标签 (或内容为空) 时为 None
+    #[serde(default)]
+    pub lens: Option<String>,
     
     // 拍摄参数
     pub iso: Option<u32>,
     pub aperture: Option<f32>,
     pub shutter_speed: String,
-    pub focal_length: Option<u32>,
+    pub focal_length: Option<f32>, // 优先等效 35mm 焦距，没有时为物理焦距 (保留小数，如 23.5 / 10.5)
+    // 🟢 新增：仅等效 35mm 焦距 (FocalLengthIn35mmFilm)
+    // 手机的物理焦距只有几毫米，印出来没有意义，手机解析器只认这个字段
+    #[serde(default)]
+    pub focal_length_35mm: Option<u32>,
+    // 🟢 曝光补偿 (ExposureBiasValue，单位 EV) 和测光模式 (MeteringMode 原始值)
+    #[serde(default)]
+    pub exposure_bias: Option<f32>,
+    #[serde(default)]
+    pub metering_mode: Option<u16>,
     
     // 时间与作者
-    pub datetime: String,
+    #[serde(default)]
+    pub datetime: Option<String>,
     pub artist: Option<String>,
     pub copyright: Option<String>,
 
@@ -29,50 +41,216 @@ pub struct RawExifData {
 }
 
 
+// 🟢 参数行的拼接方式：分隔符 + 显示哪些项 (按数组顺序)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParamField {
+    FocalLength,
+    Aperture,
+    Shutter,
+    Iso,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ParamFormatOptions<'a> {
+    pub separator: &'a str,
+    pub fields: &'a [ParamField],
+}
+
+impl ParamFormatOptions<'static> {
+    /// 焦距 / 光圈 / 快门 / ISO 全部显示
+    pub const ALL_FIELDS: &'static [ParamField] = &[ParamField::FocalLength, ParamField::Aperture, ParamField::Shutter, ParamField::Iso];
+
+    /// 默认格式：双空格分隔，视觉上更清晰
+    pub const STANDARD: Self = Self { separator: "  ", fields: Self::ALL_FIELDS };
+}
+
 // 🟢 2. 拍摄参数 (纯物理数据)
 #[allow(dead_code)]
 #[derive(Debug, Clone, Default)]
 pub struct ShootingParams {
     pub iso: Option<u32>,
     pub aperture: Option<f32>,
-    pub shutter_speed: String,
-    pub focal_length: Option<u32>,
+    pub shutter_speed: Option<ShutterSpeed>,
+    pub focal_length: Option<f32>,
     pub lens_model: String,
+    pub exposure_bias: Option<f32>, // 曝光补偿 (EV)
+    pub metering_mode: Option<MeteringMode>,
     
     pub capture_time: String, // "2023.12.30 14:00"
 }
 
 impl ShootingParams {
-    /// 辅助函数：生成标准的参数字符串 (e.g. "50mm f/1.8 1/800s ISO 100")
+    /// 辅助函数：生成标准的参数字符串 (e.g. "50mm  f/1.8  1/800s  ISO 100")
     /// 供那些不需要自定义排版的相框直接使用
     pub fn format_standard(&self) -> String {
-        let mut parts = Vec::new();
+        self.format_with(&ParamFormatOptions::STANDARD)
+    }
+
+    /// 拍摄日期 (去掉时分秒，如 "2024.03.15")；没有拍摄时间或相机写入全 0 占位时返回 None
+    pub fn capture_date(&self) -> Option<&str> {
+        self.capture_time
+            .split_whitespace()
+            .next()
+            .filter(|d| d.chars().any(|c| c.is_ascii_digit() && c != '0'))
+    }
+
+    /// 按指定的分隔符和顺序拼接参数，缺失的项直接跳过 (不会留下多余的分隔符)
+    pub fn format_with(&self, opts: &ParamFormatOptions) -> String {
+        opts.fields
+            .iter()
+            .filter_map(|field| match field {
+                ParamField::FocalLength => self.format_focal_length().map(|f| format!("{}mm", f)),
+                ParamField::Aperture => self.format_aperture().map(|a| format!("f/{}", a)),
+                ParamField::Shutter => self.shutter_speed.as_ref().map(|s| s.format_seconds()),
+                ParamField::Iso => self.iso.map(|iso| format!("ISO {}", iso)),
+            })
+            .collect::<Vec<_>>()
+            .join(opts.separator)
+    }
+
+    /// 光圈数值 (不带 f/ 前缀)，按机身显示习惯：
+    /// - 10 以下保留一位小数，整档也带 ".0" ("2.0" / "5.6")
+    /// - 10 及以上本来就是整数档 ("11" / "22")
+    /// - 1 以下的超大光圈保留两位 ("0.95")
+    ///
+    /// 先取整再格式化，手机 EXIF 里 1.7999999 这类浮点噪声显示为 "1.8"
+    pub fn format_aperture(&self) -> Option<String> {
+        let a = self.aperture.filter(|a| a.is_finite() && *a > 0.0)?;
+        Some(if a < 1.0 {
+            let a = (a * 100.0).round() / 100.0;
+            if (a * 10.0).fract() == 0.0 { format!("{:.1}", a) } else { format!("{:.2}", a) }
+        } else {
+            let a = (a * 10.0).round() / 10.0;
+            if a >= 10.0 && a.fract() == 0.0 { format!("{:.0}", a) } else { format!("{:.1}", a) }
+        })
+    }
+
+    /// 曝光补偿 (不带单位)：带符号保留一位小数 ("+0.7" / "-1.3")，
+    /// 1/3 档的 0.666.. 显示为 "0.7"；没有补偿 (取整后为 0) 时返回 None
+    pub fn format_ev(&self) -> Option<String> {
+        let ev = (self.exposure_bias.filter(|e| e.is_finite())? * 10.0).round() / 10.0;
+        (ev != 0.0).then(|| format!("{:+.1}", ev))
+    }
+
+    /// 焦距数值 (不带单位)：整数焦距显示整数 ("50")，否则保留一位小数 ("23.5")
+    /// 先按一位小数取整，避免 EXIF 有理数换算出的 49.99 之类显示成 "50.0"
+    pub fn format_focal_length(&self) -> Option<String> {
+        let f = (self.focal_length? * 10.0).round() / 10.0;
+        Some(if f.fract() == 0.0 { format!("{:.0}", f) } else { format!("{:.1}", f) })
+    }
+}
+
+
+// 🟢 2.0 测光模式 (EXIF MeteringMode)
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MeteringMode {
+    Average,
+    CenterWeighted,
+    Spot,
+    MultiSpot,
+    Pattern, // 矩阵 / 评价测光
+    Partial,
+    Other,
+}
+
+#[allow(dead_code)]
+impl MeteringMode {
+    /// 按 EXIF 数值解析，0 (未知) 视为没有数据
+    pub fn from_exif(value: u16) -> Option<Self> {
+        Some(match value {
+            0 => return None,
+            1 => Self::Average,
+            2 => Self::CenterWeighted,
+            3 => Self::Spot,
+            4 => Self::MultiSpot,
+            5 => Self::Pattern,
+            6 => Self::Partial,
+            _ => Self::Other,
+        })
+    }
 
-        // 焦距
-        if let Some(f) = self.focal_length {
-            parts.push(format!("{}mm", f));
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Average => "Average",
+            Self::CenterWeighted => "Center-weighted",
+            Self::Spot => "Spot",
+            Self::MultiSpot => "Multi-spot",
+            Self::Pattern => "Matrix",
+            Self::Partial => "Partial",
+            Self::Other => "Other",
         }
+    }
+}
+
+
+// 🟢 2.1 快门速度 (规范化)
+// EXIF 里的写法五花八门: "1/8000 s" / "0.5 s" / "30" / "10/2500" (未约分的有理数)，
+// 统一解析成分数或秒数，排版时不再各自做字符串清洗
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ShutterSpeed {
+    // 1/denom 秒 (高速快门，num 恒为 1)
+    Fraction { num: u32, denom: u32 },
+    // 秒数 (慢门，>= 0.25s 时按相机习惯显示小数)
+    Seconds(f32),
+}
 
-        // 光圈
-        if let Some(a) = self.aperture {
-            parts.push(format!("f/{}", a));
+impl ShutterSpeed {
+    // 低于该值显示为 1/x，否则显示秒数 (与机身取景器的显示习惯一致：1/4 仍是分数，0.3 起显示秒数)
+    const FRACTION_LIMIT: f32 = 0.3;
+
+    /// 解析 EXIF 文本，无法识别时返回 None
+    pub fn parse(raw: &str) -> Option<Self> {
+        let cleaned = raw
+            .trim()
+            .trim_end_matches(|c: char| c.is_alphabetic() || c.is_whitespace() || c == '.' || c == '"')
+            .trim();
+        if cleaned.is_empty() {
+            return None;
         }
 
-        // 快门 (直接用字符串，因为已经是清洗过的)
-        if !self.shutter_speed.is_empty() {
-            parts.push(self.shutter_speed.clone());
+        let seconds = match cleaned.split_once('/') {
+            Some((num, denom)) => {
+                let num: f32 = num.trim().parse().ok()?;
+                let denom: f32 = denom.trim().parse().ok()?;
+                if denom == 0.0 { return None; }
+                num / denom
+            }
+            None => cleaned.parse::<f32>().ok()?,
+        };
+        Self::from_seconds(seconds)
+    }
+
+    pub fn from_seconds(seconds: f32) -> Option<Self> {
+        if !seconds.is_finite() || seconds <= 0.0 {
+            return None;
+        }
+        if seconds < Self::FRACTION_LIMIT {
+            let denom = (1.0 / seconds).round().max(1.0) as u32;
+            Some(Self::Fraction { num: 1, denom })
+        } else {
+            Some(Self::Seconds(seconds))
         }
+    }
 
-        // ISO
-        if let Some(iso) = self.iso {
-            parts.push(format!("ISO {}", iso));
+    /// 不带单位的数值写法: "1/8000" / "0.5" / "30" (用于已有 "S" 标签的参数列)
+    pub fn format_fraction(&self) -> String {
+        match self {
+            Self::Fraction { num, denom } => format!("{}/{}", num, denom),
+            Self::Seconds(s) => {
+                // 先按十分位四舍五入再格式化 ({:.1} 直接格式化会按二进制值截断，0.25 变成 "0.2")
+                let text = format!("{:.1}", (s * 10.0).round() / 10.0);
+                text.trim_end_matches('0').trim_end_matches('.').to_string()
+            }
         }
+    }
 
-        parts.join("  ") // 用双空格分隔，视觉上更清晰
+    /// 带单位的写法: "1/8000s" / "0.5s" / "30s" (用于一行式参数)
+    pub fn format_seconds(&self) -> String {
+        format!("{}s", self.format_fraction())
     }
 }
 
-
 // 🟢 3. GPS 信息结构体
 #[allow(dead_code)]
 #[derive(Debug, Clone)]
@@ -85,13 +263,29 @@ pub struct GeoLocation {
 
 #[allow(dead_code)]
 impl GeoLocation {
-    // 辅助方法：格式化为字符串 "35°41'N 139°41'E"
+    // 两个分量都有才算有效坐标
+    pub fn from_coords(latitude: Option<f64>, longitude: Option<f64>) -> Option<Self> {
+        Some(Self { latitude: latitude?, longitude: longitude? })
+    }
+
+    // 辅助方法：格式化为字符串 "35°41'22"N 139°41'30"E"
     pub fn format_dms(&self) -> String {
-        // 这里可以实现一个简单的算法把小数转度分秒
-        format!("{:.4}, {:.4}", self.latitude, self.longitude) // 暂时简单返回
+        format!(
+            "{} {}",
+            dms_component(self.latitude, 'N', 'S'),
+            dms_component(self.longitude, 'E', 'W'),
+        )
     }
 }
 
+// 十进制度 -> 度分秒 (秒四舍五入，进位到分/度)
+fn dms_component(value: f64, positive: char, negative: char) -> String {
+    let hemisphere = if value < 0.0 { negative } else { positive };
+    let total_secs = (value.abs() * 3600.0).round() as u64;
+    let (deg, min, sec) = (total_secs / 3600, (total_secs / 60) % 60, total_secs % 60);
+    format!("{}°{}'{}\"{}", deg, min, sec, hemisphere)
+}
+
 // 🟢 4. 最终上下文 (The Clean Context)
 #[allow(dead_code)]
 #[derive(Debug, Clone)]
@@ -106,3 +300,125 @@ pub struct ParsedImageContext {
     pub gps: Option<GeoLocation>,
 }
 
+
+impl ParsedImageContext {
+    /// 边框上显示的品牌名：未识别的品牌 (Other，包括没有 EXIF 的胶片扫描) 不显示
+    pub fn brand_label(&self) -> String {
+        match self.brand {
+            Brand::Other => String::new(),
+            brand => brand.to_string(),
+        }
+    }
+
+    /// "品牌 机型"，任一为空时不留多余空格；机型已带品牌前缀 ("DJI FC1234") 时不再重复
+    pub fn title(&self) -> String {
+        let brand = self.brand_label();
+        let model = self.model_name.trim();
+        let has_prefix = model
+            .get(..brand.len())
+            .is_some_and(|p| p.eq_ignore_ascii_case(&brand))
+            && model[brand.len()..].starts_with(' ');
+        match (brand.is_empty(), model.is_empty()) {
+            (false, false) if has_prefix => model.to_string(),
+            (false, false) => format!("{} {}", brand, model),
+            (true, _) => model.to_string(),
+            (false, true) => brand,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fmt(raw: &str) -> String {
+        ShutterSpeed::parse(raw).map(|s| s.format_fraction()).unwrap_or_default()
+    }
+
+    #[test]
+    fn shutter_speed_exif_encodings() {
+        assert_eq!(fmt("1/8000"), "1/8000");
+        assert_eq!(fmt("0.5"), "0.5");
+        assert_eq!(fmt("30"), "30");
+        // 有理数编码：10/2500 = 1/250
+        assert_eq!(fmt("10/2500"), "1/250");
+        assert_eq!(fmt("2.5 s"), "2.5");
+        assert_eq!(fmt("1/0"), "");
+        assert_eq!(fmt(""), "");
+    }
+
+    #[test]
+    fn shutter_speed_fraction_boundary() {
+        let f = |s: f32| ShutterSpeed::from_seconds(s).unwrap().format_fraction();
+        assert_eq!(f(0.25), "1/4");
+        assert_eq!(f(1.0 / 3.0), "0.3");
+        assert_eq!(f(0.3), "0.3");
+        assert_eq!(f(0.5), "0.5");
+        assert_eq!(f(1.0), "1");
+        assert_eq!(f(2.5), "2.5");
+        assert_eq!(ShutterSpeed::from_seconds(2.5).unwrap().format_seconds(), "2.5s");
+    }
+
+    fn full_params() -> ShootingParams {
+        ShootingParams {
+            iso: Some(100),
+            aperture: Some(1.8),
+            shutter_speed: ShutterSpeed::parse("1/800"),
+            focal_length: Some(50.0),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn standard_format_matches_previous_output() {
+        assert_eq!(full_params().format_standard(), "50mm  f/1.8  1/800s  ISO 100");
+    }
+
+    #[test]
+    fn missing_fields_leave_no_dangling_separator() {
+        let pipes = ParamFormatOptions { separator: "  |  ", fields: ParamFormatOptions::ALL_FIELDS };
+        let no_aperture = ShootingParams { aperture: None, ..full_params() };
+        assert_eq!(no_aperture.format_with(&pipes), "50mm  |  1/800s  |  ISO 100");
+        // 首尾字段缺失
+        let ends_missing = ShootingParams { focal_length: None, iso: None, ..full_params() };
+        assert_eq!(ends_missing.format_with(&pipes), "f/1.8  |  1/800s");
+        // 只剩一个字段时不带分隔符
+        let only_iso = ShootingParams { iso: Some(3200), ..Default::default() };
+        assert_eq!(only_iso.format_with(&pipes), "ISO 3200");
+        // 没有 EXIF (胶片扫描) 时为空字符串
+        assert_eq!(ShootingParams::default().format_with(&pipes), "");
+    }
+
+    #[test]
+    fn field_order_and_subset() {
+        let opts = ParamFormatOptions { separator: " · ", fields: &[ParamField::Iso, ParamField::Aperture] };
+        assert_eq!(full_params().format_with(&opts), "ISO 100 · f/1.8");
+    }
+
+    #[test]
+    fn aperture_keeps_one_decimal() {
+        let f = |a: f32| ShootingParams { aperture: Some(a), ..Default::default() }.format_aperture();
+        assert_eq!(f(1.8).as_deref(), Some("1.8"));
+        assert_eq!(f(2.0).as_deref(), Some("2.0"));
+        assert_eq!(f(0.95).as_deref(), Some("0.95"));
+        assert_eq!(f(5.6).as_deref(), Some("5.6"));
+        // 手机 EXIF 的浮点噪声
+        assert_eq!(f(1.799_999_9_f64 as f32).as_deref(), Some("1.8"));
+        assert_eq!(f(16.0).as_deref(), Some("16"));
+        assert_eq!(f(f32::NAN), None);
+        assert_eq!(f(0.0), None);
+        // 参数行使用同一个格式
+        let params = ShootingParams { aperture: Some(2.0), ..Default::default() };
+        assert_eq!(params.format_standard(), "f/2.0");
+    }
+
+    #[test]
+    fn metering_mode_from_exif_values() {
+        // 0 是"未知"，等同于没有数据；保留值 (7..254) 与 255 归为 Other
+        assert_eq!(MeteringMode::from_exif(0), None);
+        assert_eq!(MeteringMode::from_exif(2), Some(MeteringMode::CenterWeighted));
+        assert_eq!(MeteringMode::from_exif(3), Some(MeteringMode::Spot));
+        assert_eq!(MeteringMode::from_exif(5).map(|m| m.label()), Some("Matrix"));
+        assert_eq!(MeteringMode::from_exif(255), Some(MeteringMode::Other));
+    }
+}
@@ -0,0 +1,80 @@
+// src/parser/sony_models.rs
+
+// =========================================================
+// 🟢 Sony 机型对照表：EXIF 内部型号 -> 商品名
+// =========================================================
+// EXIF 里的 Model 是内部型号 ("ILCE-7RM5")，边框上要显示商品名 ("α7R V")。
+// 新机型只需要在这里加一行；未收录的 ILCE 机型由 map_sony_model 按前缀兜底 ("ILCE-6100" -> "α6100")
+
+/// (内部型号, 商品名)，内部型号统一大写
+pub(crate) const SONY_MODELS: &[(&str, &str)] = &[
+    // --- α 旗舰 / 高速 ---
+    ("ILCE-1",    "α1"),
+    ("ILCE-9M3",  "α9 III"),
+    ("ILCE-9M2",  "α9 II"),
+    // --- α7R 高像素 ---
+    ("ILCE-7RM5", "α7R V"),
+    ("ILCE-7RM4", "α7R IV"),
+    ("ILCE-7RM3", "α7R III"),
+    // --- α7S 视频 ---
+    ("ILCE-7SM3", "α7S III"),
+    ("ILCE-7SM2", "α7S II"),
+    // --- α7 标准 ---
+    ("ILCE-7M5",  "α7 V"),
+    ("ILCE-7M4",  "α7 IV"),
+    ("ILCE-7M3",  "α7 III"),
+    ("ILCE-7M2",  "α7 II"),
+    // --- α7C 紧凑全画幅 ---
+    ("ILCE-7C",   "α7C"),
+    ("ILCE-7CM2", "α7C II"),
+    ("ILCE-7CR",  "α7CR"),
+    // --- APS-C ---
+    ("ILCE-6700", "α6700"),
+    ("ILCE-6600", "α6600"),
+    ("ILCE-6400", "α6400"),
+    // --- ZV Vlog ---
+    ("ZV-E1",     "ZV-E1"),
+    ("ZV-E10",    "ZV-E10"),
+    ("ZV-E10M2",  "ZV-E10 II"),
+    // --- Cinema Line (ILME) ---
+    ("ILME-FX3",  "FX3"),
+    ("ILME-FX30", "FX30"),
+];
+
+/// 按内部型号查商品名 (不区分大小写，忽略首尾空格)
+pub(crate) fn lookup(internal_name: &str) -> Option<&'static str> {
+    let key = internal_name.trim().to_uppercase();
+    SONY_MODELS.iter().find(|(k, _)| *k == key).map(|(_, name)| *name)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::parser::clean_model_name_logic;
+    use crate::parser::impls::SonyParser;
+    use crate::parser::models::RawExifData;
+    use crate::parser::traits::BrandParser;
+
+    #[test]
+    fn table_keys_are_unique_and_uppercase() {
+        let mut seen = HashSet::new();
+        for (key, _) in SONY_MODELS {
+            assert!(seen.insert(*key), "重复的内部型号: {}", key);
+            assert_eq!(*key, key.to_uppercase(), "内部型号应为大写: {}", key);
+        }
+    }
+
+    #[test]
+    fn parser_and_cleaner_agree_for_every_entry() {
+        for (key, name) in SONY_MODELS {
+            let raw = RawExifData { make: "SONY".into(), model: key.to_string(), ..Default::default() };
+            let parsed = SonyParser.parse(&raw).model_name;
+            assert_eq!(parsed, *name, "{}", key);
+            assert_eq!(clean_model_name_logic("SONY", key), parsed, "{}", key);
+            // 小写 / 带空格的写法查到同一项
+            assert_eq!(lookup(&format!(" {} ", key.to_lowercase())), Some(*name));
+        }
+    }
+}
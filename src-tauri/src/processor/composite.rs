@@ -0,0 +1,31 @@
+// src-tauri/src/processor/composite.rs
+use image::{DynamicImage, Rgba};
+
+use crate::error::AppError;
+use crate::parser::models::ParsedImageContext;
+use crate::processor::traits::{BackgroundKind, FrameProcessor};
+
+/// 🟢 组合处理器：按顺序执行多个处理器 (如 边框样式 -> 签名叠加)
+/// 第一阶段借用原图，之后每个阶段接管上一阶段的输出，中间结果不做额外拷贝
+pub struct CompositeProcessor {
+    pub stages: Vec<Box<dyn FrameProcessor + Send + Sync>>,
+}
+
+impl FrameProcessor for CompositeProcessor {
+    fn process(&self, img: &DynamicImage, ctx: &ParsedImageContext) -> Result<DynamicImage, AppError> {
+        let Some((first, rest)) = self.stages.split_first() else {
+            return Ok(img.clone());
+        };
+
+        let mut current = first.process(img, ctx)?;
+        for stage in rest {
+            current = stage.process_owned(current, ctx)?;
+        }
+        Ok(current)
+    }
+
+    // 画布由第一阶段 (边框样式) 决定，后续阶段只是叠加
+    fn background_kind(&self) -> BackgroundKind {
+        self.stages.first().map_or(BackgroundKind::Solid(Rgba([255, 255, 255, 255])), |s| s.background_kind())
+    }
+}
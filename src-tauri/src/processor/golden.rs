@@ -0,0 +1,354 @@
+// src/processor/golden.rs
+// 🟢 处理器回归测试 (golden)：每个样式对同一张合成渐变图出图，比对尺寸 + 感知哈希
+// 调整布局参数时能立刻看出哪个样式的版式变了 (居中偏了、底栏高度变了……)
+//
+// 基准值在 tests/golden/processors.txt，一行一个样式：`样式 宽x高 哈希`
+// 有意修改版式后重新生成：
+//   UPDATE_GOLDEN=1 cargo test golden
+// 感知哈希 (dHash) 对缩放 / 抗锯齿的细微差异不敏感，依赖库小版本升级不会误报；
+// 文字或照片位置移动几个百分点就会超出容差。
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use image::{DynamicImage, GenericImageView, Rgb, RgbImage};
+
+use crate::models::{FrameLabels, StyleOptions};
+use crate::parser::{self, models::RawExifData};
+
+// 允许不同的哈希位数 (共 256 位)
+const HASH_TOLERANCE: u32 = 8;
+
+fn golden_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/golden/processors.txt")
+}
+
+// 每个样式一份配置 (前端默认参数)
+fn fixtures() -> Vec<StyleOptions> {
+    [
+        serde_json::json!({ "style": "WhiteClassic" }),
+        serde_json::json!({ "style": "WhitePolaroid" }),
+        serde_json::json!({ "style": "WhiteMaster" }),
+        serde_json::json!({ "style": "TransparentClassic" }),
+        serde_json::json!({ "style": "TransparentMaster" }),
+        serde_json::json!({ "style": "WhiteModern" }),
+        serde_json::json!({ "style": "TravelWhite" }),
+        serde_json::json!({ "style": "AccentBar" }),
+        serde_json::json!({ "style": "Signature", "text": "Nikon Framer", "fontScale": 0.05, "bottomRatio": 0.1 }),
+    ]
+    .into_iter()
+    .map(|v| serde_json::from_value(v).unwrap())
+    .collect()
+}
+
+// 1200x800 的双向渐变，四个角颜色各不相同；叠加 150x200 的明暗格子
+// (单调渐变的 dHash 几乎全是 0，照片区域移动了也看不出来)
+fn gradient() -> DynamicImage {
+    DynamicImage::ImageRgb8(RgbImage::from_fn(1200, 800, |x, y| {
+        let blue = if (x / 150 + y / 200) % 2 == 0 { 255 } else { 0 };
+        Rgb([(x * 255 / 1199) as u8, (y * 255 / 799) as u8, blue])
+    }))
+}
+
+fn exif() -> RawExifData {
+    RawExifData {
+        make: "NIKON CORPORATION".to_string(),
+        model: "NIKON Z 8".to_string(),
+        lens: Some("NIKKOR Z 50mm f/1.8 S".to_string()),
+        iso: Some(100),
+        aperture: Some(1.8),
+        shutter_speed: "1/250 s".to_string(),
+        focal_length: Some(50.0),
+        datetime: Some("2024:01:02 03:04:05".to_string()),
+        ..Default::default()
+    }
+}
+
+/// 差值哈希：缩成 17x16 灰度，每行相邻像素比较明暗，得到 256 位
+fn dhash(img: &DynamicImage) -> [u64; 4] {
+    let small = img.resize_exact(17, 16, image::imageops::FilterType::Triangle).to_luma8();
+    let mut bits = [0u64; 4];
+    for y in 0..16u32 {
+        for x in 0..16u32 {
+            if small.get_pixel(x, y)[0] > small.get_pixel(x + 1, y)[0] {
+                let i = (y * 16 + x) as usize;
+                bits[i / 64] |= 1 << (i % 64);
+            }
+        }
+    }
+    bits
+}
+
+fn hash_hex(bits: &[u64; 4]) -> String {
+    bits.iter().map(|b| format!("{:016x}", b)).collect()
+}
+
+fn parse_hex(s: &str) -> Option<[u64; 4]> {
+    let mut bits = [0u64; 4];
+    for (i, b) in bits.iter_mut().enumerate() {
+        *b = u64::from_str_radix(s.get(i * 16..(i + 1) * 16)?, 16).ok()?;
+    }
+    Some(bits)
+}
+
+fn hamming(a: &[u64; 4], b: &[u64; 4]) -> u32 {
+    a.iter().zip(b).map(|(x, y)| (x ^ y).count_ones()).sum()
+}
+
+/// 每个样式对合成渐变图的出图 (按样式后缀排序)
+fn render_fixtures() -> BTreeMap<&'static str, DynamicImage> {
+    let img = gradient();
+    let ctx = parser::parse(exif());
+    let labels = FrameLabels::default();
+    fixtures()
+        .into_iter()
+        .map(|style| {
+            let out = super::create_processor(&style, &labels).unwrap().process(&img, &ctx).unwrap();
+            (style.filename_suffix(), out)
+        })
+        .collect()
+}
+
+/// FNV-1a 64：逐字节哈希 RGBA 像素 (与 sidecar 的源文件哈希同一算法)
+fn pixel_hash(img: &DynamicImage) -> u64 {
+    img.to_rgba8().as_raw().iter().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[test]
+fn golden_processor_outputs() {
+    let actual: BTreeMap<_, _> = render_fixtures()
+        .into_iter()
+        .map(|(name, out)| (name, (out.dimensions(), dhash(&out))))
+        .collect();
+
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        let lines: Vec<String> = actual
+            .iter()
+            .map(|(name, ((w, h), bits))| format!("{} {}x{} {}", name, w, h, hash_hex(bits)))
+            .collect();
+        std::fs::create_dir_all(golden_path().parent().unwrap()).unwrap();
+        std::fs::write(golden_path(), lines.join("\n") + "\n").unwrap();
+        return;
+    }
+
+    let golden = std::fs::read_to_string(golden_path()).expect("缺少 golden 基准，先用 UPDATE_GOLDEN=1 生成");
+    let mut expected = BTreeMap::new();
+    for line in golden.lines().filter(|l| !l.trim().is_empty()) {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        let [name, dims, hash] = parts[..] else { panic!("golden 行格式错误: {}", line) };
+        let (w, h) = dims.split_once('x').unwrap();
+        expected.insert(name, ((w.parse::<u32>().unwrap(), h.parse::<u32>().unwrap()), parse_hex(hash).unwrap()));
+    }
+
+    assert_eq!(
+        actual.keys().collect::<Vec<_>>(),
+        expected.keys().collect::<Vec<_>>(),
+        "样式列表与 golden 不一致 (新增样式后用 UPDATE_GOLDEN=1 重新生成)"
+    );
+    for (name, (dims, bits)) in &actual {
+        let (want_dims, want_bits) = &expected[name];
+        assert_eq!(dims, want_dims, "{}: 输出尺寸变化", name);
+        let distance = hamming(bits, want_bits);
+        assert!(distance <= HASH_TOLERANCE, "{}: 画面与 golden 相差 {} 位 (容差 {})", name, distance, HASH_TOLERANCE);
+    }
+}
+
+// 🟢 逐像素基准：合并绘图原语等纯重构必须与重构前逐像素一致，感知哈希的容差会放过这类细微差异
+// 基准值在 tests/golden/pixels.txt (`样式 宽x高 哈希`)，与 processors.txt 一起用 UPDATE_GOLDEN=1 重新生成。
+// 逐像素比较对依赖库升级 (缩放 / 字形光栅化) 敏感，升级后确认画面无误再重新生成
+#[test]
+fn exact_output_hashes() {
+    let path = golden_path().with_file_name("pixels.txt");
+    let lines: Vec<String> = render_fixtures()
+        .iter()
+        .map(|(name, out)| format!("{} {}x{} {:016x}", name, out.width(), out.height(), pixel_hash(out)))
+        .collect();
+
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::write(&path, lines.join("\n") + "\n").unwrap();
+        return;
+    }
+
+    let golden = std::fs::read_to_string(&path).expect("缺少逐像素基准，先用 UPDATE_GOLDEN=1 生成");
+    let expected: Vec<&str> = golden.lines().filter(|l| !l.trim().is_empty()).collect();
+    assert_eq!(lines, expected, "样式输出与逐像素基准不一致 (有意修改后用 UPDATE_GOLDEN=1 重新生成)");
+}
+
+// 🟢 极端尺寸：小图 + 默认参数 (字号相对变大) 会算出负坐标 / 越界区域，所有样式都不能 panic
+#[test]
+fn tiny_sources_render_every_style() {
+    let ctx = parser::parse(exif());
+    let labels = FrameLabels::default();
+    for (w, h) in [(64, 64), (64, 8), (8, 64), (1, 1)] {
+        let img = DynamicImage::ImageRgb8(RgbImage::from_pixel(w, h, Rgb([90, 120, 150])));
+        for style in fixtures() {
+            let name = style.filename_suffix();
+            let out = super::create_processor(&style, &labels).unwrap()
+                .process(&img, &ctx)
+                .unwrap_or_else(|e| panic!("{} {}x{}: {}", name, w, h, e));
+            assert!(out.width() > 0 && out.height() > 0, "{} {}x{}: 空输出", name, w, h);
+        }
+    }
+}
+
+#[test]
+fn dhash_detects_shifted_content() {
+    // 渐变上的黑块 (相当于一段文字) 横向移动 10%，必须超出容差
+    let with_block = |left: u32| {
+        let mut img = gradient().to_rgb8();
+        for x in left..left + 200 {
+            for y in 300..500 {
+                img.put_pixel(x, y, Rgb([0, 0, 0]));
+            }
+        }
+        DynamicImage::ImageRgb8(img)
+    };
+    let base = dhash(&with_block(300));
+    assert_eq!(hamming(&base, &dhash(&with_block(300))), 0);
+    assert!(hamming(&base, &dhash(&with_block(420))) > HASH_TOLERANCE);
+}
+
+#[test]
+fn master_dimensions_by_aspect() {
+    // Master 系列底栏按高度计算，只有比 2:3 更高的竖图封顶：各比例的成品尺寸固定下来
+    // (2:3 竖图与改动之前一致；9:16 底栏按 2:3 的高度算，不再随高度无限变大)
+    let ctx = parser::parse(exif());
+    let labels = FrameLabels::default();
+    let cases = [
+        ("WhiteMaster", [((1800, 600), (1836, 876)), ((1200, 1200), (1272, 1752)), ((800, 1200), (872, 1752)), ((900, 1600), (996, 2236))]),
+        ("TransparentMaster", [((1800, 600), (1836, 858)), ((1200, 1200), (1272, 1716)), ((800, 1200), (872, 1716)), ((900, 1600), (996, 2188))]),
+    ];
+    for (style, sizes) in cases {
+        let options: StyleOptions = serde_json::from_value(serde_json::json!({ "style": style })).unwrap();
+        let processor = super::create_processor(&options, &labels).unwrap();
+        for ((w, h), want) in sizes {
+            let img = DynamicImage::ImageRgb8(RgbImage::from_pixel(w, h, Rgb([120, 130, 140])));
+            let out = processor.process(&img, &ctx).unwrap();
+            assert_eq!(out.dimensions(), want, "{} {}x{}", style, w, h);
+        }
+    }
+}
+
+// 🟢 WhiteModern 品牌色胶囊：每个品牌一份逐像素基准 (tests/golden/accent_badges.txt，同样用 UPDATE_GOLDEN=1 重新生成)
+// 与不开品牌色的出图逐像素比较：差异只能落在底栏 (照片和阴影不受影响)，胶囊描边确实换成了压暗后的强调色
+#[test]
+fn accent_badges_per_brand() {
+    let path = golden_path().with_file_name("accent_badges.txt");
+    let img = gradient();
+    let labels = FrameLabels::default();
+    let render = |make: &str, model: &str, accent: bool| {
+        let style: StyleOptions = serde_json::from_value(serde_json::json!({ "style": "WhiteModern", "accentBadges": accent })).unwrap();
+        let ctx = parser::parse(RawExifData { make: make.to_string(), model: model.to_string(), ..exif() });
+        let brand = ctx.brand;
+        (brand, super::create_processor(&style, &labels).unwrap().process(&img, &ctx).unwrap().to_rgba8())
+    };
+
+    let cameras = [
+        ("NIKON CORPORATION", "NIKON Z 8"),
+        ("SONY", "ILCE-7M4"),
+        ("Canon", "Canon EOS R5"),
+        ("FUJIFILM", "X-T5"),
+        ("LEICA CAMERA AG", "LEICA Q3"),
+        ("Hasselblad", "X2D 100C"),
+    ];
+    let mut lines = Vec::new();
+    for (make, model) in cameras {
+        let (brand, plain) = render(make, model, false);
+        let (_, accented) = render(make, model, true);
+        assert_eq!(plain.dimensions(), accented.dimensions(), "{:?}", brand);
+
+        // 差异全部在照片下方的底栏里
+        let centre = img.get_pixel(img.width() / 2, 0);
+        let photo_top = (0..plain.height())
+            .find(|&y| *plain.get_pixel(plain.width() / 2, y) == centre)
+            .expect("找不到照片");
+        let first_diff = plain.enumerate_pixels()
+            .find(|(x, y, p)| *p != accented.get_pixel(*x, *y))
+            .map(|(_, y, _)| y)
+            .unwrap_or_else(|| panic!("{:?}: 开启品牌色后画面没有变化", brand));
+        assert!(first_diff >= photo_top + img.height(), "{:?}: 第 {} 行就出现差异", brand, first_diff);
+
+        // 胶囊描边是实心填充，压暗后的强调色会原样出现在画面上
+        let border = crate::graphics::ensure_contrast(brand.accent_color(), image::Rgba([255, 255, 255, 255]), 3.0);
+        let border_pixels = accented.pixels().filter(|p| **p == border).count();
+        assert!(border_pixels > 1000, "{:?}: 描边色 {:?} 只有 {} 个像素", brand, border, border_pixels);
+
+        lines.push(format!("{:?} {}x{} {:016x}", brand, accented.width(), accented.height(), pixel_hash(&DynamicImage::ImageRgba8(accented))));
+    }
+
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::write(&path, lines.join("\n") + "\n").unwrap();
+        return;
+    }
+    let golden = std::fs::read_to_string(&path).expect("缺少品牌色基准，先用 UPDATE_GOLDEN=1 生成");
+    let expected: Vec<&str> = golden.lines().filter(|l| !l.trim().is_empty()).collect();
+    assert_eq!(lines, expected, "品牌色胶囊与逐像素基准不一致 (有意修改后用 UPDATE_GOLDEN=1 重新生成)");
+}
+
+// 🟢 下伸部 (g / y / p) 的文字按基线放置：WhiteModern 的机型行与不带下伸部的机型共用基线，
+// 下伸部伸到基线以下 descent 处 (旧的按 text_size 居中会把整行抬高)
+#[test]
+fn descenders_hang_below_the_model_baseline() {
+    use ab_glyph::PxScale;
+    use crate::graphics::measure_text_bounds;
+    use crate::resources::{self, FontFamily, FontWeight};
+
+    // 未知品牌不画品牌标题；关掉阴影后照片下方第一段有内容的行就是机型文字的墨迹
+    let img = gradient();
+    let labels = FrameLabels::default();
+    let style: StyleOptions = serde_json::from_value(serde_json::json!({ "style": "WhiteModern", "shadowIntensity": 0.0 })).unwrap();
+    let processor = super::create_processor(&style, &labels).unwrap();
+    let ink_rows = |model: &str| {
+        let ctx = parser::parse(RawExifData { make: "Framer Test".to_string(), model: model.to_string(), ..exif() });
+        assert_eq!(ctx.brand_label(), "", "{}", model);
+        let out = processor.process(&img, &ctx).unwrap().to_rgba8();
+        let bg = *out.get_pixel(0, out.height() - 1);
+        let inked: Vec<bool> = out.rows().map(|mut row| row.any(|p| *p != bg)).collect();
+        // 连续有内容的行段：照片、机型、胶囊……
+        let mut runs = Vec::new();
+        let mut start = None;
+        for (y, &ink) in inked.iter().chain([&false]).enumerate() {
+            match (ink, start) {
+                (true, None) => start = Some(y as i32),
+                (false, Some(s)) => { runs.push((s, y as i32 - 1)); start = None; },
+                _ => {},
+            }
+        }
+        assert!(runs.len() > 2, "{}: 找不到机型行 {:?}", model, runs);
+        runs[1]
+    };
+
+    // "Z" 没有下伸部，墨迹下沿就是基线
+    let (top, bottom) = ink_rows("Z");
+    let baseline = bottom + 1;
+    // 字号由大写字母高度反推
+    let font = resources::get_font(FontFamily::InterDisplay, FontWeight::Medium).unwrap();
+    let (_, cap_100, _) = measure_text_bounds(&font, "Z", PxScale::from(100.0));
+    let scale = PxScale::from(100.0 * (baseline - top) as f32 / cap_100);
+
+    for model in ["Zgy", "Zpq"] {
+        let (_, _, descent) = measure_text_bounds(&font, model, scale);
+        let (desc_top, desc_bottom) = ink_rows(model);
+        assert!((desc_top - top).abs() <= 1, "{}: 顶部 {} vs {}", model, desc_top, top);
+        let expected = baseline as f32 + descent;
+        assert!(((desc_bottom + 1) as f32 - expected).abs() <= 2.0, "{}: 下沿 {} vs 基线 {} + {:.1}", model, desc_bottom + 1, baseline, descent);
+    }
+}
+
+// 🟢 16 位原图：只有直接在原图上绘制的样式保留 16 位，其余样式在 8 位画布上合成
+// (保存时按成品位深提醒用户降级；新增 16 位合成路径后更新这里)
+#[test]
+fn sixteen_bit_sources_keep_depth_only_when_drawn_in_place() {
+    let img = DynamicImage::ImageRgb16(gradient().to_rgb16());
+    let ctx = parser::parse(exif());
+    let labels = FrameLabels::default();
+    for style in fixtures() {
+        let name = style.filename_suffix();
+        let out = super::create_processor(&style, &labels).unwrap().process(&img, &ctx).unwrap();
+        let color = out.color();
+        let want = if matches!(name, "TransparentMaster" | "Signature") { 16 } else { 8 };
+        assert_eq!(color.bits_per_pixel() / color.channel_count() as u16, want, "{}: {:?}", name, color);
+    }
+}
@@ -0,0 +1,67 @@
+// src-tauri/src/processor/layout.rs
+
+use std::cmp::max;
+
+// =========================================================
+// 🟢 底栏高度钳制 (白底 / 透明系列共用)
+// =========================================================
+// 各样式原本直接按 src_h (或短边) 乘比例得到底栏高度，极端比例下会失控：
+// - 3:1、6:1 全景：底栏相对整幅画面只剩一条细缝，文字小到看不清
+// - 9:16 竖图：按整个高度算出的底栏巨大，参数列之间挤在一起
+// 这里统一做两件事：按高度算比例的样式的基准高度最多按 2:3 竖图计算，
+// 再把结果夹在 [数值字号下限 × 2.2, 原图高度 × 45%] 之间。常见的 3:2 / 1:1 / 2:3 比例不受影响。
+
+/// 基准高度最多是宽度的几倍 (2:3 竖图)：更高的竖图 (9:16 等) 按 2:3 的高度算
+const MAX_BASIS_ASPECT: f32 = 1.5;
+/// 数值字号的下限 (相对长边)：按高度算出的字号在全景画面里不能比这更小
+const MIN_VALUE_FONT_RATIO: f32 = 0.025;
+/// 底栏至少是数值字号下限的几倍 (文字本身 + 上下留白)
+const MIN_FONT_MULTIPLE: f32 = 2.2;
+/// 底栏最多占原图高度的比例 (与下限冲突时以它为准)
+const MAX_HEIGHT_RATIO: f32 = 0.45;
+
+/// 按高度算比例的样式 (Master 系列) 的基准尺寸：原图高度，比 2:3 更高的竖图封顶在宽度 × 1.5
+pub fn bar_basis(src_w: u32, src_h: u32) -> f32 {
+    (src_h as f32).min(src_w as f32 * MAX_BASIS_ASPECT)
+}
+
+/// 把样式按比例算出的底栏高度夹到合理范围内
+pub fn clamp_bar_height(raw: f32, src_w: u32, src_h: u32) -> u32 {
+    let long_edge = max(src_w, src_h) as f32;
+    let min_h = long_edge * MIN_VALUE_FONT_RATIO * MIN_FONT_MULTIPLE;
+    let max_h = src_h as f32 * MAX_HEIGHT_RATIO;
+    raw.max(min_h).min(max_h).max(0.0).round() as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 3:1 全景、1:1、2:3 竖图、9:16 竖图
+    const SIZES: [(u32, u32); 4] = [(1800, 600), (1200, 1200), (800, 1200), (900, 1600)];
+
+    #[test]
+    fn basis_caps_only_portraits_taller_than_2_3() {
+        let basis: Vec<f32> = SIZES.iter().map(|&(w, h)| bar_basis(w, h)).collect();
+        // 2:3 及更宽的画面按原图高度，9:16 按宽度 × 1.5
+        assert_eq!(basis, [600.0, 1200.0, 1200.0, 1350.0]);
+    }
+
+    #[test]
+    fn master_bar_heights() {
+        // Master 系列默认 bottom_ratio = 0.4
+        let heights: Vec<u32> = SIZES.iter().map(|&(w, h)| clamp_bar_height(bar_basis(w, h) * 0.4, w, h)).collect();
+        assert_eq!(heights, [240, 480, 480, 540]);
+        // 2:3 竖图与改动之前 (直接按高度) 完全一致
+        assert_eq!(heights[2], (1200.0f32 * 0.4).round() as u32);
+    }
+
+    #[test]
+    fn clamp_keeps_bar_readable_and_bounded() {
+        // 3:1 全景：按比例只有 100px，抬到数值字号下限 × 2.2 (长边 3000 × 0.055 = 165)
+        assert_eq!(clamp_bar_height(100.0, 3000, 1000), 165);
+        // 上限：原图高度的 45%；6:1 全景的下限 (198) 超过上限 (180) 时以上限为准
+        assert_eq!(clamp_bar_height(5000.0, 1000, 1000), 450);
+        assert_eq!(clamp_bar_height(400.0 * 0.4, 3600, 400), 180);
+    }
+}
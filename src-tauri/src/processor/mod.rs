@@ -1,14 +1,20 @@
 // 1. 声明子模块
+pub mod composite;
+pub mod layout;
 pub mod transparent_classic;
 pub mod traits;
 pub mod transparent_master;// 🟢
 pub mod signature;
 pub mod white;
-use image::{DynamicImage, imageops};
+#[cfg(test)]
+mod golden;
 
 
 // 3. 引入项目内部模块
-use crate::models::StyleOptions;
+use crate::error::AppError;
+use crate::graphics::effects::GlassConfig;
+use crate::models::{BatchContext, DateFont, FrameLabels, FrameTheme, SignatureConfig, StyleOptions};
+use crate::processor::composite::CompositeProcessor;
 use crate::processor::signature::SignatureProcessor;
 use crate::processor::traits::FrameProcessor; 
 
@@ -17,6 +23,8 @@ use crate::processor::white::white_classic_v2::WhiteClassicProcessorV2;
 use crate::processor::white::white_master_v2::WhiteMasterProcessorV2;
 use crate::processor::white::white_modern_v2::WhiteModernProcessorV2;
 use crate::processor::white::white_polaroid_v2::WhitePolaroidProcessorV2;
+use crate::processor::white::travel_white::TravelProcessor;
+use crate::processor::white::accent_bar::AccentBarProcessor;
 // 引入资源管理
 use crate::resources::{self, FontFamily, FontWeight};
 
@@ -24,76 +32,162 @@ use crate::resources::{self, FontFamily, FontWeight};
 use crate::processor::transparent_classic::TransparentClassicProcessor;
 
 
-// --- 公共辅助函数 ---
+// ==========================================
+// 按批次配置组装：样式处理器 + 可选的叠加签名
+// ==========================================
+pub fn create_context_processor(context: &BatchContext) -> Result<Box<dyn FrameProcessor + Send + Sync>, AppError> {
+    let labels = context.labels.clone().unwrap_or_default();
+    let style = create_processor(&context.options, &labels)?;
+    let Some(sig) = &context.signature else {
+        return Ok(style);
+    };
+    Ok(Box::new(CompositeProcessor {
+        stages: vec![style, create_signature_processor(sig)?],
+    }))
+}
 
-/// 根据高度调整图片大小 (保持长宽比)
-pub fn resize_image_by_height(img: &DynamicImage, target_height: u32) -> DynamicImage {
-    img.resize(target_height * 10, target_height, imageops::FilterType::Lanczos3)
+fn create_signature_processor(sig: &SignatureConfig) -> Result<Box<dyn FrameProcessor + Send + Sync>, AppError> {
+    Ok(Box::new(SignatureProcessor {
+        font: resources::get_font(FontFamily::InterDisplay, FontWeight::Medium)?,
+        text: sig.text.clone(),
+        font_scale: sig.font_scale,
+        bottom_ratio: sig.bottom_ratio,
+    }))
 }
 
 // ==========================================
 // 工厂函数: 核心装配车间
 // ==========================================
-pub fn create_processor(options: &StyleOptions) -> Box<dyn FrameProcessor + Send + Sync> {
-    match options {
+// 字体在这里加载，缺失/损坏时返回 AppError::Font (批次直接报错，而不是每张图都失败)
+// labels 只有带固定文字的样式 (大师系列 / Modern) 会用到
+pub fn create_processor(options: &StyleOptions, labels: &FrameLabels) -> Result<Box<dyn FrameProcessor + Send + Sync>, AppError> {
+    let processor: Box<dyn FrameProcessor + Send + Sync> = match options {
         
         // 1. 极简白底模式
-        StyleOptions::WhiteClassic => {
+        StyleOptions::WhiteClassic { brand_badge, show_artist, theme, params_separator, layout_overrides } => {
             Box::new(WhiteClassicProcessorV2 { 
-                font_data: resources::get_font(FontFamily::InterDisplay, FontWeight::Bold) 
+                font_data: resources::get_font(FontFamily::InterDisplay, FontWeight::Bold)?,
+                brand_badge: *brand_badge,
+                show_artist: *show_artist,
+                theme: *theme,
+                params_separator: *params_separator,
+                layout_overrides: layout_overrides.clone(),
             })
         },
 
         // 2. 高斯模糊模式
-        StyleOptions::TransparentClassic => {
+        StyleOptions::TransparentClassic { show_lens, shadow_intensity, glass_border_thickness, glass_border_color, bg_brightness, bg_saturation, auto_bg_brightness, layout_overrides } => {
             Box::new(TransparentClassicProcessor { 
-                font_data: resources::get_font(FontFamily::InterDisplay, FontWeight::Medium),
+                font_data: resources::get_font(FontFamily::InterDisplay, FontWeight::Medium)?,
+                show_lens: *show_lens,
+                shadow_intensity: *shadow_intensity,
+                glass: GlassConfig::with_overrides(*glass_border_thickness, *glass_border_color),
+                bg_brightness: *bg_brightness,
+                bg_saturation: *bg_saturation,
+                auto_bg_brightness: *auto_bg_brightness,
+                layout_overrides: layout_overrides.clone(),
             })
         },
 
         // 3. 大师透明模式
-        StyleOptions::TransparentMaster => {
+        StyleOptions::TransparentMaster { show_artist, bg_brightness, bg_saturation, auto_bg_brightness, layout_overrides } => {
             Box::new(TransparentMasterProcessor {
-                main_font: resources::get_font(FontFamily::InterDisplay, FontWeight::Medium),
-                script_font: resources::get_font(FontFamily::MrDafoe, FontWeight::Regular),
-                serif_font: resources::get_font(FontFamily::AbhayaLibre, FontWeight::Medium),
+                main_font: resources::get_font(FontFamily::InterDisplay, FontWeight::Medium)?,
+                script_font: resources::get_font(FontFamily::MrDafoe, FontWeight::Regular)?,
+                serif_font: resources::get_font(FontFamily::AbhayaLibre, FontWeight::Medium)?,
+                show_artist: *show_artist,
+                bg_brightness: *bg_brightness,
+                bg_saturation: *bg_saturation,
+                auto_bg_brightness: *auto_bg_brightness,
+                labels: labels.clone(),
+                layout_overrides: layout_overrides.clone(),
             })
         },
 
         // 4. 拍立得模式
-        StyleOptions::WhitePolaroid => {
+        StyleOptions::WhitePolaroid { photo_offset, theme, show_date, date_font, caption_mode, params_separator, layout_overrides } => {
+            // 日期字体只在开启时加载
+            let date_font = match (show_date, date_font) {
+                (false, _) => None,
+                (true, DateFont::Script) => Some((resources::get_font(FontFamily::MrDafoe, FontWeight::Regular)?, DateFont::Script)),
+                (true, DateFont::Regular) => Some((resources::get_font(FontFamily::InterDisplay, FontWeight::Medium)?, DateFont::Regular)),
+            };
             Box::new(WhitePolaroidProcessorV2 {
-                font_data: resources::get_font(FontFamily::InterDisplay, FontWeight::Medium),
+                font_data: resources::get_font(FontFamily::InterDisplay, FontWeight::Medium)?,
+                caption_font: resources::get_font(FontFamily::AbhayaLibre, FontWeight::Medium)?,
+                photo_offset: *photo_offset,
+                theme: *theme,
+                date_font,
+                caption_mode: *caption_mode,
+                params_separator: *params_separator,
+                layout_overrides: layout_overrides.clone(),
             })
         },
 
         // 5. 大师白底模式 (🟢 新增)
-        StyleOptions::WhiteMaster => {
+        StyleOptions::WhiteMaster { photo_offset, theme, show_ev, show_artist, layout_overrides } => {
             Box::new(WhiteMasterProcessorV2 {
-                main_font: resources::get_font(FontFamily::InterDisplay, FontWeight::Medium),
-                script_font: resources::get_font(FontFamily::MrDafoe, FontWeight::Regular),
-                serif_font: resources::get_font(FontFamily::AbhayaLibre, FontWeight::Medium),
+                main_font: resources::get_font(FontFamily::InterDisplay, FontWeight::Medium)?,
+                script_font: resources::get_font(FontFamily::MrDafoe, FontWeight::Regular)?,
+                serif_font: resources::get_font(FontFamily::AbhayaLibre, FontWeight::Medium)?,
+                photo_offset: *photo_offset,
+                theme: *theme,
+                show_ev: *show_ev,
+                show_artist: *show_artist,
+                labels: labels.clone(),
+                layout_overrides: layout_overrides.clone(),
             })
         },
 
-        StyleOptions::WhiteModern => {
+        StyleOptions::WhiteModern { photo_offset, theme, shadow_intensity, show_ev, accent_badges, layout_overrides } => {
             Box::new(WhiteModernProcessorV2 {
                 // Modern 风格建议搭配无衬线字体
-                font_bold: resources::get_font(FontFamily::InterDisplay, FontWeight::Bold),
-                font_medium: resources::get_font(FontFamily::InterDisplay, FontWeight::Medium),
-                font_script: resources::get_font(FontFamily::Birthstone, FontWeight::Regular),
-                font_regular: resources::get_font(FontFamily::InterDisplay, FontWeight::Regular),
+                // 胶囊数值用 SemiBold：Bold 在小胶囊里显得太重
+                font_semibold: resources::get_font(FontFamily::InterDisplay, FontWeight::SemiBold)?,
+                font_medium: resources::get_font(FontFamily::InterDisplay, FontWeight::Medium)?,
+                font_script: resources::get_font(FontFamily::Birthstone, FontWeight::Regular)?,
+                font_serif: resources::get_font(FontFamily::AbhayaLibre, FontWeight::Medium)?,
+                font_regular: resources::get_font(FontFamily::InterDisplay, FontWeight::Regular)?,
+                photo_offset: *photo_offset,
+                theme: *theme,
+                shadow_intensity: *shadow_intensity,
+                show_ev: *show_ev,
+                accent_badges: *accent_badges,
+                labels: labels.clone(),
+                layout_overrides: layout_overrides.clone(),
+            })
+        },
+        // 旅行白底：右侧显示坐标 + 日期
+        StyleOptions::TravelWhite { layout_overrides } => {
+            Box::new(TravelProcessor {
+                font_bold: resources::get_font(FontFamily::InterDisplay, FontWeight::Bold)?,
+                font_medium: resources::get_font(FontFamily::InterDisplay, FontWeight::Medium)?,
+                layout_overrides: layout_overrides.clone(),
+            })
+        },
+        // 主色底栏：复用经典白底的布局，底栏颜色逐张计算
+        StyleOptions::AccentBar { brand_badge, show_artist, params_separator, layout_overrides } => {
+            Box::new(AccentBarProcessor {
+                classic: WhiteClassicProcessorV2 {
+                    font_data: resources::get_font(FontFamily::InterDisplay, FontWeight::Bold)?,
+                    brand_badge: *brand_badge,
+                    show_artist: *show_artist,
+                    theme: FrameTheme::Light,
+                    params_separator: *params_separator,
+                    layout_overrides: layout_overrides.clone(),
+                },
             })
         },
         // 🟢 修复 Signature 模式的初始化逻辑
         StyleOptions::Signature { text, font_scale, bottom_ratio } => {
             Box::new(SignatureProcessor {
-                font: resources::get_font(FontFamily::InterDisplay, FontWeight::Medium),
+                font: resources::get_font(FontFamily::InterDisplay, FontWeight::Medium)?,
                 text: text.clone(),
                 font_scale: *font_scale,
                 bottom_ratio: *bottom_ratio,
             })
         },
 
-    }
+    };
+    Ok(processor)
 }
\ No newline at end of file
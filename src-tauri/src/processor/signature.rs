@@ -1,9 +1,8 @@
 use image::{DynamicImage, Rgba};
-use imageproc::drawing::draw_text_mut;
 use ab_glyph::{Font, FontArc, PxScale, ScaleFont};
 use crate::error::AppError;
 use crate::parser::models::ParsedImageContext;
-use crate::processor::traits::FrameProcessor;
+use crate::processor::traits::{BackgroundKind, FrameProcessor};
 use crate::graphics::{self, calculate_browser_baseline_offset, calculate_corrected_font_size};
 
 pub struct SignatureProcessor {
@@ -17,10 +16,18 @@ impl FrameProcessor for SignatureProcessor {
     fn process(
         &self,
         img: &DynamicImage,
-        _ctx: &ParsedImageContext
+        ctx: &ParsedImageContext
     ) -> Result<DynamicImage, AppError> {
-        
-        let mut canvas = img.clone();
+        self.process_owned(img.clone(), ctx)
+    }
+
+    // 签名不加边框，补边时按白底处理
+    fn background_kind(&self) -> BackgroundKind {
+        BackgroundKind::Solid(Rgba([255, 255, 255, 255]))
+    }
+
+    // 签名只是在原图上叠加文字，直接在传入的图上绘制
+    fn process_owned(&self, mut canvas: DynamicImage, _ctx: &ParsedImageContext) -> Result<DynamicImage, AppError> {
         let width = canvas.width();
         let height = canvas.height();
 
@@ -33,7 +40,7 @@ impl FrameProcessor for SignatureProcessor {
         let scaled_font = self.font.as_scaled(scale);
 
         // 2. X轴计算 (水平居中)
-        let (text_w, _text_h) = graphics::text_size(&self.text, scale, &self.font);
+        let (text_w, _text_h) = graphics::text_size_with_fallback(scale, &self.font, &self.text);
         let x = (width as i32 - text_w as i32) / 2;
 
         // 3. Y轴计算 (基线对齐)
@@ -51,7 +58,8 @@ impl FrameProcessor for SignatureProcessor {
         // -------------------------------------------------------------
         let white = Rgba([255, 255, 255, 240]); 
         
-        draw_text_mut(
+        // 签名由用户输入，可能含中日文：缺字部分用 CJK 回退字体
+        graphics::draw_text_with_fallback(
             &mut canvas,
             white,
             x,
@@ -1,6 +1,15 @@
 // src-tauri/src/processor/traits.rs
-use image::DynamicImage;
+use std::collections::{HashMap, HashSet};
+use std::ops::RangeInclusive;
+use std::sync::Mutex;
+
+use image::{DynamicImage, Rgba};
+use log::{debug, warn};
+use once_cell::sync::Lazy;
+
 use crate::{error::AppError, parser::models::ParsedImageContext}; // 🟢 引入新结构
+use crate::graphics::BackgroundTone;
+use crate::models::{FrameLabels, FrameTheme};
 
 pub trait FrameProcessor: Send + Sync {
     // 🟢 接口变了：不再接收 make/model/params 字符串，而是接收 ctx
@@ -9,4 +18,147 @@ pub trait FrameProcessor: Send + Sync {
         img: &DynamicImage, 
         ctx: &ParsedImageContext
     ) -> Result<DynamicImage, AppError>;
-}
\ No newline at end of file
+
+    /// 接管输入图片所有权的版本 (组合处理器的后续阶段使用)
+    /// 只在原图上叠加内容的处理器应覆盖它，直接在输入上绘制，省掉一次整图拷贝
+    fn process_owned(&self, img: DynamicImage, ctx: &ParsedImageContext) -> Result<DynamicImage, AppError> {
+        self.process(&img, ctx)
+    }
+
+    /// 成品画布的背景类型 (导出时补边到目标比例，按它延伸背景)
+    fn background_kind(&self) -> BackgroundKind;
+}
+
+/// 🟢 成品背景：纯色 (白底系列) 或原图模糊 (透明系列)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BackgroundKind {
+    Solid(Rgba<u8>),
+    // 与 generate_blurred_background 的参数一致
+    Blurred { blur_radius: f32, tone: BackgroundTone },
+}
+
+// 已经警告过的未知参数 (每张图都会应用一次覆盖，避免大批量时刷屏)
+static WARNED_KEYS: Lazy<Mutex<HashSet<String>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+// =========================================================
+// 🟢 布局参数的取值范围：覆盖值超出时夹紧到边界并警告
+// 范围只挡住明显错误的输入 (负的边框、几十倍的字号会分配出巨大的画布或画到画布外)，
+// 不限制合理的风格调整，所以都比默认值宽得多
+// =========================================================
+/// 边框 / 底栏占画布的比例：超过一半照片就比边框还小了
+pub const BORDER: RangeInclusive<f32> = 0.0..=0.5;
+/// 相对底栏 / 画布 / 行高的比例 (字号、间距、最大宽度、暗角强度……)
+pub const RATIO: RangeInclusive<f32> = 0.0..=1.0;
+/// 相对某个基准尺寸的倍数 (Logo 高度、胶囊宽高比、底栏倍数……)
+pub const SCALE: RangeInclusive<f32> = 0.0..=10.0;
+/// 位置微调 (相对底栏 / 字号)，可以为负
+pub const NUDGE: RangeInclusive<f32> = -1.0..=1.0;
+/// 模糊半径 / sigma (像素)
+pub const BLUR: RangeInclusive<f32> = 0.0..=500.0;
+
+/// 🟢 可被用户覆盖的布局配置 (StyleOptions 里的 layout_overrides)
+/// 各配置只需说明哪些键对应哪个字段以及允许的范围，未知键、非法数值和越界统一在这里处理并记录警告
+pub trait LayoutConfig {
+    // 日志里显示的样式名
+    const STYLE: &'static str;
+
+    /// 按键名返回可覆盖的字段和它的取值范围，不支持的键返回 None
+    fn field_mut(&mut self, key: &str) -> Option<(&mut f32, RangeInclusive<f32>)>;
+
+    fn apply_overrides(&mut self, overrides: &HashMap<String, f32>) {
+        for (key, &value) in overrides {
+            if !value.is_finite() {
+                warn_once(format!("⚠️ [Layout] {} 的布局参数 {:?} 数值非法 ({})，已忽略", Self::STYLE, key, value));
+                continue;
+            }
+            match self.field_mut(key) {
+                Some((slot, range)) => {
+                    let clamped = value.clamp(*range.start(), *range.end());
+                    if clamped != value {
+                        warn_once(format!(
+                            "⚠️ [Layout] {} 的布局参数 {:?} = {} 超出范围 {:?}，按 {} 处理",
+                            Self::STYLE, key, value, range, clamped
+                        ));
+                    }
+                    debug!("  -> [Layout] {} 覆盖 {} = {}", Self::STYLE, key, clamped);
+                    *slot = clamped;
+                }
+                None => warn_once(format!("⚠️ [Layout] {} 不支持布局参数 {:?}，已忽略", Self::STYLE, key)),
+            }
+        }
+    }
+}
+
+// 不带文字表的样式 (Classic / Polaroid) 共用的默认值
+static DEFAULT_LABELS: Lazy<FrameLabels> = Lazy::new(FrameLabels::default);
+
+/// 🟢 样式级的排版输入：主题、强调色、照片位移、布局覆盖、文字表
+/// 各处理器的 process_internal 统一接收这一个结构，新增选项时只加字段，不再加位置参数
+pub struct LayoutInput<'a> {
+    pub theme: FrameTheme,
+    pub accent: Option<Rgba<u8>>, // 品牌强调色 (Modern 胶囊 / AccentBar 底栏)，None 代表不用
+    pub photo_offset: Option<(f32, f32)>,
+    pub overrides: &'a HashMap<String, f32>,
+    pub labels: &'a FrameLabels,
+}
+
+impl<'a> LayoutInput<'a> {
+    /// 浅色主题、无强调色、照片居中、默认文字表；其余字段用结构体更新语法覆盖
+    pub fn new(overrides: &'a HashMap<String, f32>) -> Self {
+        Self { theme: FrameTheme::Light, accent: None, photo_offset: None, overrides, labels: &DEFAULT_LABELS }
+    }
+}
+
+fn warn_once(msg: String) {
+    if WARNED_KEYS.lock().unwrap().insert(msg.clone()) {
+        warn!("{}", msg);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct TestConfig {
+        border_ratio: f32,
+        nudge: f32,
+    }
+
+    impl LayoutConfig for TestConfig {
+        const STYLE: &'static str = "Test";
+
+        fn field_mut(&mut self, key: &str) -> Option<(&mut f32, RangeInclusive<f32>)> {
+            Some(match key {
+                "border_ratio" => (&mut self.border_ratio, BORDER),
+                "nudge" => (&mut self.nudge, NUDGE),
+                _ => return None,
+            })
+        }
+    }
+
+    fn apply(pairs: &[(&str, f32)]) -> TestConfig {
+        let mut cfg = TestConfig { border_ratio: 0.05, nudge: 0.1 };
+        cfg.apply_overrides(&pairs.iter().map(|(k, v)| (k.to_string(), *v)).collect());
+        cfg
+    }
+
+    #[test]
+    fn overrides_within_range_apply_as_is() {
+        let cfg = apply(&[("border_ratio", 0.2), ("nudge", -0.3)]);
+        assert_eq!((cfg.border_ratio, cfg.nudge), (0.2, -0.3));
+    }
+
+    // 越界夹紧到边界，非法数值和未知键不改变原值
+    #[test]
+    fn overrides_are_clamped_or_ignored() {
+        let cfg = apply(&[("border_ratio", 50.0), ("nudge", -7.0)]);
+        assert_eq!((cfg.border_ratio, cfg.nudge), (0.5, -1.0));
+
+        let cfg = apply(&[("border_ratio", -0.1)]);
+        assert_eq!(cfg.border_ratio, 0.0);
+
+        let cfg = apply(&[("border_ratio", f32::NAN), ("nudge", f32::INFINITY), ("unknown", 1.0)]);
+        assert_eq!((cfg.border_ratio, cfg.nudge), (0.05, 0.1));
+    }
+}
@@ -3,20 +3,21 @@ use ab_glyph::{Font, FontArc, PxScale};
 // 🟢 1. 引入 draw_text_mut
 use imageproc::drawing::{text_size, draw_text_mut};
 use log::info;
+use std::ops::RangeInclusive;
 use std::time::Instant;
-use std::sync::Arc;
 use std::cmp::min;
+use std::collections::HashMap;
 use crate::error::AppError;
-use crate::graphics::effects::generate_blurred_background;
+use crate::graphics::effects::{apply_vignette, generate_blurred_background, BackgroundTone, BgBrightness, GlassConfig};
 // 🟢 新增引入
 use crate::graphics::shadow::ShadowProfile;
 
-use crate::resources::LogoType;
+use crate::graphics::text_size_with_fallback;
+use crate::resources::{Brand, LogoType};
 use crate::{graphics, resources};
 use crate::parser::models::ParsedImageContext;
-use crate::processor::traits::FrameProcessor;
-// 引入父模块通用工具
-use super::resize_image_by_height;
+use crate::processor::layout::clamp_bar_height;
+use crate::processor::traits::{BackgroundKind, FrameProcessor, LayoutConfig, LayoutInput, BLUR, BORDER, RATIO, SCALE};
 
 // ==========================================
 // 1. 数据结构定义
@@ -26,34 +27,80 @@ use super::resize_image_by_height;
 // ==========================================
 pub struct TransparentClassicProcessor {
     pub font_data: FontArc,
+    pub show_lens: bool,
+    pub shadow_intensity: Option<f32>,
+    pub glass: GlassConfig,
+    pub bg_brightness: Option<i32>,   // 🟢 None 代表 BlurConfig 的默认值
+    pub bg_saturation: Option<f32>,   // 🟢 None 代表不调整
+    pub auto_bg_brightness: bool,     // 🟢 按原图平均亮度自动选择亮度偏移
+    pub layout_overrides: HashMap<String, f32>,
+}
+
+// 自动亮度的目标范围：亮图压暗到白字清楚，夜景保持原样不会压成纯黑
+const BG_AUTO_RANGE: (u8, u8) = (30, 110);
+
+impl TransparentClassicProcessor {
+    /// 当前生效的布局配置 (默认值 + layout_overrides)
+    fn config(&self) -> BlurConfig {
+        let mut cfg = BlurConfig::default();
+        cfg.apply_overrides(&self.layout_overrides);
+        cfg
+    }
+
+    /// 背景色调：用户设置优先，没有设置时沿用生效配置里的亮度
+    fn background_tone(&self, cfg: &BlurConfig) -> BackgroundTone {
+        let brightness = if self.auto_bg_brightness {
+            BgBrightness::Auto { min: BG_AUTO_RANGE.0, max: BG_AUTO_RANGE.1 }
+        } else {
+            BgBrightness::Fixed(self.bg_brightness.map_or(cfg.bg_brightness, |b| b.clamp(-255, 255)))
+        };
+        BackgroundTone { brightness, saturation: self.bg_saturation.unwrap_or(1.0) }
+    }
 }
 
 impl FrameProcessor for TransparentClassicProcessor {
     fn process(&self, img: &DynamicImage, ctx: &ParsedImageContext) -> Result<DynamicImage, AppError> {
         let assets = BlurStyleResources {
-            logo: resources::get_logo(ctx.brand, LogoType::Wordmark),
+            brand: ctx.brand,
+            logo_type: LogoType::Wordmark,
         };
         
         let params_str = ctx.params.format_standard();
         
         let input = BlurInput {
-            brand: &ctx.brand.to_string(),
+            brand: &ctx.brand_label(),
             model: &ctx.model_name,
             params: &params_str,
+            lens: if self.show_lens { ctx.params.lens_model.trim() } else { "" },
         };
         
+        let effects = BlurEffects {
+            shadow: ShadowProfile::for_intensity(self.shadow_intensity),
+            glass: &self.glass,
+            tone: self.background_tone(&self.config()),
+        };
+
         Ok(process(
             img, 
             &self.font_data, 
             input, 
-            &assets
+            &assets,
+            &effects,
+            &LayoutInput::new(&self.layout_overrides),
         ))
     }
+
+    fn background_kind(&self) -> BackgroundKind {
+        let cfg = self.config();
+        BackgroundKind::Blurred { blur_radius: cfg.blur_sigma, tone: self.background_tone(&cfg) }
+    }
 }
 
 
+// Logo 的实际缩放要等排版算出字号后才能进行，所以这里只记录“取哪个 Logo”
 pub struct BlurStyleResources {
-    pub logo: Option<Arc<DynamicImage>>, 
+    pub brand: Brand,
+    pub logo_type: LogoType,
 }
 
 #[allow(dead_code)]
@@ -61,28 +108,46 @@ pub struct BlurInput<'a> {
     pub brand: &'a str,
     pub model: &'a str,
     pub params: &'a str,
+    pub lens: &'a str, // 空字符串代表不绘制镜头行
+}
+
+/// 🟢 照片周围的效果：阴影、玻璃描边、背景色调
+pub struct BlurEffects<'a> {
+    pub shadow: Option<ShadowProfile>, // None 代表不画阴影
+    pub glass: &'a GlassConfig,
+    pub tone: BackgroundTone,
 }
 
 // ==========================================
 // 2. 布局配置
 // ==========================================
+/// 可通过 layout_overrides 覆盖的键 (与字段同名)：
+/// `border_ratio`, `bottom_extra_ratio`, `blur_sigma`, `font_scale_model`,
+/// `font_scale_params`, `font_scale_lens`, `logo_height_ratio`, `gap_logo_text_ratio`,
+/// `gap_lines_ratio`, `lens_max_width_ratio`, `vignette_strength`, `vignette_radius`
 struct BlurConfig {
     border_ratio: f32,       
     bottom_extra_ratio: f32, 
 
     blur_sigma: f32,         
     bg_brightness: i32,         
+    vignette_strength: f32, // 背景暗角强度 0-1 (0 = 关闭)
+    vignette_radius: f32,   // 暗角起始位置 (0 = 中心，1 = 四角)
 
     font_scale_model: f32,   
     font_scale_params: f32,  
+    font_scale_lens: f32,
     
     logo_height_ratio: f32,  
 
     gap_logo_text_ratio: f32, 
     gap_lines_ratio: f32,     
+    lens_max_width_ratio: f32, // 镜头行最大宽度 (相对画布宽度)
     
     text_color_model: Rgba<u8>,
     text_color_params: Rgba<u8>,
+    text_color_lens: Rgba<u8>,
+    logo_color: Rgba<u8>,
 }
 
 impl Default for BlurConfig {
@@ -92,22 +157,51 @@ impl Default for BlurConfig {
             bottom_extra_ratio: 0.85,  
 
             blur_sigma: 120.0,          
-            bg_brightness: -150,            
+            // 🔴 原来写的 -150 因为 brighten 的返回值被丢弃从未生效，默认改为 0 保持现有成品外观
+            bg_brightness: 0,
+            vignette_strength: 0.0,
+            vignette_radius: 0.5,
 
             font_scale_model: 0.56,    
             font_scale_params: 0.45,   
+            font_scale_lens: 0.36,
 
             logo_height_ratio: 0.85,   
             
             gap_logo_text_ratio: 0.6,  
             gap_lines_ratio: 0.60,     
+            lens_max_width_ratio: 0.9,
 
             text_color_model: Rgba([255, 255, 255, 255]),
             text_color_params: Rgba([220, 220, 220, 255]),
+            text_color_lens: Rgba([170, 170, 170, 255]),
+            logo_color: Rgba([255, 255, 255, 255]),
         }
     }
 }
 
+impl LayoutConfig for BlurConfig {
+    const STYLE: &'static str = "TransparentClassic";
+
+    fn field_mut(&mut self, key: &str) -> Option<(&mut f32, RangeInclusive<f32>)> {
+        Some(match key {
+            "border_ratio" => (&mut self.border_ratio, BORDER),
+            "bottom_extra_ratio" => (&mut self.bottom_extra_ratio, SCALE),
+            "blur_sigma" => (&mut self.blur_sigma, BLUR),
+            "font_scale_model" => (&mut self.font_scale_model, SCALE),
+            "font_scale_params" => (&mut self.font_scale_params, SCALE),
+            "font_scale_lens" => (&mut self.font_scale_lens, SCALE),
+            "logo_height_ratio" => (&mut self.logo_height_ratio, SCALE),
+            "gap_logo_text_ratio" => (&mut self.gap_logo_text_ratio, SCALE),
+            "gap_lines_ratio" => (&mut self.gap_lines_ratio, SCALE),
+            "lens_max_width_ratio" => (&mut self.lens_max_width_ratio, RATIO),
+            "vignette_strength" => (&mut self.vignette_strength, RATIO),
+            "vignette_radius" => (&mut self.vignette_radius, RATIO),
+            _ => return None,
+        })
+    }
+}
+
 // ==========================================
 // 3. 核心处理逻辑
 // ==========================================
@@ -115,10 +209,14 @@ pub fn process<F: Font>(
     img: &DynamicImage,
     font: &F,
     input: BlurInput,
-    assets: &BlurStyleResources 
+    assets: &BlurStyleResources,
+    effects: &BlurEffects,
+    layout: &LayoutInput, // 透明系列只用到 overrides
 ) -> DynamicImage {
     let t0 = Instant::now();
-    let cfg = BlurConfig::default();
+    let mut cfg = BlurConfig::default();
+    cfg.apply_overrides(layout.overrides);
+    let BlurEffects { shadow, glass, tone } = *effects;
     let (width, height) = img.dimensions();
 
     // -------------------------------------------------------------
@@ -126,7 +224,11 @@ pub fn process<F: Font>(
     // -------------------------------------------------------------
     let ref_size = min(width, height) as f32;
     let border_size = (ref_size * cfg.border_ratio) as u32;
-    let bottom_extra_h = (border_size as f32 * cfg.bottom_extra_ratio) as u32;
+    // 底部区域 (边框 + 额外留白) 整体钳制，边框本身不变
+    let bottom_area = clamp_bar_height(border_size as f32 * (1.0 + cfg.bottom_extra_ratio), width, height);
+    let bottom_extra_h = bottom_area.saturating_sub(border_size);
+    // 文字字号的基准：未钳制时等于边框，底部被加高时随之放大
+    let text_base = bottom_area as f32 / (1.0 + cfg.bottom_extra_ratio);
 
     let canvas_w = width + border_size * 2;
     let canvas_h = height + border_size * 2 + bottom_extra_h;
@@ -137,14 +239,17 @@ pub fn process<F: Font>(
     let t_blur = Instant::now();
     
     // 🟢 [修改] 调用公共高性能方法
-    // 这里传入 cfg.bg_brightness (通常是 -150)
-    let mut canvas = generate_blurred_background(
+    // 亮度默认是 cfg.bg_brightness (0)，用户设置 / 自动亮度由 tone 带进来
+    let mut background = generate_blurred_background(
         img, 
         canvas_w, 
         canvas_h, 
         cfg.blur_sigma, 
-        cfg.bg_brightness 
-    ).to_rgba8(); // 注意：generate 返回 DynamicImage，这里转为 RgbaImage
+        tone,
+    );
+    // 可选暗角 (亮度已在背景生成时调整过，暗角叠加在其后)
+    apply_vignette(&mut background, cfg.vignette_strength, cfg.vignette_radius);
+    let mut canvas = background.to_rgba8(); // 注意：generate 返回 DynamicImage，这里转为 RgbaImage
 
     info!("  - [PERF] Blur Background: {:.2?}", t_blur.elapsed());
 
@@ -163,42 +268,45 @@ pub fn process<F: Font>(
     let src_h = height;
 
     // 计算边框厚度 (为了对齐阴影和前景)
-    // 必须和 draw_glass_foreground_on 里的逻辑保持一致
-    let border_thickness = (width.max(height) as f32 * 0.002).clamp(3.0, 8.0) as u32;
+    // 与 draw_glass_foreground_on 使用同一份 GlassConfig，保证阴影和前景对齐
+    let border_thickness = glass.border_thickness(width, height);
     
     // 玻璃整体尺寸 (原图 + 边框)
     let glass_total_w = src_w + border_thickness * 2;
     let glass_total_h = src_h + border_thickness * 2;
 
     // 2. 计算玻璃体在画布上的左上角坐标
-    let glass_x = (canvas_w - glass_total_w) / 2;
+    // 极小原图时边框厚度 (最小 3px) 可能超过留白，允许坐标为负，交给绘制层裁剪
+    let glass_x = (canvas_w as i64 - glass_total_w as i64) / 2;
     let height_diff = (glass_total_h.saturating_sub(height)) / 2;
     let glass_y = (border_size as i64) - (height_diff as i64);
 
     // 3. 计算原图在画布上的位置 (玻璃位置 + 边框偏移)
     // draw_glass_foreground_on 需要的是“原图内容”应该画在哪里
-    let img_dest_x = glass_x as i64 + border_thickness as i64;
+    let img_dest_x = glass_x + border_thickness as i64;
     let img_dest_y = glass_y as i64 + border_thickness as i64;
     
-    let center_x = (glass_x + glass_total_w / 2) as i64;
+    let center_x = glass_x + (glass_total_w / 2) as i64;
     let center_y = (glass_y as i64) + (glass_total_h as i64) / 2;
     // 🟢 2. 直接应用模板！
     // 不需要关心图片是 600px 还是 60MP，也不需要手动算 ratio
-    ShadowProfile::preset_standard()
-        .draw_adaptive_shadow_on(
+    // 强度为 0 时整个跳过 (大图上能省下不少时间)
+    if let Some(shadow) = shadow {
+        shadow.draw_adaptive_shadow_on(
             &mut canvas,
             (glass_total_w, glass_total_h),
             (center_x, center_y)
         );
+    }
 
     // 3. 画前景
-    graphics::draw_glass_foreground_on(&mut canvas, img, img_dest_x, img_dest_y);
+    graphics::draw_glass_foreground_on(&mut canvas, img, img_dest_x, img_dest_y, glass);
 
     // -------------------------------------------------------------
     // D. 字体与排版计算
     // -------------------------------------------------------------
-    let font_size_model = border_size as f32 * cfg.font_scale_model;
-    let font_size_params = border_size as f32 * cfg.font_scale_params;
+    let font_size_model = text_base * cfg.font_scale_model;
+    let font_size_params = text_base * cfg.font_scale_params;
     let scale_model = PxScale::from(font_size_model);
     let scale_params = PxScale::from(font_size_params);
 
@@ -212,10 +320,10 @@ pub fn process<F: Font>(
     let mut logo_draw_h = 0;
     let mut scaled_logo = None;
 
-    if let Some(logo) = &assets.logo {
-        let target_h = (font_size_model * cfg.logo_height_ratio) as u32;
-        let white_logo = graphics::make_image_white(logo);
-        let resized = resize_image_by_height(&white_logo, target_h);
+    let logo_target_h = (font_size_model * cfg.logo_height_ratio) as u32;
+    if let Some(logo) = resources::get_logo_scaled(assets.brand, assets.logo_type, logo_target_h) {
+        // 缩放结果来自批次缓存，这里只做轻量的着色
+        let resized = graphics::tint_image(&logo, cfg.logo_color);
         
         logo_draw_w = resized.width() as u32;
         logo_draw_h = resized.height() as u32;
@@ -248,13 +356,28 @@ pub fn process<F: Font>(
         (0, 0)
     };
 
-    // --- 3. 垂直布局 ---
+    // --- 3. 测量第三行 [Lens] (可选) ---
+    // 镜头名可能很长，先缩小字号，仍放不下再截断，保证不超过画布宽度的 90%
+    let lens_max_w = (canvas_w as f32 * cfg.lens_max_width_ratio) as u32;
+    let lens_line = if !input.lens.is_empty() {
+        Some(fit_text_line(font, input.lens, text_base * cfg.font_scale_lens, lens_max_w))
+    } else {
+        None
+    };
+    let gap_lens = (font_size_params * cfg.gap_lines_ratio) as u32;
+
+    // --- 4. 垂直布局 ---
+    // 第三行只在真正绘制时计入文字块高度，否则前两行的居中位置保持不变
     let gap_lines = (font_size_model * cfg.gap_lines_ratio) as u32;
-    let total_block_h = line1_height + gap_lines + params_h;
+    let mut total_block_h = line1_height + gap_lines + params_h;
+    if let Some(lens) = &lens_line {
+        total_block_h += gap_lens + lens.height;
+    }
 
     let bottom_area_y = border_size + height; 
     let bottom_area_h = border_size + bottom_extra_h; 
-    let block_start_y = bottom_area_y as u32 + (bottom_area_h - total_block_h) / 2;
+    // 小图大字号时文字块可能比底部区域还高，用 saturating_sub 防止下溢
+    let block_start_y = bottom_area_y as u32 + bottom_area_h.saturating_sub(total_block_h) / 2;
 
     // -------------------------------------------------------------
     // E. 绘制
@@ -262,7 +385,7 @@ pub fn process<F: Font>(
     
     // --- 第一行 ---
     if line1_width > 0 {
-        let mut cursor_x = (canvas_w - line1_width) / 2;
+        let mut cursor_x = canvas_w.saturating_sub(line1_width) / 2;
         let line1_base_y = block_start_y; 
 
         // Logo
@@ -292,7 +415,7 @@ pub fn process<F: Font>(
 
     // --- 第二行 ---
     if params_w > 0 {
-        let line2_x = (canvas_w - params_w) / 2;
+        let line2_x = canvas_w.saturating_sub(params_w) / 2;
         let line2_y = block_start_y + line1_height + gap_lines;
         
         // 🟢 2. 直接使用 draw_text_mut
@@ -307,6 +430,110 @@ pub fn process<F: Font>(
         );
     }
 
+    // --- 第三行 ---
+    if let Some(lens) = lens_line {
+        let line3_x = canvas_w.saturating_sub(lens.width) / 2;
+        let line3_y = block_start_y + line1_height + gap_lines + params_h + gap_lens;
+
+        graphics::draw_text_with_fallback(
+            &mut canvas,
+            cfg.text_color_lens,
+            line3_x as i32,
+            line3_y as i32,
+            lens.scale,
+            font,
+            &lens.text
+        );
+    }
+
     info!("  - [PERF] Blur Total Time: {:.2?}", t0.elapsed());
     DynamicImage::ImageRgba8(canvas)
-}
\ No newline at end of file
+}
+// 一行已经适配好宽度的文字
+struct FittedLine {
+    text: String,
+    scale: PxScale,
+    width: u32,
+    height: u32,
+}
+
+// 字号最多缩小到原来的 70%，再小就和参数行差距太大，改为截断
+const MIN_FIT_SCALE: f32 = 0.7;
+
+/// 让一行文字不超过 max_w：先按比例缩小字号，仍然超宽则从末尾截断并加省略号
+/// (镜头名可能含中日文，按回退字体测量)
+fn fit_text_line<F: Font>(font: &F, text: &str, size: f32, max_w: u32) -> FittedLine {
+    let (w, _) = text_size_with_fallback(PxScale::from(size), font, text);
+    let size = if w > max_w && w > 0 {
+        size * (max_w as f32 / w as f32).max(MIN_FIT_SCALE)
+    } else {
+        size
+    };
+    let scale = PxScale::from(size);
+
+    let mut fitted = text.to_string();
+    let (mut w, mut h) = text_size_with_fallback(scale, font, &fitted);
+    let mut chars: Vec<char> = text.chars().collect();
+    while w > max_w && !chars.is_empty() {
+        chars.pop();
+        fitted = format!("{}…", chars.iter().collect::<String>().trim_end());
+        (w, h) = text_size_with_fallback(scale, font, &fitted);
+    }
+
+    FittedLine { text: fitted, scale, width: w, height: h }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{self, models::RawExifData};
+    use crate::resources::{FontFamily, FontWeight};
+
+    fn processor(auto_bg_brightness: bool, bg_brightness: Option<i32>) -> TransparentClassicProcessor {
+        TransparentClassicProcessor {
+            font_data: resources::get_font(FontFamily::InterDisplay, FontWeight::Medium).unwrap(),
+            show_lens: false,
+            shadow_intensity: Some(0.0),
+            glass: GlassConfig::default(),
+            bg_brightness,
+            bg_saturation: None,
+            auto_bg_brightness,
+            layout_overrides: HashMap::new(),
+        }
+    }
+
+    /// 纯色原图出图后左上角 (模糊背景) 的亮度
+    fn corner_luma(p: &TransparentClassicProcessor, value: u8) -> u8 {
+        let img = DynamicImage::ImageRgb8(image::RgbImage::from_pixel(300, 200, image::Rgb([value; 3])));
+        let ctx = parser::parse(RawExifData { make: "NIKON CORPORATION".into(), model: "NIKON Z 8".into(), ..Default::default() });
+        p.process(&img, &ctx).unwrap().to_rgb8().get_pixel(0, 0)[0]
+    }
+
+    #[test]
+    fn fallback_brightness_comes_from_active_config() {
+        let cfg = BlurConfig { bg_brightness: -40, ..BlurConfig::default() };
+        assert_eq!(processor(false, None).background_tone(&cfg).brightness, BgBrightness::Fixed(-40));
+        // 用户设置优先，超出范围的钳制到 ±255
+        assert_eq!(processor(false, Some(-999)).background_tone(&cfg).brightness, BgBrightness::Fixed(-255));
+        // 自动模式忽略固定偏移
+        assert_eq!(
+            processor(true, Some(-40)).background_tone(&cfg).brightness,
+            BgBrightness::Auto { min: BG_AUTO_RANGE.0, max: BG_AUTO_RANGE.1 }
+        );
+    }
+
+    #[test]
+    fn auto_brightness_keeps_background_in_range() {
+        // 缩小 / 模糊会带来几级误差
+        let near = |got: u8, want: u8| assert!(got.abs_diff(want) <= 3, "{} != {}", got, want);
+        let (min, max) = BG_AUTO_RANGE;
+        // 固定偏移 0：背景保持原亮度
+        near(corner_luma(&processor(false, None), 240), 240);
+        // 亮图压暗到范围上限，白字仍然清楚
+        near(corner_luma(&processor(true, None), 240), max);
+        // 夜景提亮到范围下限，不会被压成纯黑
+        near(corner_luma(&processor(true, None), 5), min);
+        // 本来就在范围内的不动
+        near(corner_luma(&processor(true, None), 80), 80);
+    }
+}
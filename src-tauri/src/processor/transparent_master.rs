@@ -1,12 +1,13 @@
 // src/processor/master.rs
 
 use image::{DynamicImage, Rgba, GenericImageView, imageops};
-use ab_glyph::{Font, FontArc, PxScale};
-use imageproc::drawing::{draw_text_mut, draw_line_segment_mut};
+use ab_glyph::{Font, FontArc};
+use imageproc::drawing::draw_line_segment_mut;
 use log::info;
-use std::{time::Instant};
+use std::ops::RangeInclusive;
+use std::{collections::HashMap, time::Instant};
 
-use crate::{error::AppError, graphics::generate_blurred_background, parser::models::ParsedImageContext, processor::traits::FrameProcessor};
+use crate::{error::AppError, graphics::{apply_vignette, generate_blurred_background, BackgroundTone, BgBrightness}, models::FrameLabels, parser::models::ParsedImageContext, processor::layout::{bar_basis, clamp_bar_height}, processor::traits::{BackgroundKind, FrameProcessor, LayoutConfig, LayoutInput, BLUR, BORDER, NUDGE, RATIO, SCALE}, processor::white::utils::{draw_text_aligned, TextAlign, TextStyle}};
 
 // ==========================================
 // 1. 数据结构定义
@@ -18,6 +19,12 @@ pub struct TransparentMasterProcessor {
     pub main_font: FontArc,   // 参数字体
     pub script_font: FontArc, // 手写体
     pub serif_font: FontArc,  // 标题体
+    pub show_artist: bool,    // PHOTOGRAPH 下方的署名行
+    pub bg_brightness: Option<i32>, // 🟢 背景亮度偏移，None 代表配置里的默认值
+    pub bg_saturation: Option<f32>, // 🟢 背景饱和度，None 代表不调整
+    pub auto_bg_brightness: bool,   // 🟢 按原图平均亮度自动选择亮度偏移
+    pub labels: FrameLabels,  // 参数标签 + 标题文字
+    pub layout_overrides: HashMap<String, f32>,
 }
 
 impl FrameProcessor for TransparentMasterProcessor {
@@ -25,24 +32,57 @@ impl FrameProcessor for TransparentMasterProcessor {
         // 构造输入数据
         let input = TransparentMasterInput {
             iso: ctx.params.iso.map(|v| v.to_string()).unwrap_or_default(),
-            aperture: ctx.params.aperture.map(|v| v.to_string()).unwrap_or_default(),
-            shutter: ctx.params.shutter_speed
-                .replace("s", "")
-                .trim()
-                .to_string(),
-            focal: ctx.params.focal_length.map(|v| v.to_string()).unwrap_or_default(),
+            aperture: ctx.params.format_aperture().unwrap_or_default(),
+            shutter: ctx.params.shutter_speed.map(|s| s.format_fraction()).unwrap_or_default(),
+            focal: ctx.params.format_focal_length().unwrap_or_default(),
+            artist: if self.show_artist {
+                ctx.artist_name.as_deref().and_then(|a| self.labels.shot_by_line(a))
+            } else {
+                None
+            },
         };
 
+        let layout = LayoutInput { labels: &self.labels, ..LayoutInput::new(&self.layout_overrides) };
+
         Ok(process(
             img, 
             input, 
             &self.main_font, 
             &self.script_font, 
-            &self.serif_font
+            &self.serif_font,
+            &layout,
+            self.background_tone(&self.config()),
         ))
     }
+
+    fn background_kind(&self) -> BackgroundKind {
+        let cfg = self.config();
+        BackgroundKind::Blurred { blur_radius: cfg.bg_blur_radius, tone: self.background_tone(&cfg) }
+    }
 }
 
+impl TransparentMasterProcessor {
+    /// 当前生效的布局配置 (默认值 + layout_overrides)
+    fn config(&self) -> MasterLayoutConfig {
+        let mut cfg = MasterLayoutConfig::default();
+        cfg.apply_overrides(&self.layout_overrides);
+        cfg
+    }
+
+    /// 背景色调：用户设置优先，没有设置时沿用生效配置里的亮度
+    fn background_tone(&self, cfg: &MasterLayoutConfig) -> BackgroundTone {
+        let brightness = if self.auto_bg_brightness {
+            BgBrightness::Auto { min: BG_AUTO_RANGE.0, max: BG_AUTO_RANGE.1 }
+        } else {
+            BgBrightness::Fixed(self.bg_brightness.map_or(cfg.bg_brightness, |b| b.clamp(-255, 255)))
+        };
+        BackgroundTone { brightness, saturation: self.bg_saturation.unwrap_or(1.0) }
+    }
+}
+
+// 自动亮度的目标范围：保留背景的明暗特征，只把过亮 / 过暗的拉回中间
+const BG_AUTO_RANGE: (u8, u8) = (50, 160);
+
 
 /// 🟢 [新增] Master 模式专用输入参数
 /// 用于接收已经清洗好的、分拆的参数
@@ -51,11 +91,17 @@ pub struct TransparentMasterInput {
     pub aperture: String, // 例如 "2.8" (不带 f/ 前缀)
     pub shutter: String,  // 例如 "1/1000" (不带 s 后缀)
     pub focal: String,    // 例如 "50" (不带 mm 后缀)
+    pub artist: Option<String>, // 🟢 署名行 (已套好 "Shot by ..." 模板)，None 不画
 }
 
 // ==========================================
 // 2. 布局配置中心 (保持不变)
 // ==========================================
+/// 可通过 layout_overrides 覆盖的键 (与字段同名)：
+/// `border_ratio`, `bottom_ratio`, `column_gap_ratio`, `label_bottom_margin`,
+/// `row_gap`, `text_scale_val`, `text_scale_lbl`, `separator_scale`,
+/// `header_bottom_margin`, `header_script_size`, `header_small_size`, `header_gap_top`,
+/// `header_gap_bottom`, `artist_gap`, `bg_blur_radius`, `vignette_strength`, `vignette_radius`
 struct MasterLayoutConfig {
     border_ratio: f32,
     bottom_ratio: f32,
@@ -71,7 +117,11 @@ struct MasterLayoutConfig {
     header_small_size: f32,    
     header_gap_top: f32,       
     header_gap_bottom: f32,    
+    artist_gap: f32,        // 🟢 PHOTOGRAPH 与署名行之间的间距
     bg_blur_radius: f32,
+    bg_brightness: i32,     // 背景亮度偏移 (用户没有设置时使用)
+    vignette_strength: f32, // 背景暗角强度 0-1 (0 = 关闭)
+    vignette_radius: f32,   // 暗角起始位置 (0 = 中心，1 = 四角)
 }
 
 impl MasterLayoutConfig {
@@ -91,11 +141,44 @@ impl MasterLayoutConfig {
             header_small_size: 0.05,
             header_gap_top: -0.02,
             header_gap_bottom: 0.1,
+            artist_gap: 0.03,
             bg_blur_radius: 150.0,
+            // Master 模式背景亮度默认不调整
+            // 🔴 原来写的 -15 因为 brighten 的返回值被丢弃从未生效，改为 0 保持现有成品外观
+            bg_brightness: 0,
+            vignette_strength: 0.0,
+            vignette_radius: 0.5,
         }
     }
 }
 
+impl LayoutConfig for MasterLayoutConfig {
+    const STYLE: &'static str = "TransparentMaster";
+
+    fn field_mut(&mut self, key: &str) -> Option<(&mut f32, RangeInclusive<f32>)> {
+        Some(match key {
+            "border_ratio" => (&mut self.border_ratio, BORDER),
+            "bottom_ratio" => (&mut self.bottom_ratio, RATIO),
+            "column_gap_ratio" => (&mut self.column_gap_ratio, RATIO),
+            "label_bottom_margin" => (&mut self.label_bottom_margin, RATIO),
+            "row_gap" => (&mut self.row_gap, NUDGE),
+            "text_scale_val" => (&mut self.text_scale_val, RATIO),
+            "text_scale_lbl" => (&mut self.text_scale_lbl, RATIO),
+            "separator_scale" => (&mut self.separator_scale, SCALE),
+            "header_bottom_margin" => (&mut self.header_bottom_margin, RATIO),
+            "header_script_size" => (&mut self.header_script_size, RATIO),
+            "header_small_size" => (&mut self.header_small_size, RATIO),
+            "header_gap_top" => (&mut self.header_gap_top, NUDGE),
+            "header_gap_bottom" => (&mut self.header_gap_bottom, NUDGE),
+            "artist_gap" => (&mut self.artist_gap, NUDGE),
+            "bg_blur_radius" => (&mut self.bg_blur_radius, BLUR),
+            "vignette_strength" => (&mut self.vignette_strength, RATIO),
+            "vignette_radius" => (&mut self.vignette_radius, RATIO),
+            _ => return None,
+        })
+    }
+}
+
 // ==========================================
 // 3. 核心处理逻辑
 // ==========================================
@@ -105,16 +188,20 @@ pub fn process<F: Font>(
     main_font: &F,   
     script_font: &F, 
     serif_font: &F,  
+    layout: &LayoutInput, // 透明系列只用到 labels 和 overrides
+    tone: BackgroundTone,
 ) -> DynamicImage {
     let start_total = Instant::now();
-    let cfg = MasterLayoutConfig::default();
+    let mut cfg = MasterLayoutConfig::default();
+    cfg.apply_overrides(layout.overrides);
+    let labels = layout.labels;
 
     let (img_w, img_h) = img.dimensions();
     let is_portrait = img_h > img_w;
 
     // 1. 计算尺寸
     let border_size = (img_h as f32 * cfg.border_ratio) as u32;
-    let bottom_height = (img_h as f32 * cfg.bottom_ratio) as u32;
+    let bottom_height = clamp_bar_height(bar_basis(img_w, img_h) * cfg.bottom_ratio, img_w, img_h);
     let canvas_w = img_w + (border_size * 2);
     let canvas_h = img_h + border_size + bottom_height;
 
@@ -122,14 +209,16 @@ pub fn process<F: Font>(
     let start_bg = Instant::now();
     
     // 🟢 [修改] 调用公共方法
-    // Master 模式亮度微调为 -15
+    // Master 模式亮度默认不调整，用户设置 / 自动亮度由 tone 带进来
     let mut canvas = generate_blurred_background(
         img, 
         canvas_w, 
         canvas_h, 
         cfg.bg_blur_radius, 
-        -15 
+        tone,
     );
+    // 可选暗角：低反差照片的背景容易发平，四角压暗一些更聚焦 (叠加在亮度调整之后)
+    apply_vignette(&mut canvas, cfg.vignette_strength, cfg.vignette_radius);
     
     info!("  - [PERF] Master Bg Generation: {:?}", start_bg.elapsed());
 
@@ -173,6 +262,8 @@ pub fn process<F: Font>(
     let line2_y = script_baseline_y as i32;
     let line1_y = (script_baseline_y - (script_size * 0.5) - gap_top) as i32;
     let line3_y = (script_baseline_y + (script_size * 0.1) + gap_bottom) as i32;
+    // 署名行在 PHOTOGRAPH 下方，与标题同字号
+    let line4_y = line3_y + (small_size + bh * cfg.artist_gap) as i32;
 
     // --- C. 分隔线 ---
     let sep_top = value_draw_y as f32;
@@ -189,31 +280,34 @@ pub fn process<F: Font>(
     let sep_color = Rgba([255, 255, 255, cfg.separator_opacity]);
 
     // 7. 绘制 Header
-    draw_centered_text(&mut canvas, "MASTER SERIES", center_x, line1_y, serif_font, PxScale{x: small_size, y: small_size}, small_title_color);
-    draw_centered_text(&mut canvas, "The decisive moment", center_x, line2_y, script_font, PxScale{x: script_size, y: script_size}, script_color);
-    draw_wide_text(&mut canvas, center_x, line3_y, "PHOTOGRAPH", serif_font, small_size, small_title_color);
+    let title_style = TextStyle::new(serif_font, small_size, small_title_color, TextAlign::Center);
+    draw_text_aligned(&mut canvas, &labels.series_title, center_x, line1_y, &title_style);
+    draw_text_aligned(&mut canvas, &labels.tagline, center_x, line2_y, &TextStyle::new(script_font, script_size, script_color, TextAlign::Center));
+    draw_text_aligned(&mut canvas, &labels.photograph, center_x, line3_y, &TextStyle::new(serif_font, small_size, small_title_color, TextAlign::Center).tracking(small_size * 0.4));
+    if let Some(artist) = &input.artist {
+        draw_text_aligned(&mut canvas, artist, center_x, line4_y, &title_style);
+    }
 
-    // 8. 绘制参数列
+    // 8. 绘制参数列 + 9. 竖线
+    // 只画有数值的列 (胶片扫描 / 转接手动镜头会缺项)，剩下的整体居中，竖线只画在相邻两列之间
     let gap = (canvas_w as f32 * cfg.column_gap_ratio) as i32;
-
-    if !iso_val.is_empty() {
-        draw_column_absolute(&mut canvas, center_x - gap * 1 - (gap / 2), value_draw_y, label_draw_y, &iso_val, "ISO", main_font, val_size, lbl_size, text_color, label_color);
-    }
-    if !aperture_val.is_empty() {
-        draw_column_absolute(&mut canvas, center_x - (gap / 2), value_draw_y, label_draw_y, &aperture_val, "F", main_font, val_size, lbl_size, text_color, label_color);
-    }
-    if !focal_val.is_empty() {
-        draw_column_absolute(&mut canvas, center_x + (gap / 2), value_draw_y, label_draw_y, &focal_val, "mm", main_font, val_size, lbl_size, text_color, label_color);
-    }
-    if !shutter_val.is_empty() {
-        draw_column_absolute(&mut canvas, center_x + gap * 1 + (gap / 2), value_draw_y, label_draw_y, &shutter_val, "S", main_font, val_size, lbl_size, text_color, label_color);
+    let columns: Vec<(&str, &str)> = [(&iso_val, &labels.iso), (&aperture_val, &labels.aperture), (&focal_val, &labels.focal), (&shutter_val, &labels.shutter)]
+        .into_iter()
+        .map(|(val, lbl)| (val.as_str(), lbl.as_str()))
+        .filter(|(val, _)| !val.is_empty())
+        .collect();
+    let first_x = center_x - gap * (columns.len() as i32 - 1).max(0) / 2;
+    let val_style = TextStyle::new(main_font, val_size, text_color, TextAlign::Center);
+    let lbl_style = TextStyle::new(main_font, lbl_size, label_color, TextAlign::Center);
+
+    for (i, (val, lbl)) in columns.into_iter().enumerate() {
+        let x = first_x + gap * i as i32;
+        draw_column_absolute(&mut canvas, x, (val, value_draw_y, &val_style), (lbl, label_draw_y, &lbl_style));
+        if i > 0 {
+            draw_separator(&mut canvas, x - gap / 2, sep_center_y, sep_actual_h, sep_color);
+        }
     }
 
-    // 9. 绘制竖线
-    draw_separator(&mut canvas, center_x - gap, sep_center_y, sep_actual_h, sep_color);
-    draw_separator(&mut canvas, center_x, sep_center_y, sep_actual_h, sep_color);
-    draw_separator(&mut canvas, center_x + gap, sep_center_y, sep_actual_h, sep_color);
-
     info!("  - [PERF] Master Layout: {:?}", start_overlay.elapsed());
     info!("  - [PERF] Master Total: {:?}", start_total.elapsed());
 
@@ -221,26 +315,10 @@ pub fn process<F: Font>(
 }
 
 
-fn draw_wide_text<F: Font>(canvas: &mut DynamicImage, center_x: i32, y: i32, text: &str, font: &F, size: f32, color: Rgba<u8>) {
-    let scale = PxScale { x: size, y: size };
-    let tracking = size * 0.4; 
-    let mut total_width = 0.0;
-    let char_widths: Vec<f32> = text.chars().map(|c| {
-        let (w, _) = imageproc::drawing::text_size(scale, font, &c.to_string());
-        total_width += w as f32 + tracking;
-        w as f32
-    }).collect();
-    if total_width > 0.0 { total_width -= tracking; }
-    let mut current_x = center_x as f32 - (total_width / 2.0);
-    for (i, c) in text.chars().enumerate() {
-        draw_text_mut(canvas, color, current_x as i32, y, scale, font, &c.to_string());
-        current_x += char_widths[i] + tracking;
-    }
-}
-
-fn draw_column_absolute<F: Font>(canvas: &mut DynamicImage, x: i32, val_y: i32, lbl_y: i32, value: &str, label: &str, font: &F, val_size: f32, lbl_size: f32, val_color: Rgba<u8>, lbl_color: Rgba<u8>) {
-    draw_centered_text(canvas, value, x, val_y, font, PxScale { x: val_size, y: val_size }, val_color);
-    draw_centered_text(canvas, label, x, lbl_y, font, PxScale { x: lbl_size, y: lbl_size }, lbl_color);
+// 每行是 (文字, 文字框顶部 y, 样式)
+fn draw_column_absolute<F: Font>(canvas: &mut DynamicImage, x: i32, value: (&str, i32, &TextStyle<F>), label: (&str, i32, &TextStyle<F>)) {
+    draw_text_aligned(canvas, value.0, x, value.1, value.2);
+    draw_text_aligned(canvas, label.0, x, label.1, label.2);
 }
 
 fn draw_separator(canvas: &mut DynamicImage, x: i32, center_y: f32, height: f32, color: Rgba<u8>) {
@@ -249,11 +327,6 @@ fn draw_separator(canvas: &mut DynamicImage, x: i32, center_y: f32, height: f32,
     draw_line_segment_mut(canvas, (x as f32, start_y), (x as f32, end_y), color);
 }
 
-fn draw_centered_text<F: Font>(canvas: &mut DynamicImage, text: &str, x: i32, y: i32, font: &F, scale: PxScale, color: Rgba<u8>) {
-    let (text_w, _text_h) = imageproc::drawing::text_size(scale, font, text);
-    let draw_x = x - (text_w as i32 / 2);
-    draw_text_mut(canvas, color, draw_x, y, scale, font, text);
-}
-
 // 🔴 已移除 parse_params_smart
-// 🔴 已移除 clean_param
\ No newline at end of file
+// 🔴 已移除 clean_param
+// 🔴 已移除 draw_centered_text (改用 white::utils::draw_text_aligned)
\ No newline at end of file
@@ -0,0 +1,49 @@
+// src/processor/white/accent_bar.rs
+
+use image::{DynamicImage, Rgba};
+use log::debug;
+
+use crate::error::AppError;
+use crate::graphics::{contrast_text_color, dominant_color};
+use crate::models::FrameTheme;
+use crate::parser::models::ParsedImageContext;
+use crate::processor::traits::{BackgroundKind, FrameProcessor};
+
+use super::white_classic_v2::WhiteClassicProcessorV2;
+
+// 主色保留的饱和度：原色直接做底栏太抢眼，向同亮度的灰色靠拢一半多
+const ACCENT_SATURATION: f32 = 0.45;
+
+// ==========================================
+// 主色底栏 (AccentBar)
+// ==========================================
+
+/// 经典底栏布局，底栏填充照片主色的低饱和版本，文字按底栏亮度自动选择黑 / 白
+/// 主色每张图单独计算 (确定性统计，同一张图每次结果相同)
+pub struct AccentBarProcessor {
+    pub classic: WhiteClassicProcessorV2,
+}
+
+impl FrameProcessor for AccentBarProcessor {
+    fn process(&self, img: &DynamicImage, ctx: &ParsedImageContext) -> Result<DynamicImage, AppError> {
+        let dominant = dominant_color(img);
+        let bar_color = mute(dominant, ACCENT_SATURATION);
+        // 白字代表底栏偏暗，字标 Logo 也要按深色主题着色
+        let theme = if contrast_text_color(bar_color)[0] == 255 { FrameTheme::Dark } else { FrameTheme::Light };
+        debug!("🎨 [AccentBar] 主色 {:?} -> 底栏 {:?} ({:?})", dominant.0, bar_color.0, theme);
+
+        self.classic.render(img, ctx, theme, Some(bar_color))
+    }
+
+    fn background_kind(&self) -> BackgroundKind {
+        // 底栏颜色随照片变化，补边统一用白色
+        BackgroundKind::Solid(Rgba([255, 255, 255, 255]))
+    }
+}
+
+// 向同亮度的灰色插值降低饱和度 (saturation = 1 保持原色，0 为纯灰)
+fn mute(c: Rgba<u8>, saturation: f32) -> Rgba<u8> {
+    let gray = 0.299 * c[0] as f32 + 0.587 * c[1] as f32 + 0.114 * c[2] as f32;
+    let ch = |v: u8| (gray + (v as f32 - gray) * saturation).round().clamp(0.0, 255.0) as u8;
+    Rgba([ch(c[0]), ch(c[1]), ch(c[2]), 255])
+}
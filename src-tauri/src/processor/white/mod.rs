@@ -3,4 +3,6 @@ pub mod white_polaroid_v2;
 pub mod white_classic_v2;
 pub mod white_master_v2;
 pub mod white_modern_v2;
+pub mod travel_white;
+pub mod accent_bar;
 
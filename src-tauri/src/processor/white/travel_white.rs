@@ -0,0 +1,204 @@
+// src/processor/white/travel_white.rs
+
+use image::{DynamicImage, Rgba, GenericImageView};
+use ab_glyph::FontArc;
+use log::{info, debug};
+use std::ops::RangeInclusive;
+use std::time::Instant;
+use std::cmp::min;
+use std::collections::HashMap;
+
+use crate::error::AppError;
+use crate::parser::models::ParsedImageContext;
+use crate::processor::traits::{BackgroundKind, FrameProcessor, LayoutConfig, BORDER, RATIO};
+
+use super::utils::{create_expanded_canvas, draw_text_aligned, TextAlign, TextStyle};
+
+// ==========================================
+// 1. 结构体定义
+// ==========================================
+
+/// 旅行白底：经典白色底栏，右侧显示拍摄地坐标和日期 (代替拍摄参数)
+pub struct TravelProcessor {
+    pub font_bold: FontArc,
+    pub font_medium: FontArc,
+    pub layout_overrides: HashMap<String, f32>,
+}
+
+impl FrameProcessor for TravelProcessor {
+    fn process(&self, img: &DynamicImage, ctx: &ParsedImageContext) -> Result<DynamicImage, AppError> {
+        let t_start = Instant::now();
+
+        let model_text = ctx.title().to_uppercase();
+        // 没有定位信息时右侧只显示日期
+        let gps_text = ctx.gps.as_ref().map(|g| g.format_dms());
+        let date_text = ctx.params.capture_date().unwrap_or_default();
+
+        let result = process_internal(
+            img,
+            &self.font_bold,
+            &self.font_medium,
+            &model_text,
+            gps_text.as_deref(),
+            date_text,
+            &self.layout_overrides,
+        )?;
+
+        info!("✨ [PERF] TravelWhite processed in {:.2?}", t_start.elapsed());
+        Ok(result)
+    }
+
+    fn background_kind(&self) -> BackgroundKind {
+        BackgroundKind::Solid(TravelConfig::default().bg_color)
+    }
+}
+
+// ==========================================
+// 2. 布局配置
+// ==========================================
+
+/// 可通过 layout_overrides 覆盖的键 (与字段同名)：
+/// `bar_ratio_land`, `bar_ratio_port`, `padding_ratio_land`, `padding_ratio_port`,
+/// `line_gap_ratio`, `font_scale_model_land`, `font_scale_model_port`, `font_scale_gps`,
+/// `font_scale_date`
+struct TravelConfig {
+    // 底栏高度 (相对短边)
+    bar_ratio_land: f32,
+    bar_ratio_port: f32,
+
+    // 左右边距 (相对底栏高度)
+    padding_ratio_land: f32,
+    padding_ratio_port: f32,
+    line_gap_ratio: f32, // 右侧两行之间的间距
+
+    // 字体缩放 (相对底栏高度)
+    font_scale_model_land: f32,
+    font_scale_model_port: f32,
+    font_scale_gps: f32,
+    font_scale_date: f32,
+
+    // 颜色
+    color_text_main: Rgba<u8>,
+    color_text_gps: Rgba<u8>,
+    color_text_date: Rgba<u8>,
+    bg_color: Rgba<u8>,
+}
+
+impl Default for TravelConfig {
+    fn default() -> Self {
+        Self {
+            bar_ratio_land: 0.12,
+            bar_ratio_port: 0.13,
+
+            padding_ratio_land: 0.5,
+            padding_ratio_port: 0.35,
+            line_gap_ratio: 0.08,
+
+            font_scale_model_land: 0.36,
+            font_scale_model_port: 0.28,
+            // 坐标字符多，右侧两行都比机型小一号
+            font_scale_gps: 0.26,
+            font_scale_date: 0.22,
+
+            color_text_main: Rgba([0, 0, 0, 255]),       // 纯黑
+            color_text_gps: Rgba([40, 40, 40, 255]),     // 近黑
+            color_text_date: Rgba([120, 120, 120, 255]), // 中灰
+            bg_color: Rgba([255, 255, 255, 255]),        // 纯白背景
+        }
+    }
+}
+
+impl LayoutConfig for TravelConfig {
+    const STYLE: &'static str = "TravelWhite";
+
+    fn field_mut(&mut self, key: &str) -> Option<(&mut f32, RangeInclusive<f32>)> {
+        Some(match key {
+            "bar_ratio_land" => (&mut self.bar_ratio_land, BORDER),
+            "bar_ratio_port" => (&mut self.bar_ratio_port, BORDER),
+            "padding_ratio_land" => (&mut self.padding_ratio_land, RATIO),
+            "padding_ratio_port" => (&mut self.padding_ratio_port, RATIO),
+            "line_gap_ratio" => (&mut self.line_gap_ratio, RATIO),
+            "font_scale_model_land" => (&mut self.font_scale_model_land, RATIO),
+            "font_scale_model_port" => (&mut self.font_scale_model_port, RATIO),
+            "font_scale_gps" => (&mut self.font_scale_gps, RATIO),
+            "font_scale_date" => (&mut self.font_scale_date, RATIO),
+            _ => return None,
+        })
+    }
+}
+
+// ==========================================
+// 3. 核心处理逻辑
+// ==========================================
+
+fn process_internal(
+    img: &DynamicImage,
+    font_bold: &FontArc,
+    font_medium: &FontArc,
+    model_text: &str,
+    gps_text: Option<&str>,
+    date_text: &str,
+    overrides: &HashMap<String, f32>,
+) -> Result<DynamicImage, AppError> {
+
+    let mut cfg = TravelConfig::default();
+    cfg.apply_overrides(overrides);
+    let (src_w, src_h) = img.dimensions();
+    let is_landscape = src_w >= src_h;
+
+    // A. 尺寸计算
+    let short_edge = min(src_w, src_h) as f32;
+    let ratio = if is_landscape { cfg.bar_ratio_land } else { cfg.bar_ratio_port };
+    let bar_height = (short_edge * ratio).round() as u32;
+
+    debug!("📐 [Layout] Travel: {}x{}, Bar={}, GPS={}", src_w, src_h, bar_height, gps_text.is_some());
+
+    // B. 画布构建
+    let t_canvas = Instant::now();
+    let mut canvas = DynamicImage::ImageRgba8(
+        create_expanded_canvas(img, 0, bar_height, 0, 0, cfg.bg_color)?
+    );
+    debug!("  -> [PERF] Canvas compose: {:.2?}", t_canvas.elapsed());
+
+    let (canvas_w, _canvas_h) = canvas.dimensions();
+
+    // C. 绘制内容
+    let bh = bar_height as f32;
+    let center_y = (src_h + bar_height / 2) as i32;
+    let padding_x = (bh * if is_landscape { cfg.padding_ratio_land } else { cfg.padding_ratio_port }) as i32;
+
+    // 1. 左侧：机型名称 (垂直居中)
+    let model_size = bh * if is_landscape { cfg.font_scale_model_land } else { cfg.font_scale_model_port };
+    draw_text_aligned(
+        &mut canvas, model_text, padding_x, center_y - (model_size as i32 / 2),
+        &TextStyle::new(font_bold, model_size, cfg.color_text_main, TextAlign::Left)
+    );
+
+    // 2. 右侧：坐标在上、日期在下 (右对齐)；没有坐标时日期单独垂直居中
+    let right_x = (canvas_w as i32) - padding_x;
+    let date_size = bh * cfg.font_scale_date;
+    match gps_text {
+        Some(gps) => {
+            let gps_size = bh * cfg.font_scale_gps;
+            let line_gap = (bh * cfg.line_gap_ratio) as i32;
+            let block_h = gps_size as i32 + line_gap + date_size as i32;
+            let gps_y = center_y - block_h / 2;
+            draw_text_aligned(
+                &mut canvas, gps, right_x, gps_y,
+                &TextStyle::new(font_medium, gps_size, cfg.color_text_gps, TextAlign::Right)
+            );
+            draw_text_aligned(
+                &mut canvas, date_text, right_x, gps_y + gps_size as i32 + line_gap,
+                &TextStyle::new(font_medium, date_size, cfg.color_text_date, TextAlign::Right)
+            );
+        },
+        None => {
+            draw_text_aligned(
+                &mut canvas, date_text, right_x, center_y - (date_size as i32 / 2),
+                &TextStyle::new(font_medium, date_size, cfg.color_text_date, TextAlign::Right)
+            );
+        },
+    }
+
+    Ok(canvas)
+}
@@ -1,15 +1,18 @@
 // src/processor/white/utils.rs
 
-use image::{DynamicImage, GenericImageView, Rgba, RgbaImage};
-use imageproc::drawing::{draw_text_mut, text_size, draw_filled_rect_mut, draw_polygon_mut};
-use imageproc::point::Point;
-use imageproc::rect::Rect;
+use image::{imageops, DynamicImage, GenericImageView, Rgba, RgbaImage};
 use ab_glyph::{Font, PxScale};
+use log::warn;
 use rayon::prelude::*;
-use std::f32::consts::PI;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 // 引入统一错误类型
+use crate::cancel;
 use crate::error::AppError;
+use crate::graphics::shapes::overlaps_canvas;
+use crate::graphics::{draw_text_with_fallback, draw_tracked_text, font_ascent, text_size_with_fallback, tint_image, TrackedLine};
+use crate::models::FrameTheme;
 
 /// 📐 对齐方式枚举
 #[derive(Clone, Copy, Debug)]
@@ -19,10 +22,37 @@ pub enum TextAlign {
     Right,
 }
 
+/// ✍️ 文字样式：字体、字号、颜色、对齐、字间距
+///
+/// 绘制函数只接收这一个结构，以后再加样式属性时不用改动所有调用点的参数表。
+pub struct TextStyle<'a, F: Font> {
+    pub font: &'a F,
+    pub size: f32,
+    pub color: Rgba<u8>,
+    pub align: TextAlign,
+    /// 相邻两个字符之间额外增加的像素 (0 = 普通排版)
+    pub tracking: f32,
+}
+
+impl<'a, F: Font> TextStyle<'a, F> {
+    pub fn new(font: &'a F, size: f32, color: Rgba<u8>, align: TextAlign) -> Self {
+        Self { font, size, color, align, tracking: 0.0 }
+    }
+
+    /// "P H O T O G R A P H" 这类宽字距标题
+    pub fn tracking(mut self, tracking: f32) -> Self {
+        self.tracking = tracking;
+        self
+    }
+}
+
 // ============================================================================
 // 1. 画布与合成 (Canvas & Composition) - 高性能区
 // ============================================================================
 
+// 画布逐行生成时每隔多少行检查一次取消
+const CANCEL_CHECK_ROWS: u32 = 64;
+
 /// 🚀 [高性能] 通用画布扩展器 (SIMD/Rayon Optimized)
 ///
 /// 作用：创建一个比原图大的画布，填充满背景色，并将原图贴在指定位置。
@@ -32,6 +62,8 @@ pub enum TextAlign {
 /// * `img`: 原图
 /// * `padding`: (top, bottom, left, right)
 /// * `bg_color`: 背景色
+///
+/// 当前任务被取消时 (见 cancel 模块) 中途放弃并返回 AppError::Cancelled
 pub fn create_expanded_canvas(
     img: &DynamicImage,
     top: u32,
@@ -50,11 +82,23 @@ pub fn create_expanded_canvas(
     // 预计算行的字节大小
     let row_len = (canvas_w * 4) as usize;
 
+    // 令牌要在调用线程上取出，rayon 的工作线程上没有
+    let token = cancel::current();
+    let aborted = AtomicBool::new(false);
+
     // 使用 Rayon 并行迭代器生成每一行的数据
     // flat_map + collect 会自动处理内存拼接
     let raw_buffer: Vec<u8> = (0..canvas_h)
         .into_par_iter()
         .flat_map(|y| {
+            // 已取消：剩下的行都不再生成
+            if y % CANCEL_CHECK_ROWS == 0 && token.as_ref().is_some_and(|t| t.is_cancelled()) {
+                aborted.store(true, Ordering::Relaxed);
+            }
+            if aborted.load(Ordering::Relaxed) {
+                return Vec::new();
+            }
+
             // 预分配一行内存，避免扩容
             let mut row = Vec::with_capacity(row_len);
             
@@ -91,11 +135,58 @@ pub fn create_expanded_canvas(
         })
         .collect();
 
+    if aborted.load(Ordering::Relaxed) {
+        return Err(AppError::Cancelled);
+    }
+
     // 构建 ImageBuffer
     RgbaImage::from_raw(canvas_w, canvas_h, raw_buffer)
         .ok_or_else(|| AppError::System("画布创建失败: 内存分配错误或尺寸溢出".to_string()))
 }
 
+/// 🎯 照片位移：在留白范围内重新分配四边的 padding
+///
+/// 画布尺寸不变 (文字排版照旧)，只改变原图的贴图位置。
+///
+/// # 参数
+/// * `offset`: (x, y)，取值 -1..1，表示占对应一侧留白的比例。x 正向右，y 正向上
+/// * `src_h`: 原图高度
+/// * `padding`: (top, bottom, left, right) 默认留白
+/// * `text_top`: 底部文字区顶部 (画布坐标)，照片向下移动时不会越过这里
+///
+/// # 返回
+/// 新的 (top, bottom, left, right)，可以直接传给 `create_expanded_canvas`
+pub fn offset_padding(
+    offset: Option<(f32, f32)>,
+    src_h: u32,
+    padding: (u32, u32, u32, u32),
+    text_top: i32,
+) -> (u32, u32, u32, u32) {
+    let (top, bottom, left, right) = padding;
+    let Some((x, y)) = offset else {
+        return padding;
+    };
+    let x = if x.is_finite() { x.clamp(-1.0, 1.0) } else { 0.0 };
+    let y = if y.is_finite() { y.clamp(-1.0, 1.0) } else { 0.0 };
+
+    // 水平：向右吃掉右边距，向左吃掉左边距
+    let new_left = if x >= 0.0 {
+        left + (right as f32 * x) as u32
+    } else {
+        left - (left as f32 * -x) as u32
+    };
+
+    // 垂直：向上吃掉顶部边距；向下只能用到照片底部与文字区之间的空隙
+    let new_top = if y >= 0.0 {
+        top - (top as f32 * y) as u32
+    } else {
+        let room_below = (text_top as i64 - (top + src_h) as i64).clamp(0, bottom as i64) as u32;
+        top + (room_below as f32 * -y) as u32
+    };
+
+    (new_top, top + bottom - new_top, new_left, left + right - new_left)
+}
+
 /// 辅助：快速填充行颜色
 #[inline(always)]
 fn fill_row_color(row: &mut Vec<u8>, count: u32, color: Rgba<u8>) {
@@ -115,7 +206,9 @@ fn fill_row_color(row: &mut Vec<u8>, count: u32, color: Rgba<u8>) {
 /// * `img_w`, `img_h`: 原图尺寸
 /// * `radius`: 圆角半径
 /// * `bg_color`: 必须与画布背景色一致
-#[allow(dead_code)]
+///
+/// 半径超过原图短边一半时自动截断；每个像素 2x2 超采样，按落在圆外的比例与背景色混合，
+/// 大半径 (40px 以上) 时边缘不再有明显锯齿。
 pub fn apply_inverse_corner_mask(
     canvas: &mut DynamicImage,
     img_x: u32,
@@ -125,57 +218,118 @@ pub fn apply_inverse_corner_mask(
     radius: u32,
     bg_color: Rgba<u8>
 ) {
+    mask_corners(canvas, img_x, img_y, img_w, img_h, radius, &|_, _| bg_color);
+}
+
+/// 🛠️ 圆角照片 + 阴影
+///
+/// 直接遮角会用纯背景色盖掉角上的阴影。这里先把四角遮成背景色再画阴影 (draw_shadow)，
+/// 保存四角此时的像素 (背景 + 阴影)，重贴原图后再用它们遮角。
+/// radius 为 0 时等价于 "画阴影 + 重贴原图"。
+pub fn overlay_rounded_photo_with_shadow(
+    canvas: &mut DynamicImage,
+    img: &DynamicImage,
+    img_x: u32,
+    img_y: u32,
+    radius: u32,
+    bg_color: Rgba<u8>,
+    draw_shadow: impl FnOnce(&mut DynamicImage),
+) {
+    let (img_w, img_h) = img.dimensions();
+    let radius = radius.min(img_w / 2).min(img_h / 2);
+
+    mask_corners(canvas, img_x, img_y, img_w, img_h, radius, &|_, _| bg_color);
+    draw_shadow(canvas);
+
+    // 四个角区域的左上角 (与 mask_corners 的顺序一致)
+    let origins = corner_origins(img_x, img_y, img_w, img_h, radius);
+    let backdrops: Vec<RgbaImage> = origins
+        .iter()
+        .map(|&(x, y)| imageops::crop_imm(&*canvas, x, y, radius, radius).to_image())
+        .collect();
+
+    imageops::overlay(canvas, img, img_x as i64, img_y as i64);
+
+    mask_corners(canvas, img_x, img_y, img_w, img_h, radius, &|x, y| {
+        origins
+            .iter()
+            .zip(&backdrops)
+            .find(|((ox, oy), _)| x >= *ox && y >= *oy && x < ox + radius && y < oy + radius)
+            .and_then(|((ox, oy), b)| b.get_pixel_checked(x - ox, y - oy).copied())
+            .unwrap_or(bg_color)
+    });
+}
+
+// 四个角区域的左上角：左上、右上、左下、右下
+fn corner_origins(img_x: u32, img_y: u32, img_w: u32, img_h: u32, radius: u32) -> [(u32, u32); 4] {
+    [
+        (img_x, img_y),
+        (img_x + img_w - radius, img_y),
+        (img_x, img_y + img_h - radius),
+        (img_x + img_w - radius, img_y + img_h - radius),
+    ]
+}
+
+/// 遮角核心：圆角外的像素换成 backdrop(x, y) (画布坐标)
+fn mask_corners(
+    canvas: &mut DynamicImage,
+    img_x: u32,
+    img_y: u32,
+    img_w: u32,
+    img_h: u32,
+    radius: u32,
+    backdrop: &dyn Fn(u32, u32) -> Rgba<u8>,
+) {
+    let radius = radius.min(img_w / 2).min(img_h / 2);
     if radius == 0 { return; }
 
-    let r_sq = (radius * radius) as f32;
+    let r = radius as f32;
+    let r_sq = r * r;
     let image_buffer = canvas.as_mut_rgba8().unwrap();
+    let (buf_w, buf_h) = image_buffer.dimensions();
 
-    // 🟢 修复点 1：将 check_fn 的类型改为 &dyn Fn(...)
-    // 这告诉编译器："我接受任何实现了 Fn trait 的闭包引用"
-    let mut mask_corner = |start_x: u32, start_y: u32, check_fn: &dyn Fn(f32, f32, f32) -> bool| {
+    // 2x2 子像素采样点
+    const SUB: [f32; 2] = [0.25, 0.75];
+
+    // (start_x, start_y): 角区域左上角；(cx, cy): 圆心相对角区域的位置
+    let mut mask_corner = |start_x: u32, start_y: u32, cx: f32, cy: f32| {
         for dy in 0..radius {
             for dx in 0..radius {
-                // 简单的抗锯齿中心采样 (+0.5)
-                if check_fn(dx as f32 + 0.5, dy as f32 + 0.5, radius as f32) {
-                    // 边界检查，防止越界
-                    if start_x + dx < image_buffer.width() && start_y + dy < image_buffer.height() {
-                        image_buffer.put_pixel(start_x + dx, start_y + dy, bg_color);
+                let (px, py) = (start_x + dx, start_y + dy);
+                // 边界检查，防止越界
+                if px >= buf_w || py >= buf_h { continue; }
+
+                let outside = SUB.iter()
+                    .flat_map(|&sy| SUB.iter().map(move |&sx| (sx, sy)))
+                    .filter(|&(sx, sy)| {
+                        let dist_x = dx as f32 + sx - cx;
+                        let dist_y = dy as f32 + sy - cy;
+                        dist_x * dist_x + dist_y * dist_y > r_sq
+                    })
+                    .count();
+
+                if outside == 0 { continue; }
+                let bg = backdrop(px, py);
+                let pixel = image_buffer.get_pixel_mut(px, py);
+                if outside == 4 {
+                    *pixel = bg;
+                } else {
+                    // 边缘像素：按覆盖率与背景混合
+                    let a = outside as f32 / 4.0;
+                    for c in 0..4 {
+                        pixel[c] = (pixel[c] as f32 * (1.0 - a) + bg[c] as f32 * a).round() as u8;
                     }
                 }
             }
         }
     };
 
-    // 🟢 修复点 2：在调用时，给闭包加上 & 符号 (传递引用)
-    
-    // 1. 左上角 (Top-Left)
-    // 距离圆心 (r, r) 的距离 > r 则涂色
-    mask_corner(img_x, img_y, &|dx, dy, r| {
-        let dist_x = r - dx;
-        let dist_y = r - dy;
-        (dist_x * dist_x + dist_y * dist_y) > r_sq
-    });
-
-    // 2. 右上角 (Top-Right)
-    mask_corner(img_x + img_w - radius, img_y, &|dx, dy, r| {
-        let dist_x = dx; // 圆心在左侧
-        let dist_y = r - dy;
-        (dist_x * dist_x + dist_y * dist_y) > r_sq
-    });
-
-    // 3. 左下角 (Bottom-Left)
-    mask_corner(img_x, img_y + img_h - radius, &|dx, dy, r| {
-        let dist_x = r - dx;
-        let dist_y = dy; // 圆心在上方
-        (dist_x * dist_x + dist_y * dist_y) > r_sq
-    });
-
-    // 4. 右下角 (Bottom-Right)
-    mask_corner(img_x + img_w - radius, img_y + img_h - radius, &|dx, dy, r| {
-        let dist_x = dx;
-        let dist_y = dy;
-        (dist_x * dist_x + dist_y * dist_y) > r_sq
-    });
+    // 圆心相对角区域的位置：左上角的圆心在右下，右上角的在左下，依此类推
+    let [tl, tr, bl, br] = corner_origins(img_x, img_y, img_w, img_h, radius);
+    mask_corner(tl.0, tl.1, r, r);
+    mask_corner(tr.0, tr.1, 0.0, r);
+    mask_corner(bl.0, bl.1, r, 0.0);
+    mask_corner(br.0, br.1, 0.0, 0.0);
 }
 
 
@@ -186,20 +340,29 @@ pub fn apply_inverse_corner_mask(
 /// ✍️ 通用文本绘制 (支持对齐)
 ///
 /// 封装了 `text_size` 计算，自动处理左、中、右对齐的坐标偏移。
+/// 主字体缺字 (署名里的中日文等) 时自动切换到 CJK 回退字体。
+/// style.tracking 不为 0 时按字间距排版，对齐按加上字间距后的整行宽度计算。
 pub fn draw_text_aligned<F: Font>(
     canvas: &mut DynamicImage,
-    font: &F,
     text: &str,
     x: i32, 
     y: i32, // 基准 Y 坐标 (通常是文字顶部或中心，取决于调用者逻辑，这里imageproc默认是顶部)
-    size: f32,
-    color: Rgba<u8>,
-    align: TextAlign,
+    style: &TextStyle<F>,
 ) {
     if text.is_empty() { return; }
     
+    let TextStyle { font, size, color, align, tracking } = *style;
+    if size <= 0.0 || !size.is_finite() || !tracking.is_finite() {
+        warn!("⚠️ [Draw] 非法字号 {} / 字间距 {}，跳过文字: {:?}", size, tracking, text);
+        return;
+    }
+    
     let scale = PxScale::from(size);
-    let (w, _h) = text_size(scale, font, text);
+    if tracking != 0.0 {
+        draw_tracked_aligned(canvas, text, x, y, style, scale);
+        return;
+    }
+    let (w, h) = text_size_with_fallback(scale, font, text);
 
     let draw_x = match align {
         TextAlign::Left => x,
@@ -207,85 +370,146 @@ pub fn draw_text_aligned<F: Font>(
         TextAlign::Right => x - (w as i32),
     };
 
-    draw_text_mut(canvas, color, draw_x, y, scale, font, text);
+    // 完全在画布外：记录并跳过 (部分越界交给 draw_text_mut 逐像素裁剪)
+    let box_h = (size.ceil() as u32).max(h);
+    if !overlaps_canvas(canvas, draw_x, y, w.max(1), box_h) {
+        warn!(
+            "⚠️ [Draw] 文字完全超出画布，已跳过: {:?} at ({}, {}) size={:.1} canvas={:?}",
+            text, draw_x, y, size, canvas.dimensions()
+        );
+        return;
+    }
+
+    draw_text_with_fallback(canvas, color, draw_x, y, scale, font, text);
 }
 
-/// 🔷 绘制高质量实心圆角矩形 (Polyfill)
+/// ✍️ 按基线绘制文字 (对齐方式同 draw_text_aligned)
 ///
-/// 使用多边形拟合圆角，比像素扫描质量更高。
-/// 用于绘制徽章、标签背景、分隔线等。
-pub fn draw_rounded_rect_polyfill(
-    canvas: &mut DynamicImage, 
-    rect: Rect, 
-    radius: i32, 
-    color: Rgba<u8>
+/// baseline_y 是基线位置：同一行里字号、字体不同的文字只要共用基线就能对齐，
+/// 不再需要按 text_size 的高度居中再手调偏移。
+pub fn draw_text_baseline<F: Font>(
+    canvas: &mut DynamicImage,
+    text: &str,
+    x: i32,
+    baseline_y: i32,
+    style: &TextStyle<F>,
 ) {
-    let x = rect.left() as f32;
-    let y = rect.top() as f32;
-    let w = rect.width() as f32;
-    let h = rect.height() as f32;
-    
-    let r = (radius as f32).min(w / 2.0).min(h / 2.0);
-
-    if r <= 0.5 {
-        draw_filled_rect_mut(canvas, rect, color);
+    if style.size <= 0.0 || !style.size.is_finite() {
+        warn!("⚠️ [Draw] 非法字号 {}，跳过文字: {:?}", style.size, text);
         return;
     }
+    let top_y = baseline_y - font_ascent(style.font, PxScale::from(style.size)).round() as i32;
+    draw_text_aligned(canvas, text, x, top_y, style);
+}
 
-    let segments = 16; 
-    let mut points: Vec<Point<i32>> = Vec::with_capacity(4 * (segments + 1)); 
+// 🔴 已移除 draw_text_tracked (字间距并入 TextStyle::tracking，由 draw_text_aligned 统一处理)
 
-    let mut add_arc = |cx: f32, cy: f32, start_angle: f32| {
-        for i in 0..=segments {
-            let angle = start_angle + (i as f32 / segments as f32) * (PI / 2.0);
-            let px = cx + r * angle.cos();
-            let py = cy + r * angle.sin();
-            points.push(Point::new(px.round() as i32, py.round() as i32));
-        }
+// 带字间距的一行：排版一次，宽度用于对齐，同一份结果直接绘制
+fn draw_tracked_aligned<F: Font>(canvas: &mut DynamicImage, text: &str, x: i32, y: i32, style: &TextStyle<F>, scale: PxScale) {
+    let line = TrackedLine::layout(style.font, text, scale, style.tracking);
+    let w = line.width;
+
+    let draw_x = match style.align {
+        TextAlign::Left => x as f32,
+        TextAlign::Center => x as f32 - w / 2.0,
+        TextAlign::Right => x as f32 - w,
     };
 
-    add_arc(x + w - r, y + r, -PI / 2.0);     // 右上
-    add_arc(x + w - r, y + h - r, 0.0);       // 右下
-    add_arc(x + r, y + h - r, PI / 2.0);      // 左下
-    add_arc(x + r, y + r, PI);                // 左上
-
-    // 🟢 🟢 🟢 修复开始 🟢 🟢 🟢
-    // imageproc 要求首尾点不能相同，否则会 panic。
-    // 在绘制胶囊形状（完全圆角）时，数学计算会导致首尾点重合，必须手动去重。
-    if let (Some(first), Some(last)) = (points.first(), points.last()) {
-        if first == last {
-            points.pop(); // 移除最后一个重复的点
-        }
+    if !overlaps_canvas(canvas, draw_x.floor() as i32, y, (w.ceil() as u32).max(1), (style.size.ceil() as u32).max(1)) {
+        warn!(
+            "⚠️ [Draw] 文字完全超出画布，已跳过: {:?} at ({:.0}, {}) size={:.1} canvas={:?}",
+            text, draw_x, y, style.size, canvas.dimensions()
+        );
+        return;
     }
-    // 🟢 🟢 🟢 修复结束 🟢 🟢 🟢
 
-    draw_polygon_mut(canvas, &points, color);
+    draw_tracked_text(canvas, style.color, draw_x, y, style.font, &line);
 }
 
 /// 🧱 绘制垂直参数列 (Value + Label)
 ///
 /// 专用于 WhiteMaster 风格的布局：上方是数值，下方是标签，整体居中。
+/// 两行都按基线放置 (文字, 基线, 样式)，各列的数值 / 标签分别对齐在同一条基线上。
 pub fn draw_param_column<F: Font>(
     canvas: &mut DynamicImage,
     center_x: i32,
-    val_y: i32,
-    lbl_y: i32,
-    value: &str,
-    label: &str,
-    font: &F,
-    val_size: f32,
-    lbl_size: f32,
-    val_color: Rgba<u8>,
-    lbl_color: Rgba<u8>
+    value: (&str, i32, &TextStyle<F>),
+    label: (&str, i32, &TextStyle<F>),
 ) {
     // 数值
-    draw_text_aligned(
-        canvas, font, value, 
-        center_x, val_y, val_size, val_color, TextAlign::Center
-    );
+    draw_text_baseline(canvas, value.0, center_x, value.1, value.2);
     // 标签
-    draw_text_aligned(
-        canvas, font, label, 
-        center_x, lbl_y, lbl_size, lbl_color, TextAlign::Center
-    );
-}
\ No newline at end of file
+    draw_text_baseline(canvas, label.0, center_x, label.1, label.2);
+}
+
+// ============================================================================
+// 4. 主题 (Theme)
+// ============================================================================
+
+/// 深色主题的背景色 (#111，比纯黑柔和，接近画廊卡纸)
+pub const DARK_BG_COLOR: Rgba<u8> = Rgba([17, 17, 17, 255]);
+
+/// 深色主题下字标 Logo 的颜色：浅灰，比纯白收敛，和主文字的亮度接近
+pub const DARK_LOGO_COLOR: Rgba<u8> = Rgba([230, 230, 230, 255]);
+
+/// 按主题处理字标 Logo：深色主题下黑色字标会看不见，统一着色为浅灰
+/// (徽标类 Logo 自带品牌色，调用方不应传进来)
+pub fn themed_logo(logo: Arc<DynamicImage>, theme: FrameTheme) -> Arc<DynamicImage> {
+    match theme {
+        FrameTheme::Light => logo,
+        FrameTheme::Dark => Arc::new(tint_image(&logo, DARK_LOGO_COLOR)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graphics::measure_text_bounds;
+
+    #[test]
+    fn photo_offset_stays_inside_padding() {
+        // 顶 100 / 底 300 / 左右 50，照片高 1000，文字区从 1150 开始 (照片底部下方留 50)
+        let padding = (100, 300, 50, 50);
+        assert_eq!(offset_padding(None, 1000, padding, 1150), padding);
+        // 向上 0.5：吃掉一半顶部留白，画布总高不变
+        assert_eq!(offset_padding(Some((0.0, 0.5)), 1000, padding, 1150), (50, 350, 50, 50));
+        // 向下最多到文字区顶部，不压住文字
+        assert_eq!(offset_padding(Some((0.0, -1.0)), 1000, padding, 1150), (150, 250, 50, 50));
+        // 超出范围按 ±1 处理，非有限值按 0 处理
+        assert_eq!(offset_padding(Some((3.0, 9.0)), 1000, padding, 1150), (0, 400, 100, 0));
+        assert_eq!(offset_padding(Some((f32::NAN, f32::INFINITY)), 1000, padding, 1150), padding);
+    }
+
+    #[test]
+    fn baseline_text_sits_on_baseline() {
+        let font = crate::resources::get_font(crate::resources::FontFamily::InterDisplay, crate::resources::FontWeight::Bold).unwrap();
+        let baseline = 120;
+        // 墨迹的 (首行, 末行) (白底黑字)
+        let ink = |text: &str, size: f32, align: TextAlign| {
+            let mut canvas = DynamicImage::ImageRgba8(RgbaImage::from_pixel(600, 200, Rgba([255, 255, 255, 255])));
+            draw_text_baseline(&mut canvas, text, 300, baseline, &TextStyle::new(&font, size, Rgba([0, 0, 0, 255]), align));
+            let rgba = canvas.to_rgba8();
+            let rows: Vec<u32> = (0..rgba.height()).filter(|&y| (0..rgba.width()).any(|x| rgba.get_pixel(x, y)[0] < 160)).collect();
+            (*rows.first().expect("没有墨迹") as i32, *rows.last().unwrap() as i32)
+        };
+
+        // 数字的下沿就在基线上，与字号、对齐方式无关 (不同字号的文字共用基线即可对齐)
+        for (size, align) in [(40.0, TextAlign::Center), (72.0, TextAlign::Left), (25.0, TextAlign::Right)] {
+            let (_, bottom) = ink("100", size, align);
+            assert!((bottom + 1 - baseline).abs() <= 1, "{}px: {}", size, bottom);
+        }
+
+        // "1/250" 与 "100" 的数字部分顶部对齐 (旧的按 text_size 居中会把它整体抬高几像素)
+        let (top_100, _) = ink("100", 60.0, TextAlign::Center);
+        let (top_shutter, bottom_shutter) = ink("1/250", 60.0, TextAlign::Center);
+        assert!((top_100 - top_shutter).abs() <= 1, "{} vs {}", top_100, top_shutter);
+        assert!(bottom_shutter > baseline, "斜杠应伸到基线以下");
+
+        // 带下伸部的文字：基线以下的部分与 measure_text_bounds 一致
+        let scale = PxScale::from(60.0);
+        let (_, ascent, descent) = measure_text_bounds(&font, "gyp", scale);
+        let (top, bottom) = ink("gyp", 60.0, TextAlign::Center);
+        assert!((baseline as f32 - ascent - top as f32).abs() <= 2.0, "{} vs {}", top, baseline as f32 - ascent);
+        assert!((baseline as f32 + descent - (bottom + 1) as f32).abs() <= 2.0, "{} vs {}", bottom, baseline as f32 + descent);
+    }
+}
@@ -4,17 +4,22 @@ use image::{DynamicImage, Rgba, imageops, GenericImageView};
 use imageproc::drawing::{draw_filled_rect_mut, text_size};
 use imageproc::rect::Rect;
 use ab_glyph::{FontArc, PxScale};
-use log::{info, debug};
+use log::{info, debug, warn};
+use std::ops::RangeInclusive;
 use std::time::Instant;
 use std::cmp::min;
+use std::collections::HashMap;
 
 use crate::error::AppError;
-use crate::parser::models::ParsedImageContext;
-use crate::processor::traits::FrameProcessor;
-use crate::resources::{self, LogoType};
+use crate::graphics::{contrast_text_color, text_size_with_fallback};
+use crate::parser::models::{ParamFormatOptions, ParsedImageContext, ShootingParams};
+use crate::processor::layout::clamp_bar_height;
+use crate::processor::traits::{BackgroundKind, FrameProcessor, LayoutConfig, LayoutInput, BORDER, RATIO, SCALE};
+use crate::models::{FrameTheme, ParamSeparator};
+use crate::resources::{self, Brand, LogoType};
 
 // 引入高性能工具箱
-use super::utils::{create_expanded_canvas, draw_text_aligned, TextAlign};
+use super::utils::{create_expanded_canvas, draw_text_aligned, themed_logo, TextAlign, TextStyle, DARK_BG_COLOR};
 
 // ==========================================
 // 1. 结构体定义
@@ -22,39 +27,115 @@ use super::utils::{create_expanded_canvas, draw_text_aligned, TextAlign};
 
 pub struct WhiteClassicProcessorV2 {
     pub font_data: FontArc,
+    pub brand_badge: bool,
+    pub show_artist: bool,
+    pub theme: FrameTheme,
+    pub params_separator: ParamSeparator,
+    pub layout_overrides: HashMap<String, f32>,
 }
 
 impl FrameProcessor for WhiteClassicProcessorV2 {
     fn process(&self, img: &DynamicImage, ctx: &ParsedImageContext) -> Result<DynamicImage, AppError> {
+        self.render(img, ctx, self.theme, None)
+    }
+
+    fn background_kind(&self) -> BackgroundKind {
+        let mut cfg = ClassicConfig::default();
+        cfg.apply_theme(self.theme);
+        BackgroundKind::Solid(cfg.bg_color)
+    }
+}
+
+impl WhiteClassicProcessorV2 {
+    /// 经典底栏布局；`accent` 为 Some 时底栏改用该颜色，文字颜色按亮度自动选择 (AccentBar 样式)
+    /// `theme` 决定字标 Logo 是否着色为浅色，AccentBar 按底栏亮度传入
+    pub(crate) fn render(
+        &self,
+        img: &DynamicImage,
+        ctx: &ParsedImageContext,
+        theme: FrameTheme,
+        accent: Option<Rgba<u8>>,
+    ) -> Result<DynamicImage, AppError> {
         let t_start = Instant::now();
 
-        // 1. 准备资源
-        // Classic 风格使用的是 Wordmark (文字标)
-        let logo_type = LogoType::Wordmark;
-        let logo_img = resources::get_logo(ctx.brand, logo_type);
-        
-        // 格式化文本
-        let model_text = format!("{} {}", ctx.brand, ctx.model_name).to_uppercase();
-        let params_text = ctx.params.format_standard();
+        // 1. 准备文本
+        // Logo (Wordmark) 需要等底栏尺寸算出来后再按高度取缓存，见 process_internal
+        // 参数行在布局配置确定后拼接，也在 process_internal 里完成
+        let model_text = ctx.title().to_uppercase();
+
+        // 徽标：只有开启选项且该品牌有徽标素材时才绘制
+        let badge_type = if self.brand_badge {
+            match resources::badge_logo_type(ctx.brand) {
+                Some(t) if resources::has_logo(ctx.brand, t) => Some(t),
+                _ => {
+                    warn!("⚠️ [WhiteClassic] {} 没有可用的徽标素材，已忽略 brand_badge", ctx.brand);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        // 署名：只有开启选项且 EXIF 里确实有作者时才绘制
+        let artist_text = if self.show_artist {
+            ctx.artist_name.as_deref().and_then(format_artist)
+        } else {
+            None
+        };
+
+        let input = ClassicInput {
+            model_text: &model_text,
+            params: &ctx.params,
+            params_separator: self.params_separator,
+            artist_text: artist_text.as_deref(),
+            brand: ctx.brand,
+            badge_type,
+        };
+        let layout = LayoutInput { theme, accent, ..LayoutInput::new(&self.layout_overrides) };
 
         // 2. 执行核心逻辑
-        let result = process_internal(
-            img, 
-            &self.font_data, 
-            &model_text,
-            &params_text,
-            logo_img
-        )?;
+        let result = process_internal(img, &self.font_data, &input, &layout)?;
 
         info!("✨ [PERF] WhiteClassic V2 processed in {:.2?}", t_start.elapsed());
         Ok(result)
     }
 }
 
+/// Classic 模式的单张图输入
+struct ClassicInput<'a> {
+    model_text: &'a str,
+    params: &'a ShootingParams, // 参数行按布局配置的格式拼接
+    params_separator: ParamSeparator, // 用户选的分隔符，覆盖布局配置里的默认值
+    artist_text: Option<&'a str>,
+    brand: Brand,
+    badge_type: Option<LogoType>, // 徽标 (可选)，None 不画
+}
+
+// "© {artist}"；EXIF 里常见自带 "©" / "(c)" 前缀，去掉避免重复
+fn format_artist(raw: &str) -> Option<String> {
+    let name = raw.trim();
+    let name = name.strip_prefix('©')
+        .or_else(|| name.strip_prefix("(c)"))
+        .or_else(|| name.strip_prefix("(C)"))
+        .unwrap_or(name)
+        .trim();
+    if name.is_empty() {
+        None
+    } else {
+        Some(format!("© {}", name))
+    }
+}
+
 // ==========================================
 // 2. 布局配置
 // ==========================================
 
+/// 可通过 layout_overrides 覆盖的键 (与字段同名)：
+/// `bar_ratio_land`, `bar_ratio_port`, `padding_ratio_land`, `padding_ratio_port`,
+/// `element_gap_ratio`, `text_gap_ratio_port`, `font_scale_main_land`, `font_scale_sub_land`,
+/// `font_scale_main_port`, `font_scale_sub_port`, `icon_scale_land`, `icon_scale_port`,
+/// `badge_scale`, `badge_gap_ratio`, `line_width_ratio`, `line_height_scale`,
+/// `artist_font_scale_land`, `artist_font_scale_port`
 struct ClassicConfig {
     // 基础比例
     bar_ratio_land: f32,    // 横构图底栏高度比例
@@ -75,14 +156,24 @@ struct ClassicConfig {
     // 图标与线条
     icon_scale_land: f32,
     icon_scale_port: f32,
+    badge_scale: f32,       // 徽标相对底栏高度的比例
+    badge_gap_ratio: f32,   // 徽标与机型文字的间距 (相对底栏高度)
     line_width_ratio: f32,
     line_height_scale: f32, // 线条相对于文字高度的比例
+
+    // 署名 (仅在有作者时参与布局)
+    artist_font_scale_land: f32, // 横构图：机型名称下方的第二行
+    artist_font_scale_port: f32, // 竖构图：底栏右侧，垂直居中
     
     // 颜色
     color_text_main: Rgba<u8>,
     color_text_sub: Rgba<u8>,
     color_line: Rgba<u8>,
+    color_text_artist: Rgba<u8>,
     bg_color: Rgba<u8>,
+
+    // 参数行：默认与旧版本一致 (双空格)，分隔符可由样式选项 params_separator 改为竖线
+    params_format: ParamFormatOptions<'static>,
 }
 
 impl Default for ClassicConfig {
@@ -106,16 +197,80 @@ impl Default for ClassicConfig {
             
             icon_scale_land: 0.35,
             icon_scale_port: 0.38,
+
+            badge_scale: 0.42,
+            badge_gap_ratio: 0.20,
             
             line_width_ratio: 0.025,
             line_height_scale: 1.5, // 竖线比文字略高
+
+            artist_font_scale_land: 0.24,
+            artist_font_scale_port: 0.22,
             
             color_text_main: Rgba([0, 0, 0, 255]),      // 纯黑
             color_text_sub: Rgba([60, 60, 60, 255]),    // 深灰
             color_line: Rgba([160, 160, 160, 255]),     // 浅灰线条
+            color_text_artist: Rgba([110, 110, 110, 255]), // 中灰署名
             bg_color: Rgba([255, 255, 255, 255]),       // 纯白背景
+
+            params_format: ParamFormatOptions::STANDARD,
+        }
+    }
+}
+
+impl ClassicConfig {
+    // 深色主题：近黑底 + 浅色文字，层次关系与浅色主题保持一致
+    fn apply_theme(&mut self, theme: FrameTheme) {
+        if theme == FrameTheme::Dark {
+            self.color_text_main = Rgba([240, 240, 240, 255]);
+            self.color_text_sub = Rgba([190, 190, 190, 255]);
+            self.color_line = Rgba([90, 90, 90, 255]);
+            self.color_text_artist = Rgba([140, 140, 140, 255]);
+            self.bg_color = DARK_BG_COLOR;
         }
     }
+
+    // 彩色底栏：主文字取黑 / 白对比色，其余层次按比例向底色靠拢
+    fn apply_accent(&mut self, bg: Rgba<u8>) {
+        let text = contrast_text_color(bg);
+        let mix = |t: f32| {
+            let c = |i: usize| (text[i] as f32 + (bg[i] as f32 - text[i] as f32) * t).round() as u8;
+            Rgba([c(0), c(1), c(2), 255])
+        };
+        self.color_text_main = text;
+        self.color_text_sub = mix(0.2);
+        self.color_line = mix(0.55);
+        self.color_text_artist = mix(0.4);
+        self.bg_color = bg;
+    }
+}
+
+impl LayoutConfig for ClassicConfig {
+    const STYLE: &'static str = "WhiteClassic";
+
+    fn field_mut(&mut self, key: &str) -> Option<(&mut f32, RangeInclusive<f32>)> {
+        Some(match key {
+            "bar_ratio_land" => (&mut self.bar_ratio_land, BORDER),
+            "bar_ratio_port" => (&mut self.bar_ratio_port, BORDER),
+            "padding_ratio_land" => (&mut self.padding_ratio_land, RATIO),
+            "padding_ratio_port" => (&mut self.padding_ratio_port, RATIO),
+            "element_gap_ratio" => (&mut self.element_gap_ratio, SCALE),
+            "text_gap_ratio_port" => (&mut self.text_gap_ratio_port, RATIO),
+            "font_scale_main_land" => (&mut self.font_scale_main_land, RATIO),
+            "font_scale_sub_land" => (&mut self.font_scale_sub_land, RATIO),
+            "font_scale_main_port" => (&mut self.font_scale_main_port, RATIO),
+            "font_scale_sub_port" => (&mut self.font_scale_sub_port, RATIO),
+            "icon_scale_land" => (&mut self.icon_scale_land, RATIO),
+            "icon_scale_port" => (&mut self.icon_scale_port, RATIO),
+            "badge_scale" => (&mut self.badge_scale, RATIO),
+            "badge_gap_ratio" => (&mut self.badge_gap_ratio, SCALE),
+            "line_width_ratio" => (&mut self.line_width_ratio, RATIO),
+            "line_height_scale" => (&mut self.line_height_scale, SCALE),
+            "artist_font_scale_land" => (&mut self.artist_font_scale_land, RATIO),
+            "artist_font_scale_port" => (&mut self.artist_font_scale_port, RATIO),
+            _ => return None,
+        })
+    }
 }
 
 // ==========================================
@@ -125,19 +280,28 @@ impl Default for ClassicConfig {
 fn process_internal(
     img: &DynamicImage,
     font: &FontArc,
-    model_text: &str,
-    params_text: &str,
-    logo_opt: Option<std::sync::Arc<DynamicImage>>,
+    input: &ClassicInput,
+    layout: &LayoutInput,
 ) -> Result<DynamicImage, AppError> {
     
-    let cfg = ClassicConfig::default();
+    let mut cfg = ClassicConfig::default();
+    cfg.apply_theme(layout.theme);
+    if let Some(bg) = layout.accent {
+        cfg.apply_accent(bg);
+    }
+    cfg.apply_overrides(layout.overrides);
+    let ClassicInput { model_text, params, params_separator, artist_text, brand, badge_type } = *input;
+    cfg.params_format.separator = params_separator.as_str();
+    let theme = layout.theme;
+    let params_string = params.format_with(&cfg.params_format);
+    let params_text = params_string.as_str();
     let (src_w, src_h) = img.dimensions();
     let is_landscape = src_w >= src_h;
 
     // A. 尺寸计算
     let short_edge = min(src_w, src_h) as f32;
     let ratio = if is_landscape { cfg.bar_ratio_land } else { cfg.bar_ratio_port };
-    let bar_height = (short_edge * ratio).round() as u32;
+    let bar_height = clamp_bar_height(short_edge * ratio, src_w, src_h);
 
     debug!("📐 [Layout] Classic: {}x{}, Bar={}", src_w, src_h, bar_height);
 
@@ -156,6 +320,18 @@ fn process_internal(
     let gap = (bh * cfg.element_gap_ratio) as i32;
     let line_w = (bh * cfg.line_width_ratio).max(1.0) as u32;
 
+    // 徽标 (可选)：绘制在内容起点，返回内容起点需要右移的距离
+    let badge_h = ((bh * cfg.badge_scale) as u32).max(1);
+    let badge_gap = (bh * cfg.badge_gap_ratio) as i32;
+    let draw_badge = |canvas: &mut DynamicImage, x: i32| -> i32 {
+        let Some(badge) = badge_type.and_then(|t| resources::get_logo_scaled(brand, t, badge_h)) else {
+            return 0;
+        };
+        let badge_y = center_y - (badge.height() as i32 / 2);
+        imageops::overlay(canvas, badge.as_ref(), x as i64, badge_y as i64);
+        badge.width() as i32 + badge_gap
+    };
+
     if is_landscape {
         // ===========================================
         // 🟢 布局 1: 横构图 (左右分栏)
@@ -164,59 +340,99 @@ fn process_internal(
         // ===========================================
         
         let padding_x = (bh * cfg.padding_ratio_land) as i32;
-        
-        // 1. 左侧：机型名称 (保持不变)
-        let main_size = bh * cfg.font_scale_main_land;
-        draw_text_aligned(
-            &mut canvas, font, model_text,
-            padding_x, center_y - (main_size as i32 / 2),
-            main_size, cfg.color_text_main, TextAlign::Left
-        );
-
-        // 2. 右侧：从右向左绘制 (Params -> Line -> Logo)
+
+        // 1. 左侧起点：[徽标]
+        let model_x = padding_x + draw_badge(&mut canvas, padding_x);
+
+        // 2. 先量左右两块的宽度，长机型名 + 宽 Wordmark 会在 3:2 图上撞到一起，放不下时按顺序降级
+        let icon_h = (bh * cfg.icon_scale_land) as u32;
+        // Wordmark 宽高比：降级试算时按比例估算宽度，最终只按选定高度取一次 (批次缓存)
+        let logo_aspect = resources::get_logo_scaled(brand, LogoType::Wordmark, icon_h)
+            .map(|l| l.width() as f32 / l.height().max(1) as f32);
+        let block_widths = |scale: f32, show_params: bool| -> (i32, i32) {
+            let (main_w, _) = text_size(PxScale::from(bh * cfg.font_scale_main_land * scale), font, model_text);
+            let artist_w = artist_text.map_or(0, |a| text_size_with_fallback(PxScale::from(bh * cfg.artist_font_scale_land * scale), font, a).0);
+            let logo_w = logo_aspect.map_or(0, |r| ((icon_h as f32 * scale) as u32 as f32 * r) as i32);
+            let params_w = if show_params && !params_text.is_empty() {
+                let (text_w, _) = text_size(PxScale::from(bh * cfg.font_scale_sub_land * scale), font, params_text);
+                text_w as i32 + gap + if logo_aspect.is_some() { line_w as i32 + gap } else { 0 }
+            } else {
+                0
+            };
+            (main_w.max(artist_w) as i32, params_w + logo_w)
+        };
+        // 左侧文字从 model_x 开始，右侧贴右边距，两块之间至少留一个 gap
+        let fit = fit_landscape((canvas_w as i32) - padding_x - model_x - gap, block_widths);
+
+        let main_size = bh * cfg.font_scale_main_land * fit.scale * fit.model_scale;
+        let sub_size = bh * cfg.font_scale_sub_land * fit.scale;
+        let icon_h = ((icon_h as f32 * fit.scale) as u32).max(1);
+        let params_text = if fit.show_params { params_text } else { "" };
+        // Classic 风格使用的是 Wordmark (文字标)，高度统一为 icon_h
+        let logo_opt = resources::get_logo_scaled(brand, LogoType::Wordmark, icon_h).map(|l| themed_logo(l, theme));
+        let params_w = if params_text.is_empty() {
+            0
+        } else {
+            text_size(PxScale::from(sub_size), font, params_text).0 as i32 + gap
+        };
+        let line_block_w = if logo_opt.is_some() && !params_text.is_empty() { line_w as i32 + gap } else { 0 };
+        let logo_w = logo_opt.as_ref().map_or(0, |l| l.width() as i32);
+
+        // 左侧：机型名称 (+ 署名)
+        if let Some(artist) = artist_text {
+            // 有署名时左侧变为两行堆叠：机型在上、署名在下，整体垂直居中
+            let artist_size = bh * cfg.artist_font_scale_land * fit.scale * fit.model_scale;
+            let text_gap = (bh * cfg.text_gap_ratio_port) as i32;
+            let block_h = main_size as i32 + text_gap + artist_size as i32;
+            let main_y = center_y - block_h / 2;
+            draw_text_aligned(
+                &mut canvas, model_text, model_x, main_y,
+                &TextStyle::new(font, main_size, cfg.color_text_main, TextAlign::Left)
+            );
+            draw_text_aligned(
+                &mut canvas, artist, model_x, main_y + main_size as i32 + text_gap,
+                &TextStyle::new(font, artist_size, cfg.color_text_artist, TextAlign::Left)
+            );
+        } else {
+            draw_text_aligned(
+                &mut canvas, model_text, model_x, center_y - (main_size as i32 / 2),
+                &TextStyle::new(font, main_size, cfg.color_text_main, TextAlign::Left)
+            );
+        }
+
+        // 3. 右侧：从右向左绘制 (Params -> Line -> Logo)
         // 这样视觉上就是 (Logo | Line | Params) 靠右对齐
         let mut cursor_x = (canvas_w as i32) - padding_x;
-        let icon_h = (bh * cfg.icon_scale_land) as u32;
 
         // A. 参数 (最右侧)
         if !params_text.is_empty() {
-            let sub_size = bh * cfg.font_scale_sub_land;
             // 使用右对齐绘制
             draw_text_aligned(
-                &mut canvas, font, params_text,
-                cursor_x, center_y - (sub_size as i32 / 2),
-                sub_size, cfg.color_text_sub, TextAlign::Right
+                &mut canvas, params_text, cursor_x, center_y - (sub_size as i32 / 2),
+                &TextStyle::new(font, sub_size, cfg.color_text_sub, TextAlign::Right)
             );
-            // 🟢 修复：需要测量文字宽度，以便向左移动光标给线和Logo留位置
-            let (text_w, _) = text_size(PxScale::from(sub_size), font, params_text);
-            cursor_x -= text_w as i32 + gap;
+            // 向左移动光标给线和 Logo 留位置
+            cursor_x -= params_w;
         }
 
         // B. 竖线 (中间)
-        if logo_opt.is_some() && !params_text.is_empty() {
-            let line_h = (icon_h as f32 * 1.5) as u32;
+        if line_block_w > 0 {
+            let line_h = ((icon_h as f32 * 1.5) as u32).max(1);
             let line_y = center_y - (line_h as i32 / 2);
             // 线条画在当前光标的左侧
             let rect = Rect::at(cursor_x - line_w as i32, line_y).of_size(line_w, line_h);
             draw_filled_rect_mut(&mut canvas, rect, cfg.color_line);
             
-            cursor_x -= line_w as i32 + gap;
+            cursor_x -= line_block_w;
         }
 
         // C. Logo (最左侧)
         if let Some(logo) = &logo_opt {
-            // 🔴 修改前：使用了 logo.width() 作为限制，这可能会导致大图被限制宽度而达不到目标高度
-            // let resized = logo.resize(logo.width(), icon_h, imageops::FilterType::Triangle);
-
-            // 🟢 修改后：使用 u32::MAX 作为宽度限制，强制高度统一为 icon_h
-            // 宽度会根据比例自动调整
-            let resized = logo.resize(u32::MAX, icon_h, imageops::FilterType::Triangle);
-            
-            let logo_w = resized.width() as i32;
-            let logo_y = center_y - (resized.height() as i32 / 2);
+            // 已按 icon_h 缩放好 (批次缓存)，宽度根据比例自动调整
+            let logo_y = center_y - (logo.height() as i32 / 2);
             
             // Logo 的右边缘是当前的 cursor_x，所以左边缘是 cursor_x - logo_w
-            imageops::overlay(&mut canvas, &resized, (cursor_x - logo_w) as i64, logo_y as i64);
+            imageops::overlay(&mut canvas, logo.as_ref(), (cursor_x - logo_w) as i64, logo_y as i64);
         }
 
     } else {
@@ -225,24 +441,20 @@ fn process_internal(
         // ===========================================
         let padding_x = (bh * cfg.padding_ratio_port) as i32;
         let mut cursor_x = padding_x;
+        cursor_x += draw_badge(&mut canvas, cursor_x);
         let icon_h = (bh * cfg.icon_scale_port) as u32;
+        let logo_opt = resources::get_logo_scaled(brand, LogoType::Wordmark, icon_h).map(|l| themed_logo(l, theme));
         
         // A. Logo
         if let Some(logo) = &logo_opt {
-            // 🔴 修改前
-            // let resized = logo.resize(logo.width(), icon_h, imageops::FilterType::Triangle);
-
-            // 🟢 修改后：同样使用 u32::MAX 强制固定高度
-            let resized = logo.resize(u32::MAX, icon_h, imageops::FilterType::Triangle);
-
-            let logo_y = center_y - (resized.height() as i32 / 2);
-            imageops::overlay(&mut canvas, &resized, cursor_x as i64, logo_y as i64);
-            cursor_x += resized.width() as i32 + gap;
+            let logo_y = center_y - (logo.height() as i32 / 2);
+            imageops::overlay(&mut canvas, logo.as_ref(), cursor_x as i64, logo_y as i64);
+            cursor_x += logo.width() as i32 + gap;
         }
 
         // B. 竖线
         if logo_opt.is_some() {
-            let line_h = (icon_h as f32 * cfg.line_height_scale) as u32;
+            let line_h = ((icon_h as f32 * cfg.line_height_scale) as u32).max(1);
             let line_y = center_y - (line_h as i32 / 2);
             let rect = Rect::at(cursor_x, line_y).of_size(line_w, line_h);
             draw_filled_rect_mut(&mut canvas, rect, cfg.color_line);
@@ -256,9 +468,220 @@ fn process_internal(
         let main_y = center_y - (text_gap / 2) - (main_size as i32);
         let sub_y = center_y + (text_gap / 2);
 
-        draw_text_aligned(&mut canvas, font, model_text, cursor_x, main_y, main_size, cfg.color_text_main, TextAlign::Left);
-        draw_text_aligned(&mut canvas, font, params_text, cursor_x, sub_y, sub_size, cfg.color_text_sub, TextAlign::Left);
+        draw_text_aligned(&mut canvas, model_text, cursor_x, main_y, &TextStyle::new(font, main_size, cfg.color_text_main, TextAlign::Left));
+        draw_text_aligned(&mut canvas, params_text, cursor_x, sub_y, &TextStyle::new(font, sub_size, cfg.color_text_sub, TextAlign::Left));
+
+        // D. 署名 (右对齐)：竖构图底栏左侧已经是两行，署名放在右侧，不挤压原有布局
+        if let Some(artist) = artist_text {
+            let artist_size = bh * cfg.artist_font_scale_port;
+            let (main_w, _) = text_size(PxScale::from(main_size), font, model_text);
+            let (sub_w, _) = text_size(PxScale::from(sub_size), font, params_text);
+            let (artist_w, _) = text_size_with_fallback(PxScale::from(artist_size), font, artist);
+            let left_end = cursor_x + main_w.max(sub_w) as i32;
+            let right_x = (canvas_w as i32) - padding_x;
+
+            // 窄图上放不下时宁可不画，也不和左侧文字重叠
+            if right_x - artist_w as i32 >= left_end + gap {
+                draw_text_aligned(
+                    &mut canvas, artist, right_x, center_y - (artist_size as i32 / 2),
+                    &TextStyle::new(font, artist_size, cfg.color_text_artist, TextAlign::Right)
+                );
+            } else {
+                debug!("  -> 底栏空间不足，跳过署名: {}", artist);
+            }
+        }
     }
 
     Ok(canvas)
-}
\ No newline at end of file
+}
+// ==========================================
+// 4. 横构图左右碰撞处理
+// ==========================================
+
+// 每步缩小的比例与下限 (再小就和竖构图的字号差不多了，不如直接去掉参数)
+const FIT_STEP: f32 = 0.9;
+const FIT_MIN_SCALE: f32 = 0.7;
+
+/// 横构图的降级结果
+struct LandscapeFit {
+    /// 字号与 Logo 高度的整体缩放
+    scale: f32,
+    /// 是否保留右侧参数文字 (连同竖线)
+    show_params: bool,
+    /// 左侧文字在 scale 之外的额外缩放 (最后手段)
+    model_scale: f32,
+}
+
+/// 左块 (机型 / 署名) + 右块 (Logo | Line | Params) 超出 avail 时依次降级：
+/// 1. 字号和 Logo 一起缩小，每步 FIT_STEP，最多到 FIT_MIN_SCALE
+/// 2. 还放不下：去掉参数文字，恢复原尺寸重新缩
+/// 3. 机型名实在太长：只把左侧文字缩到刚好放下
+///
+/// widths(scale, show_params) 返回该尺寸下 (左块宽, 右块宽)
+fn fit_landscape(avail: i32, widths: impl Fn(f32, bool) -> (i32, i32)) -> LandscapeFit {
+    for show_params in [true, false] {
+        let mut scale = 1.0;
+        loop {
+            let (left_w, right_w) = widths(scale, show_params);
+            if left_w + right_w <= avail {
+                if scale < 1.0 || !show_params {
+                    debug!("  -> 左右内容重叠，降级: 缩放 {:.2}，{}参数", scale, if show_params { "保留" } else { "去掉" });
+                }
+                return LandscapeFit { scale, show_params, model_scale: 1.0 };
+            }
+            if scale <= FIT_MIN_SCALE {
+                break;
+            }
+            scale = (scale * FIT_STEP).max(FIT_MIN_SCALE);
+        }
+    }
+
+    let (left_w, right_w) = widths(FIT_MIN_SCALE, false);
+    let model_scale = ((avail - right_w).max(0) as f32 / left_w.max(1) as f32).min(1.0);
+    debug!("  -> 左右内容重叠，降级: 去掉参数，机型文字缩放至 {:.2}", FIT_MIN_SCALE * model_scale);
+    LandscapeFit { scale: FIT_MIN_SCALE, show_params: false, model_scale }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage};
+    use crate::models::{FrameLabels, StyleOptions};
+    use crate::parser::{self, models::RawExifData};
+
+    fn render(make: &str, model: &str, brand_badge: bool) -> DynamicImage {
+        let style: StyleOptions = serde_json::from_value(serde_json::json!({
+            "style": "WhiteClassic", "brandBadge": brand_badge,
+        })).unwrap();
+        let ctx = parser::parse(RawExifData { make: make.into(), model: model.into(), ..Default::default() });
+        let img = DynamicImage::ImageRgb8(RgbImage::from_pixel(1500, 1000, Rgb([128, 128, 128])));
+        crate::processor::create_processor(&style, &FrameLabels::default()).unwrap().process(&img, &ctx).unwrap()
+    }
+
+    // 底栏中线 (源图高 1000)
+    fn bar_center_y(canvas: &DynamicImage) -> u32 {
+        1000 + (canvas.height() - 1000) / 2
+    }
+
+    // 底栏中线上从 from 开始第一个深色像素的 x (机型文字的起点)
+    fn first_dark_x(canvas: &DynamicImage, from: u32) -> u32 {
+        let y = bar_center_y(canvas);
+        (from..canvas.width() / 2)
+            .find(|&x| {
+                let p = canvas.get_pixel(x, y);
+                p[0] < 100 && p[1] < 100 && p[2] < 100
+            })
+            .expect("底栏左侧没有文字")
+    }
+
+    #[test]
+    fn badge_assets_are_wired() {
+        // 尼康小黄块已打包；徕卡红点素材还没有，暂不声明徽标
+        assert_eq!(resources::badge_logo_type(Brand::Nikon), Some(LogoType::IconYellowBox));
+        assert!(resources::has_logo(Brand::Nikon, LogoType::IconYellowBox));
+        assert_eq!(resources::badge_logo_type(Brand::Leica), None);
+        assert_eq!(resources::badge_logo_type(Brand::Sony), None);
+    }
+
+    #[test]
+    fn nikon_badge_shifts_model_text() {
+        let plain = render("NIKON CORPORATION", "NIKON Z 8", false);
+        let badged = render("NIKON CORPORATION", "NIKON Z 8", true);
+        assert_eq!(plain.dimensions(), badged.dimensions());
+
+        let cfg = ClassicConfig::default();
+        let bh = (badged.height() - 1000) as f32;
+        let padding_x = (bh * cfg.padding_ratio_land) as u32;
+        let badge = resources::get_logo_scaled(Brand::Nikon, LogoType::IconYellowBox, ((bh * cfg.badge_scale) as u32).max(1)).unwrap();
+        let badge_gap = (bh * cfg.badge_gap_ratio) as u32;
+
+        // 徽标画在内容起点：中心处是黄色
+        let badge_center = (padding_x + badge.width() / 2, bar_center_y(&badged));
+        let center = badged.get_pixel(badge_center.0, badge_center.1);
+        let is_yellow = |p: Rgba<u8>| p[0] > 200 && p[1] > 150 && p[2] < 100;
+        assert!(is_yellow(center), "徽标位置不是黄色: {:?}", center);
+        assert!(!is_yellow(plain.get_pixel(badge_center.0, badge_center.1)));
+
+        // 机型文字右移徽标宽度 + 间距
+        let plain_x = first_dark_x(&plain, 0);
+        let badged_x = first_dark_x(&badged, padding_x + badge.width());
+        assert_eq!(badged_x - plain_x, badge.width() + badge_gap);
+    }
+
+    #[test]
+    fn missing_badge_asset_is_a_no_op() {
+        let plain = render("LEICA CAMERA AG", "LEICA Q3", false);
+        let badged = render("LEICA CAMERA AG", "LEICA Q3", true);
+        assert_eq!(plain.to_rgba8().as_raw(), badged.to_rgba8().as_raw());
+    }
+
+    #[test]
+    fn fit_degrades_in_order() {
+        // 左右块宽度与缩放成正比；去掉参数后右块只剩 Logo
+        let widths = |left: f32, logo: f32, params: f32| move |scale: f32, show_params: bool| {
+            let right = logo + if show_params { params } else { 0.0 };
+            ((left * scale) as i32, (right * scale) as i32)
+        };
+
+        // 放得下：不降级
+        let fit = fit_landscape(1000, widths(300.0, 200.0, 300.0));
+        assert_eq!((fit.scale, fit.show_params, fit.model_scale), (1.0, true, 1.0));
+
+        // 稍宽：整体缩小，保留参数
+        let fit = fit_landscape(1000, widths(400.0, 200.0, 500.0));
+        assert!(fit.scale < 1.0 && fit.scale >= FIT_MIN_SCALE && fit.show_params);
+
+        // 缩到下限也放不下：去掉参数
+        let fit = fit_landscape(1000, widths(700.0, 200.0, 900.0));
+        assert!(!fit.show_params && fit.model_scale == 1.0);
+
+        // 机型名本身就超长：最后只缩左侧文字
+        let fit = fit_landscape(1000, widths(3000.0, 200.0, 900.0));
+        assert_eq!((fit.scale, fit.show_params), (FIT_MIN_SCALE, false));
+        let left = (3000.0 * FIT_MIN_SCALE) as i32 as f32 * fit.model_scale;
+        assert!(left + 200.0 * FIT_MIN_SCALE <= 1000.0 + 1.0, "{}", left);
+    }
+
+    #[test]
+    fn long_model_name_does_not_overlap_wordmark() {
+        // 与 process_internal 相同的量法：3:2 横图、故意超长的机型名 + 尼康字标 + 参数
+        let font = resources::get_font(resources::FontFamily::InterDisplay, resources::FontWeight::Bold).unwrap();
+        let cfg = ClassicConfig::default();
+        let (src_w, src_h) = (1500u32, 1000u32);
+        let bh = clamp_bar_height(src_h as f32 * cfg.bar_ratio_land, src_w, src_h) as f32;
+        let padding_x = (bh * cfg.padding_ratio_land) as i32;
+        let gap = (bh * cfg.element_gap_ratio) as i32;
+        let model = "NIKON Z 8 LIMITED EDITION WITH AN UNREASONABLY LONG NAME FROM A CUSTOM FIRMWARE";
+        let params = "24-120mm  f/4.0  1/250s  ISO 64";
+        let icon_h = (bh * cfg.icon_scale_land) as u32;
+        let logo = resources::get_logo_scaled(Brand::Nikon, LogoType::Wordmark, icon_h).unwrap();
+        let aspect = logo.width() as f32 / logo.height() as f32;
+        let widths = |scale: f32, show_params: bool| {
+            let (main_w, _) = text_size(PxScale::from(bh * cfg.font_scale_main_land * scale), &font, model);
+            let logo_w = ((icon_h as f32 * scale) as u32 as f32 * aspect) as i32;
+            let params_w = if show_params {
+                text_size(PxScale::from(bh * cfg.font_scale_sub_land * scale), &font, params).0 as i32 + gap * 2
+            } else {
+                0
+            };
+            (main_w as i32, params_w + logo_w)
+        };
+        let avail = src_w as i32 - padding_x * 2 - gap;
+        // 不降级时一定重叠
+        let (left, right) = widths(1.0, true);
+        assert!(left + right > avail);
+
+        let fit = fit_landscape(avail, widths);
+        let (left, right) = widths(fit.scale, fit.show_params);
+        assert!((left as f32 * fit.model_scale) as i32 + right <= avail + 1);
+        assert!(!fit.show_params, "超长机型名应当先去掉参数");
+
+        // 实际出图不 panic
+        let style: StyleOptions = serde_json::from_value(serde_json::json!({ "style": "WhiteClassic" })).unwrap();
+        let ctx = parser::parse(RawExifData { make: "NIKON CORPORATION".into(), model: model.into(), ..Default::default() });
+        let img = DynamicImage::ImageRgb8(RgbImage::from_pixel(src_w, src_h, Rgb([128, 128, 128])));
+        let out = crate::processor::create_processor(&style, &FrameLabels::default()).unwrap().process(&img, &ctx).unwrap();
+        assert_eq!(out.width(), src_w);
+    }
+}
+
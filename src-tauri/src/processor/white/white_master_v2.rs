@@ -2,21 +2,29 @@
 
 use image::{DynamicImage, Rgba, GenericImageView};
 use ab_glyph::{Font, FontArc, PxScale};
-use imageproc::drawing::{draw_filled_rect_mut, text_size};
+use imageproc::drawing::draw_filled_rect_mut;
 use imageproc::rect::Rect;
 use log::{info, debug};
+use std::ops::RangeInclusive;
 use std::time::Instant;
+use std::collections::HashMap;
 
 use crate::error::AppError;
 use crate::parser::models::ParsedImageContext;
-use crate::processor::traits::FrameProcessor;
+use crate::models::{FrameLabels, FrameTheme};
+use crate::processor::layout::{bar_basis, clamp_bar_height};
+use crate::processor::traits::{BackgroundKind, FrameProcessor, LayoutConfig, LayoutInput, BORDER, NUDGE, RATIO, SCALE};
+use crate::graphics::{font_ascent, measure_text_bounds, text_size_with_fallback};
 
 // 引入高性能工具箱
 use super::utils::{
     create_expanded_canvas, 
+    offset_padding, 
     draw_text_aligned, 
     draw_param_column, 
-    TextAlign
+    TextAlign,
+    TextStyle,
+    DARK_BG_COLOR,
 };
 
 // ==========================================
@@ -27,6 +35,12 @@ pub struct WhiteMasterProcessorV2 {
     pub main_font: FontArc,   // 用于参数数值
     pub script_font: FontArc, // 用于 "The decisive moment"
     pub serif_font: FontArc,  // 用于 "MASTER SERIES" / "PHOTOGRAPH"
+    pub photo_offset: Option<(f32, f32)>,
+    pub theme: FrameTheme,
+    pub show_ev: bool, // 追加曝光补偿列
+    pub show_artist: bool, // PHOTOGRAPH 下方的署名行
+    pub labels: FrameLabels, // 参数标签 + 标题文字
+    pub layout_overrides: HashMap<String, f32>,
 }
 
 impl FrameProcessor for WhiteMasterProcessorV2 {
@@ -36,33 +50,67 @@ impl FrameProcessor for WhiteMasterProcessorV2 {
         // 1. 数据清洗 (Data Cleaning)
         // 避免在绘图循环中做字符串处理
         let iso = ctx.params.iso.map(|v| v.to_string()).unwrap_or_default();
-        let aperture = ctx.params.aperture.map(|v| v.to_string()).unwrap_or_default();
-        let focal = ctx.params.focal_length.map(|v| v.to_string()).unwrap_or_default();
+        let aperture = ctx.params.format_aperture().unwrap_or_default();
+        let focal = ctx.params.format_focal_length().unwrap_or_default();
         
-        // 移除 "s" 并去除空格 (例如 "1/1000 s" -> "1/1000")
-        let shutter = ctx.params.shutter_speed
-            .replace("s", "")
-            .trim()
-            .to_string();
+        // 不带单位 (例如 "1/1000" / "2.5")，标签列已经写了 "S"
+        let shutter = ctx.params.shutter_speed.map(|s| s.format_fraction()).unwrap_or_default();
+        // 带符号 ("+0.7")，没有补偿时为空 (该列不画)
+        let ev = if self.show_ev { ctx.params.format_ev().unwrap_or_default() } else { String::new() };
+        // 已套好 "Shot by ..." 模板；没有作者信息时为 None (不画)
+        let artist = if self.show_artist {
+            ctx.artist_name.as_deref().and_then(|a| self.labels.shot_by_line(a))
+        } else {
+            None
+        };
+
+        let input = MasterInput { iso, aperture, shutter, focal, ev, artist };
+        let layout = LayoutInput {
+            theme: self.theme,
+            photo_offset: self.photo_offset,
+            labels: &self.labels,
+            ..LayoutInput::new(&self.layout_overrides)
+        };
 
         // 2. 核心处理
         let result = process_internal(
             img,
+            &input,
             &self.main_font,
             &self.script_font,
             &self.serif_font,
-            &iso, &aperture, &shutter, &focal
+            &layout,
         )?;
 
         info!("✨ [PERF] WhiteMaster V2 processed in {:.2?}", t_start.elapsed());
         Ok(result)
     }
+
+    fn background_kind(&self) -> BackgroundKind {
+        let mut cfg = MasterConfig::default();
+        cfg.apply_theme(self.theme);
+        BackgroundKind::Solid(cfg.bg_color)
+    }
+}
+
+/// Master 模式的单张图输入 (已清洗好的文字，没有数值的列为空字符串)
+struct MasterInput {
+    iso: String,
+    aperture: String,
+    shutter: String,
+    focal: String,
+    ev: String,
+    artist: Option<String>, // 署名行 (已套好 "Shot by ..." 模板)，None 不画
 }
 
 // ==========================================
 // 2. 布局配置
 // ==========================================
 
+/// 可通过 layout_overrides 覆盖的键 (与字段同名)：
+/// `border_ratio`, `bottom_ratio`, `text_scale_val`, `text_scale_lbl`,
+/// `header_script_size`, `header_small_size`, `column_gap_ratio`, `label_bottom_margin`,
+/// `header_gap_top`, `header_gap_bottom`, `artist_gap`, `separator_scale`, `value_label_gap`
 struct MasterConfig {
     border_ratio: f32,       // 四周白边比例
     bottom_ratio: f32,       // 底部留白比例
@@ -76,8 +124,10 @@ struct MasterConfig {
     // 间距比例
     column_gap_ratio: f32,   // 列间距
     label_bottom_margin: f32,// 参数标签距离底部的边距
+    value_label_gap: f32,    // 数值基线与标签大写字母顶部的间距
     header_gap_top: f32,     // 顶部标题微调
     header_gap_bottom: f32,  // 底部标题微调
+    artist_gap: f32,         // 🟢 PHOTOGRAPH 与署名行之间的间距
     
     // 分隔线
     separator_scale: f32,    // 分隔线高度相对于参数区高度的比例
@@ -104,8 +154,10 @@ impl Default for MasterConfig {
             
             column_gap_ratio: 0.18,
             label_bottom_margin: 0.18,
+            value_label_gap: 0.059,
             header_gap_top: 0.09,
             header_gap_bottom: 0.08,
+            artist_gap: 0.03,
             
             separator_scale: 0.75,
             
@@ -119,26 +171,68 @@ impl Default for MasterConfig {
     }
 }
 
+impl MasterConfig {
+    // 深色主题：数值/标签反转明暗，手写体换成提亮的蓝色保证对比度
+    fn apply_theme(&mut self, theme: FrameTheme) {
+        if theme == FrameTheme::Dark {
+            self.color_text_val = Rgba([230, 230, 230, 255]);
+            self.color_text_lbl = Rgba([130, 130, 130, 255]);
+            self.color_script = Rgba([130, 160, 225, 255]);
+            self.color_title = Rgba([160, 170, 180, 255]);
+            self.color_sep = Rgba([80, 80, 80, 255]);
+            self.bg_color = DARK_BG_COLOR;
+        }
+    }
+}
+
+impl LayoutConfig for MasterConfig {
+    const STYLE: &'static str = "WhiteMaster";
+
+    fn field_mut(&mut self, key: &str) -> Option<(&mut f32, RangeInclusive<f32>)> {
+        Some(match key {
+            "border_ratio" => (&mut self.border_ratio, BORDER),
+            "bottom_ratio" => (&mut self.bottom_ratio, RATIO),
+            "text_scale_val" => (&mut self.text_scale_val, RATIO),
+            "text_scale_lbl" => (&mut self.text_scale_lbl, RATIO),
+            "header_script_size" => (&mut self.header_script_size, RATIO),
+            "header_small_size" => (&mut self.header_small_size, RATIO),
+            "column_gap_ratio" => (&mut self.column_gap_ratio, RATIO),
+            "label_bottom_margin" => (&mut self.label_bottom_margin, RATIO),
+            "value_label_gap" => (&mut self.value_label_gap, RATIO),
+            "header_gap_top" => (&mut self.header_gap_top, NUDGE),
+            "header_gap_bottom" => (&mut self.header_gap_bottom, NUDGE),
+            "artist_gap" => (&mut self.artist_gap, NUDGE),
+            "separator_scale" => (&mut self.separator_scale, SCALE),
+            _ => return None,
+        })
+    }
+}
+
 // ==========================================
 // 3. 核心处理逻辑
 // ==========================================
 
 fn process_internal(
     img: &DynamicImage,
+    input: &MasterInput,
     main_font: &FontArc,
     script_font: &FontArc,
     serif_font: &FontArc,
-    iso: &str, aperture: &str, shutter: &str, focal: &str
+    layout: &LayoutInput,
 ) -> Result<DynamicImage, AppError> {
 
-    let cfg = MasterConfig::default();
+    let mut cfg = MasterConfig::default();
+    cfg.apply_theme(layout.theme);
+    cfg.apply_overrides(layout.overrides);
+    let labels = layout.labels;
     let (src_w, src_h) = img.dimensions();
 
     // -------------------------------------------------------------
     // A. 尺寸计算
     // -------------------------------------------------------------
     let border = (src_h as f32 * cfg.border_ratio).round() as u32;
-    let bottom = (src_h as f32 * cfg.bottom_ratio).round() as u32;
+    // 底栏按高度计算 (比 2:3 更高的竖图封顶) 并夹在合理范围内 (9:16 竖图不再巨大，全景不再过细)
+    let bottom = clamp_bar_height(bar_basis(src_w, src_h) * cfg.bottom_ratio, src_w, src_h);
     
     // Master 风格：四周有 border，底部额外增加 bottom
     // Canvas Height = src_h + border(Top) + border(Bottom) + bottom(Extra)
@@ -150,37 +244,34 @@ fn process_internal(
 
     debug!("📐 [Layout] Master: {}x{}, BottomArea={}", src_w, src_h, bottom);
 
-    // -------------------------------------------------------------
-    // B. 画布构建 (高性能 Rayon)
-    // -------------------------------------------------------------
-    let t_canvas = Instant::now();
-    let mut canvas = DynamicImage::ImageRgba8(
-        create_expanded_canvas(
-            img, top_pad, bottom_pad, left_pad, right_pad, cfg.bg_color
-        )?
-    );
-    debug!("  -> [PERF] Canvas compose: {:.2?}", t_canvas.elapsed());
-
-    let (canvas_w, canvas_h) = canvas.dimensions();
+    // 画布尺寸与照片位移无关，先算出来用于排版
+    let canvas_w = src_w + left_pad + right_pad;
+    let canvas_h = src_h + top_pad + bottom_pad;
     let center_x = (canvas_w / 2) as i32;
     let bh = bottom as f32; // 底部核心区域的高度基准
 
     // -------------------------------------------------------------
-    // C. 坐标系统计算
+    // B. 坐标系统计算
     // -------------------------------------------------------------
 
-    // C1. 参数区 (Params)
+    // B1. 参数区 (Params)
     let val_size = bh * cfg.text_scale_val;
     let lbl_size = bh * cfg.text_scale_lbl;
     let margin_bottom = bh * cfg.label_bottom_margin;
     
-    // 计算参数行的 Y 坐标
-    // Label 在底部
-    let label_y = (canvas_h as f32 - margin_bottom - lbl_size) as i32;
-    // Value 在 Label 上方 (加一点间距)
-    let value_y = label_y - (val_size as i32) - (bh * 0.02) as i32;
-
-    // C2. 标题区 (Header)
+    // 计算参数行的基线
+    // Label 在底部：行框 (ascent + descent，即字号) 的底部落在下边距处
+    let (val_scale, lbl_scale) = (PxScale::from(val_size), PxScale::from(lbl_size));
+    let lbl_ascent = font_ascent(main_font, lbl_scale);
+    let label_baseline = (canvas_h as f32 - margin_bottom - (lbl_size - lbl_ascent)).round() as i32;
+    // Value 在 Label 上方：以标签的大写字母高度为准 (与具体标签文字无关，各列共用一条基线)
+    let (_, lbl_cap_h, _) = measure_text_bounds(main_font, "H", lbl_scale);
+    let value_baseline = (label_baseline as f32 - lbl_cap_h - bh * cfg.value_label_gap).round() as i32;
+    // 两行的文字框顶部 (标题区和分隔线按它排版)
+    let label_y = label_baseline - lbl_ascent.round() as i32;
+    let value_y = value_baseline - font_ascent(main_font, val_scale).round() as i32;
+
+    // B2. 标题区 (Header)
     // 位于 content_base_y 和 value_y 之间
     let params_top_y = value_y as f32;
     let script_size = bh * cfg.header_script_size;
@@ -192,8 +283,10 @@ fn process_internal(
     let line_script_y = script_baseline_y as i32;
     let line_top_y = (script_baseline_y - (script_size * 0.8) + (bh * cfg.header_gap_top)) as i32;
     let line_bottom_y = (script_baseline_y + (script_size * 0.5) + (bh * cfg.header_gap_bottom)) as i32;
+    // 署名行在 PHOTOGRAPH 下方，与标题同字号
+    let line_artist_y = line_bottom_y + (small_size + bh * cfg.artist_gap) as i32;
 
-    // C3. 分隔线 (Separators)
+    // B3. 分隔线 (Separators)
     let sep_top = value_y as f32;
     let sep_bottom = (label_y as f32) + lbl_size;
     let sep_h = (sep_bottom - sep_top) * cfg.separator_scale;
@@ -202,130 +295,84 @@ fn process_internal(
     // 动态线宽: 基于画布宽度的 0.15%
     let sep_w = (canvas_w as f32 * 0.0015).max(2.0) as u32;
 
+    // -------------------------------------------------------------
+    // C. 画布构建 (高性能 Rayon)
+    // -------------------------------------------------------------
+    // 照片位移只改变贴图位置，向下移动不会压住 Header (MASTER SERIES)
+    let (photo_top, photo_bottom, photo_left, photo_right) = offset_padding(
+        layout.photo_offset,
+        src_h,
+        (top_pad, bottom_pad, left_pad, right_pad),
+        line_top_y,
+    );
+
+    let t_canvas = Instant::now();
+    let mut canvas = DynamicImage::ImageRgba8(
+        create_expanded_canvas(
+            img, photo_top, photo_bottom, photo_left, photo_right, cfg.bg_color
+        )?
+    );
+    debug!("  -> [PERF] Canvas compose: {:.2?}", t_canvas.elapsed());
+
     // -------------------------------------------------------------
     // D. 绘制内容
     // -------------------------------------------------------------
 
     // 1. 绘制 Header
+    let title_style = TextStyle::new(serif_font, small_size, cfg.color_title, TextAlign::Center);
     // Line 1: MASTER SERIES
-    draw_text_aligned(
-        &mut canvas, serif_font, "MASTER SERIES", 
-        center_x, line_top_y, small_size, cfg.color_title, TextAlign::Center
-    );
+    draw_text_aligned(&mut canvas, &labels.series_title, center_x, line_top_y, &title_style);
     
     // Line 2: The decisive moment (Script)
     draw_text_aligned(
-        &mut canvas, script_font, "The decisive moment", 
-        center_x, line_script_y, script_size, cfg.color_script, TextAlign::Center
+        &mut canvas, &labels.tagline, center_x, line_script_y,
+        &TextStyle::new(script_font, script_size, cfg.color_script, TextAlign::Center)
     );
     
-    // Line 3: PHOTOGRAPH (Wide Spacing)
-    // 这里调用私有辅助函数来实现宽字间距
-    draw_wide_text(
-        &mut canvas, serif_font, "PHOTOGRAPH", 
-        center_x, line_bottom_y, small_size, cfg.color_title
+    // Line 3: PHOTOGRAPH (Wide Spacing)，字间距为字号的 0.4 倍
+    draw_text_aligned(
+        &mut canvas, &labels.photograph, center_x, line_bottom_y,
+        &TextStyle::new(serif_font, small_size, cfg.color_title, TextAlign::Center).tracking(small_size * 0.4)
     );
 
-    // 2. 绘制参数列 & 分隔线
-    let gap = (canvas_w as f32 * cfg.column_gap_ratio) as i32;
-    let col_w = gap / 2; // 列宽的一半，用于定位
-
-    // Column 1: ISO
-    if !iso.is_empty() {
-        draw_param_column(
-            &mut canvas, center_x - gap - col_w, value_y, label_y, 
-            iso, "ISO", main_font, val_size, lbl_size, cfg.color_text_val, cfg.color_text_lbl
-        );
-    }
-    
-    // Column 2: Aperture
-    if !aperture.is_empty() {
-        draw_param_column(
-            &mut canvas, center_x - col_w, value_y, label_y, 
-            aperture, "F", main_font, val_size, lbl_size, cfg.color_text_val, cfg.color_text_lbl
-        );
+    // Line 4: Shot by {artist} (可选)
+    if let Some(artist) = &input.artist {
+        draw_text_aligned(&mut canvas, artist, center_x, line_artist_y, &title_style);
     }
-    
-    // Column 3: Focal Length
-    if !focal.is_empty() {
-        draw_param_column(
-            &mut canvas, center_x + col_w, value_y, label_y, 
-            focal, "mm", main_font, val_size, lbl_size, cfg.color_text_val, cfg.color_text_lbl
-        );
-    }
-    
-    // Column 4: Shutter
-    if !shutter.is_empty() {
-        draw_param_column(
-            &mut canvas, center_x + gap + col_w, value_y, label_y, 
-            shutter, "S", main_font, val_size, lbl_size, cfg.color_text_val, cfg.color_text_lbl
-        );
+
+    // 2. 绘制参数列 & 分隔线
+    // 只画有数值的列 (胶片扫描 / 转接手动镜头会缺项)，剩下的整体居中，分隔线只画在相邻两列之间
+    // 四列齐全时位置与原来的固定四列一致；加上 EV 列时收窄列距，总宽仍与四列相同
+    let columns: Vec<(&str, &str)> = [
+        (input.iso.as_str(), labels.iso.as_str()),
+        (input.aperture.as_str(), labels.aperture.as_str()),
+        (input.focal.as_str(), labels.focal.as_str()),
+        (input.shutter.as_str(), labels.shutter.as_str()),
+        (input.ev.as_str(), labels.ev.as_str()),
+    ]
+        .into_iter()
+        .filter(|(val, _)| !val.is_empty())
+        .collect();
+    let mut gap = (canvas_w as f32 * cfg.column_gap_ratio) as i32;
+    if columns.len() > 4 {
+        gap = gap * 3 / (columns.len() as i32 - 1);
     }
+    let first_x = center_x - gap * (columns.len() as i32 - 1).max(0) / 2;
 
-    // 3. 绘制分隔线 (使用圆角矩形 polyfill 提升质感)
-    // 🟢 修改后：使用 draw_filled_rect_mut (极速，稳定)
-    let sep_h_u32 = sep_h as u32;
+    // 分隔线使用 draw_filled_rect_mut (极速，稳定)
+    let sep_h_u32 = (sep_h as u32).max(1); // Rect 不接受 0 高度
     let start_y = (sep_center_y - sep_h / 2.0) as i32;
-    
-    // Line 1 (Left)
-    let rect1 = Rect::at(center_x - gap - (sep_w as i32 / 2), start_y).of_size(sep_w, sep_h_u32);
-    draw_filled_rect_mut(&mut canvas, rect1, cfg.color_sep);
-
-    // Line 2 (Center)
-    let rect2 = Rect::at(center_x - (sep_w as i32 / 2), start_y).of_size(sep_w, sep_h_u32);
-    draw_filled_rect_mut(&mut canvas, rect2, cfg.color_sep);
-
-    // Line 3 (Right)
-    let rect3 = Rect::at(center_x + gap - (sep_w as i32 / 2), start_y).of_size(sep_w, sep_h_u32);
-    draw_filled_rect_mut(&mut canvas, rect3, cfg.color_sep);
+    let val_style = TextStyle::new(main_font, val_size, cfg.color_text_val, TextAlign::Center);
+    let lbl_style = TextStyle::new(main_font, lbl_size, cfg.color_text_lbl, TextAlign::Center);
+
+    for (i, (val, lbl)) in columns.into_iter().enumerate() {
+        let x = first_x + gap * i as i32;
+        draw_param_column(&mut canvas, x, (val, value_baseline, &val_style), (lbl, label_baseline, &lbl_style));
+        if i > 0 {
+            let rect = Rect::at(x - gap / 2 - (sep_w as i32 / 2), start_y).of_size(sep_w, sep_h_u32);
+            draw_filled_rect_mut(&mut canvas, rect, cfg.color_sep);
+        }
+    }
 
     Ok(canvas)
 }
-
-// ==========================================
-// 4. 私有辅助函数
-// ==========================================
-
-/// 绘制宽字距文本 (特供 Master 风格)
-/// 逻辑：计算总宽 -> 居中起始点 -> 逐字绘制并增加间距
-fn draw_wide_text<F: Font>(
-    canvas: &mut DynamicImage, 
-    font: &F, 
-    text: &str, 
-    center_x: i32, 
-    y: i32, 
-    size: f32, 
-    color: Rgba<u8>
-) {
-    let scale = PxScale::from(size);
-    let tracking = size * 0.4; // 字间距系数
-    
-    // 1. 预计算每个字符的宽度
-    let char_widths: Vec<f32> = text.chars().map(|c| {
-        let (w, _) = text_size(scale, font, &c.to_string());
-        w as f32
-    }).collect();
-    
-    // 2. 计算总宽度 (字符宽 + 间距)
-    let total_chars_width: f32 = char_widths.iter().sum();
-    let total_spacing = if text.len() > 1 {
-        tracking * (text.len() - 1) as f32
-    } else {
-        0.0
-    };
-    let total_width = total_chars_width + total_spacing;
-
-    // 3. 计算起始 X
-    let mut current_x = center_x as f32 - (total_width / 2.0);
-
-    // 4. 逐字绘制
-    for (i, c) in text.chars().enumerate() {
-        // draw_text_aligned 这里用 Left 对齐即可，因为我们已经算好了确切的 current_x
-        draw_text_aligned(
-            canvas, font, &c.to_string(), 
-            current_x.round() as i32, y, 
-            size, color, TextAlign::Left
-        );
-        current_x += char_widths[i] + tracking;
-    }
-}
\ No newline at end of file
@@ -5,21 +5,33 @@ use imageproc::drawing::text_size;
 use imageproc::rect::Rect;
 use ab_glyph::{Font, FontArc, PxScale};
 use log::{info, debug};
+use std::ops::RangeInclusive;
 use std::time::Instant;
 use std::cmp::max;
+use std::collections::HashMap;
 
 use crate::error::AppError;
 use crate::parser::models::ParsedImageContext;
-use crate::processor::traits::FrameProcessor;
+use crate::models::{FrameLabels, FrameTheme};
+use crate::resources::Brand;
+use crate::processor::layout::clamp_bar_height;
+use crate::processor::traits::{BackgroundKind, FrameProcessor, LayoutConfig, LayoutInput, BORDER, NUDGE, RATIO, SCALE};
 // 假设阴影模块位置不变
 use crate::graphics::shadow::ShadowProfile; 
+use crate::graphics::shapes::draw_rounded_rect_polyfill;
+use crate::graphics::{ensure_contrast, font_ascent, measure_text_bounds};
 
 // 引入高性能工具箱
 use super::utils::{
     create_expanded_canvas, 
+    offset_padding, 
     draw_text_aligned, 
-    draw_rounded_rect_polyfill, 
-    TextAlign
+    draw_text_baseline,
+    apply_inverse_corner_mask,
+    overlay_rounded_photo_with_shadow,
+    TextAlign,
+    TextStyle,
+    DARK_BG_COLOR,
 };
 
 // ==========================================
@@ -27,10 +39,18 @@ use super::utils::{
 // ==========================================
 
 pub struct WhiteModernProcessorV2 {
-    pub font_bold: FontArc,    // 用于参数数值
+    pub font_semibold: FontArc, // 用于参数数值
     pub font_medium: FontArc,  // 用于机型 / 参数标签
     pub font_regular: FontArc, // 备用
     pub font_script: FontArc,  // 用于品牌 (手写体)
+    pub font_serif: FontArc,   // 用于品牌 (衬线体，手写体难以辨认的品牌)
+    pub photo_offset: Option<(f32, f32)>,
+    pub theme: FrameTheme,
+    pub shadow_intensity: Option<f32>,
+    pub show_ev: bool, // 追加曝光补偿胶囊
+    pub accent_badges: bool, // 胶囊描边 / 标签使用品牌强调色
+    pub labels: FrameLabels, // 胶囊下方的参数标签
+    pub layout_overrides: HashMap<String, f32>,
 }
 
 impl FrameProcessor for WhiteModernProcessorV2 {
@@ -38,33 +58,75 @@ impl FrameProcessor for WhiteModernProcessorV2 {
         let t_start = Instant::now();
 
         // 1. 数据准备
-        let brand = ctx.brand.to_string();
+        // 品牌标题默认用手写体；手写体难以辨认的品牌 (如 Hasselblad) 改用衬线大写
+        let brand_serif = uses_serif_title(ctx.brand);
+        let (brand, brand_font) = if brand_serif {
+            (ctx.brand_label().to_uppercase(), &self.font_serif)
+        } else {
+            (ctx.brand_label(), &self.font_script)
+        };
         let model = ctx.model_name.clone();
         
         let iso = ctx.params.iso.map(|v| v.to_string()).unwrap_or_default();
-        let aperture = ctx.params.aperture.map(|v| v.to_string()).unwrap_or_default();
-        let focal = ctx.params.focal_length.map(|v| v.to_string()).unwrap_or_default();
-        let shutter = ctx.params.shutter_speed.replace("s", "").trim().to_string();
+        let aperture = ctx.params.format_aperture().unwrap_or_default();
+        let focal = ctx.params.format_focal_length().unwrap_or_default();
+        let shutter = ctx.params.shutter_speed.map(|s| s.format_fraction()).unwrap_or_default();
+        // 带符号 ("+0.7")，没有补偿时为空 (该胶囊不画)
+        let ev = if self.show_ev { ctx.params.format_ev().unwrap_or_default() } else { String::new() };
+
+        let input = ModernInput { brand, model, brand_serif, iso, aperture, shutter, focal, ev };
+        let layout = LayoutInput {
+            theme: self.theme,
+            accent: self.accent_badges.then(|| ctx.brand.accent_color()),
+            photo_offset: self.photo_offset,
+            labels: &self.labels,
+            ..LayoutInput::new(&self.layout_overrides)
+        };
 
         // 2. 核心处理
         let result = process_internal(
             img,
-            &self.font_bold,
+            &input,
+            &self.font_semibold,
             &self.font_medium,
-            &self.font_script,
-            &brand, &model,
-            &iso, &aperture, &shutter, &focal
+            brand_font,
+            &layout,
+            ShadowProfile::for_intensity(self.shadow_intensity),
         )?;
 
         info!("✨ [PERF] WhiteModern V2 processed in {:.2?}", t_start.elapsed());
         Ok(result)
     }
+
+    fn background_kind(&self) -> BackgroundKind {
+        let mut cfg = ModernConfig::default();
+        cfg.apply_theme(self.theme);
+        BackgroundKind::Solid(cfg.bg_color)
+    }
+}
+
+/// Modern 模式的单张图输入 (已清洗好的文字，没有数值的参数为空字符串，对应的胶囊不画)
+struct ModernInput {
+    brand: String,     // 品牌标题 (衬线体时已转大写)
+    model: String,
+    brand_serif: bool, // 品牌标题用衬线体 (否则手写体)
+    iso: String,
+    aperture: String,
+    shutter: String,
+    focal: String,
+    ev: String, // 只在 show_ev 时有值
 }
 
 // ==========================================
 // 2. 布局配置
 // ==========================================
 
+/// 可通过 layout_overrides 覆盖的键 (与字段同名)：
+/// `border_ratio`, `bottom_ratio`, `model_text_scale`, `script_scale_ratio`,
+/// `gap_brand_model`, `gap_image_model`, `header_y_nudge`, `script_y_nudge`,
+/// `badge_height_ratio`, `badge_width_ratio`, `badge_gap`,
+/// `gap_model_params`, `param_val_scale`, `param_lbl_scale`,
+/// `corner_radius_ratio`, `header_max_width`
 struct ModernConfig {
     border_ratio: f32,       // 边框比例
     bottom_ratio: f32,       // 底部比例
@@ -75,8 +137,8 @@ struct ModernConfig {
     gap_brand_model: f32,    // 品牌与机型间距
     gap_image_model: f32,    // 图片与 Header 间距
     header_y_nudge: f32,     // Header 整体微调
-    script_y_nudge: f32,     // 手写体垂直微调
-    model_y_nudge: f32,      // 机型垂直微调
+    script_y_nudge: f32,     // 手写体垂直微调 (手写体字形高低差异大，仍按经验值修正)
+    header_max_width: f32,   // Header 最大宽度 (相对画布宽度)，超出时整体缩小
     
     // 胶囊 (Badge) 布局
     badge_height_ratio: f32, // 胶囊高度比例
@@ -87,7 +149,9 @@ struct ModernConfig {
     // 参数文字
     param_val_scale: f32,
     param_lbl_scale: f32,
-    val_y_nudge_ratio: f32,  // 数值垂直修正
+
+    // 照片圆角 (相对照片短边，0 = 直角)
+    corner_radius_ratio: f32,
     
     // 颜色
     color_text_black: Rgba<u8>,
@@ -109,7 +173,7 @@ impl Default for ModernConfig {
             gap_image_model: 0.18,
             header_y_nudge: 0.05,
             script_y_nudge: 0.3,
-            model_y_nudge: 0.18,
+            header_max_width: 0.9,
             
             badge_height_ratio: 0.22,
             badge_width_ratio: 1.8,
@@ -118,7 +182,8 @@ impl Default for ModernConfig {
             
             param_val_scale: 0.12,
             param_lbl_scale: 0.095,
-            val_y_nudge_ratio: 0.28,
+
+            corner_radius_ratio: 0.0,
             
             color_text_black: Rgba([20, 20, 20, 255]),
             color_text_gray: Rgba([100, 100, 100, 255]),
@@ -129,20 +194,74 @@ impl Default for ModernConfig {
     }
 }
 
+impl ModernConfig {
+    // 深色主题：胶囊描边变暗、内胆跟随背景色，数值文字反白
+    fn apply_theme(&mut self, theme: FrameTheme) {
+        if theme == FrameTheme::Dark {
+            self.color_text_black = Rgba([235, 235, 235, 255]);
+            self.color_text_gray = Rgba([150, 150, 150, 255]);
+            self.color_text_blue = Rgba([130, 160, 225, 255]);
+            self.color_border = Rgba([85, 85, 85, 255]);
+            self.bg_color = DARK_BG_COLOR;
+        }
+    }
+
+    // 🟢 品牌色胶囊：描边和标签改用强调色，按当前背景压暗 / 提亮到足够的对比度
+    // (描边按图形元素要求 3:1，标签是小字按 4.5:1)；需在 apply_theme 之后调用
+    fn apply_accent(&mut self, accent: Rgba<u8>) {
+        self.color_border = ensure_contrast(accent, self.bg_color, 3.0);
+        self.color_text_gray = ensure_contrast(accent, self.bg_color, 4.5);
+    }
+}
+
+impl LayoutConfig for ModernConfig {
+    const STYLE: &'static str = "WhiteModern";
+
+    fn field_mut(&mut self, key: &str) -> Option<(&mut f32, RangeInclusive<f32>)> {
+        Some(match key {
+            "border_ratio" => (&mut self.border_ratio, BORDER),
+            "bottom_ratio" => (&mut self.bottom_ratio, RATIO),
+            "model_text_scale" => (&mut self.model_text_scale, RATIO),
+            "script_scale_ratio" => (&mut self.script_scale_ratio, SCALE),
+            "gap_brand_model" => (&mut self.gap_brand_model, NUDGE),
+            "gap_image_model" => (&mut self.gap_image_model, NUDGE),
+            "header_y_nudge" => (&mut self.header_y_nudge, NUDGE),
+            "script_y_nudge" => (&mut self.script_y_nudge, NUDGE),
+            "header_max_width" => (&mut self.header_max_width, RATIO),
+            "badge_height_ratio" => (&mut self.badge_height_ratio, RATIO),
+            "badge_width_ratio" => (&mut self.badge_width_ratio, SCALE),
+            "badge_gap" => (&mut self.badge_gap, SCALE),
+            "gap_model_params" => (&mut self.gap_model_params, NUDGE),
+            "param_val_scale" => (&mut self.param_val_scale, RATIO),
+            "param_lbl_scale" => (&mut self.param_lbl_scale, RATIO),
+            "corner_radius_ratio" => (&mut self.corner_radius_ratio, RATIO),
+            _ => return None,
+        })
+    }
+}
+
 // ==========================================
 // 3. 核心处理逻辑
 // ==========================================
 
 fn process_internal(
     img: &DynamicImage,
-    font_bold: &FontArc,
+    input: &ModernInput,
+    font_semibold: &FontArc,
     font_medium: &FontArc,
-    font_script: &FontArc,
-    brand: &str, model: &str,
-    iso: &str, aperture: &str, shutter: &str, focal: &str
+    brand_font: &FontArc,
+    layout: &LayoutInput,
+    shadow: Option<ShadowProfile>,
 ) -> Result<DynamicImage, AppError> {
 
-    let cfg = ModernConfig::default();
+    let mut cfg = ModernConfig::default();
+    cfg.apply_theme(layout.theme);
+    if let Some(accent) = layout.accent {
+        cfg.apply_accent(accent);
+    }
+    cfg.apply_overrides(layout.overrides);
+    let (brand, model, brand_serif) = (input.brand.as_str(), input.model.as_str(), input.brand_serif);
+    let labels = layout.labels;
     let (src_w, src_h) = img.dimensions();
 
     // -------------------------------------------------------------
@@ -153,7 +272,8 @@ fn process_internal(
     let portrait_scale = if is_portrait { 0.55 } else { 1.0 };
 
     let border = (src_h as f32 * cfg.border_ratio * portrait_scale).round() as u32;
-    let bottom = (src_h as f32 * cfg.bottom_ratio * portrait_scale).round() as u32;
+    // 竖图已经由 portrait_scale 缩小，这里只做上下限钳制
+    let bottom = clamp_bar_height(src_h as f32 * cfg.bottom_ratio * portrait_scale, src_w, src_h);
 
     // Modern 布局：Top=border, Bottom=bottom+border, Left=border, Right=border
     let top_pad = border;
@@ -163,15 +283,80 @@ fn process_internal(
 
     debug!("📐 [Layout] Modern: {}x{}, Border={}, Bottom={}", src_w, src_h, border, bottom);
 
+    let canvas_w = src_w + left_pad + right_pad;
+    let center_x = (canvas_w / 2) as i32;
+    let bh = bottom as f32; // 底部核心区域高度
+
     // -------------------------------------------------------------
-    // B. 画布构建 & 阴影处理
+    // B. Header 排版 (Brand + Model)
     // -------------------------------------------------------------
+    // 文字位置按默认贴图位置计算，照片位移不影响排版
+    let content_start_y = (top_pad + src_h) as i32;
+    
+    // 字号计算 + 测量宽度
+    // 长机型名 ("Hasselblad X2D 100C") 在 4:5 竖图上会超出画布：
+    // 超过 header_max_width 时品牌、机型和间距整体等比缩小 (字宽按整数像素测量，最多修正几次)
+    let max_header_w = (canvas_w as f32 * cfg.header_max_width) as i32;
+    let mut header_scale = 1.0f32;
+    let (model_size, script_size, gap_px, (brand_w, brand_h), (model_w, model_h)) = loop {
+        let model_size = bh * cfg.model_text_scale * header_scale;
+        // 衬线标题与机型同字号，手写体按比例放大
+        let script_size = if brand_serif { model_size } else { model_size * cfg.script_scale_ratio };
+        let gap_px = (bh * cfg.gap_brand_model * header_scale) as i32;
+        let brand_dims = text_size(PxScale::from(script_size), brand_font, brand);
+        let model_dims = text_size(PxScale::from(model_size), font_medium, model);
+        let total_w = brand_dims.0 as i32 + gap_px + model_dims.0 as i32;
+        if total_w <= max_header_w || header_scale < 0.2 {
+            break (model_size, script_size, gap_px, brand_dims, model_dims);
+        }
+        header_scale *= max_header_w as f32 / total_w as f32 * 0.99;
+        debug!("  -> Header 过宽 ({}px > {}px)，缩放至 {:.2}", total_w, max_header_w, header_scale);
+    };
+
+    // 布局坐标
+    let header_total_w = brand_w as i32 + gap_px + model_w as i32;
+    let start_x = center_x - (header_total_w / 2);
+
+    let header_base_y = content_start_y + (bh * cfg.gap_image_model) as i32;
+    let header_y = header_base_y + (bh * cfg.header_y_nudge) as i32;
+    
+    // 对齐基准线 (以机型文字的垂直中心为基准)
+    let header_center_y_line = header_y + (model_h as i32 / 2);
+
+    // Model 坐标：按基线放置，机型文字的墨迹顶部正好落在 header_y
+    let model_x = start_x + brand_w as i32 + gap_px;
+    let model_scale = PxScale::from(model_size);
+    let (_, model_ink_ascent, _) = measure_text_bounds(font_medium, model, model_scale);
+    let model_baseline = header_y + model_ink_ascent.round() as i32;
+    let model_final_y = model_baseline - font_ascent(font_medium, model_scale).round() as i32;
+
+    // Brand 坐标：衬线标题与机型共用基线；手写体单独微调
+    let script_final_y = if brand_serif {
+        model_baseline - font_ascent(brand_font, PxScale::from(script_size)).round() as i32
+    } else {
+        let brand_offset_ratio = get_brand_script_offset(brand); // 品牌微调
+        let brand_offset_px = (script_size * brand_offset_ratio) as i32;
+        let script_y_start = header_center_y_line - (brand_h as i32 / 2);
+        script_y_start - (script_size * cfg.script_y_nudge) as i32 + brand_offset_px
+    };
+
+    // -------------------------------------------------------------
+    // C. 画布构建 & 阴影处理
+    // -------------------------------------------------------------
+    // 照片位移：阴影与照片使用同一组 padding，保证两者始终贴合；向下不会压住 Header
+    let (photo_top, photo_bottom, photo_left, photo_right) = offset_padding(
+        layout.photo_offset,
+        src_h,
+        (top_pad, bottom_pad, left_pad, right_pad),
+        script_final_y.min(model_final_y),
+    );
+
     let t_canvas = Instant::now();
     
     // 1. 快速创建底板 (此时原图已被贴上)
     let mut canvas = DynamicImage::ImageRgba8(
         create_expanded_canvas(
-            img, top_pad, bottom_pad, left_pad, right_pad, cfg.bg_color
+            img, photo_top, photo_bottom, photo_left, photo_right, cfg.bg_color
         )?
     );
 
@@ -181,97 +366,87 @@ fn process_internal(
     // 如果 ShadowProfile 可能会覆盖原图内容，我们需要在画完阴影后，
     // 把原图再贴一遍以确保清晰度（这比手动计算遮罩快得多）。
     
-    let img_center_x = (left_pad + src_w / 2) as i64;
-    let img_center_y = (top_pad + src_h / 2) as i64;
+    let img_center_x = (photo_left + src_w / 2) as i64;
+    let img_center_y = (photo_top + src_h / 2) as i64;
     
-    // 假设 ShadowProfile 存在并可用
-    ShadowProfile::preset_standard().draw_adaptive_shadow_on(
-        canvas.as_mut_rgba8().unwrap(),
-        (src_w, src_h),
-        (img_center_x, img_center_y)
-    );
-
-    // 3. 重绘原图 (确保原图在阴影之上，边缘清晰)
-    // 这一步开销很小 (Memcpy)，但能保证视觉正确性
-    imageops::overlay(&mut canvas, img, left_pad as i64, top_pad as i64);
+    let corner_radius = (src_w.min(src_h) as f32 * cfg.corner_radius_ratio).max(0.0).round() as u32;
+
+    // 强度为 0 时不画阴影，也就不需要重绘原图
+    if let Some(shadow) = shadow {
+        // 3. 画阴影后重绘原图 (确保原图在阴影之上，边缘清晰)
+        // 这一步开销很小 (Memcpy)，但能保证视觉正确性；圆角时阴影轮廓使用相同的半径
+        overlay_rounded_photo_with_shadow(
+            &mut canvas, img, photo_left, photo_top, corner_radius, cfg.bg_color,
+            |c| shadow.draw_adaptive_rounded_shadow_on(
+                c.as_mut_rgba8().unwrap(),
+                (src_w, src_h),
+                (img_center_x, img_center_y),
+                corner_radius,
+            ),
+        );
+    } else {
+        // 4. 照片圆角 (没有阴影，直接遮成背景色)
+        apply_inverse_corner_mask(&mut canvas, photo_left, photo_top, src_w, src_h, corner_radius, cfg.bg_color);
+    }
 
     debug!("  -> [PERF] Canvas & Shadow: {:.2?}", t_canvas.elapsed());
 
-    let (canvas_w, _canvas_h) = canvas.dimensions();
-    let center_x = (canvas_w / 2) as i32;
-    let bh = bottom as f32; // 底部核心区域高度
-
     // -------------------------------------------------------------
-    // C. 绘制 Header (Brand + Model)
+    // D. 绘制 Header
     // -------------------------------------------------------------
-    let content_start_y = (top_pad + src_h) as i32;
-    
-    // 字号计算
-    let model_size = bh * cfg.model_text_scale;
-    let script_size = model_size * cfg.script_scale_ratio;
-
-    // 测量宽度
-    let (brand_w, brand_h) = text_size(PxScale::from(script_size), font_script, brand);
-    let (model_w, model_h) = text_size(PxScale::from(model_size), font_medium, model);
-
-    // 布局坐标
-    let gap_px = (bh * cfg.gap_brand_model) as i32;
-    let header_total_w = brand_w as i32 + gap_px + model_w as i32;
-    let start_x = center_x - (header_total_w / 2);
-
-    let header_base_y = content_start_y + (bh * cfg.gap_image_model) as i32;
-    let header_y = header_base_y + (bh * cfg.header_y_nudge) as i32;
-    
-    // 对齐基准线 (以机型文字的垂直中心为基准)
-    let header_center_y_line = header_y + (model_h as i32 / 2);
-
-    // 1. 绘制 Brand (Script)
-    let brand_offset_ratio = get_brand_script_offset(brand); // 品牌微调
-    let brand_offset_px = (script_size * brand_offset_ratio) as i32;
-    
-    let script_y_start = header_center_y_line - (brand_h as i32 / 2);
-    let script_final_y = script_y_start - (script_size * cfg.script_y_nudge) as i32 + brand_offset_px;
-
-    draw_text_aligned(
-        &mut canvas, font_script, brand,
-        start_x, script_final_y,
-        script_size, cfg.color_text_blue, TextAlign::Left
-    );
+    // 1. 绘制 Brand (手写体 / 衬线体)
+    let brand_style = TextStyle::new(brand_font, script_size, cfg.color_text_blue, TextAlign::Left);
+    if brand_serif {
+        draw_text_baseline(&mut canvas, brand, start_x, model_baseline, &brand_style);
+    } else {
+        draw_text_aligned(&mut canvas, brand, start_x, script_final_y, &brand_style);
+    }
 
     // 2. 绘制 Model
-    let model_x = start_x + brand_w as i32 + gap_px;
-    let model_final_y = header_y - (model_size * cfg.model_y_nudge) as i32;
-
-    draw_text_aligned(
-        &mut canvas, font_medium, model,
-        model_x, model_final_y,
-        model_size, cfg.color_text_blue, TextAlign::Left
+    draw_text_baseline(
+        &mut canvas, model, model_x, model_baseline,
+        &TextStyle::new(font_medium, model_size, cfg.color_text_blue, TextAlign::Left)
     );
 
     // -------------------------------------------------------------
-    // D. 绘制胶囊参数 (Badges)
+    // E. 绘制胶囊参数 (Badges)
     // -------------------------------------------------------------
-    let badge_h = (bh * cfg.badge_height_ratio) as u32;
-    let badge_w = (badge_h as f32 * cfg.badge_width_ratio) as u32;
+    // Rect 不接受 0 尺寸，至少保留 1px
+    let badge_h = ((bh * cfg.badge_height_ratio) as u32).max(1);
+    let badge_w = ((badge_h as f32 * cfg.badge_width_ratio) as u32).max(1);
     let badge_gap = (badge_w as f32 * cfg.badge_gap) as i32;
     
     // 胶囊描边宽度 (基于原图宽度自适应)
     let badge_stroke = max(4, (src_w as f32 * 0.0030) as u32) as i32;
     let badge_radius = (badge_h / 3) as i32;
 
-    let params = vec![
-        (shutter, "S"),
-        (iso, "ISO"),
-        (focal, "mm"),
-        (aperture, "F"),
-    ];
-
-    let total_badges_w = (badge_w as i32 * 4) + (badge_gap * 3);
+    // 没有数值的参数不画胶囊 (例如手机没有等效焦距时，不显示空的 "mm")，剩下的整体居中
+    let params: Vec<(&str, &str)> = vec![
+        (input.shutter.as_str(), labels.shutter.as_str()),
+        (input.iso.as_str(), labels.iso.as_str()),
+        (input.focal.as_str(), labels.focal.as_str()),
+        (input.aperture.as_str(), labels.aperture.as_str()),
+        (input.ev.as_str(), labels.ev.as_str()), // 只在 show_ev 时有值
+    ]
+    .into_iter()
+    .filter(|(val, _)| !val.is_empty())
+    .collect();
+
+    let badge_count = params.len() as i32;
+    let total_badges_w = (badge_w as i32 * badge_count) + (badge_gap * (badge_count - 1).max(0));
     let mut current_badge_x = center_x - (total_badges_w / 2);
     let badges_y = header_y + model_h as i32 + (bh * cfg.gap_model_params) as i32;
 
     let val_size = bh * cfg.param_val_scale;
     let lbl_size = bh * cfg.param_lbl_scale;
+    // 数值共用一条基线：按所有数值里最高的墨迹在胶囊内垂直居中
+    // ("1/250" 的斜杠伸到基线以下，只看基线以上的部分，避免它比其他数值高出一截)
+    let val_ascent = params.iter()
+        .map(|(val, _)| measure_text_bounds(font_semibold, val, PxScale::from(val_size)).1)
+        .fold(0.0f32, f32::max);
+    let val_baseline = badges_y + (badge_h as i32 / 2) + (val_ascent / 2.0).round() as i32;
+    let val_style = TextStyle::new(font_semibold, val_size, cfg.color_text_black, TextAlign::Center);
+    let lbl_style = TextStyle::new(font_medium, lbl_size, cfg.color_text_gray, TextAlign::Center);
 
     for (val, lbl) in params {
         // 1. 绘制外框 (实心圆角矩形 - 灰色)
@@ -280,38 +455,26 @@ fn process_internal(
 
         // 2. 绘制内胆 (实心圆角矩形 - 白色) -> 形成镂空效果
         // 内胆半径稍微减小，防止边角穿帮
+        // 极小图片上描边可能比胶囊本身还粗，此时内胆尺寸为 0，直接画成实心胶囊
         let inner_radius = max(0, badge_radius - badge_stroke);
-        let rect_inner = Rect::at(
-            current_badge_x + badge_stroke, 
-            badges_y + badge_stroke
-        ).of_size(
-            badge_w - (badge_stroke as u32 * 2), 
-            badge_h - (badge_stroke as u32 * 2)
-        );
-        draw_rounded_rect_polyfill(&mut canvas, rect_inner, inner_radius, cfg.bg_color);
-
-        // 3. 绘制数值 (Bold) - 居中
-        // 计算数值垂直居中修正
-        let (_, val_h) = text_size(PxScale::from(val_size), font_bold, val);
-        let val_center_y = badges_y + (badge_h as i32 / 2);
-        // 上移一点点，让视觉更平衡
-        let val_draw_y = val_center_y - (val_h as i32 / 2) - (val_h as f32 * cfg.val_y_nudge_ratio) as i32;
-        
+        let inner_w = badge_w.saturating_sub(badge_stroke as u32 * 2);
+        let inner_h = badge_h.saturating_sub(badge_stroke as u32 * 2);
+        if inner_w > 0 && inner_h > 0 {
+            let rect_inner = Rect::at(
+                current_badge_x + badge_stroke, 
+                badges_y + badge_stroke
+            ).of_size(inner_w, inner_h);
+            draw_rounded_rect_polyfill(&mut canvas, rect_inner, inner_radius, cfg.bg_color);
+        }
+
+        // 3. 绘制数值 (Bold) - 居中，按基线放置
         let badge_center_x = current_badge_x + (badge_w as i32 / 2);
         
-        draw_text_aligned(
-            &mut canvas, font_bold, val,
-            badge_center_x, val_draw_y,
-            val_size, cfg.color_text_black, TextAlign::Center
-        );
+        draw_text_baseline(&mut canvas, val, badge_center_x, val_baseline, &val_style);
 
         // 4. 绘制标签 (Medium) - 胶囊下方
         let lbl_y = badges_y + badge_h as i32 + (bh * 0.08) as i32;
-        draw_text_aligned(
-            &mut canvas, font_medium, lbl,
-            badge_center_x, lbl_y,
-            lbl_size, cfg.color_text_gray, TextAlign::Center
-        );
+        draw_text_aligned(&mut canvas, lbl, badge_center_x, lbl_y, &lbl_style);
 
         current_badge_x += badge_w as i32 + badge_gap;
     }
@@ -319,13 +482,100 @@ fn process_internal(
     Ok(canvas)
 }
 
-// 辅助函数：品牌微调
+// 辅助函数：品牌标题是否改用衬线体 (手写体下难以辨认的品牌)
+fn uses_serif_title(brand: Brand) -> bool {
+    matches!(brand, Brand::Hasselblad)
+}
+
+// 辅助函数：品牌微调 (仅手写体)
 fn get_brand_script_offset(brand: &str) -> f32 {
     let b = brand.trim().to_lowercase();
     match b.as_str() {
         "sony" => 0.05, 
         "fujifilm" | "fuji" => 0.05,
-        "olympus" => 0.10,
+        "olympus" | "om system" => 0.10,
         _ => 0.0, 
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use image::{Rgb, RgbImage};
+    use crate::models::{FrameLabels, StyleOptions};
+    use crate::parser::{self, models::RawExifData};
+    use super::*;
+
+    const PHOTO: Rgba<u8> = Rgba([200, 30, 30, 255]);
+
+    fn render(photo_offset: Option<(f32, f32)>) -> DynamicImage {
+        let style: StyleOptions = serde_json::from_value(serde_json::json!({
+            "style": "WhiteModern", "photoOffset": photo_offset,
+        })).unwrap();
+        let ctx = parser::parse(RawExifData { make: "NIKON CORPORATION".into(), model: "NIKON Z 8".into(), ..Default::default() });
+        let img = DynamicImage::ImageRgb8(RgbImage::from_pixel(1200, 800, Rgb([PHOTO[0], PHOTO[1], PHOTO[2]])));
+        crate::processor::create_processor(&style, &FrameLabels::default()).unwrap().process(&img, &ctx).unwrap()
+    }
+
+    // 照片顶边：中线上第一个照片色像素
+    fn photo_top(canvas: &DynamicImage) -> u32 {
+        let x = canvas.width() / 2;
+        (0..canvas.height()).find(|&y| canvas.get_pixel(x, y) == PHOTO).expect("找不到照片")
+    }
+
+    #[test]
+    fn upward_offset_moves_photo_and_shadow_together() {
+        let base = render(None);
+        let moved = render(Some((0.0, 0.5)));
+        assert_eq!(base.dimensions(), moved.dimensions());
+
+        // 默认位置的顶边就是顶部留白，+0.5 向上移动一半
+        let top = photo_top(&base);
+        let moved_top = photo_top(&moved);
+        assert_eq!(top - moved_top, (top as f32 * 0.5) as u32);
+
+        // 照片连同周围的阴影整体平移：以照片顶边对齐后逐像素比较
+        // (阴影在缩小的缓冲区里模糊后放大，位置不同时采样网格不同，允许几级误差)
+        let margin = moved_top.min(40);
+        let h = 800 + 2 * margin;
+        let around = |canvas: &DynamicImage, top: u32| canvas.crop_imm(0, top - margin, canvas.width(), h).to_rgba8();
+        let max_diff = |a: &image::RgbaImage, b: &image::RgbaImage| a.pixels().zip(b.pixels())
+            .flat_map(|(p, q)| p.0.into_iter().zip(q.0).map(|(x, y)| x.abs_diff(y)))
+            .max()
+            .unwrap_or(0);
+        let a = around(&base, top);
+        // 阴影确实存在 (照片下方不是纯背景色)
+        let below = a.get_pixel(a.width() / 2, margin + 800 + margin / 2);
+        assert_ne!(*below, Rgba([255, 255, 255, 255]));
+        assert!(max_diff(&a, &around(&moved, moved_top)) <= 8, "照片与阴影的相对位置变了");
+        // 对照：不按照片对齐时差异远超容差
+        assert!(max_diff(&a, &around(&moved, top)) > 100);
+    }
+
+    #[test]
+    fn accent_colors_keep_contrast_in_both_themes() {
+        use crate::graphics::contrast_ratio;
+        let brands = [Brand::Nikon, Brand::Sony, Brand::Fujifilm, Brand::Leica, Brand::Canon, Brand::Hasselblad];
+        for brand in brands {
+            for theme in [FrameTheme::Light, FrameTheme::Dark] {
+                let mut cfg = ModernConfig::default();
+                cfg.apply_theme(theme);
+                cfg.apply_accent(brand.accent_color());
+                // 描边按图形元素 3:1，标签是小字按 4.5:1
+                let border = contrast_ratio(cfg.color_border, cfg.bg_color);
+                let label = contrast_ratio(cfg.color_text_gray, cfg.bg_color);
+                assert!(border >= 3.0, "{:?} {:?}: 描边对比度 {:.2}", brand, theme, border);
+                assert!(label >= 4.5, "{:?} {:?}: 标签对比度 {:.2}", brand, theme, label);
+            }
+        }
+
+        // 尼康黄在白底上压暗，但仍是黄色 (红绿远高于蓝)，不是直接换成黑色
+        let mut cfg = ModernConfig::default();
+        cfg.apply_accent(Brand::Nikon.accent_color());
+        let Rgba([r, g, b, _]) = cfg.color_border;
+        assert_ne!(cfg.color_border, Brand::Nikon.accent_color());
+        assert!(r > b + 60 && g > b + 60, "{:?}", cfg.color_border);
+        // 本身够深的颜色原样保留
+        let mut cfg = ModernConfig::default();
+        cfg.apply_accent(Brand::Leica.accent_color());
+        assert_eq!(cfg.color_border, Brand::Leica.accent_color());
+    }
+}
@@ -3,17 +3,21 @@
 use image::{DynamicImage, Rgba, imageops, GenericImageView};
 use ab_glyph::FontArc;
 use log::{info, debug};
+use std::ops::RangeInclusive;
 use std::time::Instant;
-use std::sync::Arc;
 use std::cmp::min;
+use std::collections::HashMap;
+use std::sync::Arc;
 
 use crate::error::AppError;
-use crate::parser::models::ParsedImageContext;
-use crate::processor::traits::{FrameProcessor};
-use crate::resources::{self, LogoType};
+use crate::graphics::text_size_with_fallback;
+use crate::parser::models::{ParamFormatOptions, ParsedImageContext, ShootingParams};
+use crate::processor::traits::{BackgroundKind, FrameProcessor, LayoutConfig, LayoutInput, BORDER, NUDGE, RATIO, SCALE};
+use crate::models::{DateFont, FrameTheme, ParamSeparator, PolaroidCaption};
+use crate::resources::{self, Brand, LogoType};
 
 // 引入我们新建的高性能工具箱
-use super::utils::{create_expanded_canvas, draw_text_aligned, TextAlign};
+use super::utils::{apply_inverse_corner_mask, create_expanded_canvas, draw_text_aligned, offset_padding, themed_logo, TextAlign, TextStyle, DARK_BG_COLOR};
 
 // ==========================================
 // 1. 结构体定义
@@ -21,39 +25,73 @@ use super::utils::{create_expanded_canvas, draw_text_aligned, TextAlign};
 
 pub struct WhitePolaroidProcessorV2 {
     pub font_data: FontArc,
+    pub caption_font: FontArc, // 🟢 "品牌 机型" 文字标题 (衬线体)
+    pub photo_offset: Option<(f32, f32)>,
+    pub theme: FrameTheme,
+    pub date_font: Option<(FontArc, DateFont)>, // 拍摄日期的字体，None 代表不画日期
+    pub caption_mode: PolaroidCaption,
+    pub params_separator: ParamSeparator,
+    pub layout_overrides: HashMap<String, f32>,
 }
 
 impl FrameProcessor for WhitePolaroidProcessorV2 {
     fn process(&self, img: &DynamicImage, ctx: &ParsedImageContext) -> Result<DynamicImage, AppError> {
         let t_start = Instant::now();
 
-        // 1. 准备资源
-        // Logo 获取可能会失败，但为了不中断流程，我们允许 Option
-        let logo_type = LogoType::Wordmark;
-        let logo_img = resources::get_logo(ctx.brand, logo_type);
-        
-        // 格式化参数字符串
-        let params_str = ctx.params.format_standard();
+        // 拍摄日期：开启选项且 EXIF 里有拍摄时间时才绘制
+        let date = self.date_font.as_ref().zip(ctx.params.capture_date())
+            .map(|((font, kind), text)| (font, *kind, text));
 
-        // 2. 执行核心逻辑
-        let result = process_internal(
-            img, 
-            &self.font_data, 
-            &ctx.brand.to_string(),
-            &ctx.model_name,
-            &params_str,
-            logo_img
-        )?;
+        let title = ctx.title();
+        let input = PolaroidInput {
+            caption_mode: self.caption_mode,
+            title: &title,
+            model: ctx.model_name.trim(),
+            params: &ctx.params,
+            params_separator: self.params_separator,
+            logo_brand: ctx.brand,
+            date,
+        };
+        let layout = LayoutInput {
+            theme: self.theme,
+            photo_offset: self.photo_offset,
+            ..LayoutInput::new(&self.layout_overrides)
+        };
+
+        // 执行核心逻辑
+        // Logo 需要等边框尺寸算出来后再按高度取缓存，参数行在布局配置确定后拼接，都在 process_internal 里完成
+        let result = process_internal(img, &self.font_data, &self.caption_font, &input, &layout)?;
 
         info!("✨ [PERF] WhitePolaroid V2 processed in {:.2?}", t_start.elapsed());
         Ok(result)
     }
+
+    fn background_kind(&self) -> BackgroundKind {
+        let mut cfg = PolaroidConfig::default();
+        cfg.apply_theme(self.theme);
+        BackgroundKind::Solid(cfg.bg_color)
+    }
+}
+
+/// Polaroid 模式的单张图输入
+struct PolaroidInput<'a> {
+    caption_mode: PolaroidCaption,
+    title: &'a str, // "品牌 机型"：没有字标素材或 TextOnly 时代替字标
+    model: &'a str, // LogoAndModel 时字标下方的机型行
+    params: &'a ShootingParams,
+    params_separator: ParamSeparator, // 用户选的分隔符，覆盖布局配置里的默认值
+    logo_brand: Brand, // 用于按高度获取缓存的 Logo
+    date: Option<(&'a FontArc, DateFont, &'a str)>, // 拍摄日期 (字体, 字体类型, 文字)，None 不画
 }
 
 // ==========================================
 // 2. 布局配置 (可单独提取到 config.rs)
 // ==========================================
 
+/// 可通过 layout_overrides 覆盖的键 (与字段同名)：
+/// `side_border_ratio`, `bottom_height_multiplier`, `font_scale`, `logo_height_ratio`,
+/// `line_gap_ratio`, `content_vertical_bias`, `corner_radius_ratio`, `date_font_scale`,
+/// `date_script_scale`, `date_margin_ratio`, `caption_font_scale`, `model_font_scale`
 struct PolaroidConfig {
     side_border_ratio: f32,       // 边框相对于短边的比例
     bottom_height_multiplier: f32,// 底部高度是边框的几倍
@@ -61,9 +99,19 @@ struct PolaroidConfig {
     logo_height_ratio: f32,      // Logo 高度比例
     line_gap_ratio: f32,         // 行间距
     content_vertical_bias: f32,  // 垂直偏移 (0.0 居中)
+    corner_radius_ratio: f32,    // 照片圆角 (相对照片短边，0 = 直角)
+    date_font_scale: f32,        // 日期字号 (相对边框，常规字体)
+    date_script_scale: f32,      // 日期字号 (相对边框，手写体字面偏小，单独设置)
+    date_margin_ratio: f32,      // 日期到右边缘 / 下边缘的距离 (相对边框)
+    caption_font_scale: f32,     // "品牌 机型" 文字标题字号 (相对边框，代替字标时用)
+    model_font_scale: f32,       // 字标下方机型行字号 (相对边框，LogoAndModel)
     
     text_color: Rgba<u8>,
+    date_color: Rgba<u8>,
     bg_color: Rgba<u8>,
+
+    // 参数行：默认与旧版本一致 (双空格)，分隔符可由样式选项 params_separator 改为 " · "
+    params_format: ParamFormatOptions<'static>,
 }
 
 impl Default for PolaroidConfig {
@@ -77,27 +125,91 @@ impl Default for PolaroidConfig {
             
             line_gap_ratio: 0.6,
             content_vertical_bias: 0.0,
+            corner_radius_ratio: 0.0,
+            date_font_scale: 0.65,
+            date_script_scale: 0.9,
+            date_margin_ratio: 1.0,
+            caption_font_scale: 1.1,
+            model_font_scale: 0.9,
             
             text_color: Rgba([20, 20, 20, 255]),
+            date_color: Rgba([90, 90, 90, 255]),
             bg_color: Rgba([255, 255, 255, 255]),
+
+            params_format: ParamFormatOptions::STANDARD,
         }
     }
 }
 
+impl PolaroidConfig {
+    // 深色主题：近黑底 + 浅色文字
+    fn apply_theme(&mut self, theme: FrameTheme) {
+        if theme == FrameTheme::Dark {
+            self.text_color = Rgba([225, 225, 225, 255]);
+            self.date_color = Rgba([160, 160, 160, 255]);
+            self.bg_color = DARK_BG_COLOR;
+        }
+    }
+}
+
+impl LayoutConfig for PolaroidConfig {
+    const STYLE: &'static str = "WhitePolaroid";
+
+    fn field_mut(&mut self, key: &str) -> Option<(&mut f32, RangeInclusive<f32>)> {
+        Some(match key {
+            "side_border_ratio" => (&mut self.side_border_ratio, BORDER),
+            "bottom_height_multiplier" => (&mut self.bottom_height_multiplier, SCALE),
+            "font_scale" => (&mut self.font_scale, SCALE),
+            "logo_height_ratio" => (&mut self.logo_height_ratio, SCALE),
+            "line_gap_ratio" => (&mut self.line_gap_ratio, SCALE),
+            "content_vertical_bias" => (&mut self.content_vertical_bias, NUDGE),
+            "corner_radius_ratio" => (&mut self.corner_radius_ratio, RATIO),
+            "date_font_scale" => (&mut self.date_font_scale, SCALE),
+            "date_script_scale" => (&mut self.date_script_scale, SCALE),
+            "date_margin_ratio" => (&mut self.date_margin_ratio, SCALE),
+            "caption_font_scale" => (&mut self.caption_font_scale, SCALE),
+            "model_font_scale" => (&mut self.model_font_scale, SCALE),
+            _ => return None,
+        })
+    }
+}
+
 // ==========================================
 // 3. 核心处理逻辑 (Internal)
 // ==========================================
 
+// 底部居中堆叠的一行内容：字标图片或一行文字
+enum CaptionLine<'a> {
+    Logo(Arc<DynamicImage>),
+    Text { text: &'a str, font: &'a FontArc, size: f32, color: Rgba<u8> },
+}
+
+impl CaptionLine<'_> {
+    fn height(&self) -> u32 {
+        match self {
+            CaptionLine::Logo(logo) => logo.height(),
+            // 与 draw_text_aligned 一致按缺字回退计算，否则带中日文的标题行高会量错
+            CaptionLine::Text { text, font, size, .. } => text_size_with_fallback(ab_glyph::PxScale::from(*size), *font, text).1,
+        }
+    }
+}
+
 fn process_internal(
     img: &DynamicImage,
     font: &FontArc,
-    _brand: &str, // Polaroid 风格通常不强制显示 Brand 文字，除非没 Logo
-    _model: &str,
-    params: &str,
-    logo_opt: Option<Arc<DynamicImage>>,
+    caption_font: &FontArc,
+    input: &PolaroidInput,
+    layout: &LayoutInput,
 ) -> Result<DynamicImage, AppError> {
     
-    let cfg = PolaroidConfig::default();
+    let mut cfg = PolaroidConfig::default();
+    cfg.apply_theme(layout.theme);
+    cfg.apply_overrides(layout.overrides);
+    let PolaroidInput { caption_mode, title, model, params, params_separator, logo_brand, .. } = *input;
+    cfg.params_format.separator = params_separator.as_str();
+    let theme = layout.theme;
+    let params_string = params.format_with(&cfg.params_format);
+    let params = params_string.as_str();
     let (src_w, src_h) = img.dimensions();
 
     // -------------------------------------------------------------
@@ -113,110 +225,147 @@ fn process_internal(
     debug!("📐 [Layout] Polaroid: border={}, bottom={}", border_size, bottom_area_h);
 
     // -------------------------------------------------------------
-    // B. 画布构建 (使用 utils 中的高性能并行算法)
+    // B. 底部内容排版 (先于画布计算：照片位移需要知道文字区的位置)
+    // -------------------------------------------------------------
+    let font_size = border_size as f32 * cfg.font_scale;
+    
+    // B1. 标题：字标 / "品牌 机型" 文字 / 字标 + 机型
+    // 字标获取失败 (没有素材的品牌) 时退回文字，避免底部只剩参数行和一大片空白
+    let logo_target_h = (border_size as f32 * cfg.logo_height_ratio) as u32;
+    let scaled_logo = match caption_mode {
+        PolaroidCaption::TextOnly => None,
+        PolaroidCaption::LogoOnly | PolaroidCaption::LogoAndModel => {
+            resources::get_logo_scaled(logo_brand, LogoType::Wordmark, logo_target_h)
+                .map(|l| themed_logo(l, theme))
+        }
+    };
+    let text_line = |text, scale: f32| CaptionLine::Text { text, font: caption_font, size: border_size as f32 * scale, color: cfg.text_color };
+
+    let mut lines: Vec<CaptionLine> = Vec::with_capacity(3);
+    match scaled_logo {
+        Some(logo) => {
+            lines.push(CaptionLine::Logo(logo));
+            if caption_mode == PolaroidCaption::LogoAndModel && !model.is_empty() {
+                lines.push(text_line(model, cfg.model_font_scale));
+            }
+        }
+        None if !title.is_empty() => lines.push(text_line(title, cfg.caption_font_scale)),
+        None => {}
+    }
+
+    // B2. 参数行
+    if !params.is_empty() {
+        lines.push(CaptionLine::Text { text: params, font, size: font_size, color: cfg.text_color });
+    }
+
+    // B3. 计算垂直堆叠的总高度 (1-3 行，行与行之间一个 gap)
+    let gap = font_size * cfg.line_gap_ratio;
+    let heights: Vec<u32> = lines.iter().map(CaptionLine::height).collect();
+    let total_content_h = heights.iter().sum::<u32>() as f32 + gap * lines.len().saturating_sub(1) as f32;
+
+    // B4. 计算绘制起始 Y 坐标
+    // 底部区域的起点 Y
+    let footer_start_y = border_size + src_h;
+    // 底部区域的中心 Y
+    let footer_center_y = footer_start_y as f32 + (bottom_area_h as f32 / 2.0);
+    
+    // 内容块的起始 Y (居中 + 偏移)
+    let start_y = footer_center_y - (total_content_h / 2.0) + (bottom_area_h as f32 * cfg.content_vertical_bias);
+    
+    let mut cursor_y = start_y as i32;
+
+    // -------------------------------------------------------------
+    // C. 画布构建 (使用 utils 中的高性能并行算法)
     // -------------------------------------------------------------
     // Polaroid 布局：上下左右都有 border，但底部额外增加 bottom_area_h
     // 即：Top=border, Bottom=bottom_area_h (实际上通常 polaroid 下方留白包含 border)
     // 这里我们按原版逻辑：canvas_h = h + border + bottom_area_h，贴图在 (border, border)
     // 这意味着 Top=border, Bottom=bottom_area_h, Left=border, Right=border
     
+    // 照片位移只改变贴图位置，底部内容仍按默认位置排版 (不能被照片压住)
+    let (pad_top, pad_bottom, pad_left, pad_right) = offset_padding(
+        layout.photo_offset,
+        src_h,
+        (border_size, bottom_area_h, border_size, border_size),
+        start_y as i32,
+    );
+
     let t_canvas = Instant::now();
     let mut canvas = DynamicImage::ImageRgba8(
         create_expanded_canvas(
             img, 
-            border_size, 
-            bottom_area_h, // 注意：这里 bottom 传的是额外的底部高度
-            border_size, 
-            border_size, 
+            pad_top, 
+            pad_bottom, // 注意：这里 bottom 传的是额外的底部高度
+            pad_left, 
+            pad_right, 
             cfg.bg_color
         )?
     );
+    // 照片圆角
+    let corner_radius = (base_size * cfg.corner_radius_ratio).max(0.0).round() as u32;
+    apply_inverse_corner_mask(&mut canvas, pad_left, pad_top, src_w, src_h, corner_radius, cfg.bg_color);
     debug!("  -> [PERF] Canvas compose: {:.2?}", t_canvas.elapsed());
 
     let (canvas_w, canvas_h) = canvas.dimensions();
 
-    // -------------------------------------------------------------
-    // C. 底部内容排版
-    // -------------------------------------------------------------
-    let font_size = border_size as f32 * cfg.font_scale;
-    
-    // C1. 准备 Logo (缩放)
-    let mut scaled_logo = None;
-    let mut logo_draw_h = 0;
-    
-    if let Some(src_logo) = logo_opt {
-        let target_h = (border_size as f32 * cfg.logo_height_ratio) as u32;
-        // 使用高性能缩放 (Triangle)
-        let resized = src_logo.resize(
-            src_logo.width(), // 宽度不限，保持比例
-            target_h, 
-            imageops::FilterType::Triangle
-        );
-        logo_draw_h = resized.height();
-        scaled_logo = Some(resized);
-    }
-
-    // C2. 准备文字尺寸
-    let has_text = !params.is_empty();
-    // 使用 utils 中的 text_size (其实是 imageproc 的，但在 utils 引入了)
-    let text_dims = if has_text {
-        imageproc::drawing::text_size(
-            ab_glyph::PxScale::from(font_size), 
-            font, 
-            params
-        )
-    } else {
-        (0, 0)
-    };
-    let text_h = text_dims.1 as u32;
-
-    // C3. 计算垂直堆叠的总高度 (Logo + Gap + Text)
-    let gap = if has_text && logo_draw_h > 0 {
-        font_size * cfg.line_gap_ratio
-    } else {
-        0.0
-    };
-
-    let total_content_h = logo_draw_h as f32 + gap + text_h as f32;
-
-    // C4. 计算绘制起始 Y 坐标
-    // 底部区域的起点 Y
-    let footer_start_y = border_size + src_h;
-    // 底部区域的中心 Y
-    let footer_center_y = footer_start_y as f32 + (bottom_area_h as f32 / 2.0);
-    
-    // 内容块的起始 Y (居中 + 偏移)
-    let start_y = footer_center_y - (total_content_h / 2.0) + (bottom_area_h as f32 * cfg.content_vertical_bias);
-    
-    let mut cursor_y = start_y as i32;
     let center_x = canvas_w as i32 / 2;
 
     // -------------------------------------------------------------
     // D. 绘制 (Drawing)
     // -------------------------------------------------------------
 
-    // 1. 绘制 Logo
-    if let Some(logo) = scaled_logo {
-        let logo_x = center_x - (logo.width() as i32 / 2);
-        // imageops::overlay 不需要 Result
-        imageops::overlay(&mut canvas, &logo, logo_x as i64, cursor_y as i64);
-        
-        cursor_y += logo_draw_h as i32 + gap as i32;
+    // 1. 自上而下绘制标题和参数行，全部水平居中
+    for (line, h) in lines.iter().zip(&heights) {
+        match line {
+            CaptionLine::Logo(logo) => {
+                let logo_x = center_x - (logo.width() as i32 / 2);
+                imageops::overlay(&mut canvas, logo.as_ref(), logo_x as i64, cursor_y as i64);
+            }
+            CaptionLine::Text { text, font, size, color } => {
+                draw_text_aligned(&mut canvas, text, center_x, cursor_y, &TextStyle::new(*font, *size, *color, TextAlign::Center));
+            }
+        }
+        cursor_y += *h as i32 + gap as i32;
     }
 
-    // 2. 绘制文字 (使用 utils::draw_text_aligned)
-    if has_text {
+    // 2. 拍摄日期 (右下角，像手写在相纸上)
+    // 不参与上面的居中排版，有没有日期 Logo / 参数的位置都不变
+    if let Some((date_font, kind, date_text)) = &input.date {
+        let scale = match *kind {
+            DateFont::Script => cfg.date_script_scale,
+            DateFont::Regular => cfg.date_font_scale,
+        };
+        let date_size = border_size as f32 * scale;
+        let margin = (border_size as f32 * cfg.date_margin_ratio) as i32;
+        let (_, date_h) = imageproc::drawing::text_size(ab_glyph::PxScale::from(date_size), date_font, date_text);
         draw_text_aligned(
             &mut canvas,
-            font,
-            params,
-            center_x,
-            cursor_y, // 这里 cursor_y 是文字顶部
-            font_size,
-            cfg.text_color,
-            TextAlign::Center // 🟢 极简：直接调用居中绘制
+            date_text,
+            canvas_w as i32 - margin,
+            canvas_h as i32 - margin - date_h as i32,
+            &TextStyle::new(*date_font, date_size, cfg.date_color, TextAlign::Right)
         );
     }
 
     Ok(canvas)
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resources::{FontFamily, FontWeight};
+
+    // 标题字体 (衬线体) 没有汉字，行高要按回退字体量，和实际绘制一致
+    #[test]
+    fn caption_height_measures_fallback_glyphs() {
+        let font = resources::get_font(FontFamily::AbhayaLibre, FontWeight::Medium).unwrap();
+        let size = 64.0;
+        let text = "尼康 Z 8";
+        let line = CaptionLine::Text { text, font: &font, size, color: Rgba([0, 0, 0, 255]) };
+        let (_, want) = text_size_with_fallback(ab_glyph::PxScale::from(size), &font, text);
+        assert_eq!(line.height(), want);
+        // 只按标题字体量会得到 notdef 方块的高度
+        let (_, plain) = imageproc::drawing::text_size(ab_glyph::PxScale::from(size), &font, text);
+        assert_ne!(want, plain);
+    }
+}
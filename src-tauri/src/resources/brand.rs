@@ -1,8 +1,12 @@
+use std::borrow::Cow;
 use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
-use log::{error, info};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use log::{error, info, warn};
 use once_cell::sync::Lazy;
-use image::{DynamicImage};
+use image::{DynamicImage, Rgba, imageops};
 use std::fmt; // 引入格式化库
 
 // =========================================================
@@ -19,6 +23,16 @@ pub enum Brand {
     Fujifilm,
     Leica,
     Hasselblad,
+    Olympus,
+    OmSystem, // OM Digital Solutions (2021 年起接手奥林巴斯相机业务)
+    Panasonic,
+    Ricoh,
+    Pentax,   // 理光旗下，新机身的 Make 也是 "RICOH IMAGING COMPANY, LTD."
+    Dji,      // 无人机
+    // --- 手机 ---
+    Apple,
+    Google,
+    Samsung,
     Other
     // ...
 }
@@ -35,6 +49,15 @@ impl fmt::Display for Brand {
             Brand::Fujifilm => "Fujifilm",
             Brand::Leica => "Leica",
             Brand::Hasselblad => "Hasselblad",
+            Brand::Olympus => "Olympus",
+            Brand::OmSystem => "OM System",
+            Brand::Panasonic => "Panasonic",
+            Brand::Ricoh => "Ricoh",
+            Brand::Pentax => "Pentax",
+            Brand::Dji => "DJI",
+            Brand::Apple => "Apple",
+            Brand::Google => "Google",
+            Brand::Samsung => "Samsung",
             Brand::Other => "Unkonwn", // 或者是 "Unknown"
         };
         write!(f, "{}", s)
@@ -73,7 +96,79 @@ struct LogoKey {
     l_type: LogoType,
 }
 
+// 🟢 用户 Logo 文件名用的短名 (与 assets/logos 的命名方式一致)
+impl Brand {
+    /// 🟢 品牌强调色 (WhiteModern 胶囊描边用)：原始品牌色，不考虑背景对比度
+    /// 没有代表色的品牌返回中性灰 (与浅色主题的默认胶囊边框一致)
+    pub fn accent_color(&self) -> Rgba<u8> {
+        match self {
+            Brand::Nikon => Rgba([255, 225, 0, 255]),   // 尼康黄
+            Brand::Sony => Rgba([243, 112, 33, 255]),   // 索尼橙
+            Brand::Fujifilm => Rgba([0, 140, 75, 255]), // 富士绿
+            Brand::Leica => Rgba([226, 0, 26, 255]),    // 徕卡红
+            Brand::Canon => Rgba([204, 0, 0, 255]),     // 佳能红
+            _ => Rgba([180, 180, 180, 255]),
+        }
+    }
+
+    fn slug(&self) -> &'static str {
+        match self {
+            Brand::Nikon => "nikon",
+            Brand::Sony => "sony",
+            Brand::Canon => "canon",
+            Brand::Fujifilm => "fujifilm",
+            Brand::Leica => "leica",
+            Brand::Hasselblad => "hasselblad",
+            Brand::Olympus => "olympus",
+            Brand::OmSystem => "om-system",
+            Brand::Panasonic => "panasonic",
+            Brand::Ricoh => "ricoh",
+            Brand::Pentax => "pentax",
+            Brand::Dji => "dji",
+            Brand::Apple => "apple",
+            Brand::Google => "google",
+            Brand::Samsung => "samsung",
+            Brand::Other => "other",
+        }
+    }
+}
+
+impl LogoType {
+    fn slug(&self) -> &'static str {
+        match self {
+            LogoType::Wordmark => "wordmark",
+            LogoType::WordmarkVertical => "wordmark-vertical",
+            LogoType::IconYellowBox => "icon-yellow-box",
+            LogoType::SymbolZ => "symbol-z",
+            LogoType::SymbolAlpha => "symbol-alpha",
+            LogoType::SymbolGMaster => "symbol-g-master",
+            LogoType::IconRedDot => "icon-red-dot",
+            LogoType::IconBlackDot => "icon-black-dot",
+            LogoType::SymbolGFX => "symbol-gfx",
+            LogoType::SymbolX => "symbol-x",
+        }
+    }
+}
+
 impl LogoKey {
+    /// 用户 Logo 文件路径：{用户目录}/{brand}-{type}.png，如 nikon-wordmark.png
+    fn user_path(&self) -> Option<PathBuf> {
+        let dir = USER_LOGO_DIR.lock().unwrap();
+        dir.as_ref().map(|d| d.join(format!("{}-{}.png", self.brand.slug(), self.l_type.slug())))
+    }
+
+    /// 读取用户提供的 Logo (没有初始化目录或文件不存在时返回 None)
+    fn load_user(&self) -> Option<Vec<u8>> {
+        let path = self.user_path().filter(|p| p.is_file())?;
+        match fs::read(&path) {
+            Ok(data) => Some(data),
+            Err(e) => {
+                warn!("⚠️ [Resources] 用户 Logo 读取失败，改用内置素材: {:?} ({})", path, e);
+                None
+            }
+        }
+    }
+
     // 🟢 加载逻辑：精确匹配 品牌 + 类型
     // 注意：目前仅开启 Nikon，其他品牌注释掉以防编译时找不到文件报错
     fn load_data(&self) -> Option<&'static [u8]> {
@@ -87,9 +182,16 @@ impl LogoKey {
             (Brand::Sony, LogoType::Wordmark)    => Some(include_bytes!("../../assets/logos/sony-wordmark.png")),
             // (Brand::Sony, LogoType::SymbolAlpha) => Some(include_bytes!("../assets/logos/Alpha.png")),
 
-            // === Leica (暂未添加文件) ===
-            // (Brand::Leica, LogoType::Wordmark)   => Some(include_bytes!("../assets/logos/Leica-Word.png")),
-            // (Brand::Leica, LogoType::IconRedDot) => Some(include_bytes!("../assets/logos/Leica-Red.png")),
+            // === Leica (暂未添加文件；放入 assets/logos 后取消注释，并在 badge_logo_type 里加上 Leica 的红标) ===
+            // (Brand::Leica, LogoType::Wordmark)   => Some(include_bytes!("../../assets/logos/leica-wordmark.png")),
+            // (Brand::Leica, LogoType::IconRedDot) => Some(include_bytes!("../../assets/logos/leica-icon-red-dot.png")),
+
+            // === Hasselblad (暂未添加文件；放入 assets/logos 后取消注释即可，也可以放到用户 Logo 目录) ===
+            // (Brand::Hasselblad, LogoType::Wordmark) => Some(include_bytes!("../../assets/logos/hasselblad-wordmark.png")),
+
+            // === Olympus / OM System (Inter Display Bold 排的文字字标；官方字标可放到用户 Logo 目录覆盖) ===
+            (Brand::Olympus, LogoType::Wordmark)  => Some(include_bytes!("../../assets/logos/olympus-wordmark.png")),
+            (Brand::OmSystem, LogoType::Wordmark) => Some(include_bytes!("../../assets/logos/om-system-wordmark.png")),
 
             // === Canon (暂未添加文件) ===
             (Brand::Canon, LogoType::Wordmark)   => Some(include_bytes!("../../assets/logos/canon-wordmark.png")),
@@ -100,6 +202,60 @@ impl LogoKey {
     }
 }
 
+/// 品牌的徽标类图标 (用于底栏左侧的 badge)，没有徽标概念的品牌返回 None
+pub fn badge_logo_type(brand: Brand) -> Option<LogoType> {
+    match brand {
+        Brand::Nikon => Some(LogoType::IconYellowBox),
+        // 徕卡红标素材还没有打包，加入后再返回 Some(LogoType::IconRedDot)
+        _ => None,
+    }
+}
+
+/// 检查某个 Logo 资源是否可用 (用户目录或已打包；不解码、不写缓存)
+pub fn has_logo(brand: Brand, l_type: LogoType) -> bool {
+    let key = LogoKey { brand, l_type };
+    key.user_path().is_some_and(|p| p.is_file()) || key.load_data().is_some()
+}
+
+// =========================================================
+// 🟢 用户自定义 Logo 目录 ({app_data}/logos，由 setup.rs 初始化)
+// =========================================================
+// 放入 {brand}-{type}.png 即可覆盖内置素材，或者给没有素材的品牌补上 Logo。
+// 修改后调用 reload_logos 命令清空缓存，无需重启。
+
+static USER_LOGO_DIR: Lazy<Mutex<Option<PathBuf>>> = Lazy::new(|| {
+    Mutex::new(None)
+});
+
+/// 设置用户 Logo 目录并扫描一次，返回找到的 PNG 数量
+pub fn init_user_logo_dir(path: PathBuf) -> usize {
+    *USER_LOGO_DIR.lock().unwrap() = Some(path);
+    scan_user_logos()
+}
+
+/// 列出用户目录里的 Logo 文件 (只做日志，实际加载仍按需进行)
+fn scan_user_logos() -> usize {
+    let Some(dir) = USER_LOGO_DIR.lock().unwrap().clone() else { return 0 };
+    let Ok(entries) = fs::read_dir(&dir) else {
+        info!("📂 [Resources] 用户 Logo 目录不存在或不可读: {:?}", dir);
+        return 0;
+    };
+    let names: Vec<String> = entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.file_name().to_string_lossy().into_owned())
+        .filter(|name| name.to_ascii_lowercase().ends_with(".png"))
+        .collect();
+    info!("✅ [Resources] 用户 Logo 目录 {:?}: {} 个文件 {:?}", dir, names.len(), names);
+    names.len()
+}
+
+/// 清空 Logo 缓存 (原图 + 缩放) 并重新扫描用户目录，返回找到的 PNG 数量
+pub fn reload_logos() -> usize {
+    LOGO_CACHE.lock().unwrap().clear();
+    reset_scaled_logo_cache();
+    scan_user_logos()
+}
+
 // 4. Logo 缓存池定义
 // Key: 品牌+类型, Value: 线程安全的图片引用
 type LogoCache = HashMap<LogoKey, Arc<DynamicImage>>;
@@ -124,12 +280,15 @@ pub fn get_logo(brand: Brand, l_type: LogoType) -> Option<Arc<DynamicImage>> {
     }
 
     // B. 第二步：缓存未命中，执行加载
-    // 这一步涉及文件解码，相对耗时
-    if let Some(data) = key.load_data() {
-        info!("📦 [Resources] 首次加载 Logo: {:?} - {:?}", brand, l_type);
+    // 这一步涉及文件解码，相对耗时；用户目录里的文件优先于内置素材
+    let data: Option<Cow<'static, [u8]>> = key.load_user()
+        .map(Cow::Owned)
+        .or_else(|| key.load_data().map(Cow::Borrowed));
+    if let Some(data) = data {
+        info!("📦 [Resources] 首次加载 Logo: {:?} - {:?}{}", brand, l_type, if matches!(data, Cow::Owned(_)) { " (用户目录)" } else { "" });
         
         // 解码图片 (支持 png, jpg 等格式)
-        if let Ok(img) = image::load_from_memory(data) {
+        if let Ok(img) = image::load_from_memory(&data) {
             let arc_img = Arc::new(img);
             
             // C. 第三步：写入缓存 (写锁)
@@ -147,4 +306,88 @@ pub fn get_logo(brand: Brand, l_type: LogoType) -> Option<Arc<DynamicImage>> {
     }
 
     None
-}
\ No newline at end of file
+}
+
+// 5. 缩放后 Logo 缓存 (批次级)
+// 同一批次的照片通常来自同一台相机、尺寸一致，Logo 会被反复缩放到完全相同的高度。
+// Key: 品牌+类型+目标高度, Value: 已缩放好的图片
+type ScaledLogoCache = HashMap<(LogoKey, u32), Arc<DynamicImage>>;
+
+static SCALED_LOGO_CACHE: Lazy<Mutex<ScaledLogoCache>> = Lazy::new(|| {
+    Mutex::new(HashMap::new())
+});
+
+// 命中计数 (用于批次结束时的统计日志)
+static SCALED_LOGO_HITS: AtomicUsize = AtomicUsize::new(0);
+
+/// **获取缩放到指定高度的 Logo (批次级缓存)**
+///
+/// 宽度按比例自动计算。同一 (品牌, 类型, 高度) 只会缩放一次，之后直接返回同一个 Arc。
+/// 用法: resources::get_logo_scaled(Brand::Nikon, LogoType::Wordmark, 120)
+pub fn get_logo_scaled(brand: Brand, l_type: LogoType, target_h: u32) -> Option<Arc<DynamicImage>> {
+    let key = (LogoKey { brand, l_type }, target_h);
+
+    // A. 查缓存
+    {
+        let cache = SCALED_LOGO_CACHE.lock().unwrap();
+        if let Some(img) = cache.get(&key) {
+            SCALED_LOGO_HITS.fetch_add(1, Ordering::Relaxed);
+            return Some(img.clone());
+        }
+    }
+
+    // B. 未命中：取原图并缩放 (Lanczos3 质量最好，反正只做一次)
+    let logo = get_logo(brand, l_type)?;
+    let scaled = logo.resize(u32::MAX, target_h.max(1), imageops::FilterType::Lanczos3);
+
+    // C. 写入缓存
+    // 并发时可能有两个线程同时缩放，以先写入的为准，保证之后拿到的都是同一个 Arc
+    let mut cache = SCALED_LOGO_CACHE.lock().unwrap();
+    let entry = cache.entry(key).or_insert_with(|| Arc::new(scaled));
+    Some(entry.clone())
+}
+
+/// 清空缩放缓存 (每个批次开始时调用，防止跨批次无限增长)
+pub fn reset_scaled_logo_cache() {
+    SCALED_LOGO_CACHE.lock().unwrap().clear();
+    SCALED_LOGO_HITS.store(0, Ordering::Relaxed);
+}
+
+/// 当前批次的缓存命中次数
+pub fn scaled_logo_cache_hits() -> usize {
+    SCALED_LOGO_HITS.load(Ordering::Relaxed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn olympus_and_om_system_wordmarks_are_bundled() {
+        for brand in [Brand::Olympus, Brand::OmSystem] {
+            let key = LogoKey { brand, l_type: LogoType::Wordmark };
+            let data = key.load_data().unwrap_or_else(|| panic!("{} 没有内置字标", brand));
+            let logo = image::load_from_memory(data).unwrap();
+            // 横排字标，透明底
+            assert!(logo.width() > logo.height() * 4, "{}", brand);
+            assert!(logo.color().has_alpha(), "{}", brand);
+        }
+    }
+
+    #[test]
+    fn same_height_returns_the_cached_arc() {
+        // 用其它测试不会用到的高度，避免共享的全局缓存互相干扰
+        let first = get_logo_scaled(Brand::Nikon, LogoType::Wordmark, 37).unwrap();
+        assert_eq!(first.height(), 37);
+        let hits = scaled_logo_cache_hits();
+
+        let second = get_logo_scaled(Brand::Nikon, LogoType::Wordmark, 37).unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+        assert!(scaled_logo_cache_hits() > hits);
+
+        // 不同高度是另一条缓存
+        let other = get_logo_scaled(Brand::Nikon, LogoType::Wordmark, 38).unwrap();
+        assert!(!Arc::ptr_eq(&first, &other));
+        assert_eq!(other.height(), 38);
+    }
+}
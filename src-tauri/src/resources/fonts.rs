@@ -2,8 +2,11 @@ use std::collections::HashMap;
 use std::sync::{Mutex};
 use std::path::{Path, PathBuf};
 use std::fs;
-use ab_glyph::FontArc;
-use log::{error, info};
+use std::time::Instant;
+use ab_glyph::{FontArc, FontRef, FontVec, VariableFont};
+use log::{error, info, warn};
+
+use crate::error::AppError;
 use once_cell::sync::Lazy;
 
 
@@ -29,15 +32,65 @@ pub enum FontFamily {
     MrDafoe,       // 手写体
     AbhayaLibre,   // 衬线体
     Birthstone,
+    CjkFallback,   // 🟢 中日文回退字体 (Droid Sans Fallback 的 GB2312 + JIS X 0208 子集，Apache 2.0)，只在主字体缺字时加载
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum FontWeight {
     Regular,
     Medium,
+    SemiBold,
     Bold,
 }
 
+impl FontWeight {
+    // CSS 字重数值，用于找最接近的可用字重
+    fn value(self) -> u16 {
+        match self {
+            FontWeight::Regular => 400,
+            FontWeight::Medium => 500,
+            FontWeight::SemiBold => 600,
+            FontWeight::Bold => 700,
+        }
+    }
+}
+
+impl FontFamily {
+    /// 🟢 每个字族在资源目录里实际提供的字重 (新增字体文件时在这里登记)
+    fn faces(self) -> &'static [(FontWeight, &'static str)] {
+        match self {
+            FontFamily::InterDisplay => &[
+                (FontWeight::Regular, "InterDisplay-Regular.otf"),
+                (FontWeight::Medium, "InterDisplay-Medium.otf"),
+                (FontWeight::SemiBold, "InterVariable.ttf"),
+                (FontWeight::Bold, "InterDisplay-Bold.otf"),
+            ],
+            FontFamily::MrDafoe => &[(FontWeight::Regular, "MrDafoe-Regular.ttf")],
+            FontFamily::AbhayaLibre => &[(FontWeight::Medium, "AbhayaLibre-Medium.ttf")],
+            FontFamily::Birthstone => &[(FontWeight::Regular, "Birthstone-Regular.ttf")],
+            FontFamily::CjkFallback => &[(FontWeight::Regular, "DroidSansFallback-CJK.ttf")],
+        }
+    }
+
+    /// 🟢 可变字体文件要设置的轴坐标 (静态字体为空)
+    /// Inter 4.0 的可变字体与 InterDisplay-*.otf 同一版本，opsz 32 即 Display 光学尺寸
+    fn variations(self, weight: FontWeight) -> &'static [(&'static [u8; 4], f32)] {
+        match (self, weight) {
+            (FontFamily::InterDisplay, FontWeight::SemiBold) => &[(b"opsz", 32.0), (b"wght", 600.0)],
+            _ => &[],
+        }
+    }
+
+    /// 请求的字重没有对应文件时，取数值最接近的一档 (距离相同取较细的，宁可偏细也不要比设计更粗)
+    fn resolve(self, weight: FontWeight) -> (FontWeight, &'static str) {
+        *self
+            .faces()
+            .iter()
+            .min_by_key(|(w, _)| (w.value().abs_diff(weight.value()), w.value()))
+            .expect("每个字族至少登记一个字重")
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 struct FontKey {
     family: FontFamily,
@@ -45,14 +98,25 @@ struct FontKey {
 }
 
 impl FontKey {
-    fn filename(&self) -> &'static str {
-        match (self.family, self.weight) {
-            (FontFamily::InterDisplay, FontWeight::Bold)   => "InterDisplay-Bold.otf",
-            (FontFamily::InterDisplay, FontWeight::Medium) => "InterDisplay-Medium.otf",
-            (FontFamily::InterDisplay, _)                  => "InterDisplay-Regular.otf",
-            (FontFamily::MrDafoe, _)                       => "MrDafoe-Regular.ttf",
-            (FontFamily::AbhayaLibre, _)                   => "AbhayaLibre-Medium.ttf",
-            (FontFamily::Birthstone, _)                    => "Birthstone-Regular.ttf"
+    /// 实际加载的字重和文件名；和请求不一致时打印警告 (只在首次加载时出现，之后走缓存)
+    fn resolve(&self) -> (FontWeight, &'static str) {
+        let (weight, filename) = self.family.resolve(self.weight);
+        if weight != self.weight {
+            warn!("⚠️ [Resources] {:?} 没有 {:?} 字重，使用最接近的 {:?} ({})", self.family, self.weight, weight, filename);
+        }
+        (weight, filename)
+    }
+
+    /// 🟢 编译期内嵌的关键字体：资源目录未初始化 (CLI / 测试) 或文件缺失时兜底
+    /// 只内嵌各样式都离不开的 InterDisplay，其余字体体积大且只有个别样式使用
+    /// `weight` 是 resolve 之后实际加载的字重
+    fn embedded(family: FontFamily, weight: FontWeight) -> Option<&'static [u8]> {
+        match (family, weight) {
+            (FontFamily::InterDisplay, FontWeight::Bold)    => Some(include_bytes!("../../assets/fonts/InterDisplay-Bold.otf")),
+            (FontFamily::InterDisplay, FontWeight::Medium)  => Some(include_bytes!("../../assets/fonts/InterDisplay-Medium.otf")),
+            (FontFamily::InterDisplay, FontWeight::SemiBold) => Some(include_bytes!("../../assets/fonts/InterVariable.ttf")),
+            (FontFamily::InterDisplay, FontWeight::Regular) => Some(include_bytes!("../../assets/fonts/InterDisplay-Regular.otf")),
+            _ => None,
         }
     }
 }
@@ -68,18 +132,20 @@ static FONT_CACHE: Lazy<Mutex<FontCache>> = Lazy::new(|| {
 /// 优势：
 /// 1. 缓存的是解析后的字体对象，避免重复 parse。
 /// 2. 调用者拿来即用，无需再次 try_from_slice。
-pub fn get_font(family: FontFamily, weight: FontWeight) -> FontArc {
+/// 3. 文件缺失或损坏时返回 AppError::Font，由批次/预览统一上报，不再 panic。
+/// 4. InterDisplay 读不到文件时使用内嵌副本，未初始化资源路径的 CLI / 测试环境也能直接出图。
+pub fn get_font(family: FontFamily, weight: FontWeight) -> Result<FontArc, AppError> {
     let key = FontKey { family, weight };
 
     // 1. 查缓存
     // 🟢 [修改点] 这里的 cache 已经是 HashMap<FontKey, FontArc>
     let mut cache = FONT_CACHE.lock().unwrap();
     if let Some(font) = cache.get(&key) {
-        return font.clone(); // FontArc 克隆开销很小 (类似 Arc::clone)
+        return Ok(font.clone()); // FontArc 克隆开销很小 (类似 Arc::clone)
     }
 
-    // 2. 确定文件名
-    let filename = key.filename();
+    // 2. 确定文件名 (没有该字重时取最接近的一档并警告，不再静默替换)
+    let (weight, filename) = key.resolve();
     
     // 3. 智能路径查找策略 (保持原逻辑不变)
     let base_dir_guard = FONT_BASE_DIR.lock().unwrap();
@@ -107,27 +173,107 @@ pub fn get_font(family: FontFamily, weight: FontWeight) -> FontArc {
     };
 
     info!("📦 [LazyLoad] Font: {:?} -> {:?}", key, final_path);
+    let t_load = Instant::now();
 
-    // 5. 读取文件字节
-    let data = fs::read(&final_path).unwrap_or_else(|e| {
-        error!("❌ 严重错误: 无法读取字体文件!");
-        error!("   - 尝试路径: {:?}", final_path);
-        error!("   - 系统错误: {}", e);
-        // 如果读不到文件，这里可以 Panic，或者返回一个内嵌的 Fallback 字体
-        // 这里暂时 panic，因为没有字体后续无法工作
-        panic!("无法加载核心字体资源: {:?}", final_path);
-    });
+    // 5. 读取文件字节；读不到时关键字体回退到内嵌副本
+    let data = match fs::read(&final_path) {
+        Ok(data) => data,
+        Err(e) => match FontKey::embedded(family, weight) {
+            Some(bytes) => {
+                warn!("⚠️ [Resources] 无法读取 {:?} ({})，使用内嵌字体", final_path, e);
+                let mut font = FontRef::try_from_slice(bytes)
+                    .map_err(|e| AppError::Font(format!("内嵌字体 {} 解析失败: {}", filename, e)))?;
+                set_variations(&mut font, family, weight)?;
+                let font = FontArc::new(font);
+                cache.insert(key, font.clone());
+                return Ok(font);
+            },
+            None => {
+                error!("❌ 严重错误: 无法读取字体文件!");
+                error!("   - 尝试路径: {:?}", final_path);
+                error!("   - 系统错误: {}", e);
+                return Err(AppError::Font(format!("无法读取 {:?}: {}", final_path, e)));
+            },
+        },
+    };
 
     // 6. 🟢 [核心修改] 将字节解析为 FontArc
     // FontArc::try_from_vec 会接管 data 的所有权，不会发生拷贝
-    let font = FontArc::try_from_vec(data).unwrap_or_else(|_| {
+    let mut font = FontVec::try_from_vec(data).map_err(|e| {
         error!("❌ 严重错误: 字体文件格式损坏!");
         error!("   - 路径: {:?}", final_path);
-        panic!("无法解析字体文件");
-    });
+        AppError::Font(format!("无法解析 {:?}: {}", final_path, e))
+    })?;
+    set_variations(&mut font, family, weight)?;
+    let font = FontArc::new(font);
+    // 只在首次加载时解析一次，之后每张图都直接复用缓存里的 FontArc
+    info!("  -> [PERF] Font {:?} 读取 + 解析: {:.2?} (仅首次)", key, t_load.elapsed());
 
     // 7. 存入缓存并返回
     cache.insert(key, font.clone());
     
-    font
-}
\ No newline at end of file
+    Ok(font)
+}
+
+// 可变字体按字重设置轴坐标；文件里没有对应的轴说明登记错了文件
+fn set_variations(font: &mut impl VariableFont, family: FontFamily, weight: FontWeight) -> Result<(), AppError> {
+    for (axis, value) in family.variations(weight) {
+        if !font.set_variation(axis, *value) {
+            return Err(AppError::Font(format!("{:?} {:?} 的字体文件没有 {} 轴", family, weight, String::from_utf8_lossy(*axis))));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL_FAMILIES: [FontFamily; 5] = [
+        FontFamily::InterDisplay,
+        FontFamily::MrDafoe,
+        FontFamily::AbhayaLibre,
+        FontFamily::Birthstone,
+        FontFamily::CjkFallback,
+    ];
+
+    // 登记的每个字重都必须有对应的资源文件，否则打包后只能靠回退
+    #[test]
+    fn registered_faces_exist_in_assets() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/fonts");
+        for family in ALL_FAMILIES {
+            for (weight, filename) in family.faces() {
+                assert!(dir.join(filename).exists(), "{:?} {:?} 缺少 {}", family, weight, filename);
+            }
+        }
+    }
+
+    // 各样式实际请求的字重都有原样的文件，不经过最接近字重的替换
+    #[test]
+    fn requested_weights_resolve_exactly() {
+        for (family, weight) in [
+            (FontFamily::InterDisplay, FontWeight::Bold),
+            (FontFamily::InterDisplay, FontWeight::SemiBold),
+            (FontFamily::InterDisplay, FontWeight::Medium),
+            (FontFamily::InterDisplay, FontWeight::Regular),
+            (FontFamily::AbhayaLibre, FontWeight::Medium),
+            (FontFamily::MrDafoe, FontWeight::Regular),
+            (FontFamily::Birthstone, FontWeight::Regular),
+        ] {
+            assert_eq!(family.resolve(weight).0, weight, "{:?}", family);
+        }
+    }
+
+    // SemiBold 取自可变字体：字宽介于 Medium 和 Bold 之间，不是落到了某个静态字重
+    #[test]
+    fn semibold_is_a_real_intermediate_weight() {
+        use ab_glyph::{Font, ScaleFont};
+        let width = |weight| {
+            let font = get_font(FontFamily::InterDisplay, weight).unwrap();
+            let scaled = font.as_scaled(100.0);
+            "ISO 100 1/250".chars().map(|c| scaled.h_advance(font.glyph_id(c))).sum::<f32>()
+        };
+        let (medium, semibold, bold) = (width(FontWeight::Medium), width(FontWeight::SemiBold), width(FontWeight::Bold));
+        assert!(medium < semibold && semibold < bold, "{} {} {}", medium, semibold, bold);
+    }
+}
@@ -1,20 +1,203 @@
-use log::info;
-use tauri::{App, Manager};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use chrono::{DateTime, Local, NaiveDate};
+use log::{info, warn, LevelFilter};
+use tauri::{App, Manager, Runtime};
 use tauri::path::BaseDirectory;
-use crate::resources::fonts; // 引用 crate 根目录下的 resources 模块
+use tauri::plugin::TauriPlugin;
+use tauri_plugin_log::{RotationStrategy, Target, TargetKind, TimezoneStrategy};
+use crate::resources::{self, fonts}; // 引用 crate 根目录下的 resources 模块
+
+// =========================================================
+// 🟢 日志：{app_log_dir}/nikon-framer.log
+// =========================================================
+/// 日志文件名 (不含 .log 扩展名)，get_log_path 也用它拼路径
+pub const LOG_FILE_NAME: &str = "nikon-framer";
+// 超过 10 MB 时把当前文件改名为 nikon-framer_<时间>.log 并新开一个，最多保留 7 个
+// 插件只支持按大小轮转；按天轮转由 rotate_daily_log 在启动时补上
+const LOG_MAX_FILE_SIZE: u128 = 10 * 1024 * 1024;
+const LOG_KEEP_FILES: usize = 7;
+// 轮转文件名里的时间，与插件按大小轮转时的命名一致 (按文件名排序即按时间排序)
+const ROTATED_TIME_FORMAT: &str = "%Y-%m-%d_%H-%M-%S";
+// 没有设置 RUST_LOG 时的默认级别
+const DEFAULT_LOG_LEVEL: LevelFilter = LevelFilter::Debug;
+
+// 构建插件时 logger 还没安装，RUST_LOG 里无法识别的项先存起来，setup 时再写进日志
+static PENDING_LOG_WARNINGS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// 构建日志插件 (由 init 在按天轮转之后挂载)
+/// 级别可以用 RUST_LOG 覆盖，写法同 env_logger："info" 或 "info,nikon_framer::batch=debug"
+fn log_plugin<R: Runtime>() -> TauriPlugin<R> {
+    let filter = parse_log_filter(std::env::var("RUST_LOG").ok().as_deref());
+    PENDING_LOG_WARNINGS.lock().unwrap().extend(filter.ignored.iter().map(|item| {
+        format!("⚠️ [Setup] RUST_LOG: 无法识别的级别 {:?}，已忽略", item)
+    }));
+    let LogFilter { level, overrides, .. } = filter;
+
+    let mut builder = tauri_plugin_log::Builder::new()
+        .rotation_strategy(RotationStrategy::KeepSome(LOG_KEEP_FILES))
+        .max_file_size(LOG_MAX_FILE_SIZE)
+        .timezone_strategy(TimezoneStrategy::UseLocal)
+        .targets([
+            Target::new(TargetKind::Stdout), // 让控制台显示
+            Target::new(TargetKind::LogDir { file_name: Some(LOG_FILE_NAME.into()) }), // 让文件保存
+            Target::new(TargetKind::Webview), // (可选) 让前端 F12 console 也能看到 Rust 日志
+        ])
+        .level(level);
+    for (target, target_level) in overrides {
+        builder = builder.level_for(target, target_level);
+    }
+    builder.build()
+}
+
+#[derive(Debug, PartialEq)]
+struct LogFilter {
+    level: LevelFilter,
+    overrides: Vec<(String, LevelFilter)>,
+    ignored: Vec<String>, // 无法识别的项
+}
+
+// 解析 RUST_LOG：逗号分隔，不带 "=" 的一项是全局级别，"模块=级别" 是按模块覆盖；无法识别的项忽略
+fn parse_log_filter(spec: Option<&str>) -> LogFilter {
+    let mut filter = LogFilter { level: DEFAULT_LOG_LEVEL, overrides: Vec::new(), ignored: Vec::new() };
+    for item in spec.unwrap_or_default().split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        let parsed = match item.split_once('=') {
+            Some((target, lvl)) => lvl.trim().parse().map(|lvl| filter.overrides.push((target.trim().to_string(), lvl))),
+            None => item.parse().map(|lvl| filter.level = lvl),
+        };
+        if parsed.is_err() {
+            filter.ignored.push(item.to_string());
+        }
+    }
+    filter
+}
+
+/// 🟢 按天轮转：日志最后一次写入 (本地时间) 不是今天时，改名为 nikon-framer_<最后写入时间>.log，
+/// 再按文件名只保留最新的 LOG_KEEP_FILES 个轮转文件。返回改名后的路径 (不需要轮转时为 None)
+/// 必须在插件打开日志文件之前调用；跨过午夜一直开着的会话仍写在同一个文件里，下次启动时再分开
+fn rotate_daily_log(log_dir: &Path, today: NaiveDate) -> io::Result<Option<PathBuf>> {
+    let current = log_dir.join(format!("{}.log", LOG_FILE_NAME));
+    let Ok(meta) = std::fs::metadata(&current) else {
+        return Ok(None);
+    };
+    let modified: DateTime<Local> = meta.modified()?.into();
+    if modified.date_naive() >= today {
+        return Ok(None);
+    }
+    let rotated = log_dir.join(format!("{}_{}.log", LOG_FILE_NAME, modified.format(ROTATED_TIME_FORMAT)));
+    std::fs::rename(&current, &rotated)?;
+
+    let prefix = format!("{}_", LOG_FILE_NAME);
+    let mut old_logs: Vec<PathBuf> = std::fs::read_dir(log_dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|p| p.file_name().and_then(|n| n.to_str()).is_some_and(|n| n.starts_with(&prefix) && n.ends_with(".log")))
+        .collect();
+    old_logs.sort();
+    let excess = old_logs.len().saturating_sub(LOG_KEEP_FILES);
+    for old in &old_logs[..excess] {
+        std::fs::remove_file(old)?;
+    }
+    Ok(Some(rotated))
+}
 
 pub fn init(app: &mut App) -> Result<(), Box<dyn std::error::Error>> {
     let handle = app.handle();
 
+    // 0. 日志：先按天轮转再挂载插件 (插件打开文件之后就不能改名了)，然后补记构建插件时发现的配置问题
+    let log_dir = handle.path().app_log_dir();
+    let rotated = log_dir.as_ref().ok().map(|dir| rotate_daily_log(dir, Local::now().date_naive()));
+    handle.plugin(log_plugin())?;
+    for msg in PENDING_LOG_WARNINGS.lock().unwrap().drain(..) {
+        warn!("{}", msg);
+    }
+    match rotated {
+        Some(Ok(Some(path))) => info!("📝 [Setup] 日志按天轮转: {:?}", path),
+        Some(Err(e)) => warn!("⚠️ [Setup] 日志按天轮转失败: {}", e),
+        _ => {},
+    }
+
     // 1. 解析资源路径
     let resource_path = handle.path()
         .resolve("assets/fonts", BaseDirectory::Resource)
         .expect("无法解析字体资源路径");
 
     info!("🚀 [Setup] 检测到字体资源路径: {:?}", resource_path);
+    if let Ok(log_dir) = &log_dir {
+        info!("📝 [Setup] 日志目录: {:?}", log_dir);
+    }
 
     // 2. 初始化资源模块
     fonts::init_font_path(resource_path);
 
+    // 3. 🟢 用户自定义 Logo 目录 ({app_data}/logos)：提前建好，方便用户直接放文件
+    match handle.path().app_data_dir() {
+        Ok(app_data) => {
+            let logo_dir = app_data.join("logos");
+            if let Err(e) = std::fs::create_dir_all(&logo_dir) {
+                warn!("⚠️ [Setup] 无法创建用户 Logo 目录 {:?}: {}", logo_dir, e);
+            }
+            resources::init_user_logo_dir(logo_dir);
+        },
+        Err(e) => warn!("⚠️ [Setup] 无法解析应用数据目录，跳过用户 Logo: {}", e),
+    }
+
     Ok(())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn log_filter_levels_and_overrides() {
+        let f = parse_log_filter(None);
+        assert_eq!((f.level, f.overrides.len(), f.ignored.len()), (DEFAULT_LOG_LEVEL, 0, 0));
+
+        let f = parse_log_filter(Some("info, nikon_framer::batch=trace"));
+        assert_eq!(f.level, LevelFilter::Info);
+        assert_eq!(f.overrides, vec![("nikon_framer::batch".to_string(), LevelFilter::Trace)]);
+        assert!(f.ignored.is_empty());
+    }
+
+    // 无法识别的项不影响其他项，原样留给 setup 写进日志
+    #[test]
+    fn log_filter_collects_ignored_items() {
+        let f = parse_log_filter(Some("loud,warn,nikon_framer::batch=verbose"));
+        assert_eq!(f.level, LevelFilter::Warn);
+        assert!(f.overrides.is_empty());
+        assert_eq!(f.ignored, vec!["loud".to_string(), "nikon_framer::batch=verbose".to_string()]);
+    }
+
+    #[test]
+    fn previous_day_log_is_rotated_and_old_ones_pruned() {
+        let dir = crate::test_support::temp_dir("log_rotate");
+        let today = Local::now().date_naive();
+
+        // 没有日志：什么都不做
+        assert!(rotate_daily_log(&dir, today).unwrap().is_none());
+
+        // 今天写过的不动
+        let current = dir.join(format!("{}.log", LOG_FILE_NAME));
+        std::fs::write(&current, "today\n").unwrap();
+        assert!(rotate_daily_log(&dir, today).unwrap().is_none());
+        assert!(current.exists());
+
+        // 已有 LOG_KEEP_FILES 个轮转文件，再轮转一个后最旧的被删掉
+        for day in 1..=LOG_KEEP_FILES {
+            std::fs::write(dir.join(format!("{}_2020-01-{:02}_00-00-00.log", LOG_FILE_NAME, day)), "").unwrap();
+        }
+        let yesterday = std::time::SystemTime::now() - std::time::Duration::from_secs(2 * 86_400);
+        std::fs::File::options().write(true).open(&current).unwrap().set_modified(yesterday).unwrap();
+        let rotated = rotate_daily_log(&dir, today).unwrap().expect("应当轮转");
+        let modified: DateTime<Local> = yesterday.into();
+        assert_eq!(rotated, dir.join(format!("{}_{}.log", LOG_FILE_NAME, modified.format(ROTATED_TIME_FORMAT))));
+        assert!(!current.exists());
+        assert_eq!(std::fs::read_to_string(&rotated).unwrap(), "today\n");
+        assert!(!dir.join(format!("{}_2020-01-01_00-00-00.log", LOG_FILE_NAME)).exists());
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), LOG_KEEP_FILES);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
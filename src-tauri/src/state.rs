@@ -1,13 +1,81 @@
-use std::sync::atomic::AtomicBool;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicU64};
+
+use crate::graphics::decode_cache::{DecodedImageCache, FileStamp};
 
 pub struct AppState {
     pub should_stop: AtomicBool,
+    // 预览代数：每次预览 +1，旧预览发现代数变化即放弃 (快速切换样式时避免堆积)
+    pub preview_generation: AtomicU64,
+    // 解码结果缓存：预览过的照片再预览其他样式或批处理时不必重新解码
+    pub decoded_images: DecodedImageCache,
+    // 🟢 最近一次 scan_folder_with_exif 的 EXIF 检查结果 (路径 -> (检查时的文件身份, 是否有 EXIF))，批处理直接复用
+    // 扫描之后文件被替换 / 改写 (修改时间或大小变了) 的不再采信，由批处理重新检查
+    exif_presence: Mutex<HashMap<String, (FileStamp, bool)>>,
 }
 
 impl AppState {
     pub fn new() -> Self {
         Self {
             should_stop: AtomicBool::new(false),
+            preview_generation: AtomicU64::new(0),
+            decoded_images: DecodedImageCache::default(),
+            exif_presence: Mutex::new(HashMap::new()),
         }
     }
-}
\ No newline at end of file
+
+    /// 记录扫描得到的 EXIF 检查结果 (替换上一次扫描的结果，避免无限增长)
+    /// 读不到文件身份的不记录，批处理时照常检查
+    pub fn record_exif_presence(&self, results: impl IntoIterator<Item = (String, Option<FileStamp>, bool)>) {
+        let mut map = self.exif_presence.lock().unwrap();
+        map.clear();
+        map.extend(results.into_iter().filter_map(|(path, stamp, present)| Some((path, (stamp?, present)))));
+    }
+
+    /// 扫描时记录过的 EXIF 检查结果；没有记录或文件已经变化时返回 None
+    pub fn known_exif_presence(&self, path: &str) -> Option<bool> {
+        let (stamp, present) = *self.exif_presence.lock().unwrap().get(path)?;
+        (FileStamp::read(Path::new(path))? == stamp).then_some(present)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_file(name: &str, contents: &[u8]) -> String {
+        let path = std::env::temp_dir().join(format!("framer_state_{}_{}", name, std::process::id()));
+        std::fs::write(&path, contents).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    fn record(state: &AppState, path: &str, present: bool) {
+        state.record_exif_presence([(path.to_string(), FileStamp::read(Path::new(path)), present)]);
+    }
+
+    #[test]
+    fn unchanged_file_reuses_scan_result() {
+        let state = AppState::new();
+        let path = temp_file("unchanged", b"jpeg");
+        record(&state, &path, true);
+        assert_eq!(state.known_exif_presence(&path), Some(true));
+        assert_eq!(state.known_exif_presence("/nonexistent/never-scanned.jpg"), None);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn replaced_file_is_checked_again() {
+        // 扫描时没有 EXIF，之后同名文件被换成另一张 (大小不同)：不能再沿用 "无 EXIF"
+        let state = AppState::new();
+        let path = temp_file("replaced", b"jpeg");
+        record(&state, &path, false);
+        std::fs::write(&path, b"another jpeg with exif").unwrap();
+        assert_eq!(state.known_exif_presence(&path), None);
+
+        // 删除后同样不采信
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(state.known_exif_presence(&path), None);
+    }
+}
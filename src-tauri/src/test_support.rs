@@ -0,0 +1,21 @@
+// src-tauri/src/test_support.rs
+
+use std::path::{Path, PathBuf};
+
+// =========================================================
+// 测试公用工具：样张路径 + 每个测试独立的临时目录
+// =========================================================
+
+/// tests/fixtures 下样张的完整路径
+pub fn fixture(name: &str) -> String {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(name).to_string_lossy().into_owned()
+}
+
+/// 清空并新建临时目录 framer_<name>_<pid>
+/// name 带上模块前缀 (如 "pipeline_copy_rename")，避免并行测试互相覆盖
+pub fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("framer_{}_{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
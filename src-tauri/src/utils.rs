@@ -1,6 +1,17 @@
+use std::collections::HashSet;
 use std::path::{Path, PathBuf};
 
-use crate::models::{ExportConfig, StyleOptions};
+use crate::models::{ExportConfig, ExportVariant, OriginalCopy, StyleOptions};
+use crate::parser::models::ParsedImageContext;
+
+// 🟢 文件名模板可用的额外信息
+// 拿不到时 (如预检/过滤阶段未读 EXIF) 用 Default，对应占位符渲染为空
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NamingContext<'a> {
+    pub exif: Option<&'a ParsedImageContext>,
+    // 批次内的序号 (从 1 开始)
+    pub counter: Option<usize>,
+}
 
 // 🟢 这是一个独立的、无状态的辅助函数
 // 它不依赖具体的 Context 结构体，只依赖它需要的数据
@@ -8,6 +19,7 @@ pub fn calculate_target_path_core(
     original_file_path: &str,
     export_config: &ExportConfig,
     style_options: &StyleOptions,
+    naming: NamingContext,
 ) -> Result<PathBuf, String> {
     let path_obj = Path::new(original_file_path);
     
@@ -31,7 +43,283 @@ pub fn calculate_target_path_core(
     // 4. 确定扩展名 (使用 export_config 的 Enum)
     let ext = export_config.format.extension();
 
-    // 5. 拼接
-    let filename = format!("{}_{}.{}", file_stem, suffix, ext);
-    Ok(parent.join(filename))
-}
\ No newline at end of file
+    // 5. 拼接 (有模板用模板，模板渲染为空时回退到默认命名)
+    let base = export_config.name_template.as_deref()
+        .map(|tpl| render_name_template(tpl, &file_stem, suffix, naming))
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| format!("{}_{}", file_stem, suffix));
+
+    Ok(parent.join(format!("{}.{}", base, ext)))
+}
+
+// 🟢 尺寸版本在文件名里的名字：清洗非法字符，清洗后为空时按序号命名 (v1、v2 …)
+pub fn variant_label(variant: &ExportVariant, index: usize) -> String {
+    let label = sanitize_file_name(&variant.name);
+    if label.is_empty() { format!("v{}", index + 1) } else { label }
+}
+
+// 🟢 计算尺寸版本的目标路径：主输出文件名后追加 _{版本名}，扩展名按版本自己的格式
+// 传入未去重的主输出路径 (批内重名由调用方登记时统一处理)
+pub fn calculate_variant_path(main_target: &Path, variant: &ExportVariant, index: usize) -> PathBuf {
+    let stem = main_target.file_stem().map(|s| s.to_string_lossy()).unwrap_or_default();
+    main_target.with_file_name(format!("{}_{}.{}", stem, variant_label(variant, index), variant.format.extension()))
+}
+
+// 🟢 渲染文件名模板
+// 占位符: {stem} {style} {date} {model} {iso} {counter}
+// 未知占位符原样保留；结果会清洗掉文件系统不允许的字符
+fn render_name_template(template: &str, stem: &str, style: &str, naming: NamingContext) -> String {
+    let exif = naming.exif;
+    // capture_time 形如 "2023.12.30 14.00.00"，文件名里只取日期部分
+    let date = exif
+        .and_then(|c| c.params.capture_time.split_whitespace().next())
+        .map(|d| d.replace('.', "-"))
+        .unwrap_or_default();
+    let model = exif.map(|c| c.model_name.clone()).unwrap_or_default();
+    let iso = exif.and_then(|c| c.params.iso).map(|v| v.to_string()).unwrap_or_default();
+    let counter = naming.counter.map(|n| format!("{:04}", n)).unwrap_or_default();
+
+    let rendered = template
+        .replace("{stem}", stem)
+        .replace("{style}", style)
+        .replace("{date}", &date)
+        .replace("{model}", &model)
+        .replace("{iso}", &iso)
+        .replace("{counter}", &counter);
+
+    sanitize_file_name(&rendered)
+}
+
+// Windows 保留的设备名 (不区分大小写，带扩展名也不行，如 "CON.jpg")
+const RESERVED_NAMES: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL",
+    "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8", "COM9",
+    "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+// 🟢 清洗文件名：替换 Windows/Unix 不允许的字符，去掉首尾的空格、点和分隔符
+// (缺失的占位符可能留下 "a__b" 或结尾 "_"，顺便收拾掉)；Windows 保留名后面补 "_"
+pub fn sanitize_file_name(name: &str) -> String {
+    let mut out = String::with_capacity(name.len());
+    for c in name.chars() {
+        let c = match c {
+            '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        };
+        // 合并连续的下划线
+        if c == '_' && out.ends_with('_') {
+            continue;
+        }
+        out.push(c);
+    }
+    let mut out = out.trim_matches(|c: char| c == ' ' || c == '.' || c == '_' || c == '-').to_string();
+    let device = out.split('.').next().unwrap_or_default();
+    if RESERVED_NAMES.iter().any(|r| r.eq_ignore_ascii_case(device)) {
+        out.insert(device.len(), '_');
+    }
+    out
+}
+
+// 🟢 同名冲突时追加 _1、_2 …，直到 is_taken 返回 false
+pub fn dedupe_path(path: &Path, is_taken: impl Fn(&Path) -> bool) -> PathBuf {
+    if !is_taken(path) {
+        return path.to_path_buf();
+    }
+    let stem = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let ext = path.extension().map(|e| e.to_string_lossy().to_string());
+    let parent = path.parent().map(Path::to_path_buf).unwrap_or_default();
+
+    (1..)
+        .map(|i| {
+            let name = match &ext {
+                Some(ext) => format!("{}_{}.{}", stem, i, ext),
+                None => format!("{}_{}", stem, i),
+            };
+            parent.join(name)
+        })
+        .find(|candidate| !is_taken(candidate))
+        .expect("无限序列中总能找到未占用的文件名")
+}
+
+// 🟢 一个文件的输出路径 (批内重名已处理)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlannedOutput {
+    // 未去重的主输出路径 (尺寸版本的文件名由它派生)
+    pub target: PathBuf,
+    pub main: PathBuf,
+    // 与 export.variants 顺序一致
+    pub variants: Vec<PathBuf>,
+}
+
+impl PlannedOutput {
+    // 主输出和所有尺寸版本都已存在 (新增一个尺寸版本后重跑，旧照片也要补上)
+    pub fn all_exist(&self) -> bool {
+        self.main.exists() && self.variants.iter().all(|p| p.exists())
+    }
+}
+
+// 🟢 批内输出命名：按登记顺序分配，与已登记的路径重名时追加 _1、_2 … (先登记的保留原名)
+// 磁盘上已存在的旧文件不算冲突，照常覆盖，和默认命名的行为一致
+#[derive(Debug, Default)]
+pub struct OutputNames {
+    claimed: HashSet<PathBuf>,
+}
+
+impl OutputNames {
+    pub fn contains(&self, path: &Path) -> bool {
+        self.claimed.contains(path)
+    }
+
+    pub fn claim(&mut self, path: &Path) -> PathBuf {
+        let unique = dedupe_path(path, |p| self.claimed.contains(p));
+        self.claimed.insert(unique.clone());
+        unique
+    }
+
+    // 登记主输出和各尺寸版本 (版本文件名由未去重的主输出派生，再各自去重)
+    pub fn plan(&mut self, target: PathBuf, variants: &[ExportVariant]) -> PlannedOutput {
+        let main = self.claim(&target);
+        let variants = variants.iter().enumerate()
+            .map(|(i, v)| self.claim(&calculate_variant_path(&target, v, i)))
+            .collect();
+        PlannedOutput { target, main, variants }
+    }
+}
+// 🟢 计算原图副本的目标路径
+// 与成品放在同一目录 (或其下的子文件夹)；rename_to_match 时取成品文件名去掉样式后缀
+pub fn calculate_original_copy_path(
+    original_file_path: &str,
+    output_path: &Path,
+    copy_config: &OriginalCopy,
+    style_options: &StyleOptions,
+) -> Result<PathBuf, String> {
+    let src = Path::new(original_file_path);
+
+    let mut dir = output_path.parent()
+        .ok_or_else(|| format!("无法获取输出目录: {:?}", output_path))?
+        .to_path_buf();
+    if let Some(ref sub) = copy_config.subfolder {
+        if !sub.trim().is_empty() {
+            dir = dir.join(sub.trim());
+        }
+    }
+
+    let filename = if copy_config.rename_to_match {
+        let out_stem = output_path.file_stem()
+            .ok_or_else(|| format!("无法解析输出文件名: {:?}", output_path))?
+            .to_string_lossy();
+        let style_suffix = format!("_{}", style_options.filename_suffix());
+        let base = out_stem.strip_suffix(style_suffix.as_str()).unwrap_or(&out_stem);
+
+        // 保留原图的扩展名 (NEF/JPG 等)
+        match src.extension() {
+            Some(ext) => format!("{}.{}", base, ext.to_string_lossy()),
+            None => base.to_string(),
+        }
+    } else {
+        src.file_name()
+            .ok_or_else(|| format!("无法解析文件名: {}", original_file_path))?
+            .to_string_lossy()
+            .to_string()
+    };
+
+    Ok(dir.join(filename))
+}
+
+// 🟢 原子写入：先写到同目录的临时文件，再 rename 覆盖目标
+// rename 在同一文件系统内是原子的，中途崩溃也不会留下半截文件
+pub fn write_file_atomic(target: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    let mut tmp_name = target.as_os_str().to_owned();
+    tmp_name.push(".tmp");
+    let tmp_path = PathBuf::from(tmp_name);
+
+    std::fs::write(&tmp_path, bytes)?;
+    std::fs::rename(&tmp_path, target).inspect_err(|_| {
+        // rename 失败时清理临时文件，忽略二次错误
+        let _ = std::fs::remove_file(&tmp_path);
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ExportImageFormat;
+    use crate::parser::models::ShootingParams;
+
+    fn style() -> StyleOptions {
+        serde_json::from_str(r#"{"style":"WhiteClassic"}"#).unwrap()
+    }
+
+    fn exif() -> ParsedImageContext {
+        ParsedImageContext {
+            brand: crate::resources::Brand::Nikon,
+            model_name: "Z 6_2".into(),
+            params: ShootingParams { iso: Some(800), capture_time: "2023.12.30 14.00.00".into(), ..ShootingParams::default() },
+            artist_name: None,
+            gps: None,
+        }
+    }
+
+    #[test]
+    fn template_placeholders_are_rendered() {
+        let ctx = exif();
+        let naming = NamingContext { exif: Some(&ctx), counter: Some(7) };
+        assert_eq!(
+            render_name_template("{stem}_{style}_{date}_{model}_{iso}_{counter}", "DSC_0001", "WhiteClassic", naming),
+            "DSC_0001_WhiteClassic_2023-12-30_Z 6_2_800_0007"
+        );
+        // 未知占位符原样保留，缺失的字段渲染为空并收拾多余的分隔符
+        assert_eq!(render_name_template("{stem}_{unknown}", "a", "s", NamingContext::default()), "a_{unknown}");
+        assert_eq!(render_name_template("{date}_{stem}_{counter}", "a", "s", NamingContext::default()), "a");
+    }
+
+    #[test]
+    fn empty_template_falls_back_to_default_name() {
+        let export = ExportConfig { name_template: Some("{model}".into()), ..ExportConfig::default() };
+        let path = calculate_target_path_core("/in/DSC_0001.NEF", &export, &style(), NamingContext::default()).unwrap();
+        assert_eq!(path, Path::new("/in/DSC_0001_WhiteClassic.jpg"));
+    }
+
+    #[test]
+    fn illegal_characters_are_replaced() {
+        assert_eq!(sanitize_file_name(r#"a<b>c:d"e/f\g|h?i*j"#), "a_b_c_d_e_f_g_h_i_j");
+        assert_eq!(sanitize_file_name("tab\tand\nnewline\n"), "tab_and_newline");
+        assert_eq!(sanitize_file_name("a///b"), "a_b");
+        assert_eq!(sanitize_file_name(" ._name_. "), "name");
+        assert_eq!(sanitize_file_name("照片 2024"), "照片 2024");
+    }
+
+    #[test]
+    fn windows_reserved_names_are_suffixed() {
+        assert_eq!(sanitize_file_name("CON"), "CON_");
+        assert_eq!(sanitize_file_name("nul"), "nul_");
+        assert_eq!(sanitize_file_name("com1.backup"), "com1_.backup");
+        assert_eq!(sanitize_file_name("LPT9"), "LPT9_");
+        // 只是以保留名开头的普通名字不受影响
+        assert_eq!(sanitize_file_name("CONSOLE"), "CONSOLE");
+        assert_eq!(sanitize_file_name("COM10"), "COM10");
+    }
+
+    #[test]
+    fn dedupe_appends_the_first_free_suffix() {
+        let taken: HashSet<PathBuf> = ["/o/a.jpg", "/o/a_1.jpg", "/o/b"].iter().map(PathBuf::from).collect();
+        let is_taken = |p: &Path| taken.contains(p);
+        assert_eq!(dedupe_path(Path::new("/o/c.jpg"), is_taken), Path::new("/o/c.jpg"));
+        assert_eq!(dedupe_path(Path::new("/o/a.jpg"), is_taken), Path::new("/o/a_2.jpg"));
+        // 没有扩展名
+        assert_eq!(dedupe_path(Path::new("/o/b"), is_taken), Path::new("/o/b_1"));
+    }
+
+    #[test]
+    fn output_names_follow_claim_order() {
+        let variant = ExportVariant { name: "web".into(), max_long_edge: Some(2048), format: ExportImageFormat::Jpg, quality: 90 };
+        let mut names = OutputNames::default();
+        let first = names.plan(PathBuf::from("/o/x.jpg"), std::slice::from_ref(&variant));
+        let second = names.plan(PathBuf::from("/o/x.jpg"), std::slice::from_ref(&variant));
+        assert_eq!((first.main.as_path(), first.variants[0].as_path()), (Path::new("/o/x.jpg"), Path::new("/o/x_web.jpg")));
+        assert_eq!((second.main.as_path(), second.variants[0].as_path()), (Path::new("/o/x_1.jpg"), Path::new("/o/x_web_1.jpg")));
+        assert_eq!(second.target, Path::new("/o/x.jpg"));
+        assert!(names.contains(Path::new("/o/x_web_1.jpg")));
+    }
+}
@@ -4,7 +4,7 @@ import { invoke } from '@tauri-apps/api/core';
 import { store } from '../store/index.js';
 // 1. 引入注册表，用于查询配置
 import { frameRegistry } from '../frames/registry.js';
-import { buildExportPayload, buildStylePayload } from '../utils/payloadHelper.js';
+import { buildExportPayload, buildLabelsPayload, buildStylePayload } from '../utils/payloadHelper.js';
 
 // =============================================================================
 // 🟢 辅助函数：构建上下文 (OCP 通用版)
@@ -40,7 +40,10 @@ function buildBatchContext() {
   //   - export:  #[serde(rename="exportSettings")] -> 放入 exportPayload
   return {
     ...stylePayload,       
-    exportSettings: exportPayload
+    exportSettings: exportPayload,
+    emitThumbnails: store.emitThumbnails,
+    orderedProgress: store.orderedProgress,
+    labels: buildLabelsPayload(store.labelLanguage)
   };
 }
 
@@ -67,7 +70,9 @@ export function useBatchProcess() {
   // 🟢 核心通用执行器 (Internal Executor)
   // 无论是批处理还是单张处理，最终都调用这个函数
   // =================================================
-  const executeProcess = async (targetPaths, modeName = "处理") => {
+  // resume: 沿用上次中断批次的清单，跳过已完成的文件 (参数变化时 Rust 端会自动作废清单)
+  // report: 是否写批次报告 (单张处理不需要，避免在输出目录堆积报告文件)
+  const executeProcess = async (targetPaths, modeName = "处理", { resume = false, report = false } = {}) => {
     if (targetPaths.length === 0) {
       store.setStatus("文件列表为空！", "error");
       return;
@@ -75,6 +80,7 @@ export function useBatchProcess() {
 
     // 1. 准备上下文
     const contextPayload = buildBatchContext();
+    if (!report) contextPayload.exportSettings.writeReport = false;
 
     // 2. 智能过滤：调用 Rust 检查重复文件
     store.setStatus(`正在检查${modeName}文件...`, "loading");
@@ -119,7 +125,7 @@ export function useBatchProcess() {
     // 5. 重置进度
     store.progress.percent = 0;
     store.progress.current = 0;
-    store.progress.total = filesToProcess.length;
+    store.progress.total = targetPaths.length;
 
     // 6. 启动“停止按钮”计时器 (3秒后允许终止)
     if (stopTimer) clearTimeout(stopTimer);
@@ -129,10 +135,16 @@ export function useBatchProcess() {
 
     // 7. 正式调用 Rust V3 管道接口
     try {
-      await invoke('start_batch_process_v3', {
-        filePaths: filesToProcess, 
-        context: contextPayload
+      // 返回值是批次总结：有成功文件时带 "[timings: 分步耗时]"，开启报告时末尾带 "[report: 路径]"
+      // 传完整列表、由 Rust 端跳过已存在的成品：{counter} 和重名后缀都按这个列表的顺序分配，
+      // 只传过滤后的部分会让编号和过滤时算的对不上
+      if (skippedCount > 0) contextPayload.exportSettings.skipExisting = true;
+      const summary = await invoke('start_batch_process_v3', {
+        filePaths: targetPaths, 
+        context: contextPayload,
+        resume
       });
+      console.log(`[Batch] ${summary}`);
     } catch (error) {
       console.error("启动异常:", error);
       store.isProcessing = false;
@@ -164,7 +176,8 @@ export function useBatchProcess() {
     }
 
     const allPaths = store.fileQueue.map(f => f.path);
-    await executeProcess(allPaths, "批处理");
+    // 批处理默认续传：上次被终止或崩溃时，从断点继续
+    await executeProcess(allPaths, "批处理", { resume: true, report: true });
   };
 
   // =================================================
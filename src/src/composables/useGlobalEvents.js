@@ -21,11 +21,38 @@ export function useGlobalEvents() {
 
     // 1. 进度监听
     const unlistenProgress = await listen('process-progress', (event) => {
-      const { current, total, filepath, status } = event.payload;
+      const { current, total, filepath, status, message, warnings, concurrency, resumed, watching, thumbnail, correlationId } = event.payload;
       store.updateProgress(current, total);
+      // 初始事件：还没有具体文件，只显示本批次的并发数
+      if (status === 'started') {
+        store.filmstrip = [];
+        // 监听模式：没有总数，只提示正在监听的目录
+        if (watching) {
+          store.setStatus(`正在监听: ${watching}`, 'loading');
+          return;
+        }
+        const resumedText = resumed > 0 ? `，续传跳过 ${resumed} 张` : '';
+        store.setStatus(`开始处理 ${total} 张 (并发 ${concurrency}${resumedText})`, 'loading');
+        return;
+      }
       const filename = filepath.replace(/^.*[\\/]/, '');
+      if (thumbnail) store.pushFilmstrip(filepath, thumbnail);
       
-      const msg = status === 'skipped' ? `[跳过] 无EXIF: ${filename}` : `正在处理: ${filename}`;
+      let msg = `正在处理: ${filename}`;
+      if (status === 'skipped') {
+        // message 是 { code, message }：按稳定的 code 判断，文字只用于展示
+        if (message?.code === 'output_exists') msg = `[跳过] 已存在: ${filename}`;
+        else if (message?.code === 'no_exif') msg = `[跳过] 无EXIF: ${filename}`;
+        else msg = `[跳过] ${message?.message ?? message}: ${filename}`;
+      } else if (status === 'error' && message?.code === 'TRUNCATED_FILE') {
+        // 🟢 文件不完整 (读卡中断 / 拷贝到一半)：单独提示，通常重新从存储卡拷贝即可
+        msg = `[文件不完整] ${filename}: 请重新从存储卡拷贝 (ID ${correlationId})`;
+      } else if (status === 'error') {
+        // 关联 ID 与日志文件 (get_log_path) 里的 [..] 一致，方便反馈时定位
+        msg = `[失败] ${filename}: ${message?.message ?? message} (ID ${correlationId})`;
+      } else if (warnings && warnings.length) {
+        msg = `[注意] ${filename}: ${warnings.join('; ')}`;
+      }
       store.setStatus(msg, 'loading');
     });
     listeners.push(unlistenProgress);
@@ -42,10 +69,28 @@ export function useGlobalEvents() {
       } else if (status === 'stopped') {
         store.setStatus("已终止批处理", "error");
         store.progress.total = 0;
+      } else if (status === 'aborted_storage') {
+        store.setStatus("目标磁盘已满或只读，批处理已中止", "error");
+        store.progress.total = 0;
       }
     });
     listeners.push(unlistenStatus);
 
+    // 🟢 2.1 批次总结 (紧跟在 process-status 之后)：计数、跳过原因、总耗时
+    const unlistenSummary = await listen('process-summary', (event) => {
+      const summary = event.payload;
+      store.lastSummary = summary;
+      const text = formatSummary(summary);
+      if (summary.status === 'finished') {
+        store.setStatus(`批处理完成 ✨ ${text}`, summary.failed > 0 ? 'error' : 'success');
+      } else if (summary.status === 'stopped') {
+        store.setStatus(`已终止批处理：${text}`, 'error');
+      } else {
+        store.setStatus(`目标磁盘已满或只读，批处理已中止：${text}`, 'error');
+      }
+    });
+    listeners.push(unlistenSummary);
+
     // 3. 拖拽逻辑 (进入/离开/放下)
     const unlistenEnter = await listen('tauri://drag-enter', () => {
       if (!store.isProcessing) store.isDragging = true;
@@ -83,4 +128,30 @@ export function useGlobalEvents() {
     });
     listeners.push(unlistenDrop);
   });
-}
\ No newline at end of file
+}
+
+// "成功 40 · 跳过 3 (输出已存在 2、无 EXIF 数据 1) · 失败 1 · 用时 12.3 秒 · Logo 缓存命中 39"，导出调优和提示附在末尾
+function formatSummary(summary) {
+  const parts = [`成功 ${summary.succeeded}`];
+  if (summary.skipped > 0) {
+    const reasons = summary.skippedReasons.map(r => `${r.reason} ${r.count}`).join('、');
+    parts.push(`跳过 ${summary.skipped} (${reasons})`);
+  }
+  if (summary.failed > 0) parts.push(`失败 ${summary.failed}`);
+  if (summary.originalsCopied > 0) parts.push(`原图副本 ${summary.originalsCopied}`);
+  if (summary.processed < summary.total) parts.push(`未处理 ${summary.total - summary.processed}`);
+  parts.push(`用时 ${formatElapsed(summary.elapsedMs)}`);
+  if (summary.logoCacheHits > 0) parts.push(`Logo 缓存命中 ${summary.logoCacheHits}`);
+
+  let text = parts.join(' · ');
+  if (summary.exportAdjustments?.length) text += ` [导出调优: ${summary.exportAdjustments.join('; ')}]`;
+  if (summary.notes?.length) text += ` [⚠️ ${summary.notes.join('; ')}]`;
+  return text;
+}
+
+function formatElapsed(ms) {
+  const seconds = ms / 1000;
+  if (seconds < 60) return `${seconds.toFixed(1)} 秒`;
+  const minutes = Math.floor(seconds / 60);
+  return `${minutes} 分 ${Math.floor(seconds - minutes * 60)} 秒`;
+}
@@ -1,4 +1,5 @@
 // src/frames/basic_frames.js
+import { defineAsyncComponent, markRaw } from 'vue';
 
 // 1. 扫描所有图片建立字典
 const presetAssets = import.meta.glob('../assets/presets/*.{png,jpg,jpeg,webp}', { 
@@ -16,24 +17,35 @@ const getAssetUrl = (filename) => {
 };
 
 // 3. 工厂函数
-const defineStaticFrame = (category, label, desc, imgFilename) => ({
+// 🟢 params：可选的 { defaultParams, panelComponent }，有参数的静态样式用它挂上设置面板
+const defineStaticFrame = (category, label, desc, imgFilename, params = {}) => ({
   category, 
   label,
   desc,
   features: { useRawPreview: false }, 
-  panelComponent: null,           
+  panelComponent: params.panelComponent || null,
   layerComponent: null,             
+  ...(params.defaultParams ? { defaultParams: params.defaultParams } : {}),
   
   // 🟢 必须用这个，不能用 new URL(...)
   getPresetUrl: () => getAssetUrl(imgFilename)
 });
 
+// 🟢 参数行分隔符面板 (WhiteClassic / WhitePolaroid / AccentBar)
+const ParamsPanel = markRaw(defineAsyncComponent(() => import('./params/Panel.vue')));
+const paramsSeparator = () => ({
+  defaultParams: { paramsSeparator: 'space' },
+  panelComponent: ParamsPanel
+});
+
 export const basicFrames = {
   // ClassicWhite
-  'WhiteClassic': defineStaticFrame('ClassicWhite', '经典白底', '经典系列，底部附带机型和摄影参数','white_classic.jpg'),
-  'WhitePolaroid': defineStaticFrame('ClassicWhite', '宝丽来白', '经典系列，底部附带机型和摄影参数','white_polaroid.jpg'),
+  'WhiteClassic': defineStaticFrame('ClassicWhite', '经典白底', '经典系列，底部附带机型和摄影参数','white_classic.jpg', paramsSeparator()),
+  'WhitePolaroid': defineStaticFrame('ClassicWhite', '宝丽来白', '经典系列，底部附带机型和摄影参数','white_polaroid.jpg', paramsSeparator()),
   'WhiteMaster': defineStaticFrame('ClassicWhite', '现代大师', '经典系列，底部附带机型和摄影参数','white_master.jpg'), 
   'WhiteModern': defineStaticFrame('ClassicWhite','现代白底', '经典系列，底部附带机型和摄影参数','white_modern.jpg'),
+  'TravelWhite': defineStaticFrame('ClassicWhite', '旅行白底', '经典系列，底部附带机型、拍摄地坐标和日期','travel_white.jpg'),
+  'AccentBar': defineStaticFrame('ClassicWhite', '主色底栏', '经典系列，底栏取照片主色，文字颜色自动适配','accent_bar.jpg', paramsSeparator()),
 
   // Transparent
   'TransparentClassic': defineStaticFrame('Transparent', '透明磨砂', '经典系列，底部附带机型和摄影参数','transparent_classic.jpg'),
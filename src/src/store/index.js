@@ -2,6 +2,9 @@ import { reactive, watch } from 'vue';
 import { invoke } from '@tauri-apps/api/core';
 import { frameRegistry } from '../frames/registry';
 
+// 胶片条最多保留的缩略图数量 (每张是一个十几 KB 的 data URL)
+const FILMSTRIP_MAX = 60;
+
 export const store = reactive({
   // --- 1. 文件队列与核心状态 ---
   fileQueue: [],
@@ -23,6 +26,13 @@ export const store = reactive({
     total: 0, 
     percent: 0 
   },
+  // 🟢 [新增] 本批次已完成的成品缩略图 (最新在前)，来自进度事件的 thumbnail
+  filmstrip: [],
+  // 🟢 最近一次批次总结 (process-summary 事件)：计数、跳过原因、总耗时
+  lastSummary: null,
+  emitThumbnails: true,    // 进度事件附带缩略图 (超大批次可关闭以减小事件负载)
+  orderedProgress: false,  // 🟢 进度事件按提交顺序发出 (列表从上到下依次完成，处理仍然并行)
+  labelLanguage: 'en',     // 🟢 边框固定文字语言 'en' | 'zh' (光圈 / 快门 / 大师系列标题等)
   statusText: "准备就绪",
   statusType: "normal",    // 🟢 [补全] 'normal' | 'success' | 'error'
 
@@ -44,9 +54,13 @@ export const store = reactive({
   exportSettings: {
     pathMode: 'original', // 'original' | 'custom'
     customPath: '',       // 自定义输出目录
-    format: 'jpg',        // 'jpg' | 'png' | 'webp'
-    quality: 90,          // 1-100 (仅 JPG/WebP)
-    resize: 'none',       // 'none' | 'short-2048' | 'short-4096' (预留)
+    format: 'jpg',        // 'jpg' | 'png' | 'webp' | 'tiff' | 'avif'
+    quality: 90,          // 1-100 (JPG/AVIF/WebP，WebP 100 为无损)
+    subsampling: 'yuv444', // 'yuv444' | 'yuv422' | 'yuv420' (仅 JPG)
+    maxLongEdge: null,    // 输出长边上限 (像素)，null 代表原尺寸
+    writeReport: true,    // 批处理结束后在输出目录写入 framer_report_*.json
+    reportCsv: false,     // 报告同时输出 CSV
+    colorManagement: true, // 非 sRGB 原图 (Adobe RGB / Display P3) 转换为 sRGB
   },
 
   // 🟢 [新增] 颜色模式
@@ -212,10 +226,11 @@ export const store = reactive({
     const existingPaths = new Set(this.fileQueue.map(f => f.path));
     const uniqueFiles = newFiles.filter(f => !existingPaths.has(f.path));
     
+    // 扫描文件夹时已经检查过 EXIF 的文件直接带上结果 ('ok' / 'no')，其余排队检查
     const formattedFiles = uniqueFiles.map(f => ({
       name: f.name,
       path: f.path,
-      exifStatus: 'wait'
+      exifStatus: f.exifStatus ?? 'wait'
     }));
     
     this.fileQueue.push(...formattedFiles);
@@ -261,6 +276,7 @@ export const store = reactive({
     this.processedFiles.clear();
     this.activeFilePath = null;
     this.progress = { current: 0, total: 0, percent: 0 };
+    this.filmstrip = [];
     this.statusText = "列表已清空";
     this.statusType = "normal";
   },
@@ -274,8 +290,16 @@ export const store = reactive({
   // 🟢 进度更新
   updateProgress(current, total) {
     this.progress.current = current;
-    this.progress.total = total;
+    this.progress.total = total ?? 0; // 监听模式 total 为 null
     this.progress.percent = total > 0 ? Math.round((current / total) * 100) : 0;
+  },
+
+  // 🟢 胶片条追加一张成品缩略图，只保留最近的 FILMSTRIP_MAX 张
+  // 同一文件再次完成 (监听模式重跑) 时替换旧的那张
+  pushFilmstrip(filepath, thumbnail) {
+    this.filmstrip = this.filmstrip.filter(item => item.filepath !== filepath);
+    this.filmstrip.unshift({ filepath, thumbnail });
+    if (this.filmstrip.length > FILMSTRIP_MAX) this.filmstrip.length = FILMSTRIP_MAX;
   }
 });
 
@@ -1,5 +1,7 @@
 // src/utils/payloadHelpers.js
 
+import frameLabelPresets from './frameLabelPresets.json';
+
 /**
  * 构建符合 Rust ExportConfig 结构的导出配置
  * @param {Object} settings - store.exportSettings
@@ -10,8 +12,13 @@ export function buildExportPayload(settings) {
     // 逻辑转换：只有自定义模式才传路径，否则传 null
     targetDir: settings.pathMode === 'custom' ? settings.customPath : null,
     format: settings.format,
-    // 确保转换为整数，防止滑块传字符串
-    quality: parseInt(settings.quality) || 90 
+    // 确保转换为整数，防止滑块传字符串 (WebP 100 为无损，低于 100 为有损)
+    quality: parseInt(settings.quality) || 90,
+    subsampling: settings.subsampling || 'yuv444',
+    maxLongEdge: settings.maxLongEdge || null,
+    writeReport: !!settings.writeReport,
+    reportCsv: !!settings.reportCsv,
+    colorManagement: settings.colorManagement !== false
   };
 }
 
@@ -60,4 +67,19 @@ export function buildStylePayload(styleId, userParams, registry) {
   }
 
   return payload;
-}
\ No newline at end of file
+}
+// 🟢 边框固定文字预设 (对应 Rust FrameLabels，缺省字段沿用后端的英文默认值)
+// 中文字符由后端的 CJK 回退字体绘制；预设放在 JSON 里，后端测试 (graphics/text.rs) 逐字检查回退字体不缺字
+const FRAME_LABEL_PRESETS = {
+  en: null,
+  ...frameLabelPresets
+};
+
+/**
+ * 构建 Rust BatchContext.labels；英文返回 null (后端使用默认文字)
+ * @param {String} language - store.labelLanguage ('en' | 'zh')
+ * @returns {Object|null} Rust FrameLabels
+ */
+export function buildLabelsPayload(language) {
+  return FRAME_LABEL_PRESETS[language] || null;
+}